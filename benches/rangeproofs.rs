@@ -0,0 +1,76 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Benchmarks of creating and verifying rangeproofs.
+
+#[macro_use]
+extern crate criterion;
+extern crate dalek_rangeproofs;
+extern crate rand;
+
+use criterion::{BenchmarkId, Criterion};
+
+use rand::OsRng;
+
+use dalek_rangeproofs::{RangeProof, DEFAULT_PARAMS};
+
+/// The numbers of rings to benchmark with.
+const N: [usize; 4] = [8, 16, 32, 41];
+
+/// The number of proofs in each batch verification.
+const BATCH_SIZE: usize = 64;
+
+fn create(c: &mut Criterion) {
+    let mut csprng = OsRng::new().unwrap();
+    let mut group = c.benchmark_group("create");
+
+    for &n in N.iter() {
+        group.bench_with_input(BenchmarkId::new("ct", n), &n, |b, &n| {
+            b.iter(|| RangeProof::create_with_params(n, 1666, &DEFAULT_PARAMS, &mut csprng))
+        });
+        group.bench_with_input(BenchmarkId::new("vartime", n), &n, |b, &n| {
+            b.iter(|| RangeProof::create_vartime_with_params(n, 1666, &DEFAULT_PARAMS, &mut csprng))
+        });
+    }
+    group.finish();
+}
+
+fn verify(c: &mut Criterion) {
+    let mut csprng = OsRng::new().unwrap();
+    let mut group = c.benchmark_group("verify");
+
+    for &n in N.iter() {
+        let proof = RangeProof::create_vartime_with_params(n, 1666, &DEFAULT_PARAMS, &mut csprng).unwrap().into_proof();
+        group.bench_with_input(BenchmarkId::from_parameter(n), &n, |b, &n| {
+            b.iter(|| proof.verify_with_params(n, &DEFAULT_PARAMS))
+        });
+    }
+    group.finish();
+}
+
+fn verify_batch(c: &mut Criterion) {
+    let mut csprng = OsRng::new().unwrap();
+    let mut group = c.benchmark_group("verify_batch");
+
+    for &n in N.iter() {
+        let proofs: Vec<RangeProof> = (0..BATCH_SIZE).map(|j| {
+            RangeProof::create_vartime_with_params(n, j as u64, &DEFAULT_PARAMS, &mut csprng).unwrap().into_proof()
+        }).collect();
+        group.bench_with_input(BenchmarkId::from_parameter(n), &n, |b, &n| {
+            b.iter(|| RangeProof::verify_batch_each(n, &proofs, &DEFAULT_PARAMS))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, create, verify, verify_batch);
+criterion_main!(benches);
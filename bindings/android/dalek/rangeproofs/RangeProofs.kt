@@ -0,0 +1,53 @@
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+
+package dalek.rangeproofs
+
+/**
+ * A newly created rangeproof.  The [blinding] opens the [commitment],
+ * so it must be kept as secret as any other key material.
+ */
+class CreatedProof(val proof: ByteArray, val commitment: ByteArray, val blinding: ByteArray)
+
+/**
+ * Back-Maxwell rangeproofs over Decaf, with the default parameters,
+ * backed by the `dalek_rangeproofs` native library built with the
+ * `jni` feature.
+ */
+object RangeProofs {
+    init {
+        System.loadLibrary("dalek_rangeproofs")
+    }
+
+    /** The largest number of rings a proof may have. */
+    const val MAX_N = 41
+
+    /**
+     * Construct a rangeproof that [value], read as unsigned, is in the
+     * range `[0,3^n]`.
+     *
+     * @throws IllegalArgumentException if [n] exceeds [MAX_N] or
+     * [value] is out of range.
+     */
+    fun createProof(n: Int, value: Long): CreatedProof {
+        val parts = create(n, value)
+        return CreatedProof(parts[0], parts[1], parts[2])
+    }
+
+    /**
+     * Verify a rangeproof with [n] rings, returning the compressed
+     * commitment to the in-range value, or null if it's invalid.
+     *
+     * @throws IllegalArgumentException if [n] exceeds [MAX_N].
+     */
+    fun verifyProof(proof: ByteArray, n: Int): ByteArray? = verify(proof, n)
+
+    @JvmStatic
+    private external fun create(n: Int, value: Long): Array<ByteArray>
+
+    @JvmStatic
+    private external fun verify(proof: ByteArray, n: Int): ByteArray?
+}
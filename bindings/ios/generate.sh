@@ -0,0 +1,24 @@
+#!/bin/sh
+#
+# To the extent possible under law, the authors have waived all
+# copyright and related or neighboring rights to dalek-rangeproofs,
+# using the Creative Commons "CC0" public domain dedication.  See
+# <http://creativecommons.org/publicdomain/zero/.0/> for full
+# details.
+#
+# Generate the Swift bindings into bindings/ios from the library built
+# with the `uniffi` feature.  Build the library for each iOS target
+# (e.g. with `--target aarch64-apple-ios`) and link the resulting
+# static library, together with the generated module map, into the
+# app's XCFramework.
+
+set -e
+
+cd "$(dirname "$0")/../.."
+
+cargo build --release --features uniffi
+cargo run --release --features uniffi --bin uniffi-bindgen -- \
+    generate src/dalek_rangeproofs.udl \
+    --language swift \
+    --config uniffi.toml \
+    --out-dir bindings/ios
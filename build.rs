@@ -0,0 +1,26 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+#[cfg(feature = "napi")]
+extern crate napi_build;
+#[cfg(feature = "uniffi")]
+extern crate uniffi;
+
+fn main() {
+    // Link the Node.js addon against the symbols provided by node.
+    #[cfg(feature = "napi")]
+    napi_build::setup();
+
+    // Generate the UniFFI scaffolding for the Swift bindings.
+    #[cfg(feature = "uniffi")]
+    uniffi::generate_scaffolding("src/dalek_rangeproofs.udl").unwrap();
+}
@@ -0,0 +1,221 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Accumulating verified commitments for light clients.
+//!
+//! A full node verifies every rangeproof it sees.  A
+//! `CommitmentAccumulator` ingests the verified commitments and keeps
+//! their sum `Σ C_j`, which is a commitment to the sum of the values
+//! with the sum of the blindings, together with how many proofs of
+//! each range went into it.  A light client handed the accumulator can
+//! then check aggregate claims, such as an opening of the total supply,
+//! without re-verifying any of the proofs.
+//!
+//! The accumulator can only be extended with commitments which have
+//! been verified, but a light client must still trust whoever hands it
+//! a serialized accumulator, or check it against something it already
+//! trusts, such as a commitment in a block header.
+
+#[cfg(feature = "schemars")]
+use schemars;
+
+use std::collections::BTreeMap;
+
+use curve25519_dalek::curve::Identity;
+use curve25519_dalek::decaf::DecafPoint;
+
+use errors::VerifyError;
+use opening::Opening;
+use params::RangeProofParams;
+use verification::VerifiedCommitment;
+
+use super::RangeProof;
+
+/// The running sum of verified commitments, and counts of the proofs
+/// which went into it.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct CommitmentAccumulator {
+    #[cfg_attr(feature = "serde", serde(with = "::serialization::bytes32"))]
+    #[cfg_attr(feature = "schemars", schemars(with = "::schema::Hex32"))]
+    params_id: [u8; 32],
+    #[cfg_attr(feature = "serde", serde(with = "::serialization::point"))]
+    #[cfg_attr(feature = "schemars", schemars(with = "::schema::Hex32"))]
+    total: DecafPoint,
+    count: u64,
+    counts: BTreeMap<usize, u64>,
+}
+
+impl CommitmentAccumulator {
+    /// Construct an empty accumulator for proofs made with `params`.
+    pub fn new(params: &RangeProofParams) -> CommitmentAccumulator {
+        CommitmentAccumulator {
+            params_id: *params.id(),
+            total: DecafPoint::identity(),
+            count: 0,
+            counts: BTreeMap::new(),
+        }
+    }
+
+    /// Verify `proof` for the range `[0,3^n]` and, if it is valid, add
+    /// its commitment to the accumulator.
+    ///
+    /// # Returns
+    ///
+    /// A `VerifyError::ParamsMismatch` if the accumulator is for other
+    /// parameters than `params`, and otherwise the result of verifying
+    /// the proof, as for `verify_checked`.
+    pub fn ingest(
+        &mut self,
+        n: usize,
+        proof: &RangeProof,
+        params: &RangeProofParams,
+    ) -> Result<VerifiedCommitment, VerifyError> {
+        if params.id() != &self.params_id {
+            return Err(VerifyError::ParamsMismatch);
+        }
+
+        let verified = proof.verify_checked(n, params)?;
+        self.add(n, &verified);
+        Ok(verified)
+    }
+
+    /// Add a commitment which has already been verified for the range
+    /// `[0,3^n]` with this accumulator's parameters.
+    pub fn add(&mut self, n: usize, verified: &VerifiedCommitment) {
+        self.total = &self.total + verified.commitment();
+        self.count += 1;
+        *self.counts.entry(n).or_insert(0) += 1;
+    }
+
+    /// Add everything accumulated by `other`, e.g. by another thread.
+    ///
+    /// # Returns
+    ///
+    /// A `VerifyError::ParamsMismatch` if `other` is for different
+    /// parameters, in which case nothing is added.
+    pub fn merge(&mut self, other: &CommitmentAccumulator) -> Result<(), VerifyError> {
+        if other.params_id != self.params_id {
+            return Err(VerifyError::ParamsMismatch);
+        }
+
+        self.total = &self.total + &other.total;
+        self.count += other.count;
+        for (&n, &count) in other.counts.iter() {
+            *self.counts.entry(n).or_insert(0) += count;
+        }
+        Ok(())
+    }
+
+    /// The sum of the accumulated commitments.
+    pub fn total(&self) -> &DecafPoint {
+        &self.total
+    }
+
+    /// The number of accumulated commitments.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// The number of accumulated commitments verified for the range
+    /// `[0,3^n]`.
+    pub fn count_for(&self, n: usize) -> u64 {
+        self.counts.get(&n).cloned().unwrap_or(0)
+    }
+
+    /// The id of the parameters the commitments were verified with.
+    pub fn params_id(&self) -> &[u8; 32] {
+        &self.params_id
+    }
+
+    /// Check the claim that `opening` opens the sum of the accumulated
+    /// commitments, i.e. that the committed values sum to
+    /// `opening.value()`.
+    #[must_use]
+    pub fn check_opening(&self, opening: &Opening, params: &RangeProofParams) -> bool {
+        params.id() == &self.params_id
+            && opening.commitment(params).compress() == self.total.compress()
+    }
+
+    /// Check the claim that `total` is the sum of the accumulated
+    /// commitments.
+    #[must_use]
+    pub fn check_total(&self, total: &DecafPoint) -> bool {
+        total.compress() == self.total.compress()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::OsRng;
+    #[cfg(feature = "serde")]
+    use serde_json;
+
+    use curve25519_dalek::scalar::Scalar;
+
+    use blinding::SecretBlinding;
+    use DEFAULT_PARAMS;
+
+    #[test]
+    fn accumulate_verified_commitments() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let mut accumulator = CommitmentAccumulator::new(&DEFAULT_PARAMS);
+        let mut openings = Vec::new();
+        for &(n, value) in [(8, 100), (8, 20), (16, 3)].iter() {
+            let (proof, _, blinding) =
+                RangeProof::create_with_params(n, value, &DEFAULT_PARAMS, &mut csprng).unwrap().into_parts();
+            assert!(accumulator.ingest(n, &proof, &DEFAULT_PARAMS).is_ok());
+            openings.push(Opening::new(value, blinding));
+        }
+
+        let mut bad = RangeProof::create_with_params(8, 7, &DEFAULT_PARAMS, &mut csprng).unwrap().into_proof();
+        bad.e_0 = Scalar::one();
+        assert_eq!(accumulator.ingest(8, &bad, &DEFAULT_PARAMS).err(), Some(VerifyError::InvalidProof));
+
+        assert_eq!(accumulator.count(), 3);
+        assert_eq!(accumulator.count_for(8), 2);
+        assert_eq!(accumulator.count_for(16), 1);
+
+        let total: Opening = openings.iter().sum::<Option<Opening>>().unwrap();
+        assert_eq!(total.value(), 123);
+        assert!(accumulator.check_opening(&total, &DEFAULT_PARAMS));
+        let wrong = Opening::new(124, SecretBlinding::new(*total.blinding().expose_secret()));
+        assert!(!accumulator.check_opening(&wrong, &DEFAULT_PARAMS));
+
+        let mut merged = CommitmentAccumulator::new(&DEFAULT_PARAMS);
+        merged.merge(&accumulator).unwrap();
+        merged.merge(&accumulator).unwrap();
+        assert_eq!(merged.count_for(8), 4);
+        assert!(merged.check_total(&(accumulator.total() + accumulator.total())));
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn accumulator_serde_roundtrip() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let mut accumulator = CommitmentAccumulator::new(&DEFAULT_PARAMS);
+        let (proof, C, _) = RangeProof::create_with_params(8, 5, &DEFAULT_PARAMS, &mut csprng).unwrap().into_parts();
+        accumulator.ingest(8, &proof, &DEFAULT_PARAMS).unwrap();
+
+        let json = serde_json::to_string(&accumulator).unwrap();
+        let decoded: CommitmentAccumulator = serde_json::from_str(&json).unwrap();
+        assert!(decoded.check_total(&C));
+        assert_eq!(decoded.count_for(8), 1);
+        assert_eq!(decoded.params_id(), DEFAULT_PARAMS.id());
+    }
+}
@@ -0,0 +1,335 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Adaptor rangeproofs.
+//!
+//! An adaptor rangeproof is an ordinary rangeproof for a commitment `C`
+//! whose first ring's response `s_1,0` has been offset by the adaptor
+//! secret `t` of a designated party, who alone knows `t`.  The prover
+//! adds the adaptor point `T = t*G` to the point hashed to `e_1,0`, so
+//! that the pre-proof, with `s~_1,0 = s_1,0 - t`, only verifies against
+//! `T`: the verifier adds `T` to `s~_1,0*G` in place of `t*G`.  Anyone
+//! holding `T` can check the pre-proof, but only the designated party
+//! can adapt it into a rangeproof for `C` which verifies without `T`,
+//! by adding `t` to `s~_1,0`.  Whoever holds the pre-proof then learns
+//! `t = s_1,0 - s~_1,0` from the completed proof.
+//!
+//! The designated party publishes `T` as an `AdaptorPoint`, with a
+//! Schnorr proof of knowledge of `t`, so that the prover only creates
+//! pre-proofs which can be completed.
+//!
+//! This is the same structure as an adaptor signature: an atomic swap
+//! locks one side of the swap behind the pre-proof of an output `C`,
+//! and publishing the completed proof of `C` to claim it reveals `t` to
+//! the counterparty, who uses it to claim the other side.  Since
+//! completing a proof reveals `t`, an adaptor point must never be
+//! reused.
+
+use rand::Rng;
+
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::decaf::DecafPoint;
+use curve25519_dalek::decaf::vartime;
+
+use blinding::SecretBlinding;
+use challenges::{adaptor_point_challenge, e_0_challenge};
+use encoding::{read_point, read_scalar};
+use errors::DecodingError;
+use params::RangeProofParams;
+use transcript_rng::TranscriptRng;
+
+use super::RangeProof;
+
+/// The length of an encoded `AdaptorPoint`.
+const ADAPTOR_POINT_LENGTH: usize = 96;
+
+/// An adaptor point `T = t*G`, with a Schnorr proof `(K, s)` of
+/// knowledge of the adaptor secret `t`.
+#[derive(Clone, Copy, Debug)]
+pub struct AdaptorPoint {
+    T: DecafPoint,
+    K: DecafPoint,
+    s: Scalar,
+}
+
+/// A pre-proof offset by the adaptor secret `t` of an adaptor point
+/// `T`, which can only be completed with `t`.
+pub struct AdaptorRangeProof {
+    proof: RangeProof,
+    adaptor: AdaptorPoint,
+}
+
+impl AdaptorPoint {
+    /// Construct the adaptor point for the adaptor secret `t`, with a
+    /// proof of knowledge of `t`.
+    pub fn new<T: Rng>(t: &Scalar, params: &RangeProofParams, csprng: &mut T) -> AdaptorPoint {
+        let T = params.G() * t;
+        let T_bytes = T.compress();
+
+        let mut rng = TranscriptRng::builder(b"adaptor")
+            .commit_transcript(b"T", T_bytes.as_bytes())
+            .commit_witness(b"t", t.as_bytes())
+            .finalize(csprng);
+        let k = Scalar::random(&mut rng);
+        let K = params.G() * &k;
+
+        let c = adaptor_point_challenge(&T_bytes, &K.compress());
+        let s = Scalar::multiply_add(&c, t, &k);
+
+        AdaptorPoint { T: T, K: K, s: s }
+    }
+
+    /// Verify the proof of knowledge of the adaptor secret.
+    #[must_use]
+    pub fn verify(&self, params: &RangeProofParams) -> bool {
+        let K = self.K.compress();
+        let c = adaptor_point_challenge(&self.T.compress(), &K);
+        let K_hat = vartime::k_fold_scalar_mult(&[self.s, -&c], &[params.G().basepoint(), self.T]);
+
+        K_hat.compress() == K
+    }
+
+    /// The adaptor point `T`.
+    pub fn point(&self) -> &DecafPoint {
+        &self.T
+    }
+
+    /// Encode the adaptor point as `T || K || s`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(ADAPTOR_POINT_LENGTH);
+        bytes.extend_from_slice(self.T.compress().as_bytes());
+        bytes.extend_from_slice(self.K.compress().as_bytes());
+        bytes.extend_from_slice(self.s.as_bytes());
+        bytes
+    }
+
+    /// Decode an adaptor point encoded by `to_bytes`.
+    ///
+    /// # Returns
+    ///
+    /// A `DecodingError` if `bytes` isn't exactly 96 bytes long, or if
+    /// any of the points or the scalar aren't canonically encoded.
+    pub fn from_bytes(bytes: &[u8]) -> Result<AdaptorPoint, DecodingError> {
+        if bytes.len() < ADAPTOR_POINT_LENGTH {
+            return Err(DecodingError::InvalidLength);
+        } else if bytes.len() > ADAPTOR_POINT_LENGTH {
+            return Err(DecodingError::TrailingBytes);
+        }
+
+        Ok(AdaptorPoint {
+            T: read_point(&bytes[..32])?,
+            K: read_point(&bytes[32..64])?,
+            s: read_scalar(&bytes[64..96])?,
+        })
+    }
+}
+
+impl AdaptorRangeProof {
+    /// Construct an adaptor rangeproof for `value`, in constant time,
+    /// with the `adaptor` point `T`.
+    ///
+    /// # Returns
+    ///
+    /// If `n` is `0` or greater than `RANGEPROOF_MAX_N`, `value` is not
+    /// in the range `[0,3^n]`, or the proof of knowledge of the adaptor
+    /// secret doesn't verify, return None.
+    ///
+    /// Otherwise, returns `Some((proof, commitment, blinding))`, where
+    /// `commitment = blinding*G + value*H`.
+    pub fn create<T: Rng>(
+        n: usize,
+        value: u64,
+        adaptor: &AdaptorPoint,
+        params: &RangeProofParams,
+        csprng: &mut T,
+    ) -> Option<(AdaptorRangeProof, DecafPoint, SecretBlinding)> {
+        if n == 0 || !adaptor.verify(params) {
+            return None;
+        }
+
+        let output = RangeProof::create_with_adaptor(n, value, params.G(), params.H_powers(), Some(&adaptor.T),
+                                                     |R| e_0_challenge(R.iter().cloned()), csprng).ok()?;
+        let (proof, C, blinding) = output.into_parts();
+
+        Some((AdaptorRangeProof { proof: proof, adaptor: *adaptor }, C, blinding))
+    }
+
+    /// Verify the proof of knowledge of the adaptor secret and the
+    /// pre-proof against the adaptor point, returning a Pedersen
+    /// commitment to the in-range value if successful.
+    #[must_use]
+    pub fn verify(&self, n: usize, params: &RangeProofParams) -> Option<DecafPoint> {
+        if n == 0 || !self.adaptor.verify(params) {
+            return None;
+        }
+
+        self.proof.check_with_adaptor(n, params.G(), params.H_powers(), Some(&self.adaptor.T),
+                                      |R| e_0_challenge(R.iter().cloned())).ok()
+    }
+
+    /// Complete the pre-proof with the adaptor secret `t`, giving an
+    /// ordinary rangeproof for the same commitment.
+    ///
+    /// # Returns
+    ///
+    /// The adaptor proof, unchanged, if `t*G` isn't the adaptor point.
+    pub fn complete(self, t: &Scalar, params: &RangeProofParams) -> Result<RangeProof, AdaptorRangeProof> {
+        if (params.G() * t).compress() != self.adaptor.T.compress() {
+            return Err(self);
+        }
+
+        let mut proof = self.proof;
+        match proof.s_1.first_mut() {
+            Some(s_1) => *s_1 = &*s_1 + t,
+            None => return Err(AdaptorRangeProof { proof: proof, adaptor: self.adaptor }),
+        }
+
+        Ok(proof)
+    }
+
+    /// Extract the adaptor secret `t = s_1,0 - s~_1,0` from a
+    /// `completed` rangeproof.
+    ///
+    /// # Returns
+    ///
+    /// None if `completed` isn't the completion of this pre-proof.
+    pub fn extract(&self, completed: &RangeProof, params: &RangeProofParams) -> Option<Scalar> {
+        let pre = &self.proof;
+        if completed.e_0 != pre.e_0 || completed.C != pre.C || completed.s_2 != pre.s_2
+            || completed.s_1.len() != pre.s_1.len() || completed.s_1.get(1..) != pre.s_1.get(1..)
+        {
+            return None;
+        }
+
+        let t = completed.s_1.first()? - pre.s_1.first()?;
+        if (params.G() * &t).compress() != self.adaptor.T.compress() {
+            return None;
+        }
+
+        Some(t)
+    }
+
+    /// The pre-proof, whose first ring is offset by the adaptor secret,
+    /// and which therefore doesn't verify as an ordinary rangeproof
+    /// until it is completed.
+    pub fn proof(&self) -> &RangeProof {
+        &self.proof
+    }
+
+    /// The adaptor point `T`.
+    pub fn adaptor_point(&self) -> &DecafPoint {
+        &self.adaptor.T
+    }
+
+    /// Encode the proof as `T || K || s || proof`, where `(K, s)` is
+    /// the proof of knowledge of the adaptor secret.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(ADAPTOR_POINT_LENGTH + self.proof.size_in_bytes());
+        bytes.extend_from_slice(&self.adaptor.to_bytes());
+        bytes.extend_from_slice(&self.proof.to_bytes());
+        bytes
+    }
+
+    /// Decode a proof encoded by `to_bytes`.
+    ///
+    /// # Returns
+    ///
+    /// A `DecodingError` if `bytes` is too short, if the adaptor point
+    /// can't be decoded as for `AdaptorPoint::from_bytes`, or if the
+    /// rangeproof can't be decoded.
+    pub fn from_bytes(bytes: &[u8]) -> Result<AdaptorRangeProof, DecodingError> {
+        if bytes.len() < ADAPTOR_POINT_LENGTH {
+            return Err(DecodingError::InvalidLength);
+        }
+
+        Ok(AdaptorRangeProof {
+            adaptor: AdaptorPoint::from_bytes(&bytes[..ADAPTOR_POINT_LENGTH])?,
+            proof: RangeProof::from_bytes(&bytes[ADAPTOR_POINT_LENGTH..])?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::OsRng;
+
+    use DEFAULT_PARAMS;
+    use RANGEPROOF_MAX_N;
+
+    #[test]
+    fn complete_and_extract_adaptor_secret() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let t = Scalar::random(&mut csprng);
+        let adaptor_point = AdaptorPoint::new(&t, &DEFAULT_PARAMS, &mut csprng);
+        assert!(adaptor_point.verify(&DEFAULT_PARAMS));
+
+        let (adaptor, C, _) =
+            AdaptorRangeProof::create(16, 1000, &adaptor_point, &DEFAULT_PARAMS, &mut csprng).unwrap();
+        assert_eq!(adaptor.verify(16, &DEFAULT_PARAMS).unwrap().compress(), C.compress());
+
+        // The pre-proof doesn't verify until it is completed.
+        assert!(adaptor.proof().verify_with_params(16, &DEFAULT_PARAMS).is_none());
+
+        // The counterparty keeps its own copy of the pre-proof.
+        let counterparty = AdaptorRangeProof::from_bytes(&adaptor.to_bytes()).unwrap();
+        assert_eq!(counterparty.to_bytes(), adaptor.to_bytes());
+
+        let adaptor = adaptor.complete(&Scalar::random(&mut csprng), &DEFAULT_PARAMS).err().unwrap();
+        let completed = adaptor.complete(&t, &DEFAULT_PARAMS).ok().unwrap();
+        assert_eq!(completed.verify_with_params(16, &DEFAULT_PARAMS).unwrap().compress(), C.compress());
+
+        let completed = RangeProof::from_bytes(&completed.to_bytes()).unwrap();
+        let extracted = counterparty.extract(&completed, &DEFAULT_PARAMS).unwrap();
+        assert!(extracted == t);
+
+        let (other, _, _) =
+            AdaptorRangeProof::create(16, 1000, &adaptor_point, &DEFAULT_PARAMS, &mut csprng).unwrap();
+        assert!(other.extract(&completed, &DEFAULT_PARAMS).is_none());
+
+        // An ordinary proof isn't the completion of any pre-proof.
+        let ordinary = RangeProof::create_with_params(16, 1000, &DEFAULT_PARAMS, &mut csprng)
+            .unwrap().into_proof();
+        assert!(counterparty.extract(&ordinary, &DEFAULT_PARAMS).is_none());
+    }
+
+    #[test]
+    fn adaptor_point_must_be_proven_and_bound() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let t = Scalar::random(&mut csprng);
+        let adaptor_point = AdaptorPoint::new(&t, &DEFAULT_PARAMS, &mut csprng);
+        let (adaptor, _, _) =
+            AdaptorRangeProof::create(16, 1000, &adaptor_point, &DEFAULT_PARAMS, &mut csprng).unwrap();
+
+        // An adaptor point whose secret isn't known has no valid proof.
+        let mut hiding = adaptor_point;
+        hiding.T = &hiding.T + DEFAULT_PARAMS.H();
+        assert!(!hiding.verify(&DEFAULT_PARAMS));
+        assert!(AdaptorRangeProof::create(16, 1000, &hiding, &DEFAULT_PARAMS, &mut csprng).is_none());
+
+        // Moving the pre-proof to another, valid, adaptor point
+        // invalidates it.
+        let other_point = AdaptorPoint::new(&Scalar::random(&mut csprng), &DEFAULT_PARAMS, &mut csprng);
+        let mut bytes = other_point.to_bytes();
+        bytes.extend_from_slice(&adaptor.proof().to_bytes());
+        let moved = AdaptorRangeProof::from_bytes(&bytes).unwrap();
+        assert!(moved.verify(16, &DEFAULT_PARAMS).is_none());
+
+        assert!(adaptor.verify(RANGEPROOF_MAX_N + 1, &DEFAULT_PARAMS).is_none());
+        assert!(AdaptorRangeProof::create(0, 0, &adaptor_point, &DEFAULT_PARAMS, &mut csprng).is_none());
+        assert_eq!(AdaptorPoint::from_bytes(&bytes[..95]).err(), Some(DecodingError::InvalidLength));
+        assert_eq!(AdaptorPoint::from_bytes(&bytes[..97]).err(), Some(DecodingError::TrailingBytes));
+    }
+}
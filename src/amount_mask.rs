@@ -0,0 +1,282 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Amount masks derived from an ECDH shared secret.
+//!
+//! Monero-like wallets let the recipient of an output recover its
+//! amount and blinding from the shared secret `D = r*A = a*R` between
+//! the sender's transaction key `r` and the recipient's view key `a`:
+//!
+//! ```text
+//! s      = Hs(D || varint(output_index))
+//! mask   = Hs("commitment_mask" || s)
+//! amount = encrypted_amount XOR H("amount" || s)[..8]
+//! ```
+//!
+//! where `Hs` is a hash to a scalar.  Only the structure of this
+//! derivation is followed here: both hashes are SHA-512 rather than
+//! Keccak, and the group is Decaf rather than Ed25519, so the keys,
+//! masks and encrypted amounts are not compatible with those of any
+//! Monero wallet, and no compatibility mode is provided.
+//!
+//! A `MaskedOutput` carries the public `mask_offset = mask - blinding`
+//! alongside the proof, exactly as each part of a split carries its
+//! offset; see the `opening` module for why the offset reveals
+//! nothing about the mask.
+//!
+//! To send to a recipient's published view key `A` without any
+//! interaction, the sender generates a one-time `TransactionKey`
+//! `(r, R = r*G)`, publishes `R` with the transaction, and derives the
+//! key of each output from `r*A`.  The recipient derives the same keys
+//! with `AmountKey::for_recipient` from `a*R`.
+
+use rand::Rng;
+
+use sha2::{Digest, Sha512};
+
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::decaf::DecafPoint;
+
+use blinding::SecretBlinding;
+use params::RangeProofParams;
+
+use super::RangeProof;
+
+/// Compute the ECDH shared secret between the `secret` key of one
+/// party and the `public` key of the other.
+pub fn ecdh_shared_secret(secret: &SecretBlinding, public: &DecafPoint) -> DecafPoint {
+    public * secret.expose_secret()
+}
+
+/// A one-time transaction key `(r, R = r*G)`, generated by the sender
+/// of a transaction, of which `R` is published with the transaction.
+pub struct TransactionKey {
+    secret: SecretBlinding,
+    public: DecafPoint,
+}
+
+impl TransactionKey {
+    /// Generate a fresh one-time transaction key.
+    pub fn generate<T: Rng>(params: &RangeProofParams, csprng: &mut T) -> TransactionKey {
+        let secret = SecretBlinding::new(Scalar::random(csprng));
+        let public = params.G() * secret.expose_secret();
+        TransactionKey { secret: secret, public: public }
+    }
+
+    /// The public transaction key `R`.
+    pub fn public(&self) -> &DecafPoint {
+        &self.public
+    }
+
+    /// Derive the key for the output at `output_index` to the
+    /// recipient with the public view key `recipient`.
+    pub fn amount_key(&self, recipient: &DecafPoint, output_index: u64) -> AmountKey {
+        AmountKey::derive(&ecdh_shared_secret(&self.secret, recipient), output_index)
+    }
+}
+
+/// The per-output scalar `s` derived from a shared secret, from which
+/// the amount mask and the amount encryption key are derived.
+pub struct AmountKey(SecretBlinding);
+
+impl AmountKey {
+    /// Derive the key for the output at `output_index` from the
+    /// `shared_secret`, as `Hs(D || varint(output_index))`.
+    pub fn derive(shared_secret: &DecafPoint, output_index: u64) -> AmountKey {
+        let mut bytes = shared_secret.compress().as_bytes().to_vec();
+        let mut index = output_index;
+        while index >= 0x80 {
+            bytes.push((index as u8 & 0x7f) | 0x80);
+            index >>= 7;
+        }
+        bytes.push(index as u8);
+
+        AmountKey(SecretBlinding::new(Scalar::hash_from_bytes::<Sha512>(&bytes)))
+    }
+
+    /// Derive the key for the output at `output_index` as its
+    /// recipient, from the secret `view` key and the public transaction
+    /// key `tx_public` of the sender.
+    pub fn for_recipient(view: &SecretBlinding, tx_public: &DecafPoint, output_index: u64) -> AmountKey {
+        AmountKey::derive(&ecdh_shared_secret(view, tx_public), output_index)
+    }
+
+    /// The blinding of the output commitment, `Hs("commitment_mask" || s)`.
+    pub fn mask(&self) -> SecretBlinding {
+        let mut h = Sha512::default();
+        h.input(b"commitment_mask");
+        h.input(self.0.expose_secret().as_bytes());
+        SecretBlinding::new(Scalar::from_hash(h))
+    }
+
+    /// The 8-byte pad `H("amount" || s)[..8]` for encrypting amounts.
+    fn amount_pad(&self) -> [u8; 8] {
+        let mut h = Sha512::default();
+        h.input(b"amount");
+        h.input(self.0.expose_secret().as_bytes());
+        let mut pad = [0u8; 8];
+        pad.copy_from_slice(&h.result()[..8]);
+        pad
+    }
+
+    /// Encrypt `amount` for the recipient.
+    pub fn encrypt_amount(&self, amount: u64) -> [u8; 8] {
+        let mut bytes = amount.to_le_bytes();
+        for (b, p) in bytes.iter_mut().zip(self.amount_pad().iter()) {
+            *b ^= p;
+        }
+        bytes
+    }
+
+    /// Decrypt an amount encrypted by `encrypt_amount`.
+    pub fn decrypt_amount(&self, encrypted_amount: &[u8; 8]) -> u64 {
+        let mut bytes = *encrypted_amount;
+        for (b, p) in bytes.iter_mut().zip(self.amount_pad().iter()) {
+            *b ^= p;
+        }
+        u64::from_le_bytes(bytes)
+    }
+}
+
+/// A rangeproof for an output whose amount and blinding can be
+/// recovered by its recipient.
+pub struct MaskedOutput {
+    proof: RangeProof,
+    mask_offset: Scalar,
+    encrypted_amount: [u8; 8],
+}
+
+impl MaskedOutput {
+    /// Construct a rangeproof for `amount`, in constant time, with the
+    /// output commitment blinded by the mask of `key`.
+    ///
+    /// # Returns
+    ///
+    /// If `n > RANGEPROOF_MAX_N` or `amount` is not in the range
+    /// `[0,3^n]`, return None.
+    ///
+    /// Otherwise, returns `Some((output, commitment))`, where
+    /// `commitment = key.mask()*G + amount*H`.
+    pub fn create<T: Rng>(
+        n: usize,
+        amount: u64,
+        key: &AmountKey,
+        params: &RangeProofParams,
+        csprng: &mut T,
+    ) -> Option<(MaskedOutput, DecafPoint)> {
+        let (proof, C, blinding) = RangeProof::create_with_params(n, amount, params, csprng)?.into_parts();
+        let mask_offset = key.mask().expose_secret() - blinding.expose_secret();
+        let commitment = &C + &(params.G() * &mask_offset);
+
+        Some((
+            MaskedOutput {
+                proof: proof,
+                mask_offset: mask_offset,
+                encrypted_amount: key.encrypt_amount(amount),
+            },
+            commitment,
+        ))
+    }
+
+    /// Verify the rangeproof, returning the output commitment if
+    /// successful.
+    #[must_use]
+    pub fn verify(&self, n: usize, params: &RangeProofParams) -> Option<DecafPoint> {
+        self.proof.verify_with_params(n, params)
+            .map(|C| &C + &(params.G() * &self.mask_offset))
+    }
+
+    /// Recover the amount of the output with `commitment`, as its
+    /// recipient, returning None if `key` isn't the output's key.
+    pub fn scan(&self, key: &AmountKey, commitment: &DecafPoint, params: &RangeProofParams) -> Option<u64> {
+        let amount = key.decrypt_amount(&self.encrypted_amount);
+        let C = &(params.G() * key.mask().expose_secret()) + &(params.H_table() * &Scalar::from_u64(amount));
+
+        if C.compress() == commitment.compress() {
+            Some(amount)
+        } else {
+            None
+        }
+    }
+
+    /// The underlying rangeproof.
+    pub fn proof(&self) -> &RangeProof {
+        &self.proof
+    }
+
+    /// The public difference between the mask and the blinding of the
+    /// rangeproof.
+    pub fn mask_offset(&self) -> &Scalar {
+        &self.mask_offset
+    }
+
+    /// The encrypted amount.
+    pub fn encrypted_amount(&self) -> &[u8; 8] {
+        &self.encrypted_amount
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::OsRng;
+
+    use DEFAULT_PARAMS;
+
+    #[test]
+    fn recipient_recovers_amount_and_mask() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let view = SecretBlinding::new(Scalar::random(&mut csprng));
+        let tx = SecretBlinding::new(Scalar::random(&mut csprng));
+        let A = DEFAULT_PARAMS.G() * view.expose_secret();
+        let R = DEFAULT_PARAMS.G() * tx.expose_secret();
+
+        let sender_key = AmountKey::derive(&ecdh_shared_secret(&tx, &A), 3);
+        let recipient_key = AmountKey::derive(&ecdh_shared_secret(&view, &R), 3);
+        assert!(sender_key.mask().expose_secret() == recipient_key.mask().expose_secret());
+
+        let (output, commitment) =
+            MaskedOutput::create(16, 13449261, &sender_key, &DEFAULT_PARAMS, &mut csprng).unwrap();
+        assert_eq!(output.verify(16, &DEFAULT_PARAMS).unwrap().compress(), commitment.compress());
+        assert_eq!(output.scan(&recipient_key, &commitment, &DEFAULT_PARAMS), Some(13449261));
+
+        let other_key = AmountKey::derive(&ecdh_shared_secret(&view, &R), 4);
+        assert_eq!(output.scan(&other_key, &commitment, &DEFAULT_PARAMS), None);
+        assert_eq!(other_key.decrypt_amount(&other_key.encrypt_amount(u64::max_value())), u64::max_value());
+    }
+
+    #[test]
+    fn send_to_published_view_key() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let view = SecretBlinding::new(Scalar::random(&mut csprng));
+        let A = DEFAULT_PARAMS.G() * view.expose_secret();
+
+        let tx_key = TransactionKey::generate(&DEFAULT_PARAMS, &mut csprng);
+        let (outputs, commitments): (Vec<MaskedOutput>, Vec<DecafPoint>) = [600, 400].iter().enumerate()
+            .map(|(j, &amount)| {
+                let key = tx_key.amount_key(&A, j as u64);
+                MaskedOutput::create(16, amount, &key, &DEFAULT_PARAMS, &mut csprng).unwrap()
+            }).unzip();
+
+        for (j, &amount) in [600, 400].iter().enumerate() {
+            let key = AmountKey::for_recipient(&view, tx_key.public(), j as u64);
+            assert_eq!(outputs[j].scan(&key, &commitments[j], &DEFAULT_PARAMS), Some(amount));
+        }
+
+        let other_tx_key = TransactionKey::generate(&DEFAULT_PARAMS, &mut csprng);
+        let key = AmountKey::for_recipient(&view, other_tx_key.public(), 0);
+        assert_eq!(outputs[0].scan(&key, &commitments[0], &DEFAULT_PARAMS), None);
+    }
+}
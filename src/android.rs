@@ -0,0 +1,115 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! JNI bindings for Android, with the `jni` feature.
+//!
+//! The crate builds as a shared library exporting the native methods of
+//! the class `dalek.rangeproofs.RangeProofs`, whose Kotlin source is in
+//! `bindings/android`:
+//!
+//! ```text
+//! create(n: Int, value: Long): Array<ByteArray>   // [proof, commitment, blinding]
+//! verify(proof: ByteArray, n: Int): ByteArray?    // commitment, or null
+//! ```
+//!
+//! with the same encodings as the Node.js bindings.  `value` is read as
+//! an unsigned 64-bit integer.  Invalid arguments throw an
+//! `IllegalArgumentException` rather than panicking, since unwinding
+//! across the JNI boundary is undefined behaviour.
+
+use jni::objects::{JByteArray, JClass, JObject};
+use jni::sys::{jint, jlong, jbyteArray, jobjectArray};
+use jni::JNIEnv;
+
+use commitments;
+use entropy::default_rng;
+use params::DEFAULT_PARAMS;
+
+use super::RangeProof;
+use super::RANGEPROOF_MAX_N;
+
+const ILLEGAL_ARGUMENT: &str = "java/lang/IllegalArgumentException";
+
+/// Check that `n` is a number of rings which can be proven.
+fn check_n(n: jint) -> Result<usize, String> {
+    if n < 0 || n as usize > RANGEPROOF_MAX_N {
+        return Err(format!("n = {} is not in [0,{}]", n, RANGEPROOF_MAX_N));
+    }
+    Ok(n as usize)
+}
+
+fn create(env: &mut JNIEnv, n: jint, value: jlong) -> Result<jobjectArray, String> {
+    let n = check_n(n)?;
+    let output = RangeProof::try_create_with_params(n, value as u64, &DEFAULT_PARAMS, &mut default_rng())
+        .map_err(|e| e.to_string())?;
+
+    let parts = [
+        output.proof().to_bytes(),
+        commitments::to_bytes(output.commitment()).to_vec(),
+        output.blinding().expose_secret().as_bytes().to_vec(),
+    ];
+    let array = env.new_object_array(3, "[B", JObject::null()).map_err(|e| e.to_string())?;
+    for (i, part) in parts.iter().enumerate() {
+        let bytes = env.byte_array_from_slice(part).map_err(|e| e.to_string())?;
+        env.set_object_array_element(&array, i as jint, bytes).map_err(|e| e.to_string())?;
+    }
+
+    Ok(array.into_raw())
+}
+
+fn verify(env: &mut JNIEnv, proof: &JByteArray, n: jint) -> Result<jbyteArray, String> {
+    let n = check_n(n)?;
+    let bytes = env.convert_byte_array(proof).map_err(|e| e.to_string())?;
+    let proof = match RangeProof::from_bytes_exact(&bytes, n) {
+        Ok(proof) => proof,
+        Err(_) => return Ok(JObject::null().into_raw()),
+    };
+
+    match proof.verify_checked(n, &DEFAULT_PARAMS) {
+        Ok(verified) => {
+            let commitment = commitments::to_bytes(verified.commitment());
+            Ok(env.byte_array_from_slice(&commitment).map_err(|e| e.to_string())?.into_raw())
+        }
+        Err(_) => Ok(JObject::null().into_raw()),
+    }
+}
+
+/// `RangeProofs.create`: construct a rangeproof for `value` in the range
+/// `[0,3^n]`, returning `[proof, commitment, blinding]`.
+#[no_mangle]
+pub extern "system" fn Java_dalek_rangeproofs_RangeProofs_create(
+    mut env: JNIEnv,
+    _class: JClass,
+    n: jint,
+    value: jlong,
+) -> jobjectArray {
+    create(&mut env, n, value).unwrap_or_else(|reason| {
+        let _ = env.throw_new(ILLEGAL_ARGUMENT, reason);
+        JObject::null().into_raw()
+    })
+}
+
+/// `RangeProofs.verify`: verify a rangeproof with `n` rings, returning
+/// the compressed commitment to the in-range value, or null if the
+/// proof doesn't decode or verify.
+#[no_mangle]
+pub extern "system" fn Java_dalek_rangeproofs_RangeProofs_verify(
+    mut env: JNIEnv,
+    _class: JClass,
+    proof: JByteArray,
+    n: jint,
+) -> jbyteArray {
+    verify(&mut env, &proof, n).unwrap_or_else(|reason| {
+        let _ = env.throw_new(ILLEGAL_ARGUMENT, reason);
+        JObject::null().into_raw()
+    })
+}
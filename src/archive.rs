@@ -0,0 +1,177 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Zero-copy archives of rangeproofs, using `rkyv`.
+//!
+//! An archived proof can be accessed directly from a (possibly
+//! memory-mapped) buffer after a single validation pass over its
+//! structure, without deserializing it first.  The points and scalars
+//! are archived as their 32-byte encodings, and are only decoded, and
+//! checked to be canonical, when the proof is verified.
+
+use rkyv;
+use rkyv::{AlignedVec, Archive, Deserialize, Serialize};
+
+use curve25519_dalek::curve::Identity;
+use curve25519_dalek::decaf::{CompressedDecaf, DecafPoint};
+
+use challenges::e_0_challenge;
+use encoding::{read_point, read_scalar};
+use errors::DecodingError;
+
+use super::ring_transcript;
+use super::RangeProof;
+use super::RangeProofParams;
+use super::RANGEPROOF_MAX_N;
+
+/// The archivable form of a `RangeProof`, with every point and scalar
+/// stored as its 32-byte encoding.
+#[derive(Archive, Serialize, Deserialize)]
+#[archive(check_bytes)]
+pub struct RangeProofArchive {
+    e_0: [u8; 32],
+    C: Vec<[u8; 32]>,
+    s_1: Vec<[u8; 32]>,
+    s_2: Vec<[u8; 32]>,
+}
+
+impl<'a> From<&'a RangeProof> for RangeProofArchive {
+    fn from(proof: &'a RangeProof) -> RangeProofArchive {
+        RangeProofArchive {
+            e_0: *proof.e_0.as_bytes(),
+            C: proof.C.iter().map(|C_i| *C_i.compress().as_bytes()).collect(),
+            s_1: proof.s_1.iter().map(|s| *s.as_bytes()).collect(),
+            s_2: proof.s_2.iter().map(|s| *s.as_bytes()).collect(),
+        }
+    }
+}
+
+impl ArchivedRangeProofArchive {
+    /// The number of rings in the archived proof.
+    pub fn n(&self) -> usize {
+        self.C.len()
+    }
+
+    /// Decode the archived proof.
+    ///
+    /// # Returns
+    ///
+    /// A `DecodingError` if the archived vectors have different lengths
+    /// or more than `RANGEPROOF_MAX_N` entries, or if any of the points
+    /// or scalars are invalid or not canonically encoded.
+    pub fn to_proof(&self) -> Result<RangeProof, DecodingError> {
+        let n = self.C.len();
+        if n > RANGEPROOF_MAX_N || n != self.s_1.len() || n != self.s_2.len() {
+            return Err(DecodingError::InvalidLength);
+        }
+
+        let mut C   = Vec::with_capacity(n);
+        let mut s_1 = Vec::with_capacity(n);
+        let mut s_2 = Vec::with_capacity(n);
+        for i in 0..n {
+            C.push(read_point(&self.C[i])?);
+            s_1.push(read_scalar(&self.s_1[i])?);
+            s_2.push(read_scalar(&self.s_2[i])?);
+        }
+
+        Ok(RangeProof{ e_0: read_scalar(&self.e_0)?, C: C, s_1: s_1, s_2: s_2 })
+    }
+
+    /// Verify the archived rangeproof, returning a Pedersen commitment
+    /// to the in-range value if successful.
+    ///
+    /// The proof is verified ring by ring, decoding each point and
+    /// scalar from the archive as it is used, without constructing a
+    /// `RangeProof`.  This accepts exactly the archives for which
+    /// `to_proof` followed by `verify_with_params` succeeds.
+    #[must_use]
+    pub fn verify(&self, n: usize, params: &RangeProofParams) -> Option<DecafPoint> {
+        let powers = params.H_powers();
+        let mut R = [CompressedDecaf([0u8; 32]); RANGEPROOF_MAX_N];
+        if n > R.len() || n > powers.len() || n != self.C.len() || n != self.s_1.len() || n != self.s_2.len() {
+            return None;
+        }
+
+        let e_0 = read_scalar(&self.e_0).ok()?;
+        let mut C = DecafPoint::identity();
+        let rings = self.C.iter().zip(self.s_1.iter()).zip(self.s_2.iter());
+
+        for (((C_i, s_1), s_2), ((mi_H, mi2_H), R_i)) in rings.zip(powers.iter().zip(R.iter_mut())) {
+            let C_i = read_point(C_i).ok()?;
+            let s_1 = read_scalar(s_1).ok()?;
+            let s_2 = read_scalar(s_2).ok()?;
+
+            *R_i = ring_transcript(&C_i, &s_1, &s_2, &e_0, params.G(), mi_H, mi2_H).R;
+            C = &C + &C_i;
+        }
+
+        if e_0_challenge(R.iter().take(n).cloned()) == e_0 {
+            Some(C)
+        } else {
+            None
+        }
+    }
+}
+
+impl RangeProof {
+    /// Archive this rangeproof with `rkyv`.
+    pub fn to_archive(&self) -> AlignedVec {
+        // Serializing into an AlignedVec cannot fail.
+        rkyv::to_bytes::<_, 4096>(&RangeProofArchive::from(self))
+            .expect("serializing a rangeproof archive failed")
+    }
+
+    /// Validate and access an archived rangeproof in place.
+    ///
+    /// `bytes` must be aligned as produced by `to_archive`, which is
+    /// the case for memory-mapped files.
+    ///
+    /// # Returns
+    ///
+    /// A `DecodingError::InvalidArchive` if `bytes` is not a valid
+    /// archive.  Note that this only validates the structure of the
+    /// archive; the proof itself is decoded by `to_proof` or `verify`.
+    pub fn from_archive(bytes: &[u8]) -> Result<&ArchivedRangeProofArchive, DecodingError> {
+        rkyv::check_archived_root::<RangeProofArchive>(bytes)
+            .map_err(|_| DecodingError::InvalidArchive)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::OsRng;
+
+    use DEFAULT_PARAMS;
+
+    #[test]
+    fn archive_and_verify_in_place() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let n = 16;
+        let (proof, commitment, _) =
+            RangeProof::create_with_params(n, 13449261, &DEFAULT_PARAMS, &mut csprng).unwrap().into_parts();
+
+        let bytes = proof.to_archive();
+        let archived = RangeProof::from_archive(&bytes).unwrap();
+        assert_eq!(archived.n(), n);
+        assert_eq!(archived.verify(n, &DEFAULT_PARAMS).unwrap().compress(),
+                   commitment.compress());
+        assert_eq!(archived.to_proof().unwrap().to_bytes(), proof.to_bytes());
+        assert!(archived.verify(n - 1, &DEFAULT_PARAMS).is_none());
+        assert!(archived.verify(RANGEPROOF_MAX_N + 1, &DEFAULT_PARAMS).is_none());
+
+        assert_eq!(RangeProof::from_archive(&bytes[..bytes.len() - 4]).err(),
+                   Some(DecodingError::InvalidArchive));
+    }
+}
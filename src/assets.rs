@@ -0,0 +1,208 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Per-asset value generators.
+//!
+//! As in the _Confidential Assets_ paper, the values of each asset are
+//! committed to with their own generator `H_A`, derived by hashing the
+//! asset's 32-byte id, so that commitments to different assets can't
+//! be confused.  Verifying a proof for an asset needs the parameters
+//! for `G` and `H_A`, whose tables and powers of `H_A` are far more
+//! expensive to build than the proof is to verify.  An
+//! `AssetParamsCache` keeps the parameters for the most recently used
+//! assets, so that they're built once rather than for every proof.
+
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+use std::sync::Arc;
+
+use sha2::Sha256;
+
+use curve25519_dalek::decaf::DecafPoint;
+
+use errors::VerifyError;
+use params::RangeProofParams;
+use verification::VerifiedCommitment;
+
+use super::RangeProof;
+
+/// Derive the value generator `H_A` for the asset with id `asset_id`,
+/// with blinding basepoint `G`, as
+/// `Hash("dalek-rangeproofs asset" || G || asset_id)`.
+pub fn asset_generator(G: &DecafPoint, asset_id: &[u8; 32]) -> DecafPoint {
+    let mut input = Vec::with_capacity(23 + 32 + 32);
+    input.extend_from_slice(b"dalek-rangeproofs asset");
+    input.extend_from_slice(G.compress().as_bytes());
+    input.extend_from_slice(asset_id);
+
+    DecafPoint::hash_from_bytes::<Sha256>(&input)
+}
+
+/// A least-recently-used cache of parameters, keyed by `K`.
+pub(crate) struct ParamsLru<K> {
+    capacity: usize,
+    entries: HashMap<K, (Arc<RangeProofParams>, u64)>,
+    recency: BTreeMap<u64, K>,
+    tick: u64,
+}
+
+impl<K: Copy + Eq + Hash> ParamsLru<K> {
+    pub(crate) fn new(capacity: usize) -> ParamsLru<K> {
+        ParamsLru {
+            capacity: capacity,
+            entries: HashMap::with_capacity(capacity),
+            recency: BTreeMap::new(),
+            tick: 0,
+        }
+    }
+
+    /// The parameters for `key`, building them with `build` if they
+    /// aren't cached.
+    pub(crate) fn get_or_insert_with<F>(&mut self, key: K, build: F) -> Arc<RangeProofParams>
+        where F: FnOnce() -> RangeProofParams
+    {
+        self.tick += 1;
+
+        if let Some(&mut (ref params, ref mut last_used)) = self.entries.get_mut(&key) {
+            self.recency.remove(last_used);
+            self.recency.insert(self.tick, key);
+            *last_used = self.tick;
+            return params.clone();
+        }
+
+        let params = Arc::new(build());
+
+        if self.entries.len() == self.capacity {
+            let oldest = *self.recency.keys().next().unwrap();
+            let evicted = self.recency.remove(&oldest).unwrap();
+            self.entries.remove(&evicted);
+        }
+        self.entries.insert(key, (params.clone(), self.tick));
+        self.recency.insert(self.tick, key);
+
+        params
+    }
+
+    pub(crate) fn contains(&self, key: &K) -> bool {
+        self.entries.contains_key(key)
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+        self.recency.clear();
+    }
+}
+
+/// A least-recently-used cache of the parameters for each asset.
+pub struct AssetParamsCache {
+    G: DecafPoint,
+    cache: ParamsLru<[u8; 32]>,
+}
+
+impl AssetParamsCache {
+    /// Construct a cache holding the parameters for at most `capacity`
+    /// assets, with blinding basepoint `G`.
+    ///
+    /// # Panics
+    ///
+    /// If `capacity` is zero.
+    pub fn new(G: &DecafPoint, capacity: usize) -> AssetParamsCache {
+        // Constructing a cache which can't hold anything is a programming error.
+        if capacity == 0 {
+            panic!("Error: constructed an AssetParamsCache with zero capacity");
+        }
+
+        AssetParamsCache { G: *G, cache: ParamsLru::new(capacity) }
+    }
+
+    /// The parameters for `G` and the generator of the asset with id
+    /// `asset_id`, building them if they aren't cached.
+    ///
+    /// The parameters remain usable after they're evicted from the
+    /// cache, for as long as the caller holds them.
+    pub fn params(&mut self, asset_id: &[u8; 32]) -> Arc<RangeProofParams> {
+        let G = self.G;
+        self.cache.get_or_insert_with(*asset_id, || RangeProofParams::new(&G, &asset_generator(&G, asset_id)))
+    }
+
+    /// Verify a `proof` with `n` rings of a value of the asset with id
+    /// `asset_id`, as for `RangeProof::verify_checked`.
+    pub fn verify(
+        &mut self,
+        asset_id: &[u8; 32],
+        n: usize,
+        proof: &RangeProof,
+    ) -> Result<VerifiedCommitment, VerifyError> {
+        proof.verify_checked(n, &self.params(asset_id))
+    }
+
+    /// Returns `true` if the parameters for `asset_id` are cached.
+    pub fn contains(&self, asset_id: &[u8; 32]) -> bool {
+        self.cache.contains(asset_id)
+    }
+
+    /// The number of assets whose parameters are cached.
+    pub fn len(&self) -> usize {
+        self.cache.len()
+    }
+
+    /// Returns `true` if no parameters are cached.
+    pub fn is_empty(&self) -> bool {
+        self.cache.len() == 0
+    }
+
+    /// Remove every cached parameter set.
+    pub fn clear(&mut self) {
+        self.cache.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::OsRng;
+
+    use DEFAULT_PARAMS;
+
+    #[test]
+    fn asset_params_cache_evicts_least_recently_used() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let G = DEFAULT_PARAMS.G().basepoint();
+        let assets = [[1u8; 32], [2u8; 32], [3u8; 32]];
+        let mut cache = AssetParamsCache::new(&G, 2);
+
+        let params = cache.params(&assets[0]);
+        assert_eq!(params.H().compress(), asset_generator(&G, &assets[0]).compress());
+        assert!(Arc::ptr_eq(&params, &cache.params(&assets[0])));
+
+        let (proof, C, _) = RangeProof::create_with_params(8, 42, &params, &mut csprng).unwrap().into_parts();
+        assert_eq!(cache.verify(&assets[0], 8, &proof).unwrap().commitment().compress(), C.compress());
+        assert_eq!(cache.verify(&assets[1], 8, &proof).err(), Some(VerifyError::InvalidProof));
+
+        // Use assets[0] again, so that adding assets[2] evicts assets[1].
+        cache.params(&assets[0]);
+        cache.params(&assets[2]);
+        assert_eq!(cache.len(), 2);
+        assert!(cache.contains(&assets[0]));
+        assert!(!cache.contains(&assets[1]));
+        assert!(cache.contains(&assets[2]));
+
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+}
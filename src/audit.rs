@@ -0,0 +1,177 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Machine-readable audit transcripts of verification.
+//!
+//! An `AuditTranscript` records every input to the verification of a
+//! proof, every intermediate point and every derived challenge, with
+//! points and scalars as lowercase hex of their 32-byte encodings.  It
+//! exports as JSON, so that auditors and implementations in other
+//! languages can diff it against their own computation, field by
+//! field.
+
+#[cfg(feature = "schemars")]
+use schemars;
+
+use serde_json;
+
+use curve25519_dalek::curve::Identity;
+use curve25519_dalek::decaf::DecafPoint;
+
+use challenges::e_0_challenge;
+use encoding::to_hex;
+use params::RangeProofParams;
+
+use super::RangeProof;
+use super::RANGEPROOF_MAX_N;
+
+/// The verification of a single ring, in hex.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct AuditRing {
+    /// The index `i` of the ring.
+    pub i: usize,
+    /// The digit commitment `C_i`.
+    pub C: String,
+    /// The basepoint `3^i * H`.
+    pub mi_H: String,
+    /// The basepoint `2 * 3^i * H`.
+    pub mi2_H: String,
+    /// The response `s_1,i`.
+    pub s_1: String,
+    /// The response `s_2,i`.
+    pub s_2: String,
+    /// The point `s_1,i * G - e_0 * (C_i - 3^i * H)`.
+    pub P_1: String,
+    /// The challenge `e_1,i`, hashed from `P_1`.
+    pub e_1: String,
+    /// The point `s_2,i * G - e_1,i * (C_i - 2 * 3^i * H)`.
+    pub P_2: String,
+    /// The challenge `e_2,i`, hashed from `P_2`.
+    pub e_2: String,
+    /// The point `R_i = e_2,i * C_i` closing the ring.
+    pub R: String,
+}
+
+/// The verification of a proof, in hex.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct AuditTranscript {
+    /// The number of rings the proof was verified for.
+    pub n: usize,
+    /// The basepoint `G` for blindings.
+    pub G: String,
+    /// The basepoint `H` for values.
+    pub H: String,
+    /// The challenge `e_0` carried in the proof.
+    pub e_0: String,
+    /// Each ring, in order; empty if `n > RANGEPROOF_MAX_N` or the proof
+    /// doesn't have `n` rings.
+    pub rings: Vec<AuditRing>,
+    /// The challenge recomputed from the `R_i`, if the proof has `n`
+    /// rings.
+    pub e_0_recomputed: Option<String>,
+    /// The sum of the digit commitments.
+    pub commitment: String,
+    /// Whether the proof verified.
+    pub valid: bool,
+}
+
+impl AuditTranscript {
+    /// Export the transcript as pretty-printed JSON.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("an audit transcript always serializes")
+    }
+}
+
+impl RangeProof {
+    /// Record an audit transcript of verifying this proof with `n`
+    /// rings.
+    ///
+    /// The transcript is recorded whether or not the proof is valid,
+    /// and `valid` agrees with `verify_with_params`.
+    pub fn audit_transcript(&self, n: usize, params: &RangeProofParams) -> AuditTranscript {
+        let G = params.G();
+        let powers = params.H_powers();
+        let mut rings = Vec::new();
+        let mut e_0_recomputed = None;
+
+        if n <= RANGEPROOF_MAX_N && self.n() == n {
+            let mut R = Vec::with_capacity(n);
+            for i in 0..n {
+                let ring = self.recompute_ring(i, G, powers);
+                rings.push(AuditRing {
+                    i: i,
+                    C: to_hex(self.C[i].compress().as_bytes()),
+                    mi_H: to_hex(powers.power(i).compress().as_bytes()),
+                    mi2_H: to_hex(powers.double_power(i).compress().as_bytes()),
+                    s_1: to_hex(self.s_1[i].as_bytes()),
+                    s_2: to_hex(self.s_2[i].as_bytes()),
+                    P_1: to_hex(ring.P_1.as_bytes()),
+                    e_1: to_hex(ring.e_1.as_bytes()),
+                    P_2: to_hex(ring.P_2.as_bytes()),
+                    e_2: to_hex(ring.e_2.as_bytes()),
+                    R: to_hex(ring.R.as_bytes()),
+                });
+                R.push(ring.R);
+            }
+            e_0_recomputed = Some(to_hex(e_0_challenge(R).as_bytes()));
+        }
+
+        let commitment = self.C.iter().fold(DecafPoint::identity(), |C, C_i| &C + C_i);
+
+        AuditTranscript {
+            n: n,
+            G: to_hex(G.basepoint().compress().as_bytes()),
+            H: to_hex(params.H().compress().as_bytes()),
+            e_0: to_hex(self.e_0.as_bytes()),
+            rings: rings,
+            e_0_recomputed: e_0_recomputed,
+            commitment: to_hex(commitment.compress().as_bytes()),
+            valid: self.verify_with_params(n, params).is_some(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::OsRng;
+
+    use DEFAULT_PARAMS;
+
+    #[test]
+    fn audit_transcript_of_proof() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let (proof, commitment, _) =
+            RangeProof::create_with_params(4, 42, &DEFAULT_PARAMS, &mut csprng).unwrap().into_parts();
+        let audit = proof.audit_transcript(4, &DEFAULT_PARAMS);
+        assert!(audit.valid);
+        assert_eq!(audit.rings.len(), 4);
+        assert_eq!(audit.e_0_recomputed.as_ref(), Some(&audit.e_0));
+        assert_eq!(audit.commitment, to_hex(commitment.compress().as_bytes()));
+
+        let json: serde_json::Value = serde_json::from_str(&audit.to_json()).unwrap();
+        assert_eq!(json["rings"][3]["R"].as_str(), Some(audit.rings[3].R.as_str()));
+
+        let audit = proof.audit_transcript(5, &DEFAULT_PARAMS);
+        assert!(!audit.valid);
+        assert!(audit.rings.is_empty());
+        assert_eq!(audit.e_0_recomputed, None);
+
+        let audit = proof.audit_transcript(RANGEPROOF_MAX_N + 1, &DEFAULT_PARAMS);
+        assert!(!audit.valid);
+        assert!(audit.rings.is_empty());
+    }
+}
@@ -0,0 +1,232 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Balancing the blindings of a transaction.
+//!
+//! A confidential transaction conserves value when its input and
+//! output commitments balance, `Σ C_in = Σ C_out`, which requires both
+//! the values and the blindings to balance.  Wallets usually choose
+//! every output blinding at random but the last, which must then be
+//!
+//! ```text
+//! b_last = Σ b_in - Σ b_out
+//! ```
+//!
+//! and getting the sign of this wrong is the most common mistake in
+//! wallet code.  These helpers compute it, and check the balance of
+//! the resulting commitments.
+//!
+//! Public amounts, such as fees or explicit outputs, are committed to
+//! with zero blinding as an `ExplicitCommitment`.  Its `opening` can be
+//! passed to `balancing_opening` like any other, and
+//! `verify_conservation` checks a transaction mixing explicit and
+//! confidential terms.
+
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::curve::Identity;
+use curve25519_dalek::decaf::DecafPoint;
+
+use blinding::SecretBlinding;
+use commitments;
+use opening::Opening;
+use params::RangeProofParams;
+
+/// A commitment `v*H` to a public value `v`, with zero blinding.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ExplicitCommitment(u64);
+
+impl ExplicitCommitment {
+    /// Commit to the public `value`.
+    pub fn new(value: u64) -> ExplicitCommitment {
+        ExplicitCommitment(value)
+    }
+
+    /// The public value.
+    pub fn value(&self) -> u64 {
+        self.0
+    }
+
+    /// The commitment `v*H`.
+    pub fn commitment(&self, params: &RangeProofParams) -> DecafPoint {
+        commitments::value_point(self.0, params)
+    }
+
+    /// The opening of the commitment, with zero blinding.
+    pub fn opening(&self) -> Opening {
+        Opening::new(self.0, SecretBlinding::new(Scalar::zero()))
+    }
+}
+
+impl From<u64> for ExplicitCommitment {
+    fn from(value: u64) -> ExplicitCommitment {
+        ExplicitCommitment(value)
+    }
+}
+
+/// A term of a transaction: a commitment to a hidden value, or a public
+/// value.
+#[derive(Clone, Copy, Debug)]
+pub enum BalanceTerm {
+    /// A Pedersen commitment to a hidden value.
+    Confidential(DecafPoint),
+    /// A public value.
+    Explicit(ExplicitCommitment),
+}
+
+impl From<DecafPoint> for BalanceTerm {
+    fn from(commitment: DecafPoint) -> BalanceTerm {
+        BalanceTerm::Confidential(commitment)
+    }
+}
+
+impl From<ExplicitCommitment> for BalanceTerm {
+    fn from(commitment: ExplicitCommitment) -> BalanceTerm {
+        BalanceTerm::Explicit(commitment)
+    }
+}
+
+/// Compute the opening of the last output of a transaction with the
+/// given `inputs` and all other `outputs`, so that the transaction
+/// balances.
+///
+/// # Returns
+///
+/// If the values of the `outputs` sum to more than those of the
+/// `inputs`, or either sum overflows a `u64`, return None.
+///
+/// Otherwise, returns `Some(opening)` with value `Σ v_in - Σ v_out`
+/// and blinding `Σ b_in - Σ b_out`.
+pub fn balancing_opening(inputs: &[Opening], outputs: &[Opening]) -> Option<Opening> {
+    let mut value_in = 0u64;
+    for input in inputs.iter() {
+        value_in = value_in.checked_add(input.value())?;
+    }
+    let mut value_out = 0u64;
+    for output in outputs.iter() {
+        value_out = value_out.checked_add(output.value())?;
+    }
+    let value = value_in.checked_sub(value_out)?;
+
+    Some(Opening::new(value, balancing_blinding(inputs, outputs)))
+}
+
+/// Compute the blinding `Σ b_in - Σ b_out` of the last output of a
+/// transaction with the given `inputs` and all other `outputs`.
+pub fn balancing_blinding(inputs: &[Opening], outputs: &[Opening]) -> SecretBlinding {
+    let mut blinding = Scalar::zero();
+    for input in inputs.iter() {
+        blinding = &blinding + input.blinding().expose_secret();
+    }
+    for output in outputs.iter() {
+        blinding = &blinding - output.blinding().expose_secret();
+    }
+    SecretBlinding::new(blinding)
+}
+
+/// Returns `true` if the `inputs` and `outputs` commitments balance,
+/// i.e. `Σ C_in = Σ C_out`.
+#[must_use]
+pub fn verify_balance(inputs: &[DecafPoint], outputs: &[DecafPoint]) -> bool {
+    commitments::sum(inputs).compress() == commitments::sum(outputs).compress()
+}
+
+/// Returns `true` if the `inputs` and `outputs` balance, where each
+/// may be confidential or explicit.
+///
+/// The explicit values are summed as integers, so that only a single
+/// multiple of `H` is computed for all of them.
+#[must_use]
+pub fn verify_conservation(inputs: &[BalanceTerm], outputs: &[BalanceTerm], params: &RangeProofParams) -> bool {
+    let mut excess = DecafPoint::identity();
+    let mut explicit = 0i128;
+    for term in inputs.iter() {
+        match *term {
+            BalanceTerm::Confidential(ref C) => excess = &excess + C,
+            BalanceTerm::Explicit(v) => explicit += v.value() as i128,
+        }
+    }
+    for term in outputs.iter() {
+        match *term {
+            BalanceTerm::Confidential(ref C) => excess = &excess - C,
+            BalanceTerm::Explicit(v) => explicit -= v.value() as i128,
+        }
+    }
+
+    // The sum of many u64s can exceed 2^64, so reduce it in two halves.
+    let abs = explicit.abs() as u128;
+    let two_32 = Scalar::from_u64(1 << 32);
+    let abs = Scalar::multiply_add(&(&two_32 * &two_32), &Scalar::from_u64((abs >> 64) as u64),
+                                   &Scalar::from_u64(abs as u64));
+    let magnitude = params.H_table() * &abs;
+    let excess = if explicit < 0 { &excess - &magnitude } else { &excess + &magnitude };
+    excess.compress() == DecafPoint::identity().compress()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::OsRng;
+
+    use RangeProof;
+    use DEFAULT_PARAMS;
+
+    #[test]
+    fn balance_last_output() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let mut openings = Vec::new();
+        let mut commitments = Vec::new();
+        for &value in [700, 300, 600].iter() {
+            let (_, C, blinding) =
+                RangeProof::create_with_params(16, value, &DEFAULT_PARAMS, &mut csprng).unwrap().into_parts();
+            openings.push(Opening::new(value, blinding));
+            commitments.push(C);
+        }
+        let outputs = openings.split_off(2);
+        let inputs = openings;
+
+        let change = balancing_opening(&inputs, &outputs).unwrap();
+        assert_eq!(change.value(), 400);
+
+        let mut output_commitments = vec![commitments[2]];
+        output_commitments.push(change.commitment(&DEFAULT_PARAMS));
+        assert!(verify_balance(&commitments[..2], &output_commitments));
+        assert!(!verify_balance(&commitments[..2], &output_commitments[..1]));
+
+        // The outputs can't exceed the inputs.
+        assert!(balancing_opening(&outputs, &inputs).is_none());
+    }
+
+    #[test]
+    fn conservation_with_explicit_fee() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let (_, C_in, r_in) = RangeProof::create_with_params(16, 1000, &DEFAULT_PARAMS, &mut csprng).unwrap().into_parts();
+        let fee = ExplicitCommitment::new(10);
+        let inputs = [Opening::new(1000, r_in)];
+
+        let change = balancing_opening(&inputs, &[fee.opening()]).unwrap();
+        assert_eq!(change.value(), 990);
+        let C_change = change.commitment(&DEFAULT_PARAMS);
+
+        assert!(verify_conservation(&[C_in.into()], &[C_change.into(), fee.into()], &DEFAULT_PARAMS));
+        assert!(!verify_conservation(&[C_in.into()], &[C_change.into(), ExplicitCommitment::new(11).into()],
+                                     &DEFAULT_PARAMS));
+        assert!(verify_balance(&[C_in], &[C_change, fee.commitment(&DEFAULT_PARAMS)]));
+
+        // Explicit terms may be on either side.
+        let C_out = &C_in + &ExplicitCommitment::new(5).commitment(&DEFAULT_PARAMS);
+        assert!(verify_conservation(&[C_in.into(), ExplicitCommitment::new(5).into()], &[C_out.into()],
+                                    &DEFAULT_PARAMS));
+    }
+}
@@ -0,0 +1,175 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Base45 encoding of rangeproofs, for QR codes.
+//!
+//! Base45 (RFC 9285) uses only the 45 characters of the QR
+//! alphanumeric mode, in which each character costs 5.5 bits, making
+//! it the densest way to put binary data into a QR code which is
+//! still safe to pass around as text.  A rangeproof with `n = 40`
+//! does not fit into a single code, so `RangeProof::to_qr_strings`
+//! first splits its encoding with the `chunking` module.
+
+use chunking;
+use errors::{ChunkingError, DecodingError};
+
+use super::RangeProof;
+
+const ALPHABET: &'static [u8; 45] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ $%*+-./:";
+
+/// The maximum number of characters which fit in a single QR code in
+/// alphanumeric mode (version 40, error correction level L).
+pub const QR_ALPHANUMERIC_MAX_CHARS: usize = 4296;
+
+/// Encode `bytes` in base45.
+pub fn encode(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity((bytes.len() + 1) / 2 * 3);
+
+    for pair in bytes.chunks(2) {
+        let mut x = if pair.len() == 2 {
+            ((pair[0] as usize) << 8) | pair[1] as usize
+        } else {
+            pair[0] as usize
+        };
+        let digits = if pair.len() == 2 { 3 } else { 2 };
+
+        for _ in 0..digits {
+            encoded.push(ALPHABET[x % 45] as char);
+            x /= 45;
+        }
+    }
+    encoded
+}
+
+fn decode_char(c: u8) -> Result<usize, DecodingError> {
+    ALPHABET.iter().position(|&a| a == c).ok_or(DecodingError::InvalidBase45)
+}
+
+/// Decode a base45 `string`.
+///
+/// # Returns
+///
+/// A `DecodingError::InvalidBase45` if `string` contains characters
+/// outside the base45 alphabet, has a length which is `1 mod 3`, or
+/// contains a group of characters which doesn't encode a byte or pair
+/// of bytes.
+pub fn decode(string: &str) -> Result<Vec<u8>, DecodingError> {
+    let chars = string.as_bytes();
+    if chars.len() % 3 == 1 {
+        return Err(DecodingError::InvalidBase45);
+    }
+
+    let mut decoded = Vec::with_capacity(chars.len() / 3 * 2 + 1);
+    for group in chars.chunks(3) {
+        let mut x = 0usize;
+        for &c in group.iter().rev() {
+            x = x * 45 + decode_char(c)?;
+        }
+
+        if group.len() == 3 {
+            if x > 0xffff {
+                return Err(DecodingError::InvalidBase45);
+            }
+            decoded.push((x >> 8) as u8);
+            decoded.push(x as u8);
+        } else {
+            if x > 0xff {
+                return Err(DecodingError::InvalidBase45);
+            }
+            decoded.push(x as u8);
+        }
+    }
+    Ok(decoded)
+}
+
+impl RangeProof {
+    /// Encode the self-describing encoding of this rangeproof in base45.
+    ///
+    /// # Returns
+    ///
+    /// As for `to_self_describing_bytes`.
+    pub fn to_base45(&self) -> Result<String, DecodingError> {
+        Ok(encode(&self.to_self_describing_bytes()?))
+    }
+
+    /// Decode a rangeproof from the output of `to_base45`, returning
+    /// `(n, proof)`.
+    pub fn from_base45(string: &str) -> Result<(usize, RangeProof), DecodingError> {
+        RangeProof::from_self_describing_bytes(&decode(string)?)
+    }
+
+    /// Encode this rangeproof as base45 strings of at most `max_chars`
+    /// characters each, one per QR code.
+    ///
+    /// Use `QR_ALPHANUMERIC_MAX_CHARS` for the largest QR codes, or a
+    /// smaller value for codes which are easier to scan.
+    pub fn to_qr_strings(&self, max_chars: usize) -> Result<Vec<String>, ChunkingError> {
+        let chunks = self.to_chunks(max_chars / 3 * 2)?;
+
+        Ok(chunks.iter().map(|chunk| encode(chunk)).collect())
+    }
+
+    /// Decode a rangeproof from the output of `to_qr_strings`, scanned
+    /// in any order, returning `(n, proof)`.
+    pub fn from_qr_strings<S: AsRef<str>>(strings: &[S]) -> Result<(usize, RangeProof), ChunkingError> {
+        let mut reassembler = chunking::Reassembler::new();
+        for string in strings.iter() {
+            let chunk = decode(string.as_ref()).map_err(ChunkingError::Decoding)?;
+            reassembler.push(&chunk)?;
+        }
+        let bytes = reassembler.finish()?;
+
+        RangeProof::from_self_describing_bytes(&bytes).map_err(ChunkingError::Decoding)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::OsRng;
+
+    use DEFAULT_PARAMS;
+
+    #[test]
+    fn rfc_9285_test_vectors() {
+        assert_eq!(encode(b"AB"), "BB8");
+        assert_eq!(encode(b"Hello!!"), "%69 VD92EX0");
+        assert_eq!(encode(b"base-45"), "UJCLQE7W581");
+        assert_eq!(decode("QED8WEX0").unwrap(), b"ietf!".to_vec());
+
+        assert_eq!(decode("GGW").err(), Some(DecodingError::InvalidBase45));
+        assert_eq!(decode("a").err(), Some(DecodingError::InvalidBase45));
+    }
+
+    #[test]
+    fn proof_to_qr_strings_and_back() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let n = 40;
+        let (proof, commitment, _) =
+            RangeProof::create_with_params(n, 134492616741, &DEFAULT_PARAMS, &mut csprng).unwrap().into_parts();
+
+        let (n_hat, decoded) = RangeProof::from_base45(&proof.to_base45().unwrap()).unwrap();
+        assert_eq!(n_hat, n);
+        assert_eq!(decoded.to_bytes(), proof.to_bytes());
+
+        let mut strings = proof.to_qr_strings(QR_ALPHANUMERIC_MAX_CHARS).unwrap();
+        assert_eq!(strings.len(), 2);
+        assert!(strings.iter().all(|s| s.len() <= QR_ALPHANUMERIC_MAX_CHARS));
+        strings.reverse();
+
+        let (_, decoded) = RangeProof::from_qr_strings(&strings).unwrap();
+        assert_eq!(decoded.verify_with_params(n, &DEFAULT_PARAMS).unwrap().compress(),
+                   commitment.compress());
+    }
+}
@@ -0,0 +1,68 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Command-line utilities for stored rangeproofs.
+//!
+//! Usage:
+//!
+//! ```text
+//! dalek-rangeproofs cbor-to-compact < proof.cbor > proof.bin
+//! dalek-rangeproofs compact-to-cbor < proof.bin > proof.cbor
+//! ```
+
+extern crate dalek_rangeproofs;
+
+use std::env;
+use std::io::{self, Read, Write};
+use std::process;
+
+use dalek_rangeproofs::migration;
+
+const USAGE: &'static str = "usage: dalek-rangeproofs (cbor-to-compact | compact-to-cbor) < input > output";
+
+fn main() {
+    let subcommand = match env::args().nth(1) {
+        Some(subcommand) => subcommand,
+        None => {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+
+    let mut input = Vec::new();
+    if let Err(e) = io::stdin().read_to_end(&mut input) {
+        eprintln!("error: could not read input: {}", e);
+        process::exit(1);
+    }
+
+    let output = match &subcommand[..] {
+        "cbor-to-compact" => migration::cbor_to_compact(&input),
+        "compact-to-cbor" => migration::compact_to_cbor(&input),
+        _ => {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+
+    match output {
+        Ok(bytes) => {
+            if let Err(e) = io::stdout().write_all(&bytes) {
+                eprintln!("error: could not write output: {}", e);
+                process::exit(1);
+            }
+        }
+        Err(e) => {
+            eprintln!("error: {}", e);
+            process::exit(1);
+        }
+    }
+}
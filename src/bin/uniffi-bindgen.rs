@@ -0,0 +1,20 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! The UniFFI binding generator, pinned to the version of `uniffi` the
+//! library is built with, for `bindings/ios/generate.sh`.
+
+extern crate uniffi;
+
+fn main() {
+    uniffi::uniffi_bindgen_main()
+}
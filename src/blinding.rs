@@ -0,0 +1,80 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! The secret blinding factor of a commitment.
+
+use std::fmt;
+use std::ptr;
+use std::sync::atomic;
+
+use curve25519_dalek::scalar::Scalar;
+
+/// The blinding factor `r` of a Pedersen commitment `r*G + v*H`.
+///
+/// Anyone who learns the blinding of a commitment can open it, so a
+/// `SecretBlinding` is deliberately awkward to leak: it can't be
+/// cloned, its `Debug` output is redacted, and it is overwritten with
+/// zeroes when dropped.  The scalar itself is only available through
+/// `expose_secret`.
+pub struct SecretBlinding(Scalar);
+
+impl SecretBlinding {
+    /// Wrap the blinding factor `blinding`.
+    pub fn new(blinding: Scalar) -> SecretBlinding {
+        SecretBlinding(blinding)
+    }
+
+    /// Access the blinding factor.
+    ///
+    /// # Note
+    ///
+    /// Copies of the returned scalar are not zeroed when the
+    /// `SecretBlinding` is dropped.
+    pub fn expose_secret(&self) -> &Scalar {
+        &self.0
+    }
+}
+
+impl fmt::Debug for SecretBlinding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SecretBlinding([REDACTED])")
+    }
+}
+
+impl Drop for SecretBlinding {
+    fn drop(&mut self) {
+        zeroize(&mut (self.0).0);
+    }
+}
+
+/// Overwrite the secret `bytes` with zeroes.
+pub(crate) fn zeroize(bytes: &mut [u8]) {
+    // Volatile writes can't be optimised away, even though the bytes
+    // are never read again.
+    for byte in bytes.iter_mut() {
+        unsafe { ptr::write_volatile(byte, 0) };
+    }
+    atomic::compiler_fence(atomic::Ordering::SeqCst);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn debug_is_redacted() {
+        let blinding = SecretBlinding::new(Scalar::from_u64(0x2a2a2a2a));
+
+        assert_eq!(format!("{:?}", blinding), "SecretBlinding([REDACTED])");
+        assert!(blinding.expose_secret() == &Scalar::from_u64(0x2a2a2a2a));
+    }
+}
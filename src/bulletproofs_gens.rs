@@ -0,0 +1,85 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Conversions to and from the generators of the `bulletproofs` crate.
+//!
+//! A `PedersenGens` commits to `v` with blinding `r` as
+//! `v*B + r*B_blinding`, so its `B_blinding` is our `G` and its `B` is
+//! our `H`.  The generators are exchanged as compressed points.
+//!
+//! # Note
+//!
+//! Only the encodings of the generators are exchanged.  Points here are
+//! Decaf points from `curve25519-dalek` 0.8, and those of `bulletproofs`
+//! are Ristretto points from `curve25519-dalek-ng`; nothing checks that
+//! the two decode the same bytes to the same group element, so a
+//! commitment made by one library must not be assumed to equal the
+//! commitment to the same value and blinding made by the other.
+
+use bulletproofs::PedersenGens;
+
+use curve25519_dalek_ng::ristretto::CompressedRistretto;
+
+use errors::DecodingError;
+use params::RangeProofParams;
+
+impl RangeProofParams {
+    /// Precompute parameters for the generators of a `PedersenGens`,
+    /// with `G = B_blinding` and `H = B`.
+    ///
+    /// # Returns
+    ///
+    /// A `DecodingError::InvalidPoint` if either generator can't be
+    /// decoded as a point here.
+    pub fn from_pedersen_gens(gens: &PedersenGens) -> Result<RangeProofParams, DecodingError> {
+        RangeProofParams::from_compressed_generators(gens.B_blinding.compress().as_bytes(),
+                                                     gens.B.compress().as_bytes())
+    }
+
+    /// The `PedersenGens` with `B = H` and `B_blinding = G`.
+    ///
+    /// # Returns
+    ///
+    /// A `DecodingError::InvalidPoint` if either generator can't be
+    /// decoded by the `bulletproofs` crate.
+    pub fn to_pedersen_gens(&self) -> Result<PedersenGens, DecodingError> {
+        let (G, H) = self.compressed_generators();
+
+        Ok(PedersenGens {
+            B: CompressedRistretto(H).decompress().ok_or(DecodingError::InvalidPoint)?,
+            B_blinding: CompressedRistretto(G).decompress().ok_or(DecodingError::InvalidPoint)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use DEFAULT_PARAMS;
+
+    #[test]
+    fn pedersen_gens_roundtrip() {
+        let gens = PedersenGens::default();
+        let params = RangeProofParams::from_pedersen_gens(&gens).unwrap();
+        let (G, H) = params.compressed_generators();
+        assert_eq!(&G, gens.B_blinding.compress().as_bytes());
+        assert_eq!(&H, gens.B.compress().as_bytes());
+
+        let back = params.to_pedersen_gens().unwrap();
+        assert_eq!(back.B, gens.B);
+        assert_eq!(back.B_blinding, gens.B_blinding);
+
+        let gens = DEFAULT_PARAMS.to_pedersen_gens().unwrap();
+        assert_eq!(RangeProofParams::from_pedersen_gens(&gens).unwrap().id(), DEFAULT_PARAMS.id());
+    }
+}
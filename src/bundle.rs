@@ -0,0 +1,135 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Self-contained, serializable rangeproofs.
+
+#[cfg(feature = "schemars")]
+use schemars;
+
+use curve25519_dalek::decaf::DecafPoint;
+
+use errors::VerifyError;
+use params::RangeProofParams;
+use verification::VerifiedCommitment;
+
+use super::RangeProof;
+use super::RANGEPROOF_MAX_N;
+
+/// A rangeproof together with everything needed to check it: the
+/// identifier of the parameters it was made with, its number of rings,
+/// and the commitment which it is claimed to prove.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct ProofBundle {
+    /// The `RangeProofParams::id` of the parameters.
+    #[cfg_attr(feature = "serde", serde(with = "::serialization::bytes32"))]
+    #[cfg_attr(feature = "schemars", schemars(with = "::schema::Hex32"))]
+    pub params_id: [u8; 32],
+    /// The number of rings, so that the proof is for the range `[0,3^n]`.
+    pub n: usize,
+    /// The rangeproof.
+    pub proof: RangeProof,
+    /// The commitment which the proof is claimed to prove.
+    #[cfg_attr(feature = "serde", serde(with = "::serialization::point"))]
+    #[cfg_attr(feature = "schemars", schemars(with = "::schema::Hex32"))]
+    pub claimed_commitment: DecafPoint,
+}
+
+impl ProofBundle {
+    /// Bundle a `proof` of `commitment` for the range `[0,3^n]`, made
+    /// with `params`.
+    pub fn new(
+        n: usize,
+        proof: RangeProof,
+        commitment: DecafPoint,
+        params: &RangeProofParams,
+    ) -> ProofBundle {
+        ProofBundle {
+            params_id: *params.id(),
+            n: n,
+            proof: proof,
+            claimed_commitment: commitment,
+        }
+    }
+
+    /// Check that the bundle was made with `params`, that the proof
+    /// verifies for `n` rings, and that it proves the claimed
+    /// commitment.
+    ///
+    /// # Returns
+    ///
+    /// The verified commitment, or the first inconsistency found.
+    pub fn verify(&self, params: &RangeProofParams) -> Result<VerifiedCommitment, VerifyError> {
+        if &self.params_id != params.id() {
+            return Err(VerifyError::ParamsMismatch);
+        }
+        if self.n > RANGEPROOF_MAX_N {
+            return Err(VerifyError::WrongRingCount);
+        }
+
+        let verified = self.proof.verify_checked(self.n, params)?;
+        if verified.commitment() != &self.claimed_commitment {
+            return Err(VerifyError::CommitmentMismatch);
+        }
+        Ok(verified)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::OsRng;
+    #[cfg(feature = "serde")]
+    use serde_cbor;
+    #[cfg(feature = "serde")]
+    use serde_json;
+
+    use DEFAULT_PARAMS;
+
+    #[test]
+    fn bundle_roundtrip_and_verify() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let n = 16;
+        let (proof, commitment, _) =
+            RangeProof::create_with_params(n, 13449261, &DEFAULT_PARAMS, &mut csprng).unwrap().into_parts();
+        let bundle = ProofBundle::new(n, proof, commitment, &DEFAULT_PARAMS);
+        assert_eq!(bundle.verify(&DEFAULT_PARAMS).unwrap().commitment().compress(),
+                   commitment.compress());
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn bundle_serde_roundtrip() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let n = 16;
+        let (proof, commitment, _) =
+            RangeProof::create_with_params(n, 13449261, &DEFAULT_PARAMS, &mut csprng).unwrap().into_parts();
+        let bundle = ProofBundle::new(n, proof, commitment, &DEFAULT_PARAMS);
+
+        let json = serde_json::to_string(&bundle).unwrap();
+        let mut decoded: ProofBundle = serde_json::from_str(&json).unwrap();
+        assert!(decoded.verify(&DEFAULT_PARAMS).is_ok());
+
+        decoded.claimed_commitment = *DEFAULT_PARAMS.H();
+        assert_eq!(decoded.verify(&DEFAULT_PARAMS).err(), Some(VerifyError::CommitmentMismatch));
+
+        let mut decoded: ProofBundle = serde_cbor::from_slice(&serde_cbor::to_vec(&bundle).unwrap()).unwrap();
+        decoded.n = 15;
+        assert_eq!(decoded.verify(&DEFAULT_PARAMS).err(), Some(VerifyError::WrongRingCount));
+        decoded.params_id = [0u8; 32];
+        assert_eq!(decoded.verify(&DEFAULT_PARAMS).err(), Some(VerifyError::ParamsMismatch));
+    }
+}
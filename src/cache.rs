@@ -0,0 +1,167 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Caching the results of verifying rangeproofs.
+//!
+//! A node typically sees each transaction twice, once when it's relayed
+//! to the mempool and again when it's included in a block.  A
+//! `VerificationCache` remembers the results of the most recently
+//! verified proofs, keyed by `RangeProof::id`, the parameters' id and
+//! `n`, so that the second verification is a lookup.
+
+use std::collections::{BTreeMap, HashMap};
+
+use sha2::{Digest, Sha512};
+
+use errors::VerifyError;
+use params::RangeProofParams;
+use verification::VerifiedCommitment;
+
+use super::RangeProof;
+
+/// A proof id, params id, and number of rings.
+type CacheKey = ([u8; 32], [u8; 32], usize);
+
+impl RangeProof {
+    /// A 32-byte identifier for the proof, the first 32 bytes of the
+    /// SHA-512 hash of its encoding.
+    ///
+    /// Since only canonical encodings are accepted, two proofs have
+    /// the same id exactly when they're the same proof.
+    pub fn id(&self) -> [u8; 32] {
+        let mut h = Sha512::default();
+        h.input(b"dalek-rangeproofs proof id");
+        h.input(&self.to_bytes());
+        let mut id = [0u8; 32];
+        id.copy_from_slice(&h.result()[..32]);
+        id
+    }
+}
+
+/// A least-recently-used cache of the results of verifying rangeproofs.
+pub struct VerificationCache {
+    capacity: usize,
+    entries: HashMap<CacheKey, (Result<VerifiedCommitment, VerifyError>, u64)>,
+    recency: BTreeMap<u64, CacheKey>,
+    tick: u64,
+}
+
+impl VerificationCache {
+    /// Construct a cache holding at most `capacity` results.
+    ///
+    /// # Panics
+    ///
+    /// If `capacity` is zero.
+    pub fn new(capacity: usize) -> VerificationCache {
+        // Constructing a cache which can't hold anything is a programming error.
+        if capacity == 0 {
+            panic!("Error: constructed a VerificationCache with zero capacity");
+        }
+
+        VerificationCache {
+            capacity: capacity,
+            entries: HashMap::with_capacity(capacity),
+            recency: BTreeMap::new(),
+            tick: 0,
+        }
+    }
+
+    /// Verify the `proof` as for `RangeProof::verify_checked`, or return
+    /// the cached result if it has already been verified for the same
+    /// `n` and `params`.
+    pub fn verify(
+        &mut self,
+        n: usize,
+        proof: &RangeProof,
+        params: &RangeProofParams,
+    ) -> Result<VerifiedCommitment, VerifyError> {
+        let key = (proof.id(), *params.id(), n);
+        self.tick += 1;
+
+        if let Some(&mut (result, ref mut last_used)) = self.entries.get_mut(&key) {
+            self.recency.remove(last_used);
+            self.recency.insert(self.tick, key);
+            *last_used = self.tick;
+            return result;
+        }
+
+        let result = proof.verify_checked(n, params);
+
+        if self.entries.len() == self.capacity {
+            let oldest = *self.recency.keys().next().unwrap();
+            let evicted = self.recency.remove(&oldest).unwrap();
+            self.entries.remove(&evicted);
+        }
+        self.entries.insert(key, (result, self.tick));
+        self.recency.insert(self.tick, key);
+
+        result
+    }
+
+    /// Returns `true` if the result for `proof` with `n` rings and
+    /// `params` is cached.
+    pub fn contains(&self, n: usize, proof: &RangeProof, params: &RangeProofParams) -> bool {
+        self.entries.contains_key(&(proof.id(), *params.id(), n))
+    }
+
+    /// The number of cached results.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if no results are cached.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Remove every cached result.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.recency.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::OsRng;
+
+    use DEFAULT_PARAMS;
+
+    #[test]
+    fn cache_evicts_least_recently_used() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let proofs: Vec<RangeProof> = [1, 2, 3].iter().map(|&value| {
+            RangeProof::create_with_params(4, value, &DEFAULT_PARAMS, &mut csprng).unwrap().into_proof()
+        }).collect();
+        assert!(proofs[0].id() != proofs[1].id());
+
+        let mut cache = VerificationCache::new(2);
+        assert!(cache.verify(4, &proofs[0], &DEFAULT_PARAMS).is_ok());
+        assert_eq!(cache.verify(3, &proofs[1], &DEFAULT_PARAMS).err(), Some(VerifyError::WrongRingCount));
+        assert!(cache.contains(4, &proofs[0], &DEFAULT_PARAMS));
+        assert!(!cache.contains(4, &proofs[1], &DEFAULT_PARAMS));
+
+        // Use proofs[0] again, so that adding proofs[2] evicts proofs[1].
+        assert!(cache.verify(4, &proofs[0], &DEFAULT_PARAMS).is_ok());
+        assert!(cache.verify(4, &proofs[2], &DEFAULT_PARAMS).is_ok());
+        assert_eq!(cache.len(), 2);
+        assert!(cache.contains(4, &proofs[0], &DEFAULT_PARAMS));
+        assert!(!cache.contains(3, &proofs[1], &DEFAULT_PARAMS));
+        assert!(cache.contains(4, &proofs[2], &DEFAULT_PARAMS));
+
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+}
@@ -0,0 +1,268 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Certification of in-range commitments by an issuer.
+//!
+//! A user holding a secret value asks an issuer with identity key
+//! `X = x*G` to certify that their commitment is to a value in
+//! `[0,3^n]`.  The protocol has one round:
+//!
+//! 1. the `CertificationUser` sends a `CertificationRequest`, which is a
+//!    rangeproof for the value;
+//! 2. the `CertificationIssuer` verifies the rangeproof's OR-proofs and
+//!    returns a `Certificate`, a Schnorr signature by `X` on the
+//!    commitment and `n`;
+//! 3. the user checks the certificate and keeps it with the opening.
+//!
+//! The issuer only ever sees the commitment and the rangeproof, so it
+//! learns nothing about the value beyond its being in range.  Anyone
+//! can later check the certificate against the commitment and `X`.
+
+use rand::Rng;
+
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::decaf::{CompressedDecaf, DecafPoint};
+use curve25519_dalek::decaf::vartime;
+
+use blinding::SecretBlinding;
+use challenges::certification_challenge;
+use encoding::{read_point, read_scalar};
+use errors::{DecodingError, VerifyError};
+use params::RangeProofParams;
+use transcript_rng::TranscriptRng;
+
+use super::RangeProof;
+use super::RANGEPROOF_MAX_N;
+
+/// The length in bytes of an encoded `Certificate`.
+pub const CERTIFICATE_LENGTH: usize = 64;
+
+/// A user's request for certification: a rangeproof for their value.
+pub struct CertificationRequest {
+    proof: RangeProof,
+}
+
+impl CertificationRequest {
+    /// Wrap a rangeproof received from a user as a request.
+    pub fn new(proof: RangeProof) -> CertificationRequest {
+        CertificationRequest { proof: proof }
+    }
+
+    /// The rangeproof for the user's value.
+    pub fn proof(&self) -> &RangeProof {
+        &self.proof
+    }
+}
+
+/// An issuer's certificate that a commitment is to a value in
+/// `[0,3^n]`: a Schnorr signature by the issuer on the commitment and
+/// `n`.
+pub struct Certificate {
+    R: DecafPoint,
+    s: Scalar,
+}
+
+impl Certificate {
+    /// Verify that the issuer with identity key `issuer` certified that
+    /// `commitment` is to a value in `[0,3^n]`.
+    #[must_use]
+    pub fn verify(
+        &self,
+        commitment: &DecafPoint,
+        n: usize,
+        issuer: &CompressedDecaf,
+        params: &RangeProofParams,
+    ) -> bool {
+        let X = match read_point(issuer.as_bytes()) {
+            Ok(X) => X,
+            Err(_) => return false,
+        };
+        let e = certification_challenge(issuer, n, &commitment.compress(), &self.R.compress());
+        let R = vartime::k_fold_scalar_mult(&[self.s, -&e], &[params.G().basepoint(), X]);
+
+        R.compress() == self.R.compress()
+    }
+
+    /// Encode the certificate as `R || s`, in 64 bytes.
+    pub fn to_bytes(&self) -> [u8; CERTIFICATE_LENGTH] {
+        let mut bytes = [0u8; CERTIFICATE_LENGTH];
+        bytes[..32].copy_from_slice(self.R.compress().as_bytes());
+        bytes[32..].copy_from_slice(self.s.as_bytes());
+        bytes
+    }
+
+    /// Decode a certificate encoded by `to_bytes`.
+    ///
+    /// # Returns
+    ///
+    /// A `DecodingError::InvalidLength` if `bytes` isn't 64 bytes long,
+    /// a `DecodingError::InvalidPoint` if `R` isn't canonically
+    /// encoded, or a `DecodingError::NonCanonicalScalar` if `s` isn't.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Certificate, DecodingError> {
+        if bytes.len() != CERTIFICATE_LENGTH {
+            return Err(DecodingError::InvalidLength);
+        }
+
+        Ok(Certificate { R: read_point(&bytes[..32])?, s: read_scalar(&bytes[32..])? })
+    }
+}
+
+/// The user's side of the certification protocol.
+pub struct CertificationUser<'a> {
+    params: &'a RangeProofParams,
+    n: usize,
+    commitment: DecafPoint,
+    blinding: SecretBlinding,
+}
+
+impl<'a> CertificationUser<'a> {
+    /// Begin certification of `value` in `[0,3^n]`.
+    ///
+    /// # Returns
+    ///
+    /// If `n > RANGEPROOF_MAX_N` or `value` is not in the range
+    /// `[0,3^n]`, return None.
+    ///
+    /// Otherwise, returns the user's state and the request to send to
+    /// the issuer.
+    pub fn new<T: Rng>(
+        n: usize,
+        value: u64,
+        params: &'a RangeProofParams,
+        csprng: &mut T,
+    ) -> Option<(CertificationUser<'a>, CertificationRequest)> {
+        let (proof, commitment, blinding) = RangeProof::create_with_params(n, value, params, csprng)?.into_parts();
+
+        Some((
+            CertificationUser { params: params, n: n, commitment: commitment, blinding: blinding },
+            CertificationRequest { proof: proof },
+        ))
+    }
+
+    /// The commitment being certified.
+    pub fn commitment(&self) -> &DecafPoint {
+        &self.commitment
+    }
+
+    /// Finish certification with the issuer's `certificate`.
+    ///
+    /// # Returns
+    ///
+    /// If the certificate verifies for the issuer with identity key
+    /// `issuer`, returns `Ok((commitment, blinding, certificate))`.
+    /// Otherwise, returns the user's state unchanged, so that it can
+    /// retry with another response.
+    pub fn finish(
+        self,
+        certificate: Certificate,
+        issuer: &CompressedDecaf,
+    ) -> Result<(DecafPoint, SecretBlinding, Certificate), CertificationUser<'a>> {
+        if !certificate.verify(&self.commitment, self.n, issuer, self.params) {
+            return Err(self);
+        }
+        Ok((self.commitment, self.blinding, certificate))
+    }
+}
+
+/// The issuer's side of the certification protocol.
+pub struct CertificationIssuer<'a> {
+    params: &'a RangeProofParams,
+    n: usize,
+    key: SecretBlinding,
+    X: CompressedDecaf,
+}
+
+impl<'a> CertificationIssuer<'a> {
+    /// Construct an issuer certifying commitments in `[0,3^n]` with the
+    /// identity key `key`.
+    ///
+    /// # Returns
+    ///
+    /// The issuer, or None if `n > RANGEPROOF_MAX_N`.
+    pub fn new(n: usize, key: SecretBlinding, params: &'a RangeProofParams) -> Option<CertificationIssuer<'a>> {
+        if n > RANGEPROOF_MAX_N {
+            return None;
+        }
+        let X = (params.G() * key.expose_secret()).compress();
+
+        Some(CertificationIssuer { params: params, n: n, key: key, X: X })
+    }
+
+    /// The identity key of the issuer, with which certificates verify.
+    pub fn identity(&self) -> &CompressedDecaf {
+        &self.X
+    }
+
+    /// Verify the rangeproof in `request` and, if it verifies, certify
+    /// its commitment.
+    ///
+    /// # Returns
+    ///
+    /// The commitment and its certificate, or the reason the
+    /// rangeproof failed to verify.
+    pub fn issue<T: Rng>(
+        &self,
+        request: &CertificationRequest,
+        csprng: &mut T,
+    ) -> Result<(DecafPoint, Certificate), VerifyError> {
+        let commitment = *request.proof.verify_checked(self.n, self.params)?.commitment();
+        let C = commitment.compress();
+
+        let mut rng = TranscriptRng::builder(b"certification")
+            .commit_transcript(b"X", self.X.as_bytes())
+            .commit_transcript(b"C", C.as_bytes())
+            .commit_witness(b"x", self.key.expose_secret().as_bytes())
+            .finalize(csprng);
+        let k = Scalar::random(&mut rng);
+        let R = self.params.G() * &k;
+
+        let e = certification_challenge(&self.X, self.n, &C, &R.compress());
+        let s = Scalar::multiply_add(&e, self.key.expose_secret(), &k);
+
+        Ok((commitment, Certificate { R: R, s: s }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::OsRng;
+
+    use DEFAULT_PARAMS;
+
+    #[test]
+    fn certify_commitment() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let issuer = CertificationIssuer::new(16, SecretBlinding::new(Scalar::random(&mut csprng)),
+                                              &DEFAULT_PARAMS).unwrap();
+        let (user, request) = CertificationUser::new(16, 13449261, &DEFAULT_PARAMS, &mut csprng).unwrap();
+
+        let (C, certificate) = issuer.issue(&request, &mut csprng).unwrap();
+        assert_eq!(C.compress(), user.commitment().compress());
+
+        let certificate = Certificate::from_bytes(&certificate.to_bytes()).unwrap();
+        let (C, _, certificate) = user.finish(certificate, issuer.identity()).ok().unwrap();
+        assert!(certificate.verify(&C, 16, issuer.identity(), &DEFAULT_PARAMS));
+        assert!(!certificate.verify(&C, 15, issuer.identity(), &DEFAULT_PARAMS));
+
+        // A request for the wrong range is refused.
+        let (_, request) = CertificationUser::new(8, 1, &DEFAULT_PARAMS, &mut csprng).unwrap();
+        assert_eq!(issuer.issue(&request, &mut csprng).err(), Some(VerifyError::WrongRingCount));
+
+        // Neither side can be configured beyond the largest supported bound.
+        assert!(CertificationIssuer::new(RANGEPROOF_MAX_N + 1, SecretBlinding::new(Scalar::random(&mut csprng)),
+                                         &DEFAULT_PARAMS).is_none());
+        assert!(CertificationUser::new(RANGEPROOF_MAX_N + 1, 0, &DEFAULT_PARAMS, &mut csprng).is_none());
+    }
+}
@@ -0,0 +1,326 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! The functions which derive the challenges of a rangeproof.
+//!
+//! These are exactly the functions used by the provers and the
+//! verifier in this crate, so that other implementations and auditors
+//! can recompute every challenge of a proof independently.
+//!
+//! For the `i`th ring, with commitment `C_i` and responses `s_1,i` and
+//! `s_2,i`, the verifier computes
+//!
+//! ```text
+//! e_1,i = ring_challenge(s_1,i * G - e_0   * (C_i - 3^i * H))
+//! e_2,i = ring_challenge(s_2,i * G - e_1,i * (C_i - 2 * 3^i * H))
+//! R_i   = e_2,i * C_i
+//! ```
+//!
+//! and accepts if `e_0 == e_0_challenge(R_0, ..., R_{n-1})`, where all
+//! points are passed in compressed form.  Proofs in the tree-hashed
+//! mode of `TreeRangeProof` instead use `e_0_tree_challenge`.
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use sha2::{Digest, Sha512};
+
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::decaf::CompressedDecaf;
+
+/// Derive a ring challenge `e_j,i` from the compressed point `P`, as
+/// the SHA-512 hash of its encoding reduced modulo the group order.
+pub fn ring_challenge(P: &CompressedDecaf) -> Scalar {
+    Scalar::hash_from_bytes::<Sha512>(P.as_bytes())
+}
+
+/// Derive the shared challenge `e_0` from the compressed points `R_i`
+/// closing each ring, as the SHA-512 hash of their concatenated
+/// encodings, in order, reduced modulo the group order.
+pub fn e_0_challenge<I>(R: I) -> Scalar
+    where I: IntoIterator<Item = CompressedDecaf>
+{
+    let mut e_0_hash = Sha512::default();
+    for R_i in R {
+        e_0_hash.input(R_i.as_bytes());
+    }
+    Scalar::from_hash(e_0_hash)
+}
+
+/// The version of the tree hash used by `e_0_tree_challenge`.
+pub const TREE_CHALLENGE_VERSION: u8 = 1;
+
+/// Hash a leaf or an inner node of the tree of `e_0_tree_challenge`,
+/// keeping the first 32 bytes of SHA-512.
+fn tree_hash(prefix: u8, left: &[u8], right: &[u8]) -> [u8; 32] {
+    let mut h = Sha512::default();
+    h.input(&[prefix]);
+    h.input(left);
+    h.input(right);
+    let mut node = [0u8; 32];
+    node.copy_from_slice(&h.result()[..32]);
+    node
+}
+
+/// Hash a pair of sibling nodes, or promote a node without a sibling.
+fn tree_parent(siblings: &[[u8; 32]]) -> [u8; 32] {
+    match siblings.len() {
+        2 => tree_hash(0x01, &siblings[0], &siblings[1]),
+        _ => siblings[0],
+    }
+}
+
+/// Derive the shared challenge `e_0` from the compressed points `R_i`
+/// by hashing them in a binary tree, rather than in sequence.
+///
+/// Each leaf is `H(0x00 || R_i)` and each inner node is
+/// `H(0x01 || left || right)`, where `H` is the first 32 bytes of
+/// SHA-512, and a node without a sibling is promoted to the next level
+/// unchanged.  The challenge is the SHA-512 hash of a domain separator,
+/// the version, the number of points and the root, reduced modulo the
+/// group order.  With the `parallel` feature, each level of the tree
+/// is hashed on `rayon`'s thread pool.
+pub fn e_0_tree_challenge(R: &[CompressedDecaf]) -> Scalar {
+    #[cfg(feature = "parallel")]
+    let mut level: Vec<[u8; 32]> = R.par_iter().map(|R_i| tree_hash(0x00, R_i.as_bytes(), &[])).collect();
+    #[cfg(not(feature = "parallel"))]
+    let mut level: Vec<[u8; 32]> = R.iter().map(|R_i| tree_hash(0x00, R_i.as_bytes(), &[])).collect();
+
+    while level.len() > 1 {
+        #[cfg(feature = "parallel")]
+        let parents = level.par_chunks(2).map(tree_parent).collect();
+        #[cfg(not(feature = "parallel"))]
+        let parents = level.chunks(2).map(tree_parent).collect();
+
+        level = parents;
+    }
+
+    let mut h = Sha512::default();
+    h.input(b"dalek-rangeproofs tree challenge");
+    h.input(&[TREE_CHALLENGE_VERSION]);
+    h.input(&(R.len() as u64).to_le_bytes());
+    for root in level.iter() {
+        h.input(root);
+    }
+    Scalar::from_hash(h)
+}
+
+/// Derive the challenge for the proof of knowledge of the blinding of
+/// the excess `X = C_lower + C_upper - (b - a)*H` of a two-sided
+/// rangeproof for `[a, b]`, given the compressed lower and upper
+/// commitments and the Schnorr nonce commitment `K`.
+pub fn two_sided_challenge(
+    a: u64,
+    b: u64,
+    C_lower: &CompressedDecaf,
+    C_upper: &CompressedDecaf,
+    K: &CompressedDecaf,
+) -> Scalar {
+    let mut h = Sha512::default();
+    h.input(b"dalek-rangeproofs two-sided");
+    h.input(&a.to_le_bytes());
+    h.input(&b.to_le_bytes());
+    h.input(C_lower.as_bytes());
+    h.input(C_upper.as_bytes());
+    h.input(K.as_bytes());
+    Scalar::from_hash(h)
+}
+
+/// Derive the challenge following the `i`th member of the ring of a
+/// disjunctive rangeproof over the public `ranges`, given the
+/// compressed commitments `D` and `E` to the hidden range's bounds and
+/// the compressed points `L` and `M` computed for the `i`th member.
+pub fn disjunctive_challenge(
+    ranges: &[(u64, u64)],
+    D: &CompressedDecaf,
+    E: &CompressedDecaf,
+    i: usize,
+    L: &CompressedDecaf,
+    M: &CompressedDecaf,
+) -> Scalar {
+    let mut h = Sha512::default();
+    h.input(b"dalek-rangeproofs disjunctive");
+    h.input(&(ranges.len() as u64).to_le_bytes());
+    for &(a, b) in ranges.iter() {
+        h.input(&a.to_le_bytes());
+        h.input(&b.to_le_bytes());
+    }
+    h.input(D.as_bytes());
+    h.input(E.as_bytes());
+    h.input(&(i as u64).to_le_bytes());
+    h.input(L.as_bytes());
+    h.input(M.as_bytes());
+    Scalar::from_hash(h)
+}
+
+/// Derive the challenge for a proof that the compressed commitment `C`
+/// opens to the public `value`, given the Schnorr nonce commitment `K`.
+pub fn public_value_challenge(C: &CompressedDecaf, value: u64, K: &CompressedDecaf) -> Scalar {
+    let mut h = Sha512::default();
+    h.input(b"dalek-rangeproofs public value");
+    h.input(C.as_bytes());
+    h.input(&value.to_le_bytes());
+    h.input(K.as_bytes());
+    Scalar::from_hash(h)
+}
+
+/// Derive the challenge for the proof of knowledge of the blinding of
+/// `Σ w_i*C_i - C_sum` in a weighted-sum rangeproof, given the public
+/// `weights`, the compressed `commitments` `C_i`, the compressed
+/// commitment `C_sum` of the rangeproof, and the nonce commitment `K`.
+pub fn weighted_sum_challenge(
+    weights: &[u64],
+    commitments: &[CompressedDecaf],
+    C_sum: &CompressedDecaf,
+    K: &CompressedDecaf,
+) -> Scalar {
+    let mut h = Sha512::default();
+    h.input(b"dalek-rangeproofs weighted sum");
+    h.input(&(weights.len() as u64).to_le_bytes());
+    for (w, C) in weights.iter().zip(commitments.iter()) {
+        h.input(&w.to_le_bytes());
+        h.input(C.as_bytes());
+    }
+    h.input(C_sum.as_bytes());
+    h.input(K.as_bytes());
+    Scalar::from_hash(h)
+}
+
+/// Derive the challenge for a Schnorr signature on `message` by the
+/// compressed kernel excess `X`, given the nonce commitment `R`.
+pub fn kernel_challenge(X: &CompressedDecaf, R: &CompressedDecaf, message: &[u8]) -> Scalar {
+    let mut h = Sha512::default();
+    h.input(b"dalek-rangeproofs kernel");
+    h.input(X.as_bytes());
+    h.input(R.as_bytes());
+    h.input(&(message.len() as u64).to_le_bytes());
+    h.input(message);
+    Scalar::from_hash(h)
+}
+
+/// Derive the challenge for a signature by the compressed identity key
+/// `X` of a prover on its rangeproof with `n` rings, given the nonce
+/// commitment `R` and the encoding of the rangeproof.
+pub fn issued_challenge(X: &CompressedDecaf, R: &CompressedDecaf, n: usize, proof: &[u8]) -> Scalar {
+    let mut h = Sha512::default();
+    h.input(b"dalek-rangeproofs issued");
+    h.input(X.as_bytes());
+    h.input(R.as_bytes());
+    h.input(&(n as u64).to_le_bytes());
+    h.input(proof);
+    Scalar::from_hash(h)
+}
+
+/// Derive the shared challenge `e_0` of the rangeproof signed by the
+/// compressed identity key `X` from the compressed points `R_i`
+/// closing each ring, so that the rangeproof only verifies under `X`.
+pub fn issued_e_0_challenge(X: &CompressedDecaf, R: &[CompressedDecaf]) -> Scalar {
+    let mut h = Sha512::default();
+    h.input(b"dalek-rangeproofs issued e_0");
+    h.input(X.as_bytes());
+    for R_i in R {
+        h.input(R_i.as_bytes());
+    }
+    Scalar::from_hash(h)
+}
+
+/// Derive the challenge for a certificate by the compressed identity
+/// key `X` of an issuer that the compressed commitment `C` is to a
+/// value in `[0,3^n]`, given the nonce commitment `R`.
+pub fn certification_challenge(X: &CompressedDecaf, n: usize, C: &CompressedDecaf, R: &CompressedDecaf) -> Scalar {
+    let mut h = Sha512::default();
+    h.input(b"dalek-rangeproofs certification");
+    h.input(X.as_bytes());
+    h.input(&(n as u64).to_le_bytes());
+    h.input(C.as_bytes());
+    h.input(R.as_bytes());
+    Scalar::from_hash(h)
+}
+
+/// Derive the challenge for a proof that the compressed points `X` and
+/// `Y` have the same discrete logarithm with respect to the compressed
+/// bases `A` and `B`, given the nonce commitments `K_A` and `K_B`.
+pub fn dleq_challenge(
+    A: &CompressedDecaf,
+    X: &CompressedDecaf,
+    B: &CompressedDecaf,
+    Y: &CompressedDecaf,
+    K_A: &CompressedDecaf,
+    K_B: &CompressedDecaf,
+) -> Scalar {
+    let mut h = Sha512::default();
+    h.input(b"dalek-rangeproofs dleq");
+    h.input(A.as_bytes());
+    h.input(X.as_bytes());
+    h.input(B.as_bytes());
+    h.input(Y.as_bytes());
+    h.input(K_A.as_bytes());
+    h.input(K_B.as_bytes());
+    Scalar::from_hash(h)
+}
+
+/// Derive the challenge for a proof of knowledge of the adaptor secret
+/// `t` of the compressed adaptor point `T = t*G`, given the nonce
+/// commitment `K`.
+pub fn adaptor_point_challenge(T: &CompressedDecaf, K: &CompressedDecaf) -> Scalar {
+    let mut h = Sha512::default();
+    h.input(b"dalek-rangeproofs adaptor point");
+    h.input(T.as_bytes());
+    h.input(K.as_bytes());
+    Scalar::from_hash(h)
+}
+
+/// Derive the challenge for a receipt signed by the compressed identity
+/// key `X` of a verifier, given the nonce commitment `R` and the
+/// encoding of the `statement` it signs.
+pub fn receipt_challenge(X: &CompressedDecaf, R: &CompressedDecaf, statement: &[u8]) -> Scalar {
+    let mut h = Sha512::default();
+    h.input(b"dalek-rangeproofs receipt");
+    h.input(X.as_bytes());
+    h.input(R.as_bytes());
+    h.input(&(statement.len() as u64).to_le_bytes());
+    h.input(statement);
+    Scalar::from_hash(h)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::OsRng;
+
+    use curve25519_dalek::decaf::DecafPoint;
+
+    use RangeProof;
+    use DEFAULT_PARAMS;
+
+    #[test]
+    fn recompute_challenges_of_proof() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let n = 16;
+        let proof =
+            RangeProof::create_with_params(n, 13449261, &DEFAULT_PARAMS, &mut csprng).unwrap().into_proof();
+
+        let G = DEFAULT_PARAMS.G().basepoint();
+        let powers = DEFAULT_PARAMS.H_powers();
+        let mut R = Vec::new();
+        for i in 0..n {
+            let P: DecafPoint = &(&G * &proof.s_1[i]) - &(&(&proof.C[i] - powers.power(i)) * &proof.e_0);
+            let e_1 = ring_challenge(&P.compress());
+            let P: DecafPoint = &(&G * &proof.s_2[i]) - &(&(&proof.C[i] - powers.double_power(i)) * &e_1);
+            let e_2 = ring_challenge(&P.compress());
+            R.push((&proof.C[i] * &e_2).compress());
+        }
+        assert!(e_0_challenge(R) == proof.e_0);
+    }
+}
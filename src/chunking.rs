@@ -0,0 +1,243 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Splitting encoded rangeproofs into MTU-sized chunks, for transports
+//! such as Bluetooth or NFC which can only carry small packets.
+//!
+//! Each chunk is a header followed by a slice of the self-describing
+//! encoding of the proof:
+//!
+//! ```text
+//! seq (2 bytes, big-endian) || total (2 bytes, big-endian) || tag (4 bytes) || payload
+//! ```
+//!
+//! where `tag` is the first four bytes of `Sha512(encoding)`.  The tag
+//! lets the receiver detect chunks from different proofs being mixed
+//! together, and detect corruption once all chunks have arrived.
+
+use sha2::Sha512;
+use sha2::Digest;
+
+use errors::ChunkingError;
+
+use super::RangeProof;
+
+/// The length in bytes of the header of each chunk.
+pub const CHUNK_HEADER_LENGTH: usize = 8;
+
+fn tag(bytes: &[u8]) -> [u8; 4] {
+    let mut h = Sha512::default();
+    h.input(bytes);
+
+    let mut tag = [0u8; 4];
+    tag.copy_from_slice(&h.result()[..4]);
+    tag
+}
+
+/// Split `bytes` into chunks of at most `mtu` bytes each, including
+/// the `CHUNK_HEADER_LENGTH`-byte header.
+///
+/// # Returns
+///
+/// A `ChunkingError::MtuTooSmall` if `mtu` leaves no room for a
+/// payload, or `ChunkingError::TooManyChunks` if more than `2^16 - 1`
+/// chunks would be needed.
+pub fn split(bytes: &[u8], mtu: usize) -> Result<Vec<Vec<u8>>, ChunkingError> {
+    if mtu <= CHUNK_HEADER_LENGTH {
+        return Err(ChunkingError::MtuTooSmall);
+    }
+    let payload_length = mtu - CHUNK_HEADER_LENGTH;
+    let total = (bytes.len() + payload_length - 1) / payload_length;
+    if total == 0 || total > 0xffff {
+        return Err(ChunkingError::TooManyChunks);
+    }
+    let tag = tag(bytes);
+
+    Ok(bytes.chunks(payload_length).enumerate().map(|(seq, payload)| {
+        let mut chunk = Vec::with_capacity(CHUNK_HEADER_LENGTH + payload.len());
+        chunk.push((seq >> 8) as u8);
+        chunk.push(seq as u8);
+        chunk.push((total >> 8) as u8);
+        chunk.push(total as u8);
+        chunk.extend_from_slice(&tag);
+        chunk.extend_from_slice(payload);
+        chunk
+    }).collect())
+}
+
+/// Collects chunks, in any order, until a complete encoding has been
+/// received.
+pub struct Reassembler {
+    total: usize,
+    tag: [u8; 4],
+    received: usize,
+    chunks: Vec<Option<Vec<u8>>>,
+}
+
+impl Reassembler {
+    /// Create a new `Reassembler`, which will accept chunks of whichever
+    /// encoding its first chunk belongs to.
+    pub fn new() -> Reassembler {
+        Reassembler { total: 0, tag: [0u8; 4], received: 0, chunks: Vec::new() }
+    }
+
+    /// Add a received `chunk`.
+    ///
+    /// Receiving the same chunk twice is permitted, as long as both
+    /// copies are identical.
+    ///
+    /// # Returns
+    ///
+    /// A `ChunkingError::InvalidChunk` if the chunk's header is
+    /// malformed, or a `ChunkingError::MismatchedChunk` if it belongs
+    /// to a different encoding than the chunks received so far.
+    pub fn push(&mut self, chunk: &[u8]) -> Result<(), ChunkingError> {
+        if chunk.len() <= CHUNK_HEADER_LENGTH {
+            return Err(ChunkingError::InvalidChunk);
+        }
+        let seq   = ((chunk[0] as usize) << 8) | chunk[1] as usize;
+        let total = ((chunk[2] as usize) << 8) | chunk[3] as usize;
+        if total == 0 || seq >= total {
+            return Err(ChunkingError::InvalidChunk);
+        }
+
+        if self.chunks.is_empty() {
+            self.total = total;
+            self.tag.copy_from_slice(&chunk[4..8]);
+            self.chunks = vec![None; total];
+        } else if total != self.total || &chunk[4..8] != &self.tag[..] {
+            return Err(ChunkingError::MismatchedChunk);
+        }
+
+        let payload = &chunk[CHUNK_HEADER_LENGTH..];
+        match self.chunks[seq] {
+            Some(ref existing) if &existing[..] != payload => {
+                return Err(ChunkingError::MismatchedChunk);
+            }
+            Some(_) => {}
+            None => {
+                self.chunks[seq] = Some(payload.to_vec());
+                self.received += 1;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns `true` once every chunk has been received.
+    pub fn is_complete(&self) -> bool {
+        !self.chunks.is_empty() && self.received == self.total
+    }
+
+    /// Concatenate the received chunks.
+    ///
+    /// # Returns
+    ///
+    /// A `ChunkingError::MissingChunks` if not all chunks have been
+    /// received, or a `ChunkingError::MismatchedChunk` if the
+    /// reassembled encoding doesn't match the tag in the headers.
+    pub fn finish(self) -> Result<Vec<u8>, ChunkingError> {
+        if !self.is_complete() {
+            return Err(ChunkingError::MissingChunks);
+        }
+
+        let mut bytes = Vec::new();
+        for chunk in self.chunks.into_iter() {
+            // All chunks are present, since the reassembler is complete.
+            if let Some(payload) = chunk {
+                bytes.extend_from_slice(&payload);
+            }
+        }
+
+        if tag(&bytes) != self.tag {
+            return Err(ChunkingError::MismatchedChunk);
+        }
+        Ok(bytes)
+    }
+}
+
+impl Default for Reassembler {
+    fn default() -> Reassembler {
+        Reassembler::new()
+    }
+}
+
+impl RangeProof {
+    /// Split the self-describing encoding of this rangeproof into
+    /// chunks of at most `mtu` bytes each.
+    ///
+    /// See `chunking::split` for the possible errors.
+    pub fn to_chunks(&self, mtu: usize) -> Result<Vec<Vec<u8>>, ChunkingError> {
+        split(&self.to_self_describing_bytes().map_err(ChunkingError::Decoding)?, mtu)
+    }
+
+    /// Reassemble and decode a rangeproof from the complete set of
+    /// `chunks` produced by `to_chunks`, in any order, returning
+    /// `(n, proof)`.
+    pub fn from_chunks<'a, I>(chunks: I) -> Result<(usize, RangeProof), ChunkingError>
+        where I: IntoIterator<Item = &'a [u8]>
+    {
+        let mut reassembler = Reassembler::new();
+        for chunk in chunks {
+            reassembler.push(chunk)?;
+        }
+        let bytes = reassembler.finish()?;
+
+        RangeProof::from_self_describing_bytes(&bytes).map_err(ChunkingError::Decoding)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::OsRng;
+
+    use DEFAULT_PARAMS;
+
+    #[test]
+    fn chunk_and_reassemble_out_of_order() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let n = 16;
+        let (proof, commitment, _) =
+            RangeProof::create_with_params(n, 13449261, &DEFAULT_PARAMS, &mut csprng).unwrap().into_parts();
+
+        let mut chunks = proof.to_chunks(20).unwrap();
+        assert!(chunks.iter().all(|chunk| chunk.len() <= 20));
+        chunks.reverse();
+
+        let (n_hat, decoded) =
+            RangeProof::from_chunks(chunks.iter().map(|chunk| &chunk[..])).unwrap();
+        assert_eq!(n_hat, n);
+        assert_eq!(decoded.verify_with_params(n, &DEFAULT_PARAMS).unwrap().compress(),
+                   commitment.compress());
+
+        assert_eq!(RangeProof::from_chunks(chunks[1..].iter().map(|chunk| &chunk[..])).err(),
+                   Some(ChunkingError::MissingChunks));
+        assert_eq!(proof.to_chunks(CHUNK_HEADER_LENGTH).err(), Some(ChunkingError::MtuTooSmall));
+    }
+
+    #[test]
+    fn mixed_chunks_are_rejected() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let a = RangeProof::create_with_params(4, 42, &DEFAULT_PARAMS, &mut csprng).unwrap().into_proof();
+        let b = RangeProof::create_with_params(4, 42, &DEFAULT_PARAMS, &mut csprng).unwrap().into_proof();
+        let a_chunks = a.to_chunks(64).unwrap();
+        let b_chunks = b.to_chunks(64).unwrap();
+
+        let mut reassembler = Reassembler::new();
+        reassembler.push(&a_chunks[0]).unwrap();
+        reassembler.push(&a_chunks[0]).unwrap();
+        assert_eq!(reassembler.push(&b_chunks[1]).err(), Some(ChunkingError::MismatchedChunk));
+    }
+}
@@ -0,0 +1,132 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! A `tokio_util` codec for framed rangeproofs, with the `tokio`
+//! feature.
+//!
+//! `RangeProofCodec` reads and writes the checksummed frames of
+//! `RangeProof::to_framed_bytes`, so that a `Framed` TCP stream yields
+//! `(n, proof)` pairs.  A frame's length prefix is checked against the
+//! codec's `DecodingLimits` as soon as it arrives, so a peer can't make
+//! the codec buffer more than the largest frame it accepts.
+
+use bytes::BytesMut;
+use tokio_util::codec::{Decoder, Encoder};
+
+use encoding::DecodingLimits;
+use errors::{CodecError, DecodingError};
+use framing::{split_frame, FRAME_HEADER_LENGTH};
+
+use super::RangeProof;
+
+/// Encodes and decodes rangeproofs in checksummed frames.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RangeProofCodec {
+    limits: DecodingLimits,
+}
+
+impl RangeProofCodec {
+    /// Construct a codec accepting frames within `limits`.
+    pub fn with_limits(limits: DecodingLimits) -> RangeProofCodec {
+        RangeProofCodec { limits: limits }
+    }
+}
+
+impl Decoder for RangeProofCodec {
+    type Item = (usize, RangeProof);
+    type Error = CodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<(usize, RangeProof)>, CodecError> {
+        if src.len() < FRAME_HEADER_LENGTH {
+            return Ok(None);
+        }
+        let mut length = [0u8; 4];
+        length.copy_from_slice(&src[..4]);
+        let length = u32::from_le_bytes(length) as usize;
+        if length > self.limits.max_bytes() {
+            return Err(CodecError::Decoding(DecodingError::LimitExceeded));
+        }
+        if src.len() < FRAME_HEADER_LENGTH + length {
+            src.reserve(FRAME_HEADER_LENGTH + length - src.len());
+            return Ok(None);
+        }
+
+        let frame = src.split_to(FRAME_HEADER_LENGTH + length);
+        let (payload, _) = split_frame(&frame, &self.limits)?;
+
+        Ok(Some(RangeProof::from_self_describing_bytes_with_limits(payload, &self.limits)?))
+    }
+}
+
+impl<'a> Encoder<&'a RangeProof> for RangeProofCodec {
+    type Error = CodecError;
+
+    fn encode(&mut self, proof: &'a RangeProof, dst: &mut BytesMut) -> Result<(), CodecError> {
+        dst.extend_from_slice(&proof.to_framed_bytes()?);
+        Ok(())
+    }
+}
+
+impl Encoder<RangeProof> for RangeProofCodec {
+    type Error = CodecError;
+
+    fn encode(&mut self, proof: RangeProof, dst: &mut BytesMut) -> Result<(), CodecError> {
+        <RangeProofCodec as Encoder<&RangeProof>>::encode(self, &proof, dst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::OsRng;
+
+    use DEFAULT_PARAMS;
+
+    #[test]
+    fn codec_decodes_partial_and_concatenated_frames() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let mut codec = RangeProofCodec::default();
+        let mut buffer = BytesMut::new();
+        let mut commitments = Vec::new();
+        for &n in [4, 16].iter() {
+            let (proof, C, _) =
+                RangeProof::create_with_params(n, 42, &DEFAULT_PARAMS, &mut csprng).unwrap().into_parts();
+            codec.encode(&proof, &mut buffer).unwrap();
+            commitments.push((n, C));
+        }
+
+        // Feed the frames in a byte at a time.
+        let mut received = BytesMut::new();
+        let mut decoded = Vec::new();
+        for byte in buffer.iter() {
+            received.extend_from_slice(&[*byte]);
+            if let Some(item) = codec.decode(&mut received).unwrap() {
+                decoded.push(item);
+            }
+        }
+        assert!(received.is_empty());
+        assert_eq!(decoded.len(), 2);
+        for (&(n, ref proof), &(m, C)) in decoded.iter().zip(commitments.iter()) {
+            assert_eq!(n, m);
+            assert_eq!(proof.verify_with_params(n, &DEFAULT_PARAMS).unwrap().compress(), C.compress());
+        }
+
+        // An oversized length prefix is rejected without buffering.
+        let mut oversized = BytesMut::from(&[0xffu8; FRAME_HEADER_LENGTH][..]);
+        match codec.decode(&mut oversized) {
+            Err(CodecError::Decoding(DecodingError::LimitExceeded)) => (),
+            _ => panic!("accepted an oversized frame"),
+        }
+    }
+}
@@ -0,0 +1,394 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Pedersen commitments, independently of any rangeproof.
+//!
+//! A Pedersen commitment to a value `v` with blinding `r` is the point
+//! `C = r*G + v*H`, for the generators `G` and `H` of some
+//! `RangeProofParams`.  Commitments are additively homomorphic: the
+//! sum of commitments is a commitment to the sum of the values, with
+//! the sum of the blindings.
+//!
+//! Everything needed to create, encode and combine commitments is in
+//! this module, so that a project can commit to values first and add
+//! rangeproofs for them later, using the same types.
+//!
+//! `PedersenCommitment`s implement `Sum`, so that balance equations can
+//! be written as they are in the math:
+//!
+//! ```text
+//! let inputs: PedersenCommitment = input_points.iter().sum();
+//! let outputs: PedersenCommitment = output_points.iter().sum();
+//! ```
+//!
+//! `Opening`s sum into an `Option<Opening>`, which is None if the
+//! values overflow a `u64`.
+//!
+//! A `PedersenCommitment` also supports subtraction and multiplication
+//! by a `Scalar`, which act on the value and blinding alike, and
+//! `verify_opening` checks it against a value and blinding in constant
+//! time.
+
+#[cfg(feature = "diesel")]
+use diesel;
+
+use std::iter::Sum;
+use std::ops::{Add, Mul, Sub};
+
+use rand::Rng;
+
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::curve::Identity;
+use curve25519_dalek::decaf::DecafPoint;
+
+use blinding::SecretBlinding;
+use ct;
+use encoding::read_point;
+use errors::DecodingError;
+use params::RangeProofParams;
+
+pub use balance::ExplicitCommitment;
+pub use opening::Opening;
+
+/// The length in bytes of an encoded commitment.
+pub const COMMITMENT_LENGTH: usize = 32;
+
+/// A Pedersen commitment `r*G + v*H`.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "diesel", derive(AsExpression, FromSqlRow))]
+#[cfg_attr(feature = "diesel", diesel(sql_type = diesel::sql_types::Binary))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PedersenCommitment(
+    #[cfg_attr(feature = "serde", serde(with = "::serialization::point"))]
+    DecafPoint
+);
+
+impl PedersenCommitment {
+    /// Wrap the point `C` as a commitment.
+    pub fn new(C: DecafPoint) -> PedersenCommitment {
+        PedersenCommitment(C)
+    }
+
+    /// The commitment as a point.
+    pub fn as_point(&self) -> &DecafPoint {
+        &self.0
+    }
+
+    /// Consume the commitment, returning the point.
+    pub fn into_point(self) -> DecafPoint {
+        self.0
+    }
+
+    /// Compress the commitment.
+    pub fn compress(&self) -> CompressedCommitment {
+        CompressedCommitment(to_bytes(&self.0))
+    }
+
+    /// Check that this is a commitment to `value` with `blinding`, in
+    /// constant time, as for `Opening::verify_against`.
+    #[must_use]
+    pub fn verify_opening(&self, value: u64, blinding: &SecretBlinding, params: &RangeProofParams) -> bool {
+        ct::points_equal(&commit(value, blinding, params), &self.0) == 1
+    }
+}
+
+/// A commitment in compressed form, as its 32-byte encoding.
+///
+/// Compressed commitments are totally ordered by their encodings,
+/// compared as byte strings, so that they can be used as keys of
+/// ordered maps and outputs can be sorted deterministically.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "diesel", derive(AsExpression, FromSqlRow))]
+#[cfg_attr(feature = "diesel", diesel(sql_type = diesel::sql_types::Binary))]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CompressedCommitment(
+    #[cfg_attr(feature = "serde", serde(with = "::serialization::bytes32"))]
+    pub [u8; COMMITMENT_LENGTH]
+);
+
+impl CompressedCommitment {
+    /// The encoding of the commitment.
+    pub fn as_bytes(&self) -> &[u8; COMMITMENT_LENGTH] {
+        &self.0
+    }
+
+    /// Decompress the commitment.
+    ///
+    /// # Returns
+    ///
+    /// A `DecodingError::InvalidPoint` if the encoding isn't the
+    /// canonical encoding of a point.
+    pub fn decompress(&self) -> Result<PedersenCommitment, DecodingError> {
+        Ok(PedersenCommitment(read_point(&self.0)?))
+    }
+}
+
+impl<'a> From<&'a DecafPoint> for CompressedCommitment {
+    fn from(C: &'a DecafPoint) -> CompressedCommitment {
+        CompressedCommitment(to_bytes(C))
+    }
+}
+
+impl From<DecafPoint> for PedersenCommitment {
+    fn from(C: DecafPoint) -> PedersenCommitment {
+        PedersenCommitment(C)
+    }
+}
+
+impl<'a, 'b> Add<&'b PedersenCommitment> for &'a PedersenCommitment {
+    type Output = PedersenCommitment;
+
+    fn add(self, other: &'b PedersenCommitment) -> PedersenCommitment {
+        PedersenCommitment(&self.0 + &other.0)
+    }
+}
+
+impl Add<PedersenCommitment> for PedersenCommitment {
+    type Output = PedersenCommitment;
+
+    fn add(self, other: PedersenCommitment) -> PedersenCommitment {
+        &self + &other
+    }
+}
+
+impl<'a, 'b> Sub<&'b PedersenCommitment> for &'a PedersenCommitment {
+    type Output = PedersenCommitment;
+
+    fn sub(self, other: &'b PedersenCommitment) -> PedersenCommitment {
+        PedersenCommitment(&self.0 - &other.0)
+    }
+}
+
+impl Sub<PedersenCommitment> for PedersenCommitment {
+    type Output = PedersenCommitment;
+
+    fn sub(self, other: PedersenCommitment) -> PedersenCommitment {
+        &self - &other
+    }
+}
+
+/// Multiplying a commitment to `v` with blinding `r` by `k` gives a
+/// commitment to `k*v` with blinding `k*r`.
+impl<'a, 'b> Mul<&'b Scalar> for &'a PedersenCommitment {
+    type Output = PedersenCommitment;
+
+    fn mul(self, k: &'b Scalar) -> PedersenCommitment {
+        PedersenCommitment(&self.0 * k)
+    }
+}
+
+impl Mul<Scalar> for PedersenCommitment {
+    type Output = PedersenCommitment;
+
+    fn mul(self, k: Scalar) -> PedersenCommitment {
+        &self * &k
+    }
+}
+
+impl Sum<PedersenCommitment> for PedersenCommitment {
+    fn sum<I: Iterator<Item = PedersenCommitment>>(iter: I) -> PedersenCommitment {
+        iter.fold(PedersenCommitment(DecafPoint::identity()), |acc, C| &acc + &C)
+    }
+}
+
+impl<'a> Sum<&'a PedersenCommitment> for PedersenCommitment {
+    fn sum<I: Iterator<Item = &'a PedersenCommitment>>(iter: I) -> PedersenCommitment {
+        iter.fold(PedersenCommitment(DecafPoint::identity()), |acc, C| &acc + C)
+    }
+}
+
+/// Sum raw points into a commitment, so that `points.iter().sum()`
+/// works without wrapping each point first.
+impl<'a> Sum<&'a DecafPoint> for PedersenCommitment {
+    fn sum<I: Iterator<Item = &'a DecafPoint>>(iter: I) -> PedersenCommitment {
+        PedersenCommitment(iter.fold(DecafPoint::identity(), |acc, C| &acc + C))
+    }
+}
+
+/// Commit to `value` with `blinding`, as `blinding*G + value*H`.
+pub fn commit(value: u64, blinding: &SecretBlinding, params: &RangeProofParams) -> DecafPoint {
+    &(params.G() * blinding.expose_secret()) + &value_point(value, params)
+}
+
+/// Commit to `value` with a random blinding, returning the commitment
+/// and its opening.
+pub fn commit_random<T: Rng>(value: u64, params: &RangeProofParams, csprng: &mut T) -> (DecafPoint, Opening) {
+    let opening = Opening::new(value, SecretBlinding::new(Scalar::random(csprng)));
+    (opening.commitment(params), opening)
+}
+
+/// Returns `value*H`.
+pub fn value_point(value: u64, params: &RangeProofParams) -> DecafPoint {
+    params.H_table() * &Scalar::from_u64(value)
+}
+
+/// Returns `C + value*H`, a commitment to `v + value` if `C` is a
+/// commitment to `v`.
+pub fn add_value(C: &DecafPoint, value: u64, params: &RangeProofParams) -> DecafPoint {
+    C + &value_point(value, params)
+}
+
+/// Returns `C - value*H`, a commitment to `v - value` if `C` is a
+/// commitment to `v`.
+pub fn sub_value(C: &DecafPoint, value: u64, params: &RangeProofParams) -> DecafPoint {
+    C - &value_point(value, params)
+}
+
+/// Returns `k*C`, a commitment to `k*v` with blinding `k*r` if `C` is
+/// a commitment to `v` with blinding `r`.
+pub fn scale(C: &DecafPoint, k: u64) -> DecafPoint {
+    C * &Scalar::from_u64(k)
+}
+
+/// Returns the sum of the `commitments`.
+pub fn sum(commitments: &[DecafPoint]) -> DecafPoint {
+    commitments.iter().sum::<PedersenCommitment>().into_point()
+}
+
+/// Encode a commitment as its compressed point.
+pub fn to_bytes(C: &DecafPoint) -> [u8; COMMITMENT_LENGTH] {
+    *C.compress().as_bytes()
+}
+
+/// Decode a commitment encoded by `to_bytes`.
+///
+/// # Returns
+///
+/// A `DecodingError::InvalidLength` if `bytes` isn't 32 bytes long, or
+/// a `DecodingError::InvalidPoint` if it isn't the canonical encoding
+/// of a point.
+pub fn from_bytes(bytes: &[u8]) -> Result<DecafPoint, DecodingError> {
+    if bytes.len() != COMMITMENT_LENGTH {
+        return Err(DecodingError::InvalidLength);
+    }
+    read_point(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::OsRng;
+
+    #[cfg(feature = "serde")]
+    use serde_cbor;
+    #[cfg(feature = "serde")]
+    use serde_json;
+
+    use DEFAULT_PARAMS;
+
+    #[test]
+    fn commitments_are_homomorphic() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let (C_1, opening_1) = commit_random(30, &DEFAULT_PARAMS, &mut csprng);
+        let (C_2, opening_2) = commit_random(12, &DEFAULT_PARAMS, &mut csprng);
+        assert_eq!(commit(30, opening_1.blinding(), &DEFAULT_PARAMS).compress(), C_1.compress());
+
+        let blinding = SecretBlinding::new(opening_1.blinding().expose_secret()
+                                           + opening_2.blinding().expose_secret());
+        assert_eq!(sum(&[C_1, C_2]).compress(), commit(42, &blinding, &DEFAULT_PARAMS).compress());
+        assert_eq!(sub_value(&add_value(&C_1, 5, &DEFAULT_PARAMS), 5, &DEFAULT_PARAMS).compress(),
+                   C_1.compress());
+
+        let blinding = SecretBlinding::new(opening_1.blinding().expose_secret() * &Scalar::from_u64(3));
+        assert_eq!(scale(&C_1, 3).compress(), commit(90, &blinding, &DEFAULT_PARAMS).compress());
+
+        assert_eq!(from_bytes(&to_bytes(&C_1)).unwrap().compress(), C_1.compress());
+        assert_eq!(from_bytes(&[0u8; 31]).err(), Some(DecodingError::InvalidLength));
+    }
+
+    #[test]
+    fn compressed_commitments_are_ordered_by_encoding() {
+        use std::collections::BTreeMap;
+
+        let mut csprng = OsRng::new().unwrap();
+
+        let mut outputs: Vec<CompressedCommitment> = (0..8).map(|value| {
+            CompressedCommitment::from(&commit_random(value, &DEFAULT_PARAMS, &mut csprng).0)
+        }).collect();
+        outputs.sort();
+        for pair in outputs.windows(2) {
+            assert!(pair[0].as_bytes() < pair[1].as_bytes());
+        }
+
+        let mut values = BTreeMap::new();
+        for (value, C) in outputs.iter().enumerate() {
+            values.insert(*C, value);
+        }
+        assert_eq!(values.get(&outputs[3]), Some(&3));
+
+        let C = PedersenCommitment::new(commit_random(5, &DEFAULT_PARAMS, &mut csprng).0);
+        assert_eq!(C.compress().decompress().unwrap().compress(), C.compress());
+    }
+
+    #[test]
+    fn commitment_arithmetic_and_openings() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let (C_1, opening_1) = commit_random(30, &DEFAULT_PARAMS, &mut csprng);
+        let (C_2, opening_2) = commit_random(12, &DEFAULT_PARAMS, &mut csprng);
+        let (C_1, C_2) = (PedersenCommitment::new(C_1), PedersenCommitment::new(C_2));
+        assert!(C_1.verify_opening(30, opening_1.blinding(), &DEFAULT_PARAMS));
+        assert!(!C_1.verify_opening(31, opening_1.blinding(), &DEFAULT_PARAMS));
+
+        let blinding = SecretBlinding::new(opening_1.blinding().expose_secret()
+                                           - opening_2.blinding().expose_secret());
+        assert!((C_1 - C_2).verify_opening(18, &blinding, &DEFAULT_PARAMS));
+        assert_eq!((&(&C_1 + &C_2) - &C_2).into_point().compress(), C_1.into_point().compress());
+
+        let k = Scalar::from_u64(3);
+        let blinding = SecretBlinding::new(opening_2.blinding().expose_secret() * &k);
+        assert!((C_2 * k).verify_opening(36, &blinding, &DEFAULT_PARAMS));
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serialize_commitments() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let C = PedersenCommitment::new(commit_random(5, &DEFAULT_PARAMS, &mut csprng).0);
+        let json = serde_json::to_string(&C).unwrap();
+        let decoded: PedersenCommitment = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.compress(), C.compress());
+
+        let cbor = serde_cbor::to_vec(&C.compress()).unwrap();
+        let decoded: CompressedCommitment = serde_cbor::from_slice(&cbor).unwrap();
+        assert_eq!(decoded, C.compress());
+    }
+
+    #[test]
+    fn sum_commitments_and_openings() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let (points, openings): (Vec<DecafPoint>, Vec<Opening>) =
+            [5, 6, 7].iter().map(|&value| commit_random(value, &DEFAULT_PARAMS, &mut csprng)).unzip();
+
+        let total: Opening = openings.iter().sum::<Option<Opening>>().unwrap();
+        assert_eq!(total.value(), 18);
+
+        let C: PedersenCommitment = points.iter().sum();
+        assert_eq!(C.as_point().compress(), total.commitment(&DEFAULT_PARAMS).compress());
+
+        let wrapped: Vec<PedersenCommitment> = points.iter().map(|&C_i| C_i.into()).collect();
+        let C_2: PedersenCommitment = wrapped.into_iter().sum();
+        assert_eq!(C_2.into_point().compress(), C.as_point().compress());
+
+        let empty: Option<Opening> = Vec::<Opening>::new().into_iter().sum();
+        assert_eq!(empty.unwrap().value(), 0);
+
+        let big = vec![Opening::new(u64::max_value(), SecretBlinding::new(Scalar::zero())),
+                       Opening::new(1, SecretBlinding::new(Scalar::zero()))];
+        assert!(big.iter().sum::<Option<Opening>>().is_none());
+        assert!(big.into_iter().sum::<Option<Opening>>().is_none());
+    }
+}
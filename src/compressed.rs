@@ -0,0 +1,142 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! zstd-compressed containers, with the `zstd` feature.
+//!
+//! The points and scalars of a proof are incompressible, but a
+//! container of millions of entries for archival storage has enough
+//! repeated structure, in its entry headers and in proofs with equal
+//! `n`, that compressing it is worthwhile.  A compressed container is
+//! a single zstd frame whose content is an ordinary container, written
+//! and read as a stream, so that neither side holds the whole
+//! container in memory.
+//!
+//! The frame includes zstd's content checksum, which is checked when
+//! the end of the frame is read, so that a corrupted archive is
+//! reported as a `ContainerError::Io` rather than being silently
+//! truncated.
+
+use std::io::{self, BufReader, Read, Write};
+
+use zstd::stream::read::Decoder;
+use zstd::stream::write::Encoder;
+
+use curve25519_dalek::decaf::DecafPoint;
+
+use container::{ContainerReader, ContainerWriter, StreamSummary};
+use errors::ContainerError;
+
+use super::RangeProof;
+use super::RangeProofParams;
+
+/// The zstd compression level used by `CompressedContainerWriter::new`.
+pub const DEFAULT_COMPRESSION_LEVEL: i32 = 19;
+
+/// Writes a compressed container to an underlying `Write`r, one entry
+/// at a time.
+pub struct CompressedContainerWriter<W: Write> {
+    writer: ContainerWriter<Encoder<'static, W>>,
+}
+
+impl<W: Write> CompressedContainerWriter<W> {
+    /// Begin a compressed container of proofs made with `params`, at
+    /// `DEFAULT_COMPRESSION_LEVEL`.
+    pub fn new(writer: W, params: &RangeProofParams) -> io::Result<CompressedContainerWriter<W>> {
+        CompressedContainerWriter::with_level(writer, params, DEFAULT_COMPRESSION_LEVEL)
+    }
+
+    /// Begin a compressed container of proofs made with `params`, at
+    /// the zstd compression `level`.
+    pub fn with_level(writer: W, params: &RangeProofParams, level: i32) -> io::Result<CompressedContainerWriter<W>> {
+        let mut encoder = Encoder::new(writer, level)?;
+        encoder.include_checksum(true)?;
+
+        Ok(CompressedContainerWriter { writer: ContainerWriter::new(encoder, params)? })
+    }
+
+    /// Append a `proof` of the claimed `commitment` to the container.
+    ///
+    /// # Returns
+    ///
+    /// As for `ContainerWriter::write`.
+    pub fn write(&mut self, commitment: &DecafPoint, proof: &RangeProof) -> io::Result<()> {
+        self.writer.write(commitment, proof)
+    }
+
+    /// End the zstd frame and return the underlying writer.
+    pub fn finish(self) -> io::Result<W> {
+        self.writer.finish()?.finish()
+    }
+}
+
+/// Read the header of a compressed container from `reader`, returning
+/// a `ContainerReader` which decompresses its entries as it reads them.
+pub fn compressed_container_reader<R: Read>(
+    reader: R,
+) -> Result<ContainerReader<Decoder<'static, BufReader<R>>>, ContainerError> {
+    ContainerReader::new(Decoder::new(reader)?)
+}
+
+impl RangeProof {
+    /// Verify every entry of the compressed container read from
+    /// `reader`, in bounded memory, as for `verify_batch_from_reader`.
+    pub fn verify_batch_from_compressed_reader<R: Read>(
+        reader: R,
+        params: &RangeProofParams,
+    ) -> Result<StreamSummary, ContainerError> {
+        RangeProof::verify_batch_from_reader(Decoder::new(reader)?, params)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::OsRng;
+
+    use container::ContainerEntry;
+    use DEFAULT_PARAMS;
+
+    #[test]
+    fn compressed_container_roundtrip() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let mut writer = CompressedContainerWriter::new(Vec::new(), &DEFAULT_PARAMS).unwrap();
+        for value in 0..8 {
+            let (proof, C, _) = RangeProof::create_with_params(4, value, &DEFAULT_PARAMS, &mut csprng)
+                .unwrap().into_parts();
+            writer.write(&C, &proof).unwrap();
+        }
+        let bytes = writer.finish().unwrap();
+
+        let reader = compressed_container_reader(&bytes[..]).unwrap();
+        assert_eq!(reader.params_id(), DEFAULT_PARAMS.id());
+        let entries: Vec<ContainerEntry> = reader.map(|entry| entry.unwrap()).collect();
+        assert_eq!(entries.len(), 8);
+        assert!(entries.iter().all(|entry| entry.verify(&DEFAULT_PARAMS)));
+
+        let summary = RangeProof::verify_batch_from_compressed_reader(&bytes[..], &DEFAULT_PARAMS)
+            .unwrap();
+        assert_eq!(summary.entries, 8);
+        assert!(summary.all_verified());
+
+        // Corruption is detected, at the latest by the checksum.
+        let mut corrupted = bytes.clone();
+        let middle = corrupted.len() / 2;
+        corrupted[middle] ^= 1;
+        let failed = match compressed_container_reader(&corrupted[..]) {
+            Err(_) => true,
+            Ok(mut reader) => reader.any(|entry| entry.is_err()),
+        };
+        assert!(failed);
+    }
+}
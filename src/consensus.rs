@@ -0,0 +1,327 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Frozen consensus rules for rangeproofs.
+//!
+//! Nodes of a chain which disagree about whether a proof is valid will
+//! fork, so a chain must pin exactly which encodings it accepts and how
+//! it verifies them.  Each `ConsensusVersion` names a fixed
+//! specification of the encoding, the challenge derivation and the
+//! validation rules.  A version's behaviour never changes: any change
+//! to what is accepted, however minor, is made in a new version, and a
+//! chain moves to it only at an agreed height.
+//!
+//! # Version 1
+//!
+//! Encoding.  A proof with `n` rings is exactly `32(1+3n)` bytes,
+//!
+//! ```text
+//! e_0 || C_0 || s_1,0 || s_2,0 || ... || C_{n-1} || s_1,n-1 || s_2,n-1
+//! ```
+//!
+//! where each `C_i` is the canonical encoding of a Decaf point and each
+//! scalar is 32 little-endian bytes of an integer less than the group
+//! order `l`.  `n` is fixed by the context, never inferred from the
+//! length, and `0 <= n <= 41`.
+//!
+//! Challenges.  For each ring `i`, with `H_i = 3^i*H`,
+//!
+//! ```text
+//! R_i,1 = s_1,i*G - e_0*(C_i - H_i)
+//! e_i,1 = Hs(R_i,1)
+//! R_i,2 = s_2,i*G - e_i,1*(C_i - 2*H_i)
+//! e_i,2 = Hs(R_i,2)
+//! R_i   = e_i,2*C_i
+//! e_0   = Hs(R_0 || ... || R_{n-1})
+//! ```
+//!
+//! where `Hs` is SHA-512 of the compressed points, reduced modulo `l`.
+//!
+//! Validation.  A proof is valid for `n` and parameters `(G, H)` if and
+//! only if it decodes as above and the recomputed `e_0` equals the
+//! encoded one.  It proves that `C = Σ C_i` commits to a value in
+//! `[0,3^n-1]`.  The parameters are part of the chain's configuration
+//! and are identified by `RangeProofParams::id`.
+//!
+//! Each version is implemented by its own decoder and verifier in this
+//! module, which share only the challenge functions, pinned by test
+//! vectors, with the rest of the library, so that changes elsewhere
+//! can't change what a version accepts.
+
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::curve::Identity;
+use curve25519_dalek::decaf::{CompressedDecaf, DecafPoint};
+use curve25519_dalek::decaf::vartime;
+
+use challenges::{e_0_challenge, ring_challenge};
+use errors::{ConsensusError, DecodingError, VerifyError};
+use params::RangeProofParams;
+use verification::VerifiedCommitment;
+
+use super::RangeProof;
+
+/// The largest number of rings allowed by version 1.
+const V1_MAX_N: usize = 41;
+
+/// Decode a scalar as specified by version 1.
+fn v1_scalar(bytes: &[u8]) -> Result<Scalar, DecodingError> {
+    let mut s = [0u8; 32];
+    s.copy_from_slice(bytes);
+    let s = Scalar(s);
+
+    // s is canonical if and only if it is unchanged by reduction.
+    if Scalar::multiply_add(&Scalar::one(), &s, &Scalar::zero()) != s {
+        return Err(DecodingError::NonCanonicalScalar);
+    }
+    Ok(s)
+}
+
+/// Decode a point as specified by version 1.
+fn v1_point(bytes: &[u8]) -> Result<DecafPoint, DecodingError> {
+    let mut p = [0u8; 32];
+    p.copy_from_slice(bytes);
+    let point = CompressedDecaf(p).decompress().ok_or(DecodingError::InvalidPoint)?;
+
+    if point.compress().as_bytes() != &p {
+        return Err(DecodingError::InvalidPoint);
+    }
+    Ok(point)
+}
+
+/// Decode a proof with exactly `n` rings as specified by version 1.
+fn v1_decode(bytes: &[u8], n: usize) -> Result<RangeProof, ConsensusError> {
+    if n > V1_MAX_N {
+        return Err(ConsensusError::InvalidRingCount(n));
+    }
+    let length = 32 * (1 + 3 * n);
+    if bytes.len() < length {
+        return Err(DecodingError::InvalidLength.into());
+    } else if bytes.len() > length {
+        return Err(DecodingError::TrailingBytes.into());
+    }
+
+    let e_0 = v1_scalar(&bytes[..32])?;
+    let mut C   = Vec::with_capacity(n);
+    let mut s_1 = Vec::with_capacity(n);
+    let mut s_2 = Vec::with_capacity(n);
+
+    for ring in bytes[32..].chunks(96) {
+        C.push(v1_point(&ring[0..32])?);
+        s_1.push(v1_scalar(&ring[32..64])?);
+        s_2.push(v1_scalar(&ring[64..96])?);
+    }
+
+    Ok(RangeProof { e_0: e_0, C: C, s_1: s_1, s_2: s_2 })
+}
+
+/// Verify a proof decoded by `v1_decode` with `n` rings, as specified
+/// by version 1.
+fn v1_verify(proof: &RangeProof, n: usize, params: &RangeProofParams) -> Result<DecafPoint, VerifyError> {
+    if n != proof.C.len() || n != proof.s_1.len() || n != proof.s_2.len() || n > params.H_powers().len() {
+        return Err(VerifyError::WrongRingCount);
+    }
+
+    let G = params.G().basepoint();
+    let powers = params.H_powers();
+    let mut C = DecafPoint::identity();
+    let mut R: Vec<CompressedDecaf> = Vec::with_capacity(n);
+
+    for i in 0..n {
+        let R_1 = vartime::k_fold_scalar_mult(&[proof.s_1[i], -&proof.e_0],
+                                              &[G, &proof.C[i] - powers.power(i)]);
+        let e_1 = ring_challenge(&R_1.compress());
+        let R_2 = vartime::k_fold_scalar_mult(&[proof.s_2[i], -&e_1],
+                                              &[G, &proof.C[i] - powers.double_power(i)]);
+        let e_2 = ring_challenge(&R_2.compress());
+        R.push((&proof.C[i] * &e_2).compress());
+        C = &C + &proof.C[i];
+    }
+
+    if e_0_challenge(R) == proof.e_0 {
+        Ok(C)
+    } else {
+        Err(VerifyError::InvalidProof)
+    }
+}
+
+/// A version of the frozen consensus rules.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub enum ConsensusVersion {
+    /// Version 1, as specified in the module documentation.
+    V1,
+}
+
+impl ConsensusVersion {
+    /// The most recent version of the rules.
+    pub const LATEST: ConsensusVersion = ConsensusVersion::V1;
+
+    /// The version with the given number.
+    ///
+    /// # Returns
+    ///
+    /// A `ConsensusError::UnsupportedVersion` if this library doesn't
+    /// implement the version.
+    pub fn from_u8(version: u8) -> Result<ConsensusVersion, ConsensusError> {
+        match version {
+            1 => Ok(ConsensusVersion::V1),
+            v => Err(ConsensusError::UnsupportedVersion(v)),
+        }
+    }
+
+    /// The number of this version.
+    pub fn to_u8(&self) -> u8 {
+        match *self {
+            ConsensusVersion::V1 => 1,
+        }
+    }
+
+    /// Encode `proof` under these rules.
+    pub fn encode(&self, proof: &RangeProof) -> Vec<u8> {
+        match *self {
+            ConsensusVersion::V1 => proof.to_bytes(),
+        }
+    }
+
+    /// Decode a proof with exactly `n` rings under these rules.
+    ///
+    /// # Returns
+    ///
+    /// A `ConsensusError::InvalidRingCount` if the rules don't allow
+    /// proofs with `n` rings, and a `ConsensusError::Decoding` if
+    /// `bytes` isn't a valid encoding.
+    pub fn decode(&self, bytes: &[u8], n: usize) -> Result<RangeProof, ConsensusError> {
+        match *self {
+            ConsensusVersion::V1 => v1_decode(bytes, n),
+        }
+    }
+
+    /// Decode and verify a proof with exactly `n` rings under these
+    /// rules.
+    ///
+    /// A ring count which the rules don't allow is reported as such,
+    /// rather than as a proof with the wrong number of rings, since it
+    /// may come from the network.
+    ///
+    /// # Returns
+    ///
+    /// The verified commitment, or why the proof is invalid.
+    pub fn verify(
+        &self,
+        bytes: &[u8],
+        n: usize,
+        params: &RangeProofParams,
+    ) -> Result<VerifiedCommitment, ConsensusError> {
+        match *self {
+            ConsensusVersion::V1 => {
+                let proof = v1_decode(bytes, n)?;
+                Ok(VerifiedCommitment(v1_verify(&proof, n, params)?))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::OsRng;
+
+    use RANGEPROOF_MAX_N;
+    use DEFAULT_PARAMS;
+
+    #[test]
+    fn v1_encoding_is_frozen() {
+        let proof = RangeProof {
+            e_0: Scalar::one(),
+            C: vec![DecafPoint::identity()],
+            s_1: vec![Scalar::zero()],
+            s_2: vec![Scalar::one()],
+        };
+        let mut expected = vec![0u8; 128];
+        expected[0] = 1;
+        expected[96] = 1;
+        assert_eq!(ConsensusVersion::V1.encode(&proof), expected);
+    }
+
+    #[test]
+    fn v1_challenges_are_frozen() {
+        assert!(ring_challenge(&CompressedDecaf([0u8; 32])) == Scalar([
+            157,  87,  68, 148, 160,  45, 114, 245, 255,  49,  28, 240, 251, 132,  77,  15,
+            221,  97,   3, 177, 114,  85,  39,  78,   2, 155, 222, 237, 114,   7, 212,   9,
+        ]));
+
+        let mut R_1 = [0u8; 32];
+        R_1[0] = 1;
+        let e_0 = e_0_challenge(vec![CompressedDecaf([0u8; 32]), CompressedDecaf(R_1)]);
+        assert!(e_0 == Scalar([
+            243,  42, 136,  71, 235,  70, 168,  95,  55,  76, 158,  33,  75, 110, 174, 175,
+             47, 167, 135, 171,  90,  83, 108,  84,  73, 130, 219, 230,   5, 181, 236,   3,
+        ]));
+    }
+
+    #[test]
+    fn v1_validation() {
+        let mut csprng = OsRng::new().unwrap();
+
+        assert_eq!(ConsensusVersion::from_u8(1).unwrap(), ConsensusVersion::LATEST);
+        assert_eq!(ConsensusVersion::LATEST.to_u8(), 1);
+        assert!(ConsensusVersion::from_u8(2).is_err());
+
+        let (proof, C, _) = RangeProof::create_with_params(16, 1000, &DEFAULT_PARAMS, &mut csprng).unwrap().into_parts();
+        let mut bytes = ConsensusVersion::V1.encode(&proof);
+        let verified = ConsensusVersion::V1.verify(&bytes, 16, &DEFAULT_PARAMS).unwrap();
+        assert_eq!(verified.commitment().compress(), C.compress());
+
+        match ConsensusVersion::V1.verify(&bytes, 42, &DEFAULT_PARAMS) {
+            Err(ConsensusError::InvalidRingCount(42)) => (),
+            _ => panic!("accepted a proof with too many rings"),
+        }
+        match ConsensusVersion::V1.verify(&bytes, 15, &DEFAULT_PARAMS) {
+            Err(ConsensusError::Decoding(DecodingError::TrailingBytes)) => (),
+            _ => panic!("accepted trailing bytes"),
+        }
+
+        bytes[0] ^= 1;
+        match ConsensusVersion::V1.verify(&bytes, 16, &DEFAULT_PARAMS) {
+            Err(ConsensusError::Verify(VerifyError::InvalidProof)) => (),
+            _ => panic!("accepted an invalid proof"),
+        }
+    }
+
+    #[test]
+    fn v1_agrees_with_rangeproof() {
+        let mut csprng = OsRng::new().unwrap();
+
+        assert_eq!(V1_MAX_N, RANGEPROOF_MAX_N);
+
+        for n in [0, 1, 16, RANGEPROOF_MAX_N].iter().cloned() {
+            let (proof, C, _) =
+                RangeProof::create_with_params(n, 0, &DEFAULT_PARAMS, &mut csprng).unwrap().into_parts();
+            let bytes = ConsensusVersion::V1.encode(&proof);
+            let decoded = ConsensusVersion::V1.decode(&bytes, n).unwrap();
+            assert_eq!(decoded.to_bytes(), bytes);
+
+            let verified = ConsensusVersion::V1.verify(&bytes, n, &DEFAULT_PARAMS).unwrap();
+            assert_eq!(verified.commitment().compress(), C.compress());
+            assert_eq!(proof.verify_with_params(n, &DEFAULT_PARAMS).unwrap().compress(), C.compress());
+        }
+
+        // A non-canonical scalar is rejected when decoding.
+        let (proof, _, _) = RangeProof::create_with_params(4, 80, &DEFAULT_PARAMS, &mut csprng).unwrap().into_parts();
+        let mut bytes = ConsensusVersion::V1.encode(&proof);
+        bytes[31] = 0xff;
+        match ConsensusVersion::V1.verify(&bytes, 4, &DEFAULT_PARAMS) {
+            Err(ConsensusError::Decoding(DecodingError::NonCanonicalScalar)) => (),
+            _ => panic!("accepted a non-canonical scalar"),
+        }
+    }
+}
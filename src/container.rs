@@ -0,0 +1,414 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! A container format for many rangeproofs made with the same
+//! parameters.
+//!
+//! A container is a single header followed by any number of entries:
+//!
+//! ```text
+//! header = "DRPB" || version (1 byte) || params id (32 bytes)
+//! entry  = n (1 byte) || commitment (32 bytes) || proof (32(1+3n) bytes)
+//! ```
+//!
+//! where the proof is encoded as by `RangeProof::to_bytes`.  The
+//! container ends at the end of its last entry, so that it can be
+//! written and read in a streaming fashion without knowing the number
+//! of entries in advance.
+
+use std::io::{self, Read, Write};
+
+use curve25519_dalek::decaf::DecafPoint;
+
+use deadline::Cancellation;
+use encoding::{read_point, DecodingLimits, RING_LENGTH};
+use errors::{ContainerError, DecodingError};
+
+use super::RangeProof;
+use super::RangeProofParams;
+use super::RANGEPROOF_MAX_N;
+
+/// The magic bytes at the start of every container.
+pub const CONTAINER_MAGIC: &'static [u8; 4] = b"DRPB";
+
+/// The current version of the container format.
+pub const CONTAINER_VERSION: u8 = 1;
+
+/// The length in bytes of the container header.
+pub const CONTAINER_HEADER_LENGTH: usize = 4 + 1 + 32;
+
+/// A single entry of a container: a rangeproof with `n` rings, along
+/// with the commitment which the prover claims it proves.
+pub struct ContainerEntry {
+    /// The number of rings, so that the proof is for the range `[0,3^n]`.
+    pub n: usize,
+    /// The claimed commitment to the value.
+    pub commitment: DecafPoint,
+    /// The rangeproof.
+    pub proof: RangeProof,
+}
+
+impl ContainerEntry {
+    /// Verify the rangeproof, and check that it proves the claimed
+    /// commitment.
+    #[must_use]
+    pub fn verify(&self, params: &RangeProofParams) -> bool {
+        match self.proof.verify_with_params(self.n, params) {
+            Some(C) => C == self.commitment,
+            None => false,
+        }
+    }
+}
+
+/// Writes a container to an underlying `Write`r, one entry at a time.
+pub struct ContainerWriter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> ContainerWriter<W> {
+    /// Write the header for a container of proofs made with `params`.
+    pub fn new(mut writer: W, params: &RangeProofParams) -> io::Result<ContainerWriter<W>> {
+        writer.write_all(CONTAINER_MAGIC)?;
+        writer.write_all(&[CONTAINER_VERSION])?;
+        writer.write_all(params.id())?;
+
+        Ok(ContainerWriter { writer: writer })
+    }
+
+    /// Append a `proof` of the claimed `commitment` to the container.
+    ///
+    /// # Returns
+    ///
+    /// An `io::ErrorKind::InvalidInput` error, without writing
+    /// anything, if the proof has more than `RANGEPROOF_MAX_N` rings,
+    /// and otherwise any error of the underlying writer.
+    pub fn write(&mut self, commitment: &DecafPoint, proof: &RangeProof) -> io::Result<()> {
+        let n = proof.C.len();
+        if n > RANGEPROOF_MAX_N {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "rangeproof has too many rings"));
+        }
+
+        self.writer.write_all(&[n as u8])?;
+        self.writer.write_all(commitment.compress().as_bytes())?;
+        self.writer.write_all(&proof.to_bytes())
+    }
+
+    /// Flush the underlying writer and return it.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+/// Check the magic bytes and version of a container `header`,
+/// returning the parameters id.
+pub(crate) fn parse_header(header: &[u8; CONTAINER_HEADER_LENGTH]) -> Result<[u8; 32], ContainerError> {
+    if &header[0..4] != &CONTAINER_MAGIC[..] {
+        return Err(ContainerError::InvalidHeader);
+    }
+    if header[4] != CONTAINER_VERSION {
+        return Err(ContainerError::UnsupportedVersion(header[4]));
+    }
+    let mut params_id = [0u8; 32];
+    params_id.copy_from_slice(&header[5..]);
+
+    Ok(params_id)
+}
+
+/// Reads a container from an underlying `Read`er, yielding one
+/// `ContainerEntry` at a time.
+pub struct ContainerReader<R: Read> {
+    reader: R,
+    params_id: [u8; 32],
+    limits: DecodingLimits,
+    done: bool,
+}
+
+impl<R: Read> ContainerReader<R> {
+    /// Read the header of a container.
+    ///
+    /// # Returns
+    ///
+    /// A `ContainerError::InvalidHeader` if the magic bytes are wrong,
+    /// or a `ContainerError::UnsupportedVersion` for unknown versions.
+    pub fn new(reader: R) -> Result<ContainerReader<R>, ContainerError> {
+        ContainerReader::with_limits(reader, DecodingLimits::default())
+    }
+
+    /// Read the header of a container, as for `new`, and reject any
+    /// entry whose proof exceeds `limits` before reading it.
+    pub fn with_limits(mut reader: R, limits: DecodingLimits) -> Result<ContainerReader<R>, ContainerError> {
+        let mut header = [0u8; CONTAINER_HEADER_LENGTH];
+        reader.read_exact(&mut header)?;
+        let params_id = parse_header(&header)?;
+
+        Ok(ContainerReader { reader: reader, params_id: params_id, limits: limits, done: false })
+    }
+
+    /// The identifier of the parameters the proofs were made with,
+    /// which should be compared against `RangeProofParams::id`.
+    pub fn params_id(&self) -> &[u8; 32] {
+        &self.params_id
+    }
+
+    /// Read the next entry, or `None` at the end of the container.
+    fn read_entry(&mut self) -> Result<Option<ContainerEntry>, ContainerError> {
+        let mut n = [0u8; 1];
+        loop {
+            match self.reader.read(&mut n) {
+                Ok(0) => return Ok(None),
+                Ok(_) => break,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(ContainerError::Io(e)),
+            }
+        }
+        let n = n[0] as usize;
+        if n > RANGEPROOF_MAX_N {
+            return Err(ContainerError::Decoding(DecodingError::InvalidLength));
+        }
+        self.limits.check(n, 32 + RING_LENGTH * n)?;
+
+        let mut commitment = [0u8; 32];
+        self.reader.read_exact(&mut commitment)?;
+        let commitment = read_point(&commitment)?;
+
+        let mut proof = vec![0u8; 32 + RING_LENGTH * n];
+        self.reader.read_exact(&mut proof)?;
+        let proof = RangeProof::from_bytes_exact(&proof, n)?;
+
+        Ok(Some(ContainerEntry { n: n, commitment: commitment, proof: proof }))
+    }
+
+    /// Read and verify every remaining entry, as for
+    /// `ContainerEntry::verify`, unless `cancel` fires first.
+    ///
+    /// # Returns
+    ///
+    /// Whether each entry verified, in order, or a
+    /// `ContainerError::ParamsMismatch` if the container wasn't made
+    /// with `params`, a `ContainerError::Aborted` with the number of
+    /// entries verified before `cancel` fired, or the first error
+    /// reading an entry.
+    pub fn verify_until<C: Cancellation>(
+        self,
+        params: &RangeProofParams,
+        cancel: &C,
+    ) -> Result<Vec<bool>, ContainerError> {
+        if self.params_id != *params.id() {
+            return Err(ContainerError::ParamsMismatch);
+        }
+
+        let mut results = Vec::new();
+        for entry in self {
+            if cancel.is_cancelled() {
+                return Err(ContainerError::Aborted { verified: results.len() });
+            }
+            results.push(entry?.verify(params));
+        }
+        Ok(results)
+    }
+}
+
+/// The outcome of verifying a stream of rangeproofs.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct StreamSummary {
+    /// The number of entries read.
+    pub entries: u64,
+    /// The indices of the entries which failed to verify, in order.
+    pub failed: Vec<u64>,
+}
+
+impl StreamSummary {
+    /// Returns `true` if every entry verified.
+    pub fn all_verified(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+impl RangeProof {
+    /// Verify every entry of the container read from `reader`, in
+    /// bounded memory.
+    ///
+    /// Entries are read and verified one at a time, as for
+    /// `ContainerEntry::verify`, and only the indices of those which
+    /// failed are kept.
+    ///
+    /// # Returns
+    ///
+    /// The number of entries and the indices of those which failed, a
+    /// `ContainerError::ParamsMismatch` if the container wasn't made
+    /// with `params`, or the first error reading the container.
+    pub fn verify_batch_from_reader<R: Read>(
+        reader: R,
+        params: &RangeProofParams,
+    ) -> Result<StreamSummary, ContainerError> {
+        let reader = ContainerReader::new(reader)?;
+        if reader.params_id() != params.id() {
+            return Err(ContainerError::ParamsMismatch);
+        }
+
+        let mut summary = StreamSummary::default();
+        for entry in reader {
+            let entry = entry?;
+            if !entry.verify(params) {
+                summary.failed.push(summary.entries);
+            }
+            summary.entries += 1;
+        }
+
+        Ok(summary)
+    }
+}
+
+impl<R: Read> Iterator for ContainerReader<R> {
+    type Item = Result<ContainerEntry, ContainerError>;
+
+    fn next(&mut self) -> Option<Result<ContainerEntry, ContainerError>> {
+        if self.done {
+            return None;
+        }
+        match self.read_entry() {
+            Ok(Some(entry)) => Some(Ok(entry)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(e) => {
+                // A malformed entry leaves the reader at an unknown
+                // position, so stop after reporting it.
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::OsRng;
+
+    use DEFAULT_PARAMS;
+
+    #[test]
+    fn write_and_read_container() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let mut writer = ContainerWriter::new(Vec::new(), &DEFAULT_PARAMS).unwrap();
+        for &(n, value) in [(4, 42), (16, 13449261), (1, 2)].iter() {
+            let (proof, commitment, _) =
+                RangeProof::create_with_params(n, value, &DEFAULT_PARAMS, &mut csprng).unwrap().into_parts();
+            writer.write(&commitment, &proof).unwrap();
+        }
+        let bytes = writer.finish().unwrap();
+
+        let reader = ContainerReader::new(&bytes[..]).unwrap();
+        assert_eq!(reader.params_id(), DEFAULT_PARAMS.id());
+
+        let entries: Vec<ContainerEntry> = reader.map(|entry| entry.unwrap()).collect();
+        assert_eq!(entries.iter().map(|entry| entry.n).collect::<Vec<_>>(), vec![4, 16, 1]);
+        assert!(entries.iter().all(|entry| entry.verify(&DEFAULT_PARAMS)));
+
+        // A proof with too many rings can't be written.
+        let mut writer = ContainerWriter::new(Vec::new(), &DEFAULT_PARAMS).unwrap();
+        let (proof, commitment, _) =
+            RangeProof::create_with_params(1, 2, &DEFAULT_PARAMS, &mut csprng).unwrap().into_parts();
+        let long = RangeProof {
+            e_0: proof.e_0,
+            C: vec![proof.C[0]; RANGEPROOF_MAX_N + 1],
+            s_1: vec![proof.s_1[0]; RANGEPROOF_MAX_N + 1],
+            s_2: vec![proof.s_2[0]; RANGEPROOF_MAX_N + 1],
+        };
+        assert_eq!(writer.write(&commitment, &long).err().unwrap().kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(writer.finish().unwrap().len(), CONTAINER_HEADER_LENGTH);
+
+        // A truncated container reports an error for the last entry.
+        let mut reader = ContainerReader::new(&bytes[..bytes.len() - 1]).unwrap();
+        assert!(reader.next().unwrap().is_ok());
+        assert!(reader.next().unwrap().is_ok());
+        assert!(reader.next().unwrap().is_err());
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn verify_batch_from_reader_finds_failures() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let mut writer = ContainerWriter::new(Vec::new(), &DEFAULT_PARAMS).unwrap();
+        let (_, other, _) = RangeProof::create_with_params(4, 7, &DEFAULT_PARAMS, &mut csprng).unwrap().into_parts();
+        for j in 0..10 {
+            let (proof, commitment, _) =
+                RangeProof::create_with_params(4, j as u64, &DEFAULT_PARAMS, &mut csprng).unwrap().into_parts();
+            // Claim the wrong commitment for two of the entries.
+            let claimed = if j == 5 || j == 8 { other } else { commitment };
+            writer.write(&claimed, &proof).unwrap();
+        }
+        let bytes = writer.finish().unwrap();
+
+        let summary = RangeProof::verify_batch_from_reader(&bytes[..], &DEFAULT_PARAMS).unwrap();
+        assert_eq!(summary.entries, 10);
+        assert_eq!(summary.failed, vec![5, 8]);
+        assert!(!summary.all_verified());
+
+        assert!(RangeProof::verify_batch_from_reader(&bytes[..bytes.len() - 1], &DEFAULT_PARAMS).is_err());
+    }
+
+    #[test]
+    fn containers_for_other_params_are_rejected() {
+        use std::sync::atomic::AtomicBool;
+
+        let mut csprng = OsRng::new().unwrap();
+
+        let other_params = RangeProofParams::new(DEFAULT_PARAMS.H(), &DEFAULT_PARAMS.G().basepoint());
+        let mut writer = ContainerWriter::new(Vec::new(), &other_params).unwrap();
+        let (proof, commitment, _) =
+            RangeProof::create_with_params(4, 42, &other_params, &mut csprng).unwrap().into_parts();
+        writer.write(&commitment, &proof).unwrap();
+        let bytes = writer.finish().unwrap();
+
+        match RangeProof::verify_batch_from_reader(&bytes[..], &DEFAULT_PARAMS) {
+            Err(ContainerError::ParamsMismatch) => (),
+            _ => panic!("expected the parameters to be rejected"),
+        }
+        let reader = ContainerReader::new(&bytes[..]).unwrap();
+        match reader.verify_until(&DEFAULT_PARAMS, &AtomicBool::new(false)) {
+            Err(ContainerError::ParamsMismatch) => (),
+            _ => panic!("expected the parameters to be rejected"),
+        }
+    }
+
+    #[test]
+    fn verify_container_until_cancelled() {
+        use std::sync::atomic::AtomicBool;
+
+        let mut csprng = OsRng::new().unwrap();
+
+        let mut writer = ContainerWriter::new(Vec::new(), &DEFAULT_PARAMS).unwrap();
+        for &value in [1, 2].iter() {
+            let (proof, commitment, _) =
+                RangeProof::create_with_params(4, value, &DEFAULT_PARAMS, &mut csprng).unwrap().into_parts();
+            writer.write(&commitment, &proof).unwrap();
+        }
+        let bytes = writer.finish().unwrap();
+
+        let reader = ContainerReader::new(&bytes[..]).unwrap();
+        assert_eq!(reader.verify_until(&DEFAULT_PARAMS, &AtomicBool::new(false)).unwrap(),
+                   vec![true, true]);
+
+        let reader = ContainerReader::new(&bytes[..]).unwrap();
+        match reader.verify_until(&DEFAULT_PARAMS, &AtomicBool::new(true)) {
+            Err(ContainerError::Aborted { verified }) => assert_eq!(verified, 0),
+            _ => panic!("expected verification to be cancelled"),
+        }
+    }
+}
@@ -0,0 +1,116 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Constant-time selection helpers.
+//!
+//! These are the helpers used by the constant-time prover to choose
+//! between the branches of each ring according to the secret base 3
+//! digit, without branching on it.  They are exposed for protocol code
+//! building adjacent gadgets, which needs the same care.
+//!
+//! A *choice* is a `u8` which is `1` for true and `0` for false; any
+//! other value gives unspecified results.  All of the functions here
+//! run in time independent of their secret inputs.
+
+use curve25519_dalek::decaf::DecafPoint;
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::subtle::bytes_equal_ct;
+use curve25519_dalek::subtle::byte_is_nonzero;
+
+pub use curve25519_dalek::subtle::CTAssignable;
+
+/// Returns the choice `1` if `digit` is nonzero, and `0` otherwise.
+#[inline]
+pub fn digit_is_nonzero(digit: u8) -> u8 {
+    byte_is_nonzero(digit)
+}
+
+/// Returns the choice `1` if `digit == d`, and `0` otherwise.
+#[inline]
+pub fn digit_equals(digit: u8, d: u8) -> u8 {
+    bytes_equal_ct(digit, d)
+}
+
+/// Returns `b` if `choice` is `1`, and `a` if it is `0`.
+#[inline]
+pub fn select<T: CTAssignable + Copy>(a: &T, b: &T, choice: u8) -> T {
+    let mut x = *a;
+    x.conditional_assign(b, choice);
+    x
+}
+
+/// Returns `b` if `choice` is `1`, and `a` if it is `0`.
+#[inline]
+pub fn select_point(a: &DecafPoint, b: &DecafPoint, choice: u8) -> DecafPoint {
+    select(a, b, choice)
+}
+
+/// Returns `b` if `choice` is `1`, and `a` if it is `0`.
+#[inline]
+pub fn select_scalar(a: &Scalar, b: &Scalar, choice: u8) -> Scalar {
+    select(a, b, choice)
+}
+
+/// Returns the choice `1` if `a == b`, and `0` otherwise, comparing
+/// every byte of their encodings rather than stopping at the first
+/// which differs.
+#[inline]
+pub fn points_equal(a: &DecafPoint, b: &DecafPoint) -> u8 {
+    let a = a.compress();
+    let b = b.compress();
+
+    let mut equal = 1u8;
+    for (x, y) in a.as_bytes().iter().zip(b.as_bytes().iter()) {
+        equal &= bytes_equal_ct(*x, *y);
+    }
+    equal
+}
+
+/// Returns the multiple of `3^i * H` committed to by the base 3
+/// `digit`, given `mi_H = 3^i * H` and `mi2_H = 2 * 3^i * H`: that is,
+/// `mi2_H` if `digit == 2` and `mi_H` otherwise.
+///
+/// # Note
+///
+/// The result is `mi_H` for `digit == 0` too; the caller is expected to
+/// discard it in that case, as the prover does with
+/// `digit_is_nonzero`.
+#[inline]
+pub fn digit_multiple_of_H(digit: u8, mi_H: &DecafPoint, mi2_H: &DecafPoint) -> DecafPoint {
+    select_point(mi_H, mi2_H, digit_equals(digit, 2))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use DEFAULT_PARAMS;
+
+    #[test]
+    fn select_by_digit() {
+        let a = Scalar::from_u64(5);
+        let b = Scalar::from_u64(7);
+
+        for digit in 0..3u8 {
+            assert_eq!(digit_is_nonzero(digit), (digit != 0) as u8);
+            assert!(select_scalar(&a, &b, digit_equals(digit, 1)) == if digit == 1 { b } else { a });
+        }
+
+        let powers = DEFAULT_PARAMS.H_powers();
+        let mi_H = *powers.power(3);
+        let mi2_H = *powers.double_power(3);
+        assert_eq!(digit_multiple_of_H(1, &mi_H, &mi2_H).compress(), mi_H.compress());
+        assert_eq!(digit_multiple_of_H(2, &mi_H, &mi2_H).compress(), mi2_H.compress());
+        assert_eq!(points_equal(&mi_H, &mi_H), 1);
+        assert_eq!(points_equal(&mi_H, &mi2_H), 0);
+    }
+}
@@ -0,0 +1,161 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Verification which can be cancelled, or bounded by a deadline.
+//!
+//! A server verifying batches of proofs on behalf of untrusted clients
+//! can't bound the time a batch takes by its size alone.  The `_until`
+//! variants of the batch and streaming verifiers check a
+//! `Cancellation` before each proof, and stop with the results so far
+//! once it fires.
+
+use std::error::Error;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use errors::VerifyError;
+use params::RangeProofParams;
+use verification::VerifiedCommitment;
+
+use super::RangeProof;
+
+/// A condition under which verification should stop early.
+pub trait Cancellation {
+    /// Returns `true` once verification should stop.
+    fn is_cancelled(&self) -> bool;
+}
+
+/// A deadline, which has passed once the current time reaches it.
+impl Cancellation for Instant {
+    fn is_cancelled(&self) -> bool {
+        Instant::now() >= *self
+    }
+}
+
+/// A cancellation token, set by another thread to cancel.
+impl Cancellation for AtomicBool {
+    fn is_cancelled(&self) -> bool {
+        self.load(Ordering::Relaxed)
+    }
+}
+
+/// The error returned when a batch verification was cancelled before
+/// every proof was verified.
+#[derive(Debug)]
+pub struct BatchAborted {
+    /// The results for the proofs verified before cancellation, in
+    /// order: `results[j]` is the result for `proofs[j]`, and the
+    /// remaining proofs weren't verified.
+    pub results: Vec<Result<VerifiedCommitment, VerifyError>>,
+}
+
+impl fmt::Display for BatchAborted {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Rangeproof verification cancelled after {} proofs", self.results.len())
+    }
+}
+
+impl Error for BatchAborted {
+    fn description(&self) -> &str {
+        "rangeproof verification cancelled"
+    }
+}
+
+impl RangeProof {
+    /// Verify each of the `proofs` as for `verify_batch_each`, unless
+    /// `cancel` fires first.
+    ///
+    /// # Returns
+    ///
+    /// The result for every proof, or a `BatchAborted` with the results
+    /// for the proofs verified before `cancel` fired.  If
+    /// `n > RANGEPROOF_MAX_N`, every result is a
+    /// `VerifyError::WrongRingCount`.
+    ///
+    /// # Note
+    ///
+    /// If `cancel` never fires, the results are the same as those of
+    /// `verify_batch_each`.  Otherwise, how many proofs were verified
+    /// depends on timing and thread scheduling, so an aborted batch
+    /// must not be used in consensus-critical validation.
+    pub fn verify_batch_each_until<C: Cancellation + Sync>(
+        n: usize,
+        proofs: &[RangeProof],
+        params: &RangeProofParams,
+        cancel: &C,
+    ) -> Result<Vec<Result<VerifiedCommitment, VerifyError>>, BatchAborted> {
+        let verify = |proof: &RangeProof| {
+            if cancel.is_cancelled() {
+                None
+            } else {
+                Some(proof.verify_checked(n, params))
+            }
+        };
+
+        #[cfg(feature = "parallel")]
+        let results: Vec<_> = proofs.par_iter().map(verify).collect();
+        #[cfg(not(feature = "parallel"))]
+        let results: Vec<_> = proofs.iter().map(verify).collect();
+
+        if results.iter().all(|result| result.is_some()) {
+            Ok(results.into_iter().map(|result| result.unwrap()).collect())
+        } else {
+            // In parallel, proofs after the first skipped one may have
+            // been verified anyway; report only the contiguous prefix.
+            Err(BatchAborted {
+                results: results.into_iter().take_while(|result| result.is_some())
+                    .map(|result| result.unwrap()).collect(),
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::Duration;
+
+    use rand::OsRng;
+
+    use DEFAULT_PARAMS;
+    use RANGEPROOF_MAX_N;
+
+    #[test]
+    fn cancelled_batch_reports_partial_progress() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let proofs: Vec<RangeProof> = [1, 2, 3].iter().map(|&value| {
+            RangeProof::create_with_params(8, value, &DEFAULT_PARAMS, &mut csprng).unwrap().into_proof()
+        }).collect();
+
+        let deadline = Instant::now() + Duration::from_secs(3600);
+        let results = RangeProof::verify_batch_each_until(8, &proofs, &DEFAULT_PARAMS, &deadline).unwrap();
+        assert!(results.iter().all(|result| result.is_ok()));
+
+        let cancelled = AtomicBool::new(true);
+        let aborted = RangeProof::verify_batch_each_until(8, &proofs, &DEFAULT_PARAMS, &cancelled)
+            .err().unwrap();
+        assert!(aborted.results.is_empty());
+
+        let passed = Instant::now();
+        assert!(RangeProof::verify_batch_each_until(8, &proofs, &DEFAULT_PARAMS, &passed).is_err());
+
+        let results = RangeProof::verify_batch_each_until(RANGEPROOF_MAX_N + 1, &proofs, &DEFAULT_PARAMS,
+                                                          &deadline).unwrap();
+        assert!(results.iter().all(|result| result.as_ref().err() == Some(&VerifyError::WrongRingCount)));
+    }
+}
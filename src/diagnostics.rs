@@ -0,0 +1,140 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Verification with diagnostics, for debugging interoperability.
+//!
+//! When another implementation's proofs fail to verify, `None` says
+//! nothing about where the two transcripts diverged.  With the
+//! `diagnostics` feature, `RangeProof::verify_with_diagnostics` returns
+//! the challenges and `R_i` points it recomputed for every ring, which
+//! can be compared against the other implementation's values ring by
+//! ring.
+
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::decaf::CompressedDecaf;
+
+use challenges::e_0_challenge;
+use errors::VerifyError;
+use params::RangeProofParams;
+use verification::VerifiedCommitment;
+
+use super::RangeProof;
+
+/// The values recomputed by the verifier for a single ring.
+#[derive(Clone, Debug)]
+pub struct RingDiagnostics {
+    /// The challenge `e_1,i` at index 1 of the ring.
+    pub e_1: Scalar,
+    /// The challenge `e_2,i` at index 2 of the ring.
+    pub e_2: Scalar,
+    /// The point `R_i = e_2,i * C_i` closing the ring.
+    pub R: CompressedDecaf,
+}
+
+/// The transcript recomputed while verifying a proof which failed.
+#[derive(Clone, Debug)]
+pub struct VerificationDiagnostics {
+    /// Why verification failed.
+    pub error: VerifyError,
+    /// The recomputed values for each ring, in order.  This is empty if
+    /// the proof was rejected before any ring was recomputed.
+    pub rings: Vec<RingDiagnostics>,
+    /// The challenge `e_0` in the proof.
+    pub e_0: Scalar,
+    /// The challenge `e_0` recomputed from the `R_i`, if any.
+    pub e_0_recomputed: Option<Scalar>,
+}
+
+impl RangeProof {
+    /// Verify the rangeproof as for `verify_checked`, returning the
+    /// recomputed transcript on failure.
+    ///
+    /// # Returns
+    ///
+    /// The verified commitment, or the diagnostics for the failure.  If
+    /// `n > RANGEPROOF_MAX_N`, the error is `VerifyError::WrongRingCount`
+    /// and no rings are recomputed.
+    pub fn verify_with_diagnostics(
+        &self,
+        n: usize,
+        params: &RangeProofParams,
+    ) -> Result<VerifiedCommitment, VerificationDiagnostics> {
+        let error = match self.verify_checked(n, params) {
+            Ok(C) => return Ok(C),
+            Err(error) => error,
+        };
+
+        let mut diagnostics = VerificationDiagnostics {
+            error: error,
+            rings: Vec::new(),
+            e_0: self.e_0,
+            e_0_recomputed: None,
+        };
+        if error != VerifyError::InvalidProof {
+            return Err(diagnostics);
+        }
+
+        for i in 0..n {
+            let ring = self.recompute_ring(i, params.G(), params.H_powers());
+            diagnostics.rings.push(RingDiagnostics { e_1: ring.e_1, e_2: ring.e_2, R: ring.R });
+        }
+        diagnostics.e_0_recomputed = Some(e_0_challenge(diagnostics.rings.iter().map(|ring| ring.R)));
+
+        Err(diagnostics)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::OsRng;
+
+    use DEFAULT_PARAMS;
+    use RANGEPROOF_MAX_N;
+
+    #[test]
+    fn diagnostics_of_failed_verification() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let (proof, commitment, _) =
+            RangeProof::create_with_params(16, 13449261, &DEFAULT_PARAMS, &mut csprng).unwrap().into_parts();
+        assert_eq!(proof.verify_with_diagnostics(16, &DEFAULT_PARAMS).unwrap().commitment().compress(),
+                   commitment.compress());
+
+        let diagnostics = proof.verify_with_diagnostics(15, &DEFAULT_PARAMS).err().unwrap();
+        assert_eq!(diagnostics.error, VerifyError::WrongRingCount);
+        assert!(diagnostics.rings.is_empty());
+
+        let diagnostics = proof.verify_with_diagnostics(RANGEPROOF_MAX_N + 1, &DEFAULT_PARAMS).err().unwrap();
+        assert_eq!(diagnostics.error, VerifyError::WrongRingCount);
+        assert!(diagnostics.rings.is_empty());
+
+        // Tamper with the last ring only: the recomputed values for the
+        // earlier rings still match those of the valid proof.
+        let mut bytes = proof.to_bytes();
+        let last = bytes.len() - 32;
+        bytes[last] ^= 1;
+        let tampered = RangeProof::from_bytes(&bytes).unwrap();
+        let diagnostics = tampered.verify_with_diagnostics(16, &DEFAULT_PARAMS).err().unwrap();
+        assert_eq!(diagnostics.error, VerifyError::InvalidProof);
+        assert_eq!(diagnostics.rings.len(), 16);
+        assert!(diagnostics.e_0_recomputed.unwrap() != diagnostics.e_0);
+
+        for i in 0..15 {
+            let ring = proof.recompute_ring(i, DEFAULT_PARAMS.G(), DEFAULT_PARAMS.H_powers());
+            assert!(diagnostics.rings[i].e_1 == ring.e_1);
+            assert!(diagnostics.rings[i].e_2 == ring.e_2);
+            assert_eq!(diagnostics.rings[i].R, ring.R);
+        }
+    }
+}
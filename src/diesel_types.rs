@@ -0,0 +1,87 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! `diesel` column types, with the `diesel` feature.
+//!
+//! As with the `sqlx` feature, a `RangeProof` is stored as a `Binary`
+//! column (`BYTEA` in Postgres, `BLOB` in MySQL) of its canonical
+//! encoding `RangeProof::to_bytes`, and a `PedersenCommitment` or
+//! `CompressedCommitment` as the 32 bytes of its compressed point, so
+//! that they can be used directly as fields of `Queryable` and
+//! `Insertable` structs.  Decoding a column checks it exactly as
+//! `RangeProof::from_bytes` and `commitments::from_bytes` do.
+//!
+//! The encodings are written to the backends whose bind parameters are
+//! raw bytes, which are Postgres and MySQL.
+
+use std::io::Write;
+
+use diesel::backend::Backend;
+use diesel::deserialize::{self, FromSql};
+use diesel::query_builder::bind_collector::RawBytesBindCollector;
+use diesel::serialize::{self, IsNull, Output, ToSql};
+use diesel::sql_types::Binary;
+
+use commitments::{self, CompressedCommitment, PedersenCommitment};
+
+use super::RangeProof;
+
+impl<DB> ToSql<Binary, DB> for RangeProof
+    where for<'c> DB: Backend<BindCollector<'c> = RawBytesBindCollector<DB>>
+{
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, DB>) -> serialize::Result {
+        out.write_all(&self.to_bytes())?;
+        Ok(IsNull::No)
+    }
+}
+
+impl<DB: Backend> FromSql<Binary, DB> for RangeProof where Vec<u8>: FromSql<Binary, DB> {
+    fn from_sql(bytes: DB::RawValue<'_>) -> deserialize::Result<RangeProof> {
+        let bytes = <Vec<u8> as FromSql<Binary, DB>>::from_sql(bytes)?;
+
+        Ok(RangeProof::from_bytes(&bytes)?)
+    }
+}
+
+impl<DB> ToSql<Binary, DB> for PedersenCommitment
+    where for<'c> DB: Backend<BindCollector<'c> = RawBytesBindCollector<DB>>
+{
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, DB>) -> serialize::Result {
+        out.write_all(&commitments::to_bytes(self.as_point()))?;
+        Ok(IsNull::No)
+    }
+}
+
+impl<DB: Backend> FromSql<Binary, DB> for PedersenCommitment where Vec<u8>: FromSql<Binary, DB> {
+    fn from_sql(bytes: DB::RawValue<'_>) -> deserialize::Result<PedersenCommitment> {
+        let bytes = <Vec<u8> as FromSql<Binary, DB>>::from_sql(bytes)?;
+
+        Ok(PedersenCommitment::new(commitments::from_bytes(&bytes)?))
+    }
+}
+
+impl<DB> ToSql<Binary, DB> for CompressedCommitment
+    where for<'c> DB: Backend<BindCollector<'c> = RawBytesBindCollector<DB>>
+{
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, DB>) -> serialize::Result {
+        out.write_all(self.as_bytes())?;
+        Ok(IsNull::No)
+    }
+}
+
+impl<DB: Backend> FromSql<Binary, DB> for CompressedCommitment where Vec<u8>: FromSql<Binary, DB> {
+    fn from_sql(bytes: DB::RawValue<'_>) -> deserialize::Result<CompressedCommitment> {
+        let bytes = <Vec<u8> as FromSql<Binary, DB>>::from_sql(bytes)?;
+
+        Ok(CompressedCommitment::from(&commitments::from_bytes(&bytes)?))
+    }
+}
@@ -0,0 +1,122 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Standalone proofs for a single ring, i.e. a single digit.
+//!
+//! A `DigitProof` proves that a commitment `C` hides `0`, `1` or `2`
+//! times a public point `P`, i.e. that `C` is one of `r*G`,
+//! `r*G + P` or `r*G + 2*P` for some blinding `r`.  A `RangeProof`
+//! for `[0,3^n]` is `n` of these statements with `P = 3^i * H`, joined
+//! by a shared challenge; a `DigitProof` is the same construction with
+//! a single ring and an arbitrary `P`, so that other statements can be
+//! composed from it.
+//!
+//! Creating and verifying a `DigitProof` runs exactly the same code as
+//! a one-ring `RangeProof`.
+
+use rand::Rng;
+
+use curve25519_dalek::decaf::{DecafPoint, DecafBasepointTable};
+
+use blinding::SecretBlinding;
+use output::ProverOutput;
+use params::PowersOfH;
+
+use super::RangeProof;
+
+/// A proof that a commitment hides `0`, `1` or `2` times a public
+/// point `P`.
+pub struct DigitProof {
+    proof: RangeProof,
+}
+
+impl DigitProof {
+    /// Construct a proof that a fresh commitment hides `digit * P`, in
+    /// constant time.
+    ///
+    /// # Inputs
+    ///
+    /// * The `digit`, which must be `0`, `1` or `2`;
+    /// * The point `P` which the digit multiplies;
+    /// * The blinding basepoint `G`;
+    /// * `csprng`, an implementation of `rand::Rng`, which should be
+    /// cryptographically secure.
+    ///
+    /// # Returns
+    ///
+    /// If `digit > 2`, return None.
+    ///
+    /// Otherwise, returns `Some((proof, commitment, blinding))`, where
+    /// `commitment = blinding*G + digit*P`.
+    pub fn create<T: Rng>(
+        digit: u8,
+        P: &DecafPoint,
+        G: &DecafBasepointTable,
+        csprng: &mut T,
+    ) -> Option<(DigitProof, DecafPoint, SecretBlinding)> {
+        if digit > 2 {
+            return None;
+        }
+
+        RangeProof::create_with_powers(1, digit as u64, G, &PowersOfH::with_len(1, P), csprng)
+            .ok()
+            .map(ProverOutput::into_parts)
+            .map(|(proof, commitment, blinding)| (DigitProof { proof: proof }, commitment, blinding))
+    }
+
+    /// Verify the proof for the point `P`, returning the commitment
+    /// to `0`, `1` or `2` times `P` if successful.
+    #[must_use]
+    pub fn verify(&self, P: &DecafPoint, G: &DecafBasepointTable) -> Option<DecafPoint> {
+        self.proof.verify_with_powers(1, G, &PowersOfH::with_len(1, P))
+    }
+
+    /// The commitment which this proof claims hides a digit, *without
+    /// verifying the proof*.
+    pub fn commitment(&self) -> &DecafPoint {
+        &self.proof.C[0]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::OsRng;
+
+    use DEFAULT_PARAMS;
+
+    #[test]
+    fn prove_and_verify_digits() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let G = DEFAULT_PARAMS.G();
+        let P = DEFAULT_PARAMS.H_powers().power(5);
+
+        for digit in 0..3 {
+            let (proof, commitment, blinding) = DigitProof::create(digit, P, G, &mut csprng).unwrap();
+            assert_eq!(proof.verify(P, G).unwrap().compress(), commitment.compress());
+            assert_eq!(proof.commitment().compress(), commitment.compress());
+
+            let mut expected = G * blinding.expose_secret();
+            for _ in 0..digit {
+                expected = &expected + P;
+            }
+            assert_eq!(commitment.compress(), expected.compress());
+
+            // The proof is bound to P.
+            assert!(proof.verify(DEFAULT_PARAMS.H(), G).is_none());
+        }
+
+        assert!(DigitProof::create(3, P, G, &mut csprng).is_none());
+    }
+}
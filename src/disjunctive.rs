@@ -0,0 +1,341 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Rangeproofs for a union of public intervals.
+//!
+//! To prove that a value `v` is in one of the intervals
+//! `[a_0, b_0], ..., [a_{k-1}, b_{k-1}]`, say `[a_j, b_j]`, without
+//! revealing `j`, the prover commits to the hidden bounds as
+//!
+//! ```text
+//! D = t*G + a_j*H
+//! E = t'*G + b_j*H
+//! ```
+//!
+//! and proves, with two ordinary rangeproofs as for a
+//! `TwoSidedRangeProof`, that `C - D` and `E - C` are commitments to
+//! values in `[0, 3^n)`, so that `a_j <= v <= b_j`.  A ring signature
+//! over the pairs `(D - a_i*H, E - b_i*H)`, with a single challenge
+//! per member, shows that for some `i` both points are multiples of
+//! `G` alone, i.e. that `D` and `E` commit to the bounds of the same
+//! public interval.
+//!
+//! The prover sends `D`; the verifier recovers `C` and `E` from `D`
+//! and the commitments output by the two rangeproofs.
+
+use rand::Rng;
+
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::decaf::DecafPoint;
+use curve25519_dalek::decaf::vartime;
+
+use blinding::SecretBlinding;
+use challenges::disjunctive_challenge;
+use encoding::{read_point, read_scalar, RING_LENGTH};
+use errors::DecodingError;
+use params::RangeProofParams;
+use transcript_rng::TranscriptRng;
+use two_sided::rings_for_width;
+
+use super::RangeProof;
+use super::RANGEPROOF_MAX_N;
+
+/// A proof that a committed value is in one of several public
+/// intervals, without revealing which.
+pub struct DisjunctiveRangeProof {
+    D: DecafPoint,
+    lower: RangeProof,
+    upper: RangeProof,
+    e_0: Scalar,
+    s_1: Vec<Scalar>,
+    s_2: Vec<Scalar>,
+}
+
+/// The number of rings needed for the widest of the `ranges`, or
+/// `None` if any of them is empty or reversed.
+fn rings_for_ranges(ranges: &[(u64, u64)]) -> Option<usize> {
+    if ranges.is_empty() {
+        return None;
+    }
+    let mut width = 0;
+    for &(a, b) in ranges.iter() {
+        if a > b {
+            return None;
+        }
+        width = width.max(b - a);
+    }
+    Some(rings_for_width(width))
+}
+
+/// The ring members `(D - a_i*H, E - b_i*H)` for each of the `ranges`.
+fn ring_keys(
+    ranges: &[(u64, u64)],
+    D: &DecafPoint,
+    E: &DecafPoint,
+    params: &RangeProofParams,
+) -> Vec<(DecafPoint, DecafPoint)> {
+    ranges.iter().map(|&(a, b)| {
+        (D - &(params.H_table() * &Scalar::from_u64(a)),
+         E - &(params.H_table() * &Scalar::from_u64(b)))
+    }).collect()
+}
+
+impl DisjunctiveRangeProof {
+    /// Construct a proof that `value` is in one of the `ranges`.
+    ///
+    /// The rangeproofs are created in constant time, but the ring
+    /// signature takes time depending on which range contains `value`.
+    ///
+    /// # Inputs
+    ///
+    /// * The public `ranges`, as inclusive bounds `(a_i, b_i)`;
+    /// * The `value` to prove within one of the ranges;
+    /// * The precomputed `params`;
+    /// * `csprng`, an implementation of `rand::Rng`, which should be
+    /// cryptographically secure.
+    ///
+    /// # Returns
+    ///
+    /// If `ranges` is empty, any range has `a_i > b_i`, or `value` is
+    /// in none of them, return None.
+    ///
+    /// Otherwise, returns `Some((proof, commitment, blinding))`, where
+    /// `commitment = blinding*G + value*H`.
+    pub fn create<T: Rng>(
+        ranges: &[(u64, u64)],
+        value: u64,
+        params: &RangeProofParams,
+        csprng: &mut T,
+    ) -> Option<(DisjunctiveRangeProof, DecafPoint, SecretBlinding)> {
+        let n = rings_for_ranges(ranges)?;
+        let j = ranges.iter().position(|&(a, b)| a <= value && value <= b)?;
+        let (a_j, b_j) = ranges[j];
+
+        let (lower, C_lower, r_lower) =
+            RangeProof::create_with_params(n, value - a_j, params, csprng)?.into_parts();
+        let (upper, C_upper, r_upper) =
+            RangeProof::create_with_params(n, b_j - value, params, csprng)?.into_parts();
+
+        let mut rng = TranscriptRng::builder(b"disjunctive")
+            .commit_transcript(b"C_lower", C_lower.compress().as_bytes())
+            .commit_transcript(b"C_upper", C_upper.compress().as_bytes())
+            .commit_witness(b"value", &value.to_le_bytes())
+            .finalize(csprng);
+
+        // C = C_lower + D and E = C + C_upper, so that
+        // t' = t + r_lower + r_upper.
+        let t = Scalar::random(&mut rng);
+        let t_prime = &(&t + r_lower.expose_secret()) + r_upper.expose_secret();
+        let D = &(params.G() * &t) + &(params.H_table() * &Scalar::from_u64(a_j));
+        let C = &C_lower + &D;
+        let E = &C + &C_upper;
+
+        let D_bytes = D.compress();
+        let E_bytes = E.compress();
+        let keys = ring_keys(ranges, &D, &E, params);
+        let k = ranges.len();
+
+        let mut e   = vec![Scalar::zero(); k];
+        let mut s_1 = vec![Scalar::zero(); k];
+        let mut s_2 = vec![Scalar::zero(); k];
+
+        // Begin the ring just after the real member j.
+        let alpha = Scalar::random(&mut rng);
+        let beta = Scalar::random(&mut rng);
+        e[(j + 1) % k] = disjunctive_challenge(ranges, &D_bytes, &E_bytes, j,
+                                               &(params.G() * &alpha).compress(),
+                                               &(params.G() * &beta).compress());
+
+        let mut i = (j + 1) % k;
+        while i != j {
+            s_1[i] = Scalar::random(&mut rng);
+            s_2[i] = Scalar::random(&mut rng);
+            let L = vartime::k_fold_scalar_mult(&[s_1[i], -&e[i]],
+                                                &[params.G().basepoint(), keys[i].0]);
+            let M = vartime::k_fold_scalar_mult(&[s_2[i], -&e[i]],
+                                                &[params.G().basepoint(), keys[i].1]);
+            e[(i + 1) % k] = disjunctive_challenge(ranges, &D_bytes, &E_bytes, i,
+                                                   &L.compress(), &M.compress());
+            i = (i + 1) % k;
+        }
+
+        // Close the ring at j.
+        s_1[j] = Scalar::multiply_add(&e[j], &t, &alpha);
+        s_2[j] = Scalar::multiply_add(&e[j], &t_prime, &beta);
+
+        let blinding = SecretBlinding::new(r_lower.expose_secret() + &t);
+
+        Some((
+            DisjunctiveRangeProof { D: D, lower: lower, upper: upper, e_0: e[0], s_1: s_1, s_2: s_2 },
+            C,
+            blinding,
+        ))
+    }
+
+    /// Verify that the proof is for a value in one of the `ranges`,
+    /// returning a Pedersen commitment to the value if successful.
+    #[must_use]
+    pub fn verify(&self, ranges: &[(u64, u64)], params: &RangeProofParams) -> Option<DecafPoint> {
+        let n = rings_for_ranges(ranges)?;
+        let k = ranges.len();
+        if self.s_1.len() != k || self.s_2.len() != k {
+            return None;
+        }
+
+        let C_lower = self.lower.verify_with_params(n, params)?;
+        let C_upper = self.upper.verify_with_params(n, params)?;
+        let C = &C_lower + &self.D;
+        let E = &C + &C_upper;
+
+        let D_bytes = self.D.compress();
+        let E_bytes = E.compress();
+        let keys = ring_keys(ranges, &self.D, &E, params);
+
+        let mut e = self.e_0;
+        for i in 0..k {
+            let L = vartime::k_fold_scalar_mult(&[self.s_1[i], -&e],
+                                                &[params.G().basepoint(), keys[i].0]);
+            let M = vartime::k_fold_scalar_mult(&[self.s_2[i], -&e],
+                                                &[params.G().basepoint(), keys[i].1]);
+            e = disjunctive_challenge(ranges, &D_bytes, &E_bytes, i, &L.compress(), &M.compress());
+        }
+
+        if e == self.e_0 {
+            Some(C)
+        } else {
+            None
+        }
+    }
+
+    /// Encode the proof as
+    /// `n || D || e_0 || lower || upper || s_1[0] || s_2[0] || ...`,
+    /// where `n` is the number of rings of each of the rangeproofs, as
+    /// a single byte, and `lower` and `upper` are encoded as for
+    /// `RangeProof::to_bytes`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(65 + self.lower.size_in_bytes() + self.upper.size_in_bytes()
+                                           + 64 * self.s_1.len());
+        bytes.push(self.lower.C.len() as u8);
+        bytes.extend_from_slice(self.D.compress().as_bytes());
+        bytes.extend_from_slice(self.e_0.as_bytes());
+        bytes.extend_from_slice(&self.lower.to_bytes());
+        bytes.extend_from_slice(&self.upper.to_bytes());
+        for (s_1, s_2) in self.s_1.iter().zip(self.s_2.iter()) {
+            bytes.extend_from_slice(s_1.as_bytes());
+            bytes.extend_from_slice(s_2.as_bytes());
+        }
+        bytes
+    }
+
+    /// Decode a proof encoded by `to_bytes`.
+    ///
+    /// # Returns
+    ///
+    /// A `DecodingError::InvalidLength` if `n > RANGEPROOF_MAX_N`, or
+    /// if the length of `bytes` isn't that of a proof with `n` rings
+    /// and at least one range, and otherwise as for
+    /// `RangeProof::from_bytes` if `D`, any of the scalars, or either
+    /// rangeproof isn't canonically encoded.
+    pub fn from_bytes(bytes: &[u8]) -> Result<DisjunctiveRangeProof, DecodingError> {
+        let n = *bytes.first().ok_or(DecodingError::InvalidLength)? as usize;
+        if n > RANGEPROOF_MAX_N {
+            return Err(DecodingError::InvalidLength);
+        }
+        let proof_length = 32 + RING_LENGTH * n;
+        let header_length = 65 + 2 * proof_length;
+        if bytes.len() <= header_length || (bytes.len() - header_length) % 64 != 0 {
+            return Err(DecodingError::InvalidLength);
+        }
+        let k = (bytes.len() - header_length) / 64;
+
+        let mut s_1 = Vec::with_capacity(k);
+        let mut s_2 = Vec::with_capacity(k);
+        for member in bytes[header_length..].chunks(64) {
+            s_1.push(read_scalar(&member[..32])?);
+            s_2.push(read_scalar(&member[32..])?);
+        }
+
+        Ok(DisjunctiveRangeProof {
+            D: read_point(&bytes[1..33])?,
+            e_0: read_scalar(&bytes[33..65])?,
+            lower: RangeProof::from_bytes_exact(&bytes[65..65 + proof_length], n)?,
+            upper: RangeProof::from_bytes_exact(&bytes[65 + proof_length..header_length], n)?,
+            s_1: s_1,
+            s_2: s_2,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::OsRng;
+
+    use DEFAULT_PARAMS;
+
+    #[test]
+    fn prove_and_verify_union_of_ranges() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let ranges = [(0, 100), (1000, 5000), (1000000, 1000000)];
+        for &value in [0, 57, 100, 1000, 4321, 1000000].iter() {
+            let (proof, commitment, blinding) =
+                DisjunctiveRangeProof::create(&ranges, value, &DEFAULT_PARAMS, &mut csprng).unwrap();
+            let C = proof.verify(&ranges, &DEFAULT_PARAMS).unwrap();
+            assert_eq!(C.compress(), commitment.compress());
+
+            let C_hat = &(DEFAULT_PARAMS.G() * blinding.expose_secret())
+                + &(DEFAULT_PARAMS.H() * &Scalar::from_u64(value));
+            assert_eq!(C.compress(), C_hat.compress());
+
+            // The proof doesn't verify for other ranges.
+            assert!(proof.verify(&ranges[..2], &DEFAULT_PARAMS).is_none());
+            assert!(proof.verify(&[(0, 100), (1000, 5000), (999999, 1000000)], &DEFAULT_PARAMS).is_none());
+        }
+
+        assert!(DisjunctiveRangeProof::create(&ranges, 101, &DEFAULT_PARAMS, &mut csprng).is_none());
+        assert!(DisjunctiveRangeProof::create(&[], 0, &DEFAULT_PARAMS, &mut csprng).is_none());
+        assert!(DisjunctiveRangeProof::create(&[(5, 4)], 5, &DEFAULT_PARAMS, &mut csprng).is_none());
+    }
+
+    #[test]
+    fn disjunctive_encoding_roundtrip() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let ranges = [(0, 100), (1000, 5000), (1000000, 1000000)];
+        let (proof, commitment, _) =
+            DisjunctiveRangeProof::create(&ranges, 4321, &DEFAULT_PARAMS, &mut csprng).unwrap();
+        let bytes = proof.to_bytes();
+        assert_eq!(bytes.len(), 65 + 2 * (32 + RING_LENGTH * 8) + 64 * 3);
+
+        let decoded = DisjunctiveRangeProof::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.to_bytes(), bytes);
+        assert_eq!(decoded.verify(&ranges, &DEFAULT_PARAMS).unwrap().compress(), commitment.compress());
+
+        assert_eq!(DisjunctiveRangeProof::from_bytes(&bytes[..bytes.len() - 1]).err(),
+                   Some(DecodingError::InvalidLength));
+        assert_eq!(DisjunctiveRangeProof::from_bytes(&bytes[..bytes.len() - 64 * 3]).err(),
+                   Some(DecodingError::InvalidLength));
+
+        // Each of the ring scalars must be reduced.
+        let mut non_canonical = bytes.clone();
+        let last = non_canonical.len() - 1;
+        non_canonical[last] = 0xff;
+        assert_eq!(DisjunctiveRangeProof::from_bytes(&non_canonical).err(),
+                   Some(DecodingError::NonCanonicalScalar));
+        let mut non_canonical = bytes.clone();
+        non_canonical[64] = 0xff;
+        assert_eq!(DisjunctiveRangeProof::from_bytes(&non_canonical).err(),
+                   Some(DecodingError::NonCanonicalScalar));
+    }
+}
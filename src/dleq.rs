@@ -0,0 +1,168 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Proofs of discrete logarithm equality.
+//!
+//! A DLEQ proof shows that `X = x*A` and `Y = x*B` for the same secret
+//! scalar `x` and public bases `A` and `B`, without revealing `x`.  It
+//! is the Chaum-Pedersen protocol, made non-interactive: the prover
+//! commits to `K_A = k*A` and `K_B = k*B`, derives the challenge `c`
+//! from the statement and the commitments, and responds with
+//! `s = k + c*x`.
+//!
+//! For example, with `A = G`, a DLEQ proof links the blinding `r` of a
+//! commitment to a value `v` with known `C - v*H = r*G` to the point
+//! `r*P` for an encryption key `P`.
+
+#[cfg(feature = "schemars")]
+use schemars;
+
+use rand::Rng;
+
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::decaf::DecafPoint;
+use curve25519_dalek::decaf::vartime;
+
+use challenges::dleq_challenge;
+use encoding::read_scalar;
+use errors::DecodingError;
+use transcript_rng::TranscriptRng;
+
+/// The length in bytes of an encoded `DleqProof`.
+pub const DLEQ_PROOF_LENGTH: usize = 64;
+
+/// A proof that two points have the same discrete logarithm with
+/// respect to two bases.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct DleqProof {
+    #[cfg_attr(feature = "serde", serde(with = "::serialization::scalar"))]
+    #[cfg_attr(feature = "schemars", schemars(with = "::schema::Hex32"))]
+    c: Scalar,
+    #[cfg_attr(feature = "serde", serde(with = "::serialization::scalar"))]
+    #[cfg_attr(feature = "schemars", schemars(with = "::schema::Hex32"))]
+    s: Scalar,
+}
+
+impl DleqProof {
+    /// Prove that `x*A` and `x*B` have the same discrete logarithm `x`
+    /// with respect to `A` and `B`.
+    ///
+    /// # Returns
+    ///
+    /// A tuple `(proof, X, Y)`, where `X = x*A` and `Y = x*B`.
+    pub fn create<T: Rng>(
+        x: &Scalar,
+        A: &DecafPoint,
+        B: &DecafPoint,
+        csprng: &mut T,
+    ) -> (DleqProof, DecafPoint, DecafPoint) {
+        let X = A * x;
+        let Y = B * x;
+        let (A_c, X_c, B_c, Y_c) = (A.compress(), X.compress(), B.compress(), Y.compress());
+
+        let mut rng = TranscriptRng::builder(b"dleq")
+            .commit_transcript(b"A", A_c.as_bytes())
+            .commit_transcript(b"X", X_c.as_bytes())
+            .commit_transcript(b"B", B_c.as_bytes())
+            .commit_transcript(b"Y", Y_c.as_bytes())
+            .commit_witness(b"x", x.as_bytes())
+            .finalize(csprng);
+        let k = Scalar::random(&mut rng);
+        let K_A = A * &k;
+        let K_B = B * &k;
+
+        let c = dleq_challenge(&A_c, &X_c, &B_c, &Y_c, &K_A.compress(), &K_B.compress());
+        let s = Scalar::multiply_add(&c, x, &k);
+
+        (DleqProof { c: c, s: s }, X, Y)
+    }
+
+    /// Verify that `X` and `Y` have the same discrete logarithm with
+    /// respect to `A` and `B`.
+    #[must_use]
+    pub fn verify(&self, A: &DecafPoint, X: &DecafPoint, B: &DecafPoint, Y: &DecafPoint) -> bool {
+        let K_A = vartime::k_fold_scalar_mult(&[self.s, -&self.c], &[*A, *X]);
+        let K_B = vartime::k_fold_scalar_mult(&[self.s, -&self.c], &[*B, *Y]);
+
+        dleq_challenge(&A.compress(), &X.compress(), &B.compress(), &Y.compress(),
+                       &K_A.compress(), &K_B.compress()) == self.c
+    }
+
+    /// Encode the proof as `c || s`, in 64 bytes.
+    pub fn to_bytes(&self) -> [u8; DLEQ_PROOF_LENGTH] {
+        let mut bytes = [0u8; DLEQ_PROOF_LENGTH];
+        bytes[..32].copy_from_slice(self.c.as_bytes());
+        bytes[32..].copy_from_slice(self.s.as_bytes());
+        bytes
+    }
+
+    /// Decode a proof encoded by `to_bytes`.
+    ///
+    /// # Returns
+    ///
+    /// A `DecodingError::InvalidLength` if `bytes` isn't 64 bytes long,
+    /// or a `DecodingError::NonCanonicalScalar` if either scalar isn't
+    /// canonically encoded.
+    pub fn from_bytes(bytes: &[u8]) -> Result<DleqProof, DecodingError> {
+        if bytes.len() != DLEQ_PROOF_LENGTH {
+            return Err(DecodingError::InvalidLength);
+        }
+
+        Ok(DleqProof { c: read_scalar(&bytes[..32])?, s: read_scalar(&bytes[32..])? })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::OsRng;
+    #[cfg(feature = "serde")]
+    use serde_json;
+
+    use DEFAULT_PARAMS;
+
+    #[test]
+    fn prove_and_verify_dleq() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let A = DEFAULT_PARAMS.G().basepoint();
+        let B = DEFAULT_PARAMS.G() * &Scalar::random(&mut csprng);
+        let x = Scalar::random(&mut csprng);
+
+        let (proof, X, Y) = DleqProof::create(&x, &A, &B, &mut csprng);
+        assert!(proof.verify(&A, &X, &B, &Y));
+        assert!(!proof.verify(&A, &X, &B, &(&Y + &B)));
+        assert!(!proof.verify(&B, &Y, &A, &X));
+
+        let decoded = DleqProof::from_bytes(&proof.to_bytes()).unwrap();
+        assert!(decoded.verify(&A, &X, &B, &Y));
+        assert_eq!(DleqProof::from_bytes(&[0u8; 63]).err(), Some(DecodingError::InvalidLength));
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn dleq_proof_serde_roundtrip() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let A = DEFAULT_PARAMS.G().basepoint();
+        let B = DEFAULT_PARAMS.G() * &Scalar::random(&mut csprng);
+        let (proof, X, Y) = DleqProof::create(&Scalar::random(&mut csprng), &A, &B, &mut csprng);
+
+        let json = serde_json::to_string(&proof).unwrap();
+        let decoded: DleqProof = serde_json::from_str(&json).unwrap();
+        assert!(decoded.verify(&A, &X, &B, &Y));
+    }
+}
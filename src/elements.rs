@@ -0,0 +1,270 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Rangeproofs with the container fields of Elements.
+//!
+//! The rangeproofs of `libsecp256k1-zkp`, as used by Elements, prove
+//! that a committed value is
+//!
+//! ```text
+//! v = min_value + 10^exp * x,    0 <= x < 2^mantissa
+//! ```
+//!
+//! and carry `min_value`, `exp` and `mantissa` in the clear, along
+//! with a `message` for the recipient.  An `ElementsRangeProof` has the
+//! same fields, with the same meaning, so that data models ported from
+//! Elements map onto it one-to-one.  Underneath, `x` is proven with a
+//! `RangeProof` with the least `n` such that `3^n >= 2^mantissa`, and
+//! the commitment to `v` is `10^exp * C_x + min_value * H`.
+//!
+//! Unlike in Elements, the `message` is not hidden in the proof and
+//! can't be recovered by rewinding it: it's carried in the container
+//! as is, and should be encrypted by the caller if it's private.  Only
+//! exponents in `0..=ELEMENTS_MAX_EXP` are supported, not the exact
+//! value proofs of `exp = -1`.
+
+use rand::Rng;
+
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::decaf::DecafPoint;
+
+use blinding::SecretBlinding;
+use errors::DecodingError;
+use params::RangeProofParams;
+use two_sided::rings_for_width;
+
+use super::RangeProof;
+
+/// The largest supported decimal exponent, so that `10^exp` fits in a
+/// `u64`.
+pub const ELEMENTS_MAX_EXP: u8 = 18;
+
+/// The length in bytes of the encoded header,
+/// `exp || mantissa || min_value || message length`.
+const HEADER_LENGTH: usize = 12;
+
+/// A rangeproof with the `min_value`, `exp`, `mantissa` and `message`
+/// fields of an Elements rangeproof.
+pub struct ElementsRangeProof {
+    min_value: u64,
+    exp: u8,
+    mantissa: u8,
+    message: Vec<u8>,
+    proof: RangeProof,
+}
+
+/// The number of rings proving a mantissa of `mantissa` bits, or None
+/// if `mantissa` isn't in `1..=64`.
+fn rings_for_mantissa(mantissa: u8) -> Option<usize> {
+    if mantissa == 0 || mantissa > 64 {
+        return None;
+    }
+    Some(rings_for_width(u64::max_value() >> (64 - mantissa)))
+}
+
+impl ElementsRangeProof {
+    /// Construct a proof that `value = min_value + 10^exp * x` with
+    /// `x < 2^mantissa`, in constant time.
+    ///
+    /// # Inputs
+    ///
+    /// * The `value` to prove;
+    /// * The public `min_value`, `exp` and `mantissa`, with
+    /// `exp <= ELEMENTS_MAX_EXP` and `1 <= mantissa <= 64`;
+    /// * The `message` to carry with the proof, in the clear;
+    /// * The precomputed `params`;
+    /// * `csprng`, an implementation of `rand::Rng`, which should be
+    /// cryptographically secure.
+    ///
+    /// # Returns
+    ///
+    /// If the parameters are out of bounds, `value < min_value`,
+    /// `value - min_value` isn't a multiple of `10^exp`, or `x` has
+    /// more than `mantissa` bits, return None.
+    ///
+    /// Otherwise, returns `Some((proof, commitment, blinding))`, where
+    /// `commitment = blinding*G + value*H`.
+    pub fn create<T: Rng>(
+        value: u64,
+        min_value: u64,
+        exp: u8,
+        mantissa: u8,
+        message: &[u8],
+        params: &RangeProofParams,
+        csprng: &mut T,
+    ) -> Option<(ElementsRangeProof, DecafPoint, SecretBlinding)> {
+        if exp > ELEMENTS_MAX_EXP || message.len() > u16::max_value() as usize || value < min_value {
+            return None;
+        }
+        let n = rings_for_mantissa(mantissa)?;
+        let scale = 10u64.pow(exp as u32);
+        let shifted = value - min_value;
+        if shifted % scale != 0 {
+            return None;
+        }
+        let x = shifted / scale;
+        if mantissa < 64 && x >> mantissa != 0 {
+            return None;
+        }
+
+        let (proof, C_x, r_x) = RangeProof::create_with_params(n, x, params, csprng)?.into_parts();
+        let scale = Scalar::from_u64(scale);
+        let commitment = &(&C_x * &scale) + &(params.H_table() * &Scalar::from_u64(min_value));
+        let blinding = SecretBlinding::new(r_x.expose_secret() * &scale);
+
+        Some((
+            ElementsRangeProof {
+                min_value: min_value,
+                exp: exp,
+                mantissa: mantissa,
+                message: message.to_vec(),
+                proof: proof,
+            },
+            commitment,
+            blinding,
+        ))
+    }
+
+    /// Verify the proof, returning the commitment to the value along
+    /// with the least and greatest values it can open to, as for
+    /// `secp256k1_rangeproof_verify`.
+    ///
+    /// Since the underlying proof is over base-3 digits, the greatest
+    /// value can exceed `min_value + 10^exp * (2^mantissa - 1)`.
+    #[must_use]
+    pub fn verify(&self, params: &RangeProofParams) -> Option<(DecafPoint, u64, u64)> {
+        if self.exp > ELEMENTS_MAX_EXP {
+            return None;
+        }
+        let n = rings_for_mantissa(self.mantissa)?;
+        let C_x = self.proof.verify_with_params(n, params)?;
+
+        let scale = 10u64.pow(self.exp as u32);
+        let commitment = &(&C_x * &Scalar::from_u64(scale))
+            + &(params.H_table() * &Scalar::from_u64(self.min_value));
+        let max_x = 3u128.pow(n as u32) - 1;
+        let max_value = (self.min_value as u128 + scale as u128 * max_x).min(u64::max_value() as u128);
+
+        Some((commitment, self.min_value, max_value as u64))
+    }
+
+    /// The public minimum value.
+    pub fn min_value(&self) -> u64 {
+        self.min_value
+    }
+
+    /// The public decimal exponent.
+    pub fn exp(&self) -> u8 {
+        self.exp
+    }
+
+    /// The public number of bits of the mantissa.
+    pub fn mantissa(&self) -> u8 {
+        self.mantissa
+    }
+
+    /// The message carried with the proof.
+    pub fn message(&self) -> &[u8] {
+        &self.message
+    }
+
+    /// The underlying rangeproof for the mantissa.
+    pub fn proof(&self) -> &RangeProof {
+        &self.proof
+    }
+
+    /// Encode the proof as
+    ///
+    /// ```text
+    /// exp || mantissa || min_value || len(message) || message || proof
+    /// ```
+    ///
+    /// where `min_value` is 8 little-endian bytes, the length of the
+    /// message is 2 little-endian bytes, and the proof is encoded as for
+    /// `RangeProof::to_bytes`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let proof = self.proof.to_bytes();
+        let mut bytes = Vec::with_capacity(HEADER_LENGTH + self.message.len() + proof.len());
+        bytes.push(self.exp);
+        bytes.push(self.mantissa);
+        bytes.extend_from_slice(&self.min_value.to_le_bytes());
+        bytes.extend_from_slice(&(self.message.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(&self.message);
+        bytes.extend_from_slice(&proof);
+        bytes
+    }
+
+    /// Decode a proof encoded by `to_bytes`.
+    ///
+    /// # Returns
+    ///
+    /// A `DecodingError::InvalidLength` if `bytes` is too short for its
+    /// header or message, or any error from `RangeProof::from_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<ElementsRangeProof, DecodingError> {
+        if bytes.len() < HEADER_LENGTH {
+            return Err(DecodingError::InvalidLength);
+        }
+        let mut min_value = [0u8; 8];
+        min_value.copy_from_slice(&bytes[2..10]);
+        let message_len = bytes[10] as usize | (bytes[11] as usize) << 8;
+        if bytes.len() < HEADER_LENGTH + message_len {
+            return Err(DecodingError::InvalidLength);
+        }
+        let (message, proof) = bytes[HEADER_LENGTH..].split_at(message_len);
+
+        Ok(ElementsRangeProof {
+            min_value: u64::from_le_bytes(min_value),
+            exp: bytes[0],
+            mantissa: bytes[1],
+            message: message.to_vec(),
+            proof: RangeProof::from_bytes(proof)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::OsRng;
+
+    use DEFAULT_PARAMS;
+
+    #[test]
+    fn prove_and_verify_elements_fields() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let value = 1000 + 300 * 100;
+        let (proof, commitment, blinding) =
+            ElementsRangeProof::create(value, 1000, 2, 10, b"memo", &DEFAULT_PARAMS, &mut csprng).unwrap();
+        let (C, min_value, max_value) = proof.verify(&DEFAULT_PARAMS).unwrap();
+        assert_eq!(C.compress(), commitment.compress());
+        assert_eq!(min_value, 1000);
+        // 3^7 - 1 >= 2^10 - 1
+        assert_eq!(max_value, 1000 + 100 * 2186);
+
+        let C_hat = &(DEFAULT_PARAMS.G() * blinding.expose_secret())
+            + &(DEFAULT_PARAMS.H() * &Scalar::from_u64(value));
+        assert_eq!(C.compress(), C_hat.compress());
+
+        let decoded = ElementsRangeProof::from_bytes(&proof.to_bytes()).unwrap();
+        assert_eq!(decoded.message(), b"memo");
+        assert_eq!((decoded.exp(), decoded.mantissa(), decoded.min_value()), (2, 10, 1000));
+        assert_eq!(decoded.verify(&DEFAULT_PARAMS).unwrap().0.compress(), commitment.compress());
+
+        // Not a multiple of 10^exp, below the minimum, or too many bits.
+        assert!(ElementsRangeProof::create(value + 1, 1000, 2, 10, b"", &DEFAULT_PARAMS, &mut csprng).is_none());
+        assert!(ElementsRangeProof::create(999, 1000, 0, 10, b"", &DEFAULT_PARAMS, &mut csprng).is_none());
+        assert!(ElementsRangeProof::create(1024, 0, 0, 10, b"", &DEFAULT_PARAMS, &mut csprng).is_none());
+        assert!(ElementsRangeProof::create(u64::max_value(), 0, 0, 64, b"", &DEFAULT_PARAMS, &mut csprng).is_some());
+    }
+}
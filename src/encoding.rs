@@ -0,0 +1,737 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Compact byte encodings of rangeproofs.
+//!
+//! A rangeproof with `n` rings is encoded in the optimal `32(1+3n)`
+//! bytes as
+//!
+//! ```text
+//! e_0 || C_0 || s_1,0 || s_2,0 || ... || C_{n-1} || s_1,n-1 || s_2,n-1
+//! ```
+//!
+//! where scalars are encoded as their 32 little-endian bytes and
+//! points are encoded as compressed Decaf points.  The number of
+//! rings is implied by the length of the encoding.
+//!
+//! The self-describing encoding prefixes this with two bytes, the
+//! base `m` and the number of rings `n`, so that a verifier can learn
+//! the range `[0,m^n]` from the proof itself.
+//!
+//! The reduced encoding omits `C_{n-1}`, which the verifier can
+//! reconstruct from the commitment `C = Σ C_i` if it is sent anyway.
+//! A proof with no rings has no `C_{n-1}`, so its reduced encoding is
+//! `e_0` alone.
+
+use std::mem;
+
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::curve::Identity;
+use curve25519_dalek::decaf::{CompressedDecaf, DecafBasepointTable, DecafPoint};
+
+use challenges::e_0_challenge;
+use errors::DecodingError;
+use params::PowersOfH;
+
+#[cfg(feature = "no_panic")]
+use no_panic::no_panic;
+
+use super::ring_transcript;
+use super::RangeProof;
+use super::RangeProofParams;
+use super::RANGEPROOF_MAX_N;
+
+/// The base `m` of the digits proven in each ring.
+pub const RANGEPROOF_BASE: u8 = 3;
+
+/// The length in bytes of a single encoded ring, `C_i || s_1,i || s_2,i`.
+pub(crate) const RING_LENGTH: usize = 96;
+
+/// The length in bytes of the self-describing header, `m || n`.
+pub(crate) const SELF_DESCRIBING_HEADER_LENGTH: usize = 2;
+
+/// Limits on the size of the rangeproofs a decoder accepts.
+///
+/// The limits are checked against the length of the input, and against
+/// any number of rings claimed by a header, before anything is
+/// allocated or any point is decompressed, so that a peer can't make a
+/// node do more work than the largest proof it is willing to accept.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct DecodingLimits {
+    max_n: usize,
+    max_bytes: usize,
+}
+
+impl DecodingLimits {
+    /// Accept proofs with at most `max_n` rings, whose encodings are at
+    /// most `max_bytes` long.
+    ///
+    /// # Returns
+    ///
+    /// The limits, or None if `max_n > RANGEPROOF_MAX_N`.
+    pub fn new(max_n: usize, max_bytes: usize) -> Option<DecodingLimits> {
+        if max_n > RANGEPROOF_MAX_N {
+            return None;
+        }
+
+        Some(DecodingLimits { max_n: max_n, max_bytes: max_bytes })
+    }
+
+    /// The largest number of rings accepted.
+    pub fn max_n(&self) -> usize {
+        self.max_n
+    }
+
+    /// The length in bytes of the longest encoding accepted.
+    pub fn max_bytes(&self) -> usize {
+        self.max_bytes
+    }
+
+    /// Check an encoding of `length` bytes claiming `n` rings.
+    pub(crate) fn check(&self, n: usize, length: usize) -> Result<(), DecodingError> {
+        if n > self.max_n || length > self.max_bytes {
+            return Err(DecodingError::LimitExceeded);
+        }
+        Ok(())
+    }
+}
+
+impl Default for DecodingLimits {
+    /// Accept every proof which this library can verify, up to the
+    /// self-describing encoding of a proof with `RANGEPROOF_MAX_N`
+    /// rings.
+    fn default() -> DecodingLimits {
+        DecodingLimits {
+            max_n: RANGEPROOF_MAX_N,
+            max_bytes: SELF_DESCRIBING_HEADER_LENGTH + 32 + RING_LENGTH * RANGEPROOF_MAX_N,
+        }
+    }
+}
+
+/// Returns `true` if `s` is the canonical representative of its
+/// residue class modulo the group order `l`, i.e. if `s < l`.
+pub(crate) fn scalar_is_canonical(s: &Scalar) -> bool {
+    Scalar::multiply_add(&Scalar::one(), s, &Scalar::zero()) == *s
+}
+
+/// Read a scalar from the 32 bytes at the start of `bytes`, rejecting
+/// any encoding of a scalar which is not reduced modulo `l`.
+pub(crate) fn read_scalar(bytes: &[u8]) -> Result<Scalar, DecodingError> {
+    let mut s = [0u8; 32];
+    s.copy_from_slice(bytes.get(..32).ok_or(DecodingError::InvalidLength)?);
+    let s = Scalar(s);
+
+    if !scalar_is_canonical(&s) {
+        return Err(DecodingError::NonCanonicalScalar);
+    }
+    Ok(s)
+}
+
+/// Read and decompress a point from the 32 bytes at the start of
+/// `bytes`, rejecting any encoding which doesn't recompress to itself.
+pub(crate) fn read_point(bytes: &[u8]) -> Result<DecafPoint, DecodingError> {
+    let mut p = [0u8; 32];
+    p.copy_from_slice(bytes.get(..32).ok_or(DecodingError::InvalidLength)?);
+    let point = CompressedDecaf(p).decompress().ok_or(DecodingError::InvalidPoint)?;
+
+    if point.compress().as_bytes() != &p {
+        return Err(DecodingError::InvalidPoint);
+    }
+    Ok(point)
+}
+
+/// Encode `bytes` as lowercase hex.
+#[cfg_attr(not(feature = "serde"), allow(dead_code))]
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+    const DIGITS: &'static [u8; 16] = b"0123456789abcdef";
+
+    let mut hex = String::with_capacity(2 * bytes.len());
+    for b in bytes.iter() {
+        hex.push(DIGITS[(b >> 4) as usize] as char);
+        hex.push(DIGITS[(b & 0xf) as usize] as char);
+    }
+    hex
+}
+
+/// Decode a hex string encoding exactly 32 bytes.
+#[cfg_attr(not(feature = "serde"), allow(dead_code))]
+pub(crate) fn from_hex_32(hex: &str) -> Result<[u8; 32], DecodingError> {
+    fn nibble(c: u8) -> Result<u8, DecodingError> {
+        match c {
+            b'0'...b'9' => Ok(c - b'0'),
+            b'a'...b'f' => Ok(c - b'a' + 10),
+            b'A'...b'F' => Ok(c - b'A' + 10),
+            _ => Err(DecodingError::InvalidHex),
+        }
+    }
+
+    let hex = hex.as_bytes();
+    if hex.len() != 64 {
+        return Err(DecodingError::InvalidHex);
+    }
+    let mut bytes = [0u8; 32];
+    for i in 0..32 {
+        bytes[i] = (nibble(hex[2*i])? << 4) | nibble(hex[2*i + 1])?;
+    }
+    Ok(bytes)
+}
+
+impl RangeProof {
+    /// The length in bytes of the encoding produced by `to_bytes`,
+    /// `32(1+3n)`, computed without encoding the proof.
+    pub fn size_in_bytes(&self) -> usize {
+        32 + RING_LENGTH * self.C.len()
+    }
+
+    /// The number of bytes this rangeproof holds on the heap, for
+    /// memory accounting.  This excludes the `size_of::<RangeProof>()`
+    /// bytes of the proof itself.
+    pub fn heap_size(&self) -> usize {
+        self.C.capacity() * mem::size_of::<DecafPoint>()
+            + self.s_1.capacity() * mem::size_of::<Scalar>()
+            + self.s_2.capacity() * mem::size_of::<Scalar>()
+    }
+
+    /// Encode this rangeproof in `32(1+3n)` bytes.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let n = self.C.len();
+        let mut bytes = Vec::with_capacity(32 + RING_LENGTH * n);
+
+        bytes.extend_from_slice(self.e_0.as_bytes());
+        for i in 0..n {
+            bytes.extend_from_slice(self.C[i].compress().as_bytes());
+            bytes.extend_from_slice(self.s_1[i].as_bytes());
+            bytes.extend_from_slice(self.s_2[i].as_bytes());
+        }
+        bytes
+    }
+
+    /// Decode a rangeproof from the encoding produced by `to_bytes`.
+    ///
+    /// Only canonical encodings are accepted, so that for any `bytes`
+    /// which decode successfully, `from_bytes(bytes)?.to_bytes() == bytes`.
+    /// Systems which identify proofs by hashing their encodings can
+    /// therefore rely on the encoding of a proof being unique.
+    ///
+    /// # Returns
+    ///
+    /// A `DecodingError` if the length of `bytes` is not `32(1+3n)` for
+    /// some `n <= RANGEPROOF_MAX_N`, if any of the points fail to
+    /// decompress, or if any of the points or scalars are not
+    /// canonically encoded.
+    pub fn from_bytes(bytes: &[u8]) -> Result<RangeProof, DecodingError> {
+        if bytes.len() < 32 || (bytes.len() - 32) % RING_LENGTH != 0 {
+            return Err(DecodingError::InvalidLength);
+        }
+        let n = (bytes.len() - 32) / RING_LENGTH;
+        if n > RANGEPROOF_MAX_N {
+            return Err(DecodingError::InvalidLength);
+        }
+
+        let e_0 = read_scalar(&bytes[..32])?;
+        let mut C   = Vec::with_capacity(n);
+        let mut s_1 = Vec::with_capacity(n);
+        let mut s_2 = Vec::with_capacity(n);
+
+        for ring in bytes[32..].chunks(RING_LENGTH) {
+            C.push(read_point(&ring[0..32])?);
+            s_1.push(read_scalar(&ring[32..64])?);
+            s_2.push(read_scalar(&ring[64..96])?);
+        }
+
+        Ok(RangeProof{ e_0: e_0, C: C, s_1: s_1, s_2: s_2 })
+    }
+
+    /// Decode a rangeproof from the encoding produced by `to_bytes`,
+    /// rejecting it before decoding anything if it exceeds `limits`.
+    ///
+    /// # Returns
+    ///
+    /// A `DecodingError::LimitExceeded` if `bytes` is longer than
+    /// `limits.max_bytes()` or has more than `limits.max_n()` rings,
+    /// and otherwise as for `from_bytes`.
+    pub fn from_bytes_with_limits(bytes: &[u8], limits: &DecodingLimits) -> Result<RangeProof, DecodingError> {
+        limits.check(bytes.len().saturating_sub(32) / RING_LENGTH, bytes.len())?;
+
+        RangeProof::from_bytes(bytes)
+    }
+
+    /// Decode a rangeproof with exactly `n` rings from the encoding
+    /// produced by `to_bytes`.
+    ///
+    /// Unlike `from_bytes`, which infers `n` from the length of `bytes`,
+    /// this rejects any extra bytes following the `n`-th ring rather
+    /// than interpreting them as further rings.
+    ///
+    /// # Returns
+    ///
+    /// A `DecodingError::TrailingBytes` if `bytes` is longer than
+    /// `32(1+3n)`, a `DecodingError::InvalidLength` if it is shorter or
+    /// if `n > RANGEPROOF_MAX_N`, and otherwise as for `from_bytes`.
+    pub fn from_bytes_exact(bytes: &[u8], n: usize) -> Result<RangeProof, DecodingError> {
+        if n > RANGEPROOF_MAX_N {
+            return Err(DecodingError::InvalidLength);
+        }
+        let length = 32 + RING_LENGTH * n;
+        if bytes.len() < length {
+            return Err(DecodingError::InvalidLength);
+        } else if bytes.len() > length {
+            return Err(DecodingError::TrailingBytes);
+        }
+
+        RangeProof::from_bytes(bytes)
+    }
+
+    /// Verify a rangeproof with `n` rings directly from the encoding
+    /// produced by `to_bytes`, returning a Pedersen commitment to the
+    /// in-range value if successful.
+    ///
+    /// The proof is verified ring by ring as it is decoded, without
+    /// constructing a `RangeProof`, and verification stops at the
+    /// first ring which fails to decode.  This accepts exactly the
+    /// encodings for which `from_bytes_exact(bytes, n)` followed by
+    /// `verify` succeeds, and so returns None if `n > RANGEPROOF_MAX_N`.
+    #[must_use]
+    pub fn verify_bytes(
+        bytes: &[u8],
+        n: usize,
+        G: &DecafBasepointTable,
+        H: &DecafPoint,
+    ) -> Option<DecafPoint> {
+        if n > RANGEPROOF_MAX_N || bytes.len() != 32 + RING_LENGTH * n {
+            return None;
+        }
+
+        RangeProof::verify_bytes_with_powers(bytes, n, G, &PowersOfH::with_len(n, H))
+    }
+
+    /// Verify a rangeproof with `n` rings directly from its encoding,
+    /// given the precomputed powers of `H` for `i` in `0..n`.
+    ///
+    /// This can't panic, whatever the bytes, and with the `no_panic`
+    /// feature this is checked at link time.
+    #[cfg_attr(feature = "no_panic", no_panic)]
+    fn verify_bytes_with_powers(
+        bytes: &[u8],
+        n: usize,
+        G: &DecafBasepointTable,
+        powers: &PowersOfH,
+    ) -> Option<DecafPoint> {
+        let mut R = [CompressedDecaf([0u8; 32]); RANGEPROOF_MAX_N];
+        if n > R.len() || n > powers.len() || bytes.len() != 32 + RING_LENGTH * n {
+            return None;
+        }
+
+        let e_0 = read_scalar(bytes).ok()?;
+        let mut C = DecafPoint::identity();
+        let rings = bytes.get(32..)?.chunks(RING_LENGTH);
+
+        for (ring, ((mi_H, mi2_H), R_i)) in rings.zip(powers.iter().zip(R.iter_mut())) {
+            let C_i = read_point(ring).ok()?;
+            let s_1 = read_scalar(ring.get(32..)?).ok()?;
+            let s_2 = read_scalar(ring.get(64..)?).ok()?;
+
+            *R_i = ring_transcript(&C_i, &s_1, &s_2, &e_0, G, mi_H, mi2_H).R;
+            C = &C + &C_i;
+        }
+
+        if e_0_challenge(R.iter().take(n).cloned()) == e_0 {
+            Some(C)
+        } else {
+            None
+        }
+    }
+
+    /// Returns `true` if this rangeproof has a unique encoding, i.e. all
+    /// of its scalars are reduced modulo `l` and it has the same number
+    /// of entries in each of its vectors.
+    ///
+    /// Proofs obtained from `from_bytes` are always canonical.  Proofs
+    /// obtained by other means, e.g. through `serde`, may not be, and
+    /// `verify` rejects any proof which is not canonical.
+    pub fn is_canonical(&self) -> bool {
+        let n = self.C.len();
+        if n != self.s_1.len() || n != self.s_2.len() {
+            return false;
+        }
+
+        scalar_is_canonical(&self.e_0)
+            && self.s_1.iter().all(scalar_is_canonical)
+            && self.s_2.iter().all(scalar_is_canonical)
+    }
+
+    /// Encode this rangeproof along with its base `m = 3` and its
+    /// number of rings `n`, in `2 + 32(1+3n)` bytes.
+    ///
+    /// # Returns
+    ///
+    /// A `DecodingError::InvalidLength` if `n > RANGEPROOF_MAX_N`, which
+    /// `from_self_describing_bytes` would reject.
+    pub fn to_self_describing_bytes(&self) -> Result<Vec<u8>, DecodingError> {
+        let n = self.C.len();
+        if n > RANGEPROOF_MAX_N {
+            return Err(DecodingError::InvalidLength);
+        }
+
+        let mut bytes = Vec::with_capacity(SELF_DESCRIBING_HEADER_LENGTH + 32 + RING_LENGTH * n);
+        bytes.push(RANGEPROOF_BASE);
+        bytes.push(n as u8);
+        bytes.extend_from_slice(&self.to_bytes());
+        Ok(bytes)
+    }
+
+    /// Decode a rangeproof from the encoding produced by
+    /// `to_self_describing_bytes`, returning `(n, proof)`.
+    ///
+    /// # Returns
+    ///
+    /// A `DecodingError` if the base is not `3`, if the number of rings
+    /// in the header disagrees with the length of the encoding, or if
+    /// the proof itself fails to decode, as for `from_bytes_exact`.
+    pub fn from_self_describing_bytes(bytes: &[u8]) -> Result<(usize, RangeProof), DecodingError> {
+        if bytes.len() < SELF_DESCRIBING_HEADER_LENGTH {
+            return Err(DecodingError::InvalidLength);
+        }
+        if bytes[0] != RANGEPROOF_BASE {
+            return Err(DecodingError::UnsupportedBase(bytes[0]));
+        }
+        let n = bytes[1] as usize;
+        let proof = RangeProof::from_bytes_exact(&bytes[SELF_DESCRIBING_HEADER_LENGTH..], n)?;
+
+        Ok((n, proof))
+    }
+
+    /// Decode a rangeproof from the encoding produced by
+    /// `to_self_describing_bytes`, rejecting it before decoding the
+    /// proof if its length or its header exceeds `limits`.
+    ///
+    /// # Returns
+    ///
+    /// A `DecodingError::LimitExceeded` if `bytes` is longer than
+    /// `limits.max_bytes()` or the header claims more than
+    /// `limits.max_n()` rings, and otherwise as for
+    /// `from_self_describing_bytes`.
+    pub fn from_self_describing_bytes_with_limits(
+        bytes: &[u8],
+        limits: &DecodingLimits,
+    ) -> Result<(usize, RangeProof), DecodingError> {
+        let n = bytes.get(1).map_or(0, |&n| n as usize);
+        limits.check(n, bytes.len())?;
+
+        RangeProof::from_self_describing_bytes(bytes)
+    }
+
+    /// Decode and verify a self-describing rangeproof, so that the
+    /// verifier doesn't need to be told `n` out-of-band.
+    ///
+    /// # Returns
+    ///
+    /// If the proof decodes and verifies, returns `Some((n, C))`, where
+    /// `C` is a Pedersen commitment to a value in the range `[0,3^n]`.
+    /// Callers **must** check that `n` is acceptable to them, since the
+    /// prover chose it.
+    ///
+    /// Otherwise, returns `None`.
+    #[must_use]
+    pub fn verify_self_describing(
+        bytes: &[u8],
+        params: &RangeProofParams,
+    ) -> Option<(usize, DecafPoint)> {
+        let (n, proof) = match RangeProof::from_self_describing_bytes(bytes) {
+            Ok(x)  => x,
+            Err(_) => return None,
+        };
+
+        proof.verify_with_params(n, params).map(|C| (n, C))
+    }
+
+    /// Encode this rangeproof without its last ring commitment
+    /// `C_{n-1}`, in `96n` bytes.
+    ///
+    /// When the commitment `C = Σ C_i` is transmitted alongside the
+    /// proof anyway, `C_{n-1} = C - Σ_{i<n-1} C_i` is redundant, and
+    /// `from_reduced_bytes` reconstructs it from `C`.
+    ///
+    /// A proof with no rings is encoded as `e_0` alone, in 32 bytes.
+    pub fn to_reduced_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.to_bytes();
+        let n = self.C.len();
+        if n == 0 {
+            return bytes;
+        }
+
+        // Remove C_{n-1}, which begins the last ring.
+        let last = 32 + RING_LENGTH * (n - 1);
+        bytes.drain(last..last + 32);
+        bytes
+    }
+
+    /// Decode a rangeproof from the encoding produced by
+    /// `to_reduced_bytes`, given the `commitment` it is for.
+    ///
+    /// The reconstructed proof verifies only if it proves exactly
+    /// `commitment`.
+    ///
+    /// # Returns
+    ///
+    /// A `DecodingError` if the length of `bytes` is not `96n` for some
+    /// `0 < n <= RANGEPROOF_MAX_N`, or `32` for a proof with no rings,
+    /// and otherwise as for `from_bytes`.  A proof with no rings only
+    /// proves the identity, so a `DecodingError::InvalidLength` is also
+    /// returned if it is decoded for any other `commitment`.
+    pub fn from_reduced_bytes(bytes: &[u8], commitment: &DecafPoint) -> Result<RangeProof, DecodingError> {
+        if bytes.len() == 32 {
+            if commitment.compress() != DecafPoint::identity().compress() {
+                return Err(DecodingError::InvalidLength);
+            }
+            return RangeProof::from_bytes(bytes);
+        }
+        if bytes.len() == 0 || bytes.len() % RING_LENGTH != 0 {
+            return Err(DecodingError::InvalidLength);
+        }
+        let n = bytes.len() / RING_LENGTH;
+        if n > RANGEPROOF_MAX_N {
+            return Err(DecodingError::InvalidLength);
+        }
+
+        // Everything but the last ring is encoded as usual.
+        let last = 32 + RING_LENGTH * (n - 1);
+        let mut proof = RangeProof::from_bytes(&bytes[..last])?;
+
+        let mut C_last = *commitment;
+        for C_i in proof.C.iter() {
+            C_last = &C_last - C_i;
+        }
+        proof.C.push(C_last);
+        proof.s_1.push(read_scalar(&bytes[last..last + 32])?);
+        proof.s_2.push(read_scalar(&bytes[last + 32..])?);
+
+        Ok(proof)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::OsRng;
+
+    use DEFAULT_PARAMS;
+
+    #[test]
+    fn to_bytes_from_bytes_roundtrip() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let n = 16;
+        let value = 13449261;
+        let (proof, commitment, _) =
+            RangeProof::create_with_params(n, value, &DEFAULT_PARAMS, &mut csprng).unwrap().into_parts();
+
+        let bytes = proof.to_bytes();
+        assert_eq!(bytes.len(), 32 * (1 + 3 * n));
+        assert_eq!(proof.size_in_bytes(), bytes.len());
+        assert!(proof.heap_size() >= n * (mem::size_of::<DecafPoint>() + 2 * mem::size_of::<Scalar>()));
+
+        let decoded = RangeProof::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.to_bytes(), bytes);
+        assert_eq!(decoded.verify_with_params(n, &DEFAULT_PARAMS).unwrap().compress(),
+                   commitment.compress());
+
+        assert_eq!(RangeProof::from_bytes(&bytes[1..]).err(), Some(DecodingError::InvalidLength));
+    }
+
+    #[test]
+    fn non_canonical_encodings_are_rejected() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let n = 4;
+        let proof =
+            RangeProof::create_with_params(n, 42, &DEFAULT_PARAMS, &mut csprng).unwrap().into_proof();
+        assert!(proof.is_canonical());
+
+        let bytes = proof.to_bytes();
+
+        // 2^256 - 1 is not reduced modulo l, whether as e_0 or s_1,0.
+        let mut malleated = bytes.clone();
+        for b in malleated[0..32].iter_mut() {
+            *b = 0xff;
+        }
+        assert_eq!(RangeProof::from_bytes(&malleated).err(),
+                   Some(DecodingError::NonCanonicalScalar));
+        let mut malleated = bytes.clone();
+        for b in malleated[64..96].iter_mut() {
+            *b = 0xff;
+        }
+        assert_eq!(RangeProof::from_bytes(&malleated).err(),
+                   Some(DecodingError::NonCanonicalScalar));
+
+        let unreduced = RangeProof{
+            e_0: Scalar([0xff; 32]),
+            C: proof.C.clone(),
+            s_1: proof.s_1.clone(),
+            s_2: proof.s_2.clone(),
+        };
+        assert!(!unreduced.is_canonical());
+        assert!(unreduced.verify_with_params(n, &DEFAULT_PARAMS).is_none());
+    }
+
+    #[test]
+    fn from_bytes_exact_rejects_trailing_bytes() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let proof =
+            RangeProof::create_with_params(4, 42, &DEFAULT_PARAMS, &mut csprng).unwrap().into_proof();
+        let other =
+            RangeProof::create_with_params(1, 2, &DEFAULT_PARAMS, &mut csprng).unwrap().into_proof();
+
+        let mut bytes = proof.to_bytes();
+        assert!(RangeProof::from_bytes_exact(&bytes, 4).is_ok());
+        assert_eq!(RangeProof::from_bytes_exact(&bytes, 5).err(),
+                   Some(DecodingError::InvalidLength));
+
+        // Appending a well-formed ring would be accepted by from_bytes
+        // as a proof with n = 5, but not by from_bytes_exact.
+        bytes.extend_from_slice(&other.to_bytes()[32..]);
+        assert!(RangeProof::from_bytes(&bytes).is_ok());
+        assert_eq!(RangeProof::from_bytes_exact(&bytes, 4).err(),
+                   Some(DecodingError::TrailingBytes));
+
+        let mut bytes = proof.to_self_describing_bytes().unwrap();
+        bytes.push(0);
+        assert_eq!(RangeProof::from_self_describing_bytes(&bytes).err(),
+                   Some(DecodingError::TrailingBytes));
+
+        // A proof with more rings than the header can describe is
+        // rejected rather than having its n truncated.
+        let too_long = RangeProof{
+            e_0: proof.e_0.clone(),
+            C: vec![other.C[0].clone(); RANGEPROOF_MAX_N + 1],
+            s_1: vec![other.s_1[0].clone(); RANGEPROOF_MAX_N + 1],
+            s_2: vec![other.s_2[0].clone(); RANGEPROOF_MAX_N + 1],
+        };
+        assert_eq!(too_long.to_self_describing_bytes().err(),
+                   Some(DecodingError::InvalidLength));
+    }
+
+    #[test]
+    fn decoding_limits_are_enforced() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let proof =
+            RangeProof::create_with_params(8, 42, &DEFAULT_PARAMS, &mut csprng).unwrap().into_proof();
+        let bytes = proof.to_bytes();
+        let described = proof.to_self_describing_bytes().unwrap();
+
+        let default = DecodingLimits::default();
+        assert!(RangeProof::from_bytes_with_limits(&bytes, &default).is_ok());
+        assert!(RangeProof::from_self_describing_bytes_with_limits(&described, &default).is_ok());
+
+        let few_rings = DecodingLimits::new(4, default.max_bytes()).unwrap();
+        assert_eq!(RangeProof::from_bytes_with_limits(&bytes, &few_rings).err(),
+                   Some(DecodingError::LimitExceeded));
+        assert_eq!(RangeProof::from_self_describing_bytes_with_limits(&described, &few_rings).err(),
+                   Some(DecodingError::LimitExceeded));
+
+        let few_bytes = DecodingLimits::new(RANGEPROOF_MAX_N, bytes.len() - 1).unwrap();
+        assert_eq!(RangeProof::from_bytes_with_limits(&bytes, &few_bytes).err(),
+                   Some(DecodingError::LimitExceeded));
+
+        // The header is checked against the limits before the length.
+        let mut forged = described.clone();
+        forged[1] = 40;
+        assert_eq!(RangeProof::from_self_describing_bytes_with_limits(&forged, &few_rings).err(),
+                   Some(DecodingError::LimitExceeded));
+
+        assert!(DecodingLimits::new(RANGEPROOF_MAX_N + 1, default.max_bytes()).is_none());
+    }
+
+    #[test]
+    fn verify_directly_from_bytes() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let n = 8;
+        let (proof, commitment, _) =
+            RangeProof::create_with_params(n, 6000, &DEFAULT_PARAMS, &mut csprng).unwrap().into_parts();
+        let G = DEFAULT_PARAMS.G();
+        let H = DEFAULT_PARAMS.H();
+
+        let mut bytes = proof.to_bytes();
+        assert_eq!(RangeProof::verify_bytes(&bytes, n, G, H).unwrap().compress(),
+                   commitment.compress());
+        assert!(RangeProof::verify_bytes(&bytes, n - 1, G, H).is_none());
+        assert!(RangeProof::verify_bytes(&bytes[..bytes.len() - 1], n, G, H).is_none());
+
+        bytes[32 + 64] ^= 1;
+        assert!(RangeProof::verify_bytes(&bytes, n, G, H).is_none());
+    }
+
+    #[test]
+    fn verify_self_describing() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let n = 16;
+        let value = 13449261;
+        let (proof, commitment, _) =
+            RangeProof::create_with_params(n, value, &DEFAULT_PARAMS, &mut csprng).unwrap().into_parts();
+
+        let mut bytes = proof.to_self_describing_bytes().unwrap();
+        let (n_hat, C) = RangeProof::verify_self_describing(&bytes, &DEFAULT_PARAMS).unwrap();
+        assert_eq!(n_hat, n);
+        assert_eq!(C.compress(), commitment.compress());
+
+        // A header which disagrees with the encoded proof is rejected.
+        bytes[1] = 15;
+        assert!(RangeProof::verify_self_describing(&bytes, &DEFAULT_PARAMS).is_none());
+        bytes[1] = 17;
+        assert_eq!(RangeProof::from_self_describing_bytes(&bytes).err(),
+                   Some(DecodingError::InvalidLength));
+        bytes[1] = 16;
+        bytes[0] = 2;
+        assert_eq!(RangeProof::from_self_describing_bytes(&bytes).err(),
+                   Some(DecodingError::UnsupportedBase(2)));
+    }
+
+    #[test]
+    fn reduced_bytes_omit_last_commitment() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let n = 16;
+        let (proof, commitment, _) =
+            RangeProof::create_with_params(n, 13449261, &DEFAULT_PARAMS, &mut csprng).unwrap().into_parts();
+
+        let bytes = proof.to_reduced_bytes();
+        assert_eq!(bytes.len(), proof.to_bytes().len() - 32);
+
+        let decoded = RangeProof::from_reduced_bytes(&bytes, &commitment).unwrap();
+        assert_eq!(decoded.to_bytes(), proof.to_bytes());
+        assert_eq!(decoded.verify_with_params(n, &DEFAULT_PARAMS).unwrap().compress(),
+                   commitment.compress());
+
+        // With the wrong commitment, the proof doesn't verify.
+        let decoded = RangeProof::from_reduced_bytes(&bytes, DEFAULT_PARAMS.H()).unwrap();
+        assert!(decoded.verify_with_params(n, &DEFAULT_PARAMS).is_none());
+
+        assert_eq!(RangeProof::from_reduced_bytes(&bytes[1..], &commitment).err(),
+                   Some(DecodingError::InvalidLength));
+
+        // A proof with no rings has nothing to omit.
+        let (proof, commitment, _) =
+            RangeProof::create_with_params(0, 0, &DEFAULT_PARAMS, &mut csprng).unwrap().into_parts();
+        let bytes = proof.to_reduced_bytes();
+        assert_eq!(bytes, proof.to_bytes());
+        let decoded = RangeProof::from_reduced_bytes(&bytes, &commitment).unwrap();
+        assert_eq!(decoded.verify_with_params(0, &DEFAULT_PARAMS).unwrap().compress(),
+                   commitment.compress());
+        assert_eq!(RangeProof::from_reduced_bytes(&bytes, DEFAULT_PARAMS.H()).err(),
+                   Some(DecodingError::InvalidLength));
+    }
+}
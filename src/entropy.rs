@@ -0,0 +1,115 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! The default source of entropy.
+//!
+//! Every function in this crate which needs randomness takes a
+//! `rand::Rng` as an argument, so callers are always free to supply
+//! their own.  Convenience functions such as
+//! `RangeProof::create_with_os_rng` instead use `default_rng()`, which
+//! is:
+//!
+//! * with the `getrandom` feature (enabled by default), a
+//!   `GetrandomRng`, which reads from the `getrandom` crate and so
+//!   works uniformly on Linux, Windows, macOS, WASM (with `getrandom`'s
+//!   `js` feature) and any target for which a custom `getrandom`
+//!   backend is registered with `getrandom::register_custom_getrandom!`;
+//! * otherwise, with the `std` feature, `rand::OsRng`.
+//!
+//! On embedded targets, the entropy source is therefore overridden by
+//! registering a custom `getrandom` backend, without any changes to
+//! this crate.
+
+#[cfg(feature = "getrandom")]
+use getrandom;
+
+#[cfg(feature = "getrandom")]
+use rand::Rng;
+#[cfg(all(feature = "std", not(feature = "getrandom")))]
+use rand::OsRng;
+
+/// A `rand::Rng` reading from the `getrandom` crate.
+///
+/// # Panics
+///
+/// Each method panics if `getrandom` fails, since there's no way to
+/// report the failure through `rand::Rng`.
+#[cfg(feature = "getrandom")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct GetrandomRng;
+
+#[cfg(feature = "getrandom")]
+impl Rng for GetrandomRng {
+    fn next_u32(&mut self) -> u32 {
+        let mut bytes = [0u8; 4];
+        self.fill_bytes(&mut bytes);
+        u32::from_le_bytes(bytes)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut bytes = [0u8; 8];
+        self.fill_bytes(&mut bytes);
+        u64::from_le_bytes(bytes)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        getrandom::getrandom(dest).expect("getrandom failed to provide entropy");
+    }
+}
+
+/// The default source of entropy, as described in the module
+/// documentation.
+#[cfg(feature = "getrandom")]
+pub type DefaultRng = GetrandomRng;
+
+/// The default source of entropy, as described in the module
+/// documentation.
+#[cfg(all(feature = "std", not(feature = "getrandom")))]
+pub type DefaultRng = OsRng;
+
+/// Construct the default source of entropy.
+///
+/// # Panics
+///
+/// Without the `getrandom` feature, if the operating system's RNG
+/// can't be opened.
+#[cfg(feature = "getrandom")]
+pub fn default_rng() -> DefaultRng {
+    GetrandomRng
+}
+
+/// Construct the default source of entropy.
+///
+/// # Panics
+///
+/// Without the `getrandom` feature, if the operating system's RNG
+/// can't be opened.
+#[cfg(all(feature = "std", not(feature = "getrandom")))]
+pub fn default_rng() -> DefaultRng {
+    OsRng::new().expect("could not open the operating system's RNG")
+}
+
+#[cfg(test)]
+#[cfg(any(feature = "std", feature = "getrandom"))]
+mod tests {
+    use super::*;
+
+    use rand::Rng;
+
+    #[test]
+    fn default_rng_is_not_constant() {
+        let mut rng = default_rng();
+        let a: Vec<u32> = rng.gen_iter().take(8).collect();
+        let b: Vec<u32> = rng.gen_iter().take(8).collect();
+        assert!(a != b);
+    }
+}
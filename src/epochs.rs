@@ -0,0 +1,140 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Rotating the value generator by epoch.
+//!
+//! A long-lived system may want to rotate its parameters, e.g. so that
+//! a generator whose discrete log is ever suspected to be known only
+//! affects the proofs of one epoch.  Rather than distributing a new
+//! `H` for each epoch, every epoch's generator is derived from a fixed
+//! master label as
+//!
+//! ```text
+//! H_epoch = Hash("dalek-rangeproofs epoch" || len(label) || label || epoch)
+//! ```
+//!
+//! where `len(label)` and `epoch` are 8-byte little-endian integers,
+//! so that anyone knowing the label can rederive the parameters of any
+//! past epoch and verify its proofs.  The blinding generator `G` is
+//! the same for every epoch.
+
+use std::sync::Arc;
+
+use rand::Rng;
+use sha2::Sha256;
+
+use curve25519_dalek::decaf::DecafPoint;
+
+use assets::ParamsLru;
+use errors::{RangeProofError, VerifyError};
+use output::ProverOutput;
+use params::RangeProofParams;
+use verification::VerifiedCommitment;
+
+use super::RangeProof;
+
+/// Derive the value generator `H_epoch` for `epoch` from the master
+/// `label`.
+pub fn epoch_generator(label: &[u8], epoch: u64) -> DecafPoint {
+    let mut input = Vec::with_capacity(23 + 8 + label.len() + 8);
+    input.extend_from_slice(b"dalek-rangeproofs epoch");
+    input.extend_from_slice(&(label.len() as u64).to_le_bytes());
+    input.extend_from_slice(label);
+    input.extend_from_slice(&epoch.to_le_bytes());
+
+    DecafPoint::hash_from_bytes::<Sha256>(&input)
+}
+
+/// The parameters of every epoch derived from a master label, keeping
+/// those of the most recently used epochs.
+pub struct EpochParams {
+    G: DecafPoint,
+    label: Vec<u8>,
+    cache: ParamsLru<u64>,
+}
+
+impl EpochParams {
+    /// Derive the parameters of each epoch from the blinding basepoint
+    /// `G` and the master `label`, keeping the parameters of at most
+    /// `capacity` epochs.
+    ///
+    /// # Panics
+    ///
+    /// If `capacity` is zero.
+    pub fn new(G: &DecafPoint, label: &[u8], capacity: usize) -> EpochParams {
+        // Constructing a cache which can't hold anything is a programming error.
+        if capacity == 0 {
+            panic!("Error: constructed an EpochParams with zero capacity");
+        }
+
+        EpochParams { G: *G, label: label.to_vec(), cache: ParamsLru::new(capacity) }
+    }
+
+    /// The master label.
+    pub fn label(&self) -> &[u8] {
+        &self.label
+    }
+
+    /// The parameters of `epoch`, building them if they aren't cached.
+    pub fn params(&mut self, epoch: u64) -> Arc<RangeProofParams> {
+        let G = self.G;
+        let label = &self.label;
+        self.cache.get_or_insert_with(epoch, || RangeProofParams::new(&G, &epoch_generator(label, epoch)))
+    }
+
+    /// Construct a rangeproof for `value` in the range `[0,3^n]` with
+    /// the parameters of `epoch`, as for `try_create_with_params`.
+    pub fn create<T: Rng>(
+        &mut self,
+        epoch: u64,
+        n: usize,
+        value: u64,
+        csprng: &mut T,
+    ) -> Result<ProverOutput, RangeProofError> {
+        RangeProof::try_create_with_params(n, value, &self.params(epoch), csprng)
+    }
+
+    /// Verify a `proof` with `n` rings made in `epoch`, as for
+    /// `RangeProof::verify_checked`.
+    pub fn verify(&mut self, epoch: u64, n: usize, proof: &RangeProof) -> Result<VerifiedCommitment, VerifyError> {
+        proof.verify_checked(n, &self.params(epoch))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::OsRng;
+
+    use DEFAULT_PARAMS;
+
+    #[test]
+    fn proofs_verify_only_in_their_epoch() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let G = DEFAULT_PARAMS.G().basepoint();
+        let mut epochs = EpochParams::new(&G, b"example chain", 2);
+        assert_eq!(epochs.params(7).H().compress(), epoch_generator(b"example chain", 7).compress());
+        assert!(epoch_generator(b"example chain", 7).compress() != epoch_generator(b"example chain", 8).compress());
+        assert!(epoch_generator(b"example chain", 7).compress() != epoch_generator(b"other chain", 7).compress());
+
+        let (proof, C, _) = epochs.create(7, 8, 42, &mut csprng).unwrap().into_parts();
+        assert_eq!(epochs.verify(7, 8, &proof).unwrap().commitment().compress(), C.compress());
+        assert_eq!(epochs.verify(8, 8, &proof).err(), Some(VerifyError::InvalidProof));
+
+        // A historical epoch's parameters are rederived after eviction.
+        epochs.params(9);
+        epochs.params(10);
+        assert_eq!(epochs.verify(7, 8, &proof).unwrap().commitment().compress(), C.compress());
+    }
+}
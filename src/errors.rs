@@ -0,0 +1,571 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Errors which may occur when handling rangeproofs.
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+use RANGEPROOF_MAX_N;
+
+/// An error which may occur while decoding a rangeproof from bytes.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DecodingError {
+    /// The encoding has a length which doesn't correspond to any
+    /// valid number of rings.
+    InvalidLength,
+    /// The encoding is followed by extra bytes.
+    TrailingBytes,
+    /// One of the encoded points is not the canonical encoding of a
+    /// Decaf point.
+    InvalidPoint,
+    /// One of the encoded scalars is not reduced modulo the group order.
+    NonCanonicalScalar,
+    /// The encoding is for a rangeproof with a base `m` other than 3.
+    UnsupportedBase(u8),
+    /// The encoding has a version which this library doesn't support.
+    UnsupportedVersion(u8),
+    /// A base45 string is malformed.
+    InvalidBase45,
+    /// A CBOR encoding is malformed.
+    InvalidCbor,
+    /// An `rkyv` archive is malformed.
+    InvalidArchive,
+    /// A `postcard` encoding is malformed.
+    InvalidPostcard,
+    /// A hex string is malformed or has the wrong length.
+    InvalidHex,
+    /// The encoding is for a proof larger than the decoder's
+    /// `DecodingLimits` allow.
+    LimitExceeded,
+    /// A framed encoding doesn't match its checksum.
+    ChecksumMismatch,
+}
+
+impl fmt::Display for DecodingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DecodingError::InvalidLength =>
+                write!(f, "Invalid rangeproof length"),
+            DecodingError::TrailingBytes =>
+                write!(f, "Trailing bytes after rangeproof"),
+            DecodingError::InvalidPoint =>
+                write!(f, "Invalid Decaf point in rangeproof"),
+            DecodingError::NonCanonicalScalar =>
+                write!(f, "Non-canonical scalar in rangeproof"),
+            DecodingError::UnsupportedBase(m) =>
+                write!(f, "Unsupported rangeproof base m = {}", m),
+            DecodingError::UnsupportedVersion(v) =>
+                write!(f, "Unsupported rangeproof encoding version {}", v),
+            DecodingError::InvalidBase45 =>
+                write!(f, "Invalid base45 string"),
+            DecodingError::InvalidCbor =>
+                write!(f, "Invalid CBOR encoding of rangeproof"),
+            DecodingError::InvalidArchive =>
+                write!(f, "Invalid rangeproof archive"),
+            DecodingError::InvalidPostcard =>
+                write!(f, "Invalid postcard encoding"),
+            DecodingError::InvalidHex =>
+                write!(f, "Invalid hex string"),
+            DecodingError::LimitExceeded =>
+                write!(f, "Rangeproof exceeds the decoding limits"),
+            DecodingError::ChecksumMismatch =>
+                write!(f, "Rangeproof frame checksum mismatch"),
+        }
+    }
+}
+
+impl Error for DecodingError {
+    fn description(&self) -> &str {
+        match *self {
+            DecodingError::InvalidLength         => "invalid rangeproof length",
+            DecodingError::TrailingBytes         => "trailing bytes after rangeproof",
+            DecodingError::InvalidPoint          => "invalid Decaf point in rangeproof",
+            DecodingError::NonCanonicalScalar    => "non-canonical scalar in rangeproof",
+            DecodingError::UnsupportedBase(_)    => "unsupported rangeproof base",
+            DecodingError::UnsupportedVersion(_) => "unsupported rangeproof encoding version",
+            DecodingError::InvalidBase45         => "invalid base45 string",
+            DecodingError::InvalidCbor           => "invalid CBOR encoding of rangeproof",
+            DecodingError::InvalidArchive        => "invalid rangeproof archive",
+            DecodingError::InvalidPostcard       => "invalid postcard encoding",
+            DecodingError::InvalidHex            => "invalid hex string",
+            DecodingError::LimitExceeded         => "rangeproof exceeds the decoding limits",
+            DecodingError::ChecksumMismatch      => "rangeproof frame checksum mismatch",
+        }
+    }
+}
+
+/// An error which may occur while splitting a rangeproof into chunks,
+/// or while reassembling it.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ChunkingError {
+    /// The MTU is too small to hold a chunk header and any payload.
+    MtuTooSmall,
+    /// The encoding would need more than `2^16 - 1` chunks.
+    TooManyChunks,
+    /// A chunk is too short or has an inconsistent header.
+    InvalidChunk,
+    /// A chunk belongs to a different encoding than the others.
+    MismatchedChunk,
+    /// Not all of the chunks have been received.
+    MissingChunks,
+    /// The reassembled encoding could not be decoded.
+    Decoding(DecodingError),
+}
+
+impl fmt::Display for ChunkingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ChunkingError::MtuTooSmall =>
+                write!(f, "MTU too small for a rangeproof chunk"),
+            ChunkingError::TooManyChunks =>
+                write!(f, "Too many rangeproof chunks"),
+            ChunkingError::InvalidChunk =>
+                write!(f, "Invalid rangeproof chunk"),
+            ChunkingError::MismatchedChunk =>
+                write!(f, "Rangeproof chunk belongs to a different proof"),
+            ChunkingError::MissingChunks =>
+                write!(f, "Missing rangeproof chunks"),
+            ChunkingError::Decoding(ref e) =>
+                write!(f, "Could not decode reassembled rangeproof: {}", e),
+        }
+    }
+}
+
+impl Error for ChunkingError {
+    fn description(&self) -> &str {
+        match *self {
+            ChunkingError::MtuTooSmall     => "MTU too small for a rangeproof chunk",
+            ChunkingError::TooManyChunks   => "too many rangeproof chunks",
+            ChunkingError::InvalidChunk    => "invalid rangeproof chunk",
+            ChunkingError::MismatchedChunk => "rangeproof chunk belongs to a different proof",
+            ChunkingError::MissingChunks   => "missing rangeproof chunks",
+            ChunkingError::Decoding(_)     => "could not decode reassembled rangeproof",
+        }
+    }
+
+    fn cause(&self) -> Option<&Error> {
+        match *self {
+            ChunkingError::Decoding(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// An error which may occur while reading a container of rangeproofs.
+#[derive(Debug)]
+pub enum ContainerError {
+    /// The underlying reader failed, or the container was truncated.
+    Io(io::Error),
+    /// The container doesn't begin with the expected magic bytes.
+    InvalidHeader,
+    /// The container has a version which this library doesn't support.
+    UnsupportedVersion(u8),
+    /// The container's proofs were made with other parameters than
+    /// those they were to be verified with.
+    ParamsMismatch,
+    /// An entry of the container could not be decoded.
+    Decoding(DecodingError),
+    /// Verification was cancelled after `verified` entries.
+    Aborted {
+        /// The number of entries verified before cancellation.
+        verified: usize,
+    },
+}
+
+impl From<io::Error> for ContainerError {
+    fn from(e: io::Error) -> ContainerError {
+        ContainerError::Io(e)
+    }
+}
+
+impl From<DecodingError> for ContainerError {
+    fn from(e: DecodingError) -> ContainerError {
+        ContainerError::Decoding(e)
+    }
+}
+
+impl fmt::Display for ContainerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ContainerError::Io(ref e) =>
+                write!(f, "Could not read rangeproof container: {}", e),
+            ContainerError::InvalidHeader =>
+                write!(f, "Invalid rangeproof container header"),
+            ContainerError::UnsupportedVersion(v) =>
+                write!(f, "Unsupported rangeproof container version {}", v),
+            ContainerError::ParamsMismatch =>
+                write!(f, "Rangeproof container was made with other parameters"),
+            ContainerError::Decoding(ref e) =>
+                write!(f, "Could not decode rangeproof container entry: {}", e),
+            ContainerError::Aborted { verified } =>
+                write!(f, "Rangeproof container verification cancelled after {} entries", verified),
+        }
+    }
+}
+
+impl Error for ContainerError {
+    fn description(&self) -> &str {
+        match *self {
+            ContainerError::Io(_)                 => "could not read rangeproof container",
+            ContainerError::InvalidHeader         => "invalid rangeproof container header",
+            ContainerError::UnsupportedVersion(_) => "unsupported rangeproof container version",
+            ContainerError::ParamsMismatch        => "rangeproof container made with other parameters",
+            ContainerError::Decoding(_)           => "could not decode rangeproof container entry",
+            ContainerError::Aborted { .. }        => "rangeproof container verification cancelled",
+        }
+    }
+
+    fn cause(&self) -> Option<&Error> {
+        match *self {
+            ContainerError::Io(ref e)       => Some(e),
+            ContainerError::Decoding(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// An error which may occur while reading framed rangeproofs from a
+/// stream.
+#[derive(Debug)]
+pub enum CodecError {
+    /// The underlying stream failed.
+    Io(io::Error),
+    /// A frame could not be decoded.
+    Decoding(DecodingError),
+}
+
+impl From<io::Error> for CodecError {
+    fn from(e: io::Error) -> CodecError {
+        CodecError::Io(e)
+    }
+}
+
+impl From<DecodingError> for CodecError {
+    fn from(e: DecodingError) -> CodecError {
+        CodecError::Decoding(e)
+    }
+}
+
+impl fmt::Display for CodecError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CodecError::Io(ref e) =>
+                write!(f, "Could not read rangeproof stream: {}", e),
+            CodecError::Decoding(ref e) =>
+                write!(f, "Could not decode rangeproof frame: {}", e),
+        }
+    }
+}
+
+impl Error for CodecError {
+    fn description(&self) -> &str {
+        match *self {
+            CodecError::Io(_)       => "could not read rangeproof stream",
+            CodecError::Decoding(_) => "could not decode rangeproof frame",
+        }
+    }
+
+    fn cause(&self) -> Option<&Error> {
+        match *self {
+            CodecError::Io(ref e)       => Some(e),
+            CodecError::Decoding(ref e) => Some(e),
+        }
+    }
+}
+
+/// The reason a rangeproof is invalid under the consensus rules.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ConsensusError {
+    /// The rules have a version which this library doesn't implement.
+    UnsupportedVersion(u8),
+    /// The rules don't allow proofs with this many rings.
+    InvalidRingCount(usize),
+    /// The proof isn't a valid encoding.
+    Decoding(DecodingError),
+    /// The proof doesn't verify.
+    Verify(VerifyError),
+}
+
+impl From<DecodingError> for ConsensusError {
+    fn from(e: DecodingError) -> ConsensusError {
+        ConsensusError::Decoding(e)
+    }
+}
+
+impl From<VerifyError> for ConsensusError {
+    fn from(e: VerifyError) -> ConsensusError {
+        ConsensusError::Verify(e)
+    }
+}
+
+impl fmt::Display for ConsensusError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ConsensusError::UnsupportedVersion(v) =>
+                write!(f, "Unsupported consensus rules version {}", v),
+            ConsensusError::InvalidRingCount(n) =>
+                write!(f, "Consensus rules don't allow rangeproofs with {} rings", n),
+            ConsensusError::Decoding(ref e) =>
+                write!(f, "Could not decode rangeproof: {}", e),
+            ConsensusError::Verify(ref e) =>
+                write!(f, "Invalid rangeproof: {}", e),
+        }
+    }
+}
+
+impl Error for ConsensusError {
+    fn description(&self) -> &str {
+        match *self {
+            ConsensusError::UnsupportedVersion(_) => "unsupported consensus rules version",
+            ConsensusError::InvalidRingCount(_)   => "ring count not allowed by consensus rules",
+            ConsensusError::Decoding(_)           => "could not decode rangeproof",
+            ConsensusError::Verify(_)             => "invalid rangeproof",
+        }
+    }
+
+    fn cause(&self) -> Option<&Error> {
+        match *self {
+            ConsensusError::Decoding(ref e) => Some(e),
+            ConsensusError::Verify(ref e)   => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// The reason a rangeproof failed to verify.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum VerifyError {
+    /// The proof doesn't have the expected number of rings.
+    WrongRingCount,
+    /// One of the scalars in the proof is not reduced modulo the
+    /// group order.
+    NonCanonicalScalar,
+    /// The ring signatures in the proof don't verify.
+    InvalidProof,
+    /// The proof was made with different parameters.
+    ParamsMismatch,
+    /// The proof is valid, but for a different commitment than claimed.
+    CommitmentMismatch,
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            VerifyError::WrongRingCount =>
+                write!(f, "Rangeproof has the wrong number of rings"),
+            VerifyError::NonCanonicalScalar =>
+                write!(f, "Non-canonical scalar in rangeproof"),
+            VerifyError::InvalidProof =>
+                write!(f, "Rangeproof does not verify"),
+            VerifyError::ParamsMismatch =>
+                write!(f, "Rangeproof was made with different parameters"),
+            VerifyError::CommitmentMismatch =>
+                write!(f, "Rangeproof is for a different commitment"),
+        }
+    }
+}
+
+impl Error for VerifyError {
+    fn description(&self) -> &str {
+        match *self {
+            VerifyError::WrongRingCount     => "rangeproof has the wrong number of rings",
+            VerifyError::NonCanonicalScalar => "non-canonical scalar in rangeproof",
+            VerifyError::InvalidProof       => "rangeproof does not verify",
+            VerifyError::ParamsMismatch     => "rangeproof was made with different parameters",
+            VerifyError::CommitmentMismatch => "rangeproof is for a different commitment",
+        }
+    }
+}
+
+/// The error returned when a value is too large to be proven in range
+/// `[0,3^n]` for the requested `n`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct OutOfRange {
+    /// The value which was to be proven in range.
+    pub value: u64,
+    /// The requested number of rings.
+    pub n: usize,
+    /// The largest value which can be proven in range with `n` rings.
+    pub max: u64,
+    /// The least number of rings which would fit `value`.
+    pub min_n: usize,
+}
+
+impl fmt::Display for OutOfRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Value {} exceeds the maximum {} for n = {}; at least n = {} is needed",
+               self.value, self.max, self.n, self.min_n)
+    }
+}
+
+impl Error for OutOfRange {
+    fn description(&self) -> &str {
+        "value out of range for rangeproof"
+    }
+}
+
+/// The error returned when the values of openings sum to more than can
+/// be proven in range `[0,3^n]`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ValueOverflow {
+    /// The sum of the values, which can't overflow a `u128`.
+    pub sum: u128,
+    /// The requested number of rings.
+    pub n: usize,
+    /// The largest value which can be proven in range with `n` rings.
+    pub max: u64,
+}
+
+impl fmt::Display for ValueOverflow {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Sum of values {} exceeds the maximum {} for n = {}",
+               self.sum, self.max, self.n)
+    }
+}
+
+impl Error for ValueOverflow {
+    fn description(&self) -> &str {
+        "sum of values out of range for rangeproof"
+    }
+}
+
+/// The reason a `CappedProver` refused to prove a value.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CapError {
+    /// Proving the value would take the session's total past its cap.
+    CapExceeded {
+        /// The value which was to be proven.
+        value: u64,
+        /// The total of the values already proven in the session.
+        proven: u64,
+        /// The cap on the total.
+        cap: u64,
+    },
+    /// The value can't be proven in range with the prover's `n`.
+    OutOfRange(OutOfRange),
+}
+
+impl From<OutOfRange> for CapError {
+    fn from(e: OutOfRange) -> CapError {
+        CapError::OutOfRange(e)
+    }
+}
+
+impl fmt::Display for CapError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CapError::CapExceeded { value, proven, cap } =>
+                write!(f, "Proving {} would exceed the cap {} with {} already proven",
+                       value, cap, proven),
+            CapError::OutOfRange(ref e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for CapError {
+    fn description(&self) -> &str {
+        match *self {
+            CapError::CapExceeded { .. } => "session value cap exceeded",
+            CapError::OutOfRange(_)      => "value out of range for rangeproof",
+        }
+    }
+
+    fn cause(&self) -> Option<&Error> {
+        match *self {
+            CapError::OutOfRange(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// Any reason creating or verifying a rangeproof can fail, as returned
+/// by the `try_` variants of `RangeProof::create`,
+/// `RangeProof::create_vartime` and `RangeProof::verify`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RangeProofError {
+    /// The value can't be proven in range with the requested `n`.
+    OutOfRange(OutOfRange),
+    /// The requested number of rings exceeds `RANGEPROOF_MAX_N`.
+    InvalidRingCount(usize),
+    /// The values of openings sum to more than can be proven in range.
+    ValueOverflow(ValueOverflow),
+    /// The precomputation was made with different parameters.
+    PrecomputationMismatch,
+    /// The proof isn't a valid encoding.
+    Decoding(DecodingError),
+    /// The proof doesn't verify.
+    Verify(VerifyError),
+}
+
+impl From<OutOfRange> for RangeProofError {
+    fn from(e: OutOfRange) -> RangeProofError {
+        RangeProofError::OutOfRange(e)
+    }
+}
+
+impl From<ValueOverflow> for RangeProofError {
+    fn from(e: ValueOverflow) -> RangeProofError {
+        RangeProofError::ValueOverflow(e)
+    }
+}
+
+impl From<DecodingError> for RangeProofError {
+    fn from(e: DecodingError) -> RangeProofError {
+        RangeProofError::Decoding(e)
+    }
+}
+
+impl From<VerifyError> for RangeProofError {
+    fn from(e: VerifyError) -> RangeProofError {
+        RangeProofError::Verify(e)
+    }
+}
+
+impl fmt::Display for RangeProofError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RangeProofError::OutOfRange(ref e) => write!(f, "{}", e),
+            RangeProofError::InvalidRingCount(n) =>
+                write!(f, "Number of rings {} exceeds the maximum {}", n, RANGEPROOF_MAX_N),
+            RangeProofError::ValueOverflow(ref e) => write!(f, "{}", e),
+            RangeProofError::PrecomputationMismatch =>
+                write!(f, "Precomputation was made with different parameters"),
+            RangeProofError::Decoding(ref e) => write!(f, "{}", e),
+            RangeProofError::Verify(ref e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl Error for RangeProofError {
+    fn description(&self) -> &str {
+        match *self {
+            RangeProofError::OutOfRange(_)          => "value out of range for rangeproof",
+            RangeProofError::InvalidRingCount(_)    => "too many rings for rangeproof",
+            RangeProofError::ValueOverflow(_)       => "sum of values out of range for rangeproof",
+            RangeProofError::PrecomputationMismatch => "precomputation was made with different parameters",
+            RangeProofError::Decoding(_)            => "invalid rangeproof encoding",
+            RangeProofError::Verify(_)              => "invalid rangeproof",
+        }
+    }
+
+    fn cause(&self) -> Option<&Error> {
+        match *self {
+            RangeProofError::OutOfRange(ref e)    => Some(e),
+            RangeProofError::ValueOverflow(ref e) => Some(e),
+            RangeProofError::Decoding(ref e)      => Some(e),
+            RangeProofError::Verify(ref e)        => Some(e),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,154 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Checksummed frames, with the `framing` feature.
+//!
+//! A frame wraps the self-describing encoding of a rangeproof as
+//!
+//! ```text
+//! length (4 bytes) || crc32c(payload) (4 bytes) || payload
+//! ```
+//!
+//! with both integers little-endian, so that a proof corrupted in
+//! transport is rejected by a CRC32C, which runs at several gigabytes
+//! per second, before any point is decompressed or any proof
+//! verified.  The CRC only detects accidental corruption: anyone can
+//! compute it, so it says nothing about who sent the proof.
+//!
+//! Frames can be concatenated, and `split_frame` separates the first
+//! from the rest.
+
+use crc32c::crc32c;
+
+use encoding::DecodingLimits;
+use errors::DecodingError;
+
+use super::RangeProof;
+
+/// The length in bytes of a frame header, `length || crc32c`.
+pub const FRAME_HEADER_LENGTH: usize = 8;
+
+/// Split the first frame from `bytes` and check its checksum, without
+/// decoding its payload.
+///
+/// # Returns
+///
+/// The payload of the first frame and the bytes following it, or a
+/// `DecodingError::LimitExceeded` if the length prefix is longer than
+/// `limits.max_bytes()`, a `DecodingError::InvalidLength` if `bytes`
+/// is shorter than the frame, or a `DecodingError::ChecksumMismatch`
+/// if the payload was corrupted.
+pub fn split_frame<'a>(
+    bytes: &'a [u8],
+    limits: &DecodingLimits,
+) -> Result<(&'a [u8], &'a [u8]), DecodingError> {
+    if bytes.len() < FRAME_HEADER_LENGTH {
+        return Err(DecodingError::InvalidLength);
+    }
+    let mut length = [0u8; 4];
+    length.copy_from_slice(&bytes[..4]);
+    let length = u32::from_le_bytes(length) as usize;
+    if length > limits.max_bytes() {
+        return Err(DecodingError::LimitExceeded);
+    }
+    if bytes.len() - FRAME_HEADER_LENGTH < length {
+        return Err(DecodingError::InvalidLength);
+    }
+
+    let mut checksum = [0u8; 4];
+    checksum.copy_from_slice(&bytes[4..8]);
+    let (payload, rest) = bytes[FRAME_HEADER_LENGTH..].split_at(length);
+    if crc32c(payload) != u32::from_le_bytes(checksum) {
+        return Err(DecodingError::ChecksumMismatch);
+    }
+
+    Ok((payload, rest))
+}
+
+impl RangeProof {
+    /// Encode this rangeproof in a checksummed frame, in
+    /// `8 + 2 + 32(1+3n)` bytes.
+    ///
+    /// # Returns
+    ///
+    /// As for `to_self_describing_bytes`.
+    pub fn to_framed_bytes(&self) -> Result<Vec<u8>, DecodingError> {
+        let payload = self.to_self_describing_bytes()?;
+
+        let mut bytes = Vec::with_capacity(FRAME_HEADER_LENGTH + payload.len());
+        bytes.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&crc32c(&payload).to_le_bytes());
+        bytes.extend_from_slice(&payload);
+        Ok(bytes)
+    }
+
+    /// Decode a rangeproof from the frame produced by
+    /// `to_framed_bytes`, returning `(n, proof)`.
+    ///
+    /// # Returns
+    ///
+    /// A `DecodingError::TrailingBytes` if `bytes` continues after the
+    /// frame, and otherwise as for `split_frame` with the default
+    /// `DecodingLimits`, followed by `from_self_describing_bytes`.
+    pub fn from_framed_bytes(bytes: &[u8]) -> Result<(usize, RangeProof), DecodingError> {
+        let limits = DecodingLimits::default();
+        let (payload, rest) = split_frame(bytes, &limits)?;
+        if !rest.is_empty() {
+            return Err(DecodingError::TrailingBytes);
+        }
+
+        RangeProof::from_self_describing_bytes_with_limits(payload, &limits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::OsRng;
+
+    use DEFAULT_PARAMS;
+
+    #[test]
+    fn framed_roundtrip_and_corruption() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let n = 8;
+        let (proof, C, _) =
+            RangeProof::create_with_params(n, 42, &DEFAULT_PARAMS, &mut csprng).unwrap().into_parts();
+        let bytes = proof.to_framed_bytes().unwrap();
+        assert_eq!(bytes.len(), FRAME_HEADER_LENGTH + 2 + proof.size_in_bytes());
+
+        let (m, decoded) = RangeProof::from_framed_bytes(&bytes).unwrap();
+        assert_eq!(m, n);
+        assert_eq!(decoded.verify_with_params(n, &DEFAULT_PARAMS).unwrap().compress(), C.compress());
+
+        let mut corrupted = bytes.clone();
+        corrupted[100] ^= 0x10;
+        assert_eq!(RangeProof::from_framed_bytes(&corrupted).err(), Some(DecodingError::ChecksumMismatch));
+
+        // Concatenated frames are split one at a time.
+        let mut two = bytes.clone();
+        two.extend_from_slice(&bytes);
+        let (payload, rest) = split_frame(&two, &DecodingLimits::default()).unwrap();
+        assert_eq!(payload, &bytes[FRAME_HEADER_LENGTH..]);
+        assert_eq!(rest, &bytes[..]);
+        assert_eq!(RangeProof::from_framed_bytes(&two).err(), Some(DecodingError::TrailingBytes));
+
+        // An oversized length prefix is rejected before anything else.
+        let mut oversized = bytes.clone();
+        oversized[..4].copy_from_slice(&u32::max_value().to_le_bytes());
+        assert_eq!(RangeProof::from_framed_bytes(&oversized).err(), Some(DecodingError::LimitExceeded));
+        assert_eq!(RangeProof::from_framed_bytes(&bytes[..bytes.len() - 1]).err(),
+                   Some(DecodingError::InvalidLength));
+    }
+}
@@ -0,0 +1,364 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Back-Maxwell rangeproofs over any `RangeProofGroup`.
+//!
+//! A `GenericRangeProof<Gr>` is the same construction as `RangeProof`,
+//! with the same `m = 3` rings and encoding
+//!
+//! ```text
+//! e_0 || C_0 || s_1,0 || s_2,0 || ... || C_{n-1} || s_1,n-1 || s_2,n-1
+//! ```
+//!
+//! but with scalars and points encoded as by the group `Gr`.  The
+//! generators `G` and `H` are supplied by the caller, so that they can
+//! be shared with other protocols over the same group.
+
+use std::marker::PhantomData;
+
+use rand::Rng;
+
+use group::RangeProofGroup;
+use transcript_rng::TranscriptRng;
+
+use super::base3_digits;
+use super::RANGEPROOF_MAX_N;
+
+/// The generators for rangeproofs over the group `Gr`, with the
+/// precomputed `3^i * H` and `2 * 3^i * H` for `i` in
+/// `0..RANGEPROOF_MAX_N`.
+pub struct GenericParams<Gr: RangeProofGroup> {
+    G: Gr::Point,
+    H: Gr::Point,
+    powers: Vec<(Gr::Point, Gr::Point)>,
+}
+
+impl<Gr: RangeProofGroup> GenericParams<Gr> {
+    /// Construct parameters from the generators `G` and `H`, whose
+    /// relative discrete logarithm must be unknown.
+    pub fn new(G: Gr::Point, H: Gr::Point) -> GenericParams<Gr> {
+        let mut powers = Vec::with_capacity(RANGEPROOF_MAX_N);
+        let mut mi_H = H;
+        for _ in 0..RANGEPROOF_MAX_N {
+            let mi2_H = Gr::add(&mi_H, &mi_H);
+            powers.push((mi_H, mi2_H));
+            mi_H = Gr::add(&mi2_H, &mi_H);
+        }
+        GenericParams { G: G, H: H, powers: powers }
+    }
+
+    /// The generator `G`, for blindings.
+    pub fn G(&self) -> &Gr::Point {
+        &self.G
+    }
+
+    /// The generator `H`, for values.
+    pub fn H(&self) -> &Gr::Point {
+        &self.H
+    }
+}
+
+/// A rangeproof over the group `Gr`.
+pub struct GenericRangeProof<Gr: RangeProofGroup> {
+    e_0: Gr::Scalar,
+    C: Vec<Gr::Point>,
+    s_1: Vec<Gr::Scalar>,
+    s_2: Vec<Gr::Scalar>,
+    group: PhantomData<Gr>,
+}
+
+/// Derive a ring challenge from the point `P`.
+fn ring_challenge<Gr: RangeProofGroup>(P: &Gr::Point) -> Gr::Scalar {
+    Gr::hash_to_scalar(&Gr::point_to_bytes(P))
+}
+
+/// Derive the shared challenge `e_0` from the points `R_i` closing
+/// each ring.
+fn e_0_challenge<Gr: RangeProofGroup>(R: &[Gr::Point]) -> Gr::Scalar {
+    let mut bytes = Vec::with_capacity(R.len() * Gr::POINT_LENGTH);
+    for R_i in R.iter() {
+        bytes.extend_from_slice(&Gr::point_to_bytes(R_i));
+    }
+    Gr::hash_to_scalar(&bytes)
+}
+
+impl<Gr: RangeProofGroup> GenericRangeProof<Gr> {
+    /// Verify the rangeproof, returning a Pedersen commitment to the
+    /// in-range value if successful.
+    ///
+    /// # Returns
+    ///
+    /// None if the proof doesn't verify, including when
+    /// `n > RANGEPROOF_MAX_N`.
+    #[must_use]
+    pub fn verify(&self, n: usize, params: &GenericParams<Gr>) -> Option<Gr::Point> {
+        if n > RANGEPROOF_MAX_N || n != self.C.len() || n != self.s_1.len() || n != self.s_2.len() {
+            return None;
+        }
+
+        let mut R = Vec::with_capacity(n);
+        let mut C = Gr::identity();
+        let minus_e_0 = Gr::scalar_neg(&self.e_0);
+
+        for i in 0..n {
+            let (ref mi_H, ref mi2_H) = params.powers[i];
+
+            let P = Gr::double_mul(&self.s_1[i], &params.G, &minus_e_0, &Gr::sub(&self.C[i], mi_H));
+            let ei_1 = ring_challenge::<Gr>(&P);
+
+            let P = Gr::double_mul(&self.s_2[i], &params.G, &Gr::scalar_neg(&ei_1),
+                                   &Gr::sub(&self.C[i], mi2_H));
+            let ei_2 = ring_challenge::<Gr>(&P);
+
+            R.push(Gr::mul(&self.C[i], &ei_2));
+            C = Gr::add(&C, &self.C[i]);
+        }
+
+        if e_0_challenge::<Gr>(&R) == self.e_0 {
+            Some(C)
+        } else {
+            None
+        }
+    }
+
+    /// Construct a rangeproof for `value`, in variable time.
+    ///
+    /// # Inputs
+    ///
+    /// * `n`, so that the range is `[0,3^n]` with `n <= RANGEPROOF_MAX_N`;
+    /// * The `value` to prove within range `[0,3^n]`;
+    /// * The `params` for the group;
+    /// * `csprng`, an implementation of `rand::Rng`, which should be
+    /// cryptographically secure.
+    ///
+    /// # Returns
+    ///
+    /// If `n > RANGEPROOF_MAX_N` or `value` is not in the range
+    /// `[0,3^n]`, return None.  None is also returned, with negligible
+    /// probability, if a ring challenge to be inverted is zero.
+    ///
+    /// Otherwise, returns `Some((proof, commitment, blinding))`, where
+    /// `commitment = blinding*G + value*H`.
+    pub fn create_vartime<T: Rng>(
+        n: usize,
+        value: u64,
+        params: &GenericParams<Gr>,
+        csprng: &mut T,
+    ) -> Option<(GenericRangeProof<Gr>, Gr::Point, Gr::Scalar)> {
+        if n > RANGEPROOF_MAX_N {
+            return None;
+        }
+
+        // Check that value is in range: all digits above n should be 0
+        let v = base3_digits(value);
+        for i in n..41 {
+            if v[i] != 0 { return None; }
+        }
+
+        let G = &params.G;
+        let zero = Gr::scalar_from_u64(0);
+
+        // Derive the nonces from the statement and the value as well as
+        // from csprng, so that a faulty csprng can't leak the value.
+        let mut rng = TranscriptRng::builder(b"generic rangeproof")
+            .commit_transcript(b"n", &[n as u8])
+            .commit_transcript(b"G", &Gr::point_to_bytes(G))
+            .commit_transcript(b"H", &Gr::point_to_bytes(&params.H))
+            .commit_witness(b"value", &value.to_le_bytes())
+            .finalize(csprng);
+
+        let mut R   = vec![Gr::identity(); n];
+        let mut C   = vec![Gr::identity(); n];
+        let mut k   = vec![zero; n];
+        let mut r   = vec![zero; n];
+        let mut s_1 = vec![zero; n];
+        let mut s_2 = vec![zero; n];
+        let mut e_1 = vec![zero; n];
+        let mut e_2 = vec![zero; n];
+
+        for i in 0..n {
+            let (ref mi_H, ref mi2_H) = params.powers[i];
+            k[i] = Gr::random_scalar(&mut rng);
+
+            if v[i] == 0 {
+                R[i] = Gr::mul(G, &k[i]);
+            } else if v[i] == 1 {
+                r[i] = Gr::random_scalar(&mut rng);
+                C[i] = Gr::add(&Gr::mul(G, &r[i]), mi_H);
+                // Begin at index 1 in the ring.
+                e_1[i] = ring_challenge::<Gr>(&Gr::mul(G, &k[i]));
+                s_2[i] = Gr::random_scalar(&mut rng);
+                let P = Gr::double_mul(&s_2[i], G, &Gr::scalar_neg(&e_1[i]), &Gr::sub(&C[i], mi2_H));
+                e_2[i] = ring_challenge::<Gr>(&P);
+
+                R[i] = Gr::mul(&C[i], &e_2[i]);
+            } else {
+                r[i] = Gr::random_scalar(&mut rng);
+                C[i] = Gr::add(&Gr::mul(G, &r[i]), mi2_H);
+                // Begin at index 2 in the ring.
+                e_2[i] = ring_challenge::<Gr>(&Gr::mul(G, &k[i]));
+
+                R[i] = Gr::mul(&C[i], &e_2[i]);
+            }
+        }
+
+        let e_0 = e_0_challenge::<Gr>(&R);
+
+        for i in 0..n {
+            let (ref mi_H, ref mi2_H) = params.powers[i];
+            if v[i] == 0 {
+                let k_1 = Gr::random_scalar(&mut rng);
+                e_1[i] = ring_challenge::<Gr>(&Gr::double_mul(&k_1, G, &e_0, mi_H));
+
+                let k_2 = Gr::random_scalar(&mut rng);
+                e_2[i] = ring_challenge::<Gr>(&Gr::double_mul(&k_2, G, &e_1[i], mi2_H));
+
+                let e_2_inv = Gr::scalar_invert(&e_2[i])?;
+                r[i] = Gr::scalar_mul(&e_2_inv, &k[i]);
+                C[i] = Gr::mul(G, &r[i]);
+
+                s_1[i] = Gr::scalar_add(&k_1, &Gr::scalar_mul(&e_0, &r[i]));
+                s_2[i] = Gr::scalar_add(&k_2, &Gr::scalar_mul(&e_1[i], &r[i]));
+            } else if v[i] == 1 {
+                s_1[i] = Gr::scalar_add(&Gr::scalar_mul(&e_0, &r[i]), &k[i]);
+            } else {
+                s_1[i] = Gr::random_scalar(&mut rng);
+                let P = Gr::double_mul(&s_1[i], G, &Gr::scalar_neg(&e_0), &Gr::sub(&C[i], mi_H));
+                e_1[i] = ring_challenge::<Gr>(&P);
+                s_2[i] = Gr::scalar_add(&Gr::scalar_mul(&e_1[i], &r[i]), &k[i]);
+            }
+        }
+
+        let mut blinding = zero;
+        let mut commitment = Gr::identity();
+        for i in 0..n {
+            blinding = Gr::scalar_add(&blinding, &r[i]);
+            commitment = Gr::add(&commitment, &C[i]);
+        }
+
+        Some((
+            GenericRangeProof { e_0: e_0, C: C, s_1: s_1, s_2: s_2, group: PhantomData },
+            commitment,
+            blinding,
+        ))
+    }
+
+    /// The length in bytes of an encoded proof with `n` rings,
+    /// `SCALAR_LENGTH + n * (POINT_LENGTH + 2 * SCALAR_LENGTH)`.
+    ///
+    /// This is `32(1+3n)` for `Decaf255` and `56(1+3n)` for `Decaf448`.
+    pub fn encoded_length(n: usize) -> usize {
+        Gr::SCALAR_LENGTH + n * (Gr::POINT_LENGTH + 2 * Gr::SCALAR_LENGTH)
+    }
+
+    /// Encode the proof in `encoded_length(n)` bytes, in the same
+    /// layout as `RangeProof::to_bytes`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(GenericRangeProof::<Gr>::encoded_length(self.C.len()));
+
+        bytes.extend_from_slice(&Gr::scalar_to_bytes(&self.e_0));
+        for i in 0..self.C.len() {
+            bytes.extend_from_slice(&Gr::point_to_bytes(&self.C[i]));
+            bytes.extend_from_slice(&Gr::scalar_to_bytes(&self.s_1[i]));
+            bytes.extend_from_slice(&Gr::scalar_to_bytes(&self.s_2[i]));
+        }
+        bytes
+    }
+
+    /// Decode a proof encoded by `to_bytes`.
+    ///
+    /// # Returns
+    ///
+    /// None if the length of `bytes` isn't that of a proof with at most
+    /// `RANGEPROOF_MAX_N` rings, or if any scalar or point isn't
+    /// canonically encoded.
+    pub fn from_bytes(bytes: &[u8]) -> Option<GenericRangeProof<Gr>> {
+        let ring_length = Gr::POINT_LENGTH + 2 * Gr::SCALAR_LENGTH;
+        if bytes.len() < Gr::SCALAR_LENGTH || (bytes.len() - Gr::SCALAR_LENGTH) % ring_length != 0 {
+            return None;
+        }
+        let n = (bytes.len() - Gr::SCALAR_LENGTH) / ring_length;
+        if n > RANGEPROOF_MAX_N {
+            return None;
+        }
+
+        let e_0 = Gr::scalar_from_bytes(&bytes[..Gr::SCALAR_LENGTH])?;
+        let mut C = Vec::with_capacity(n);
+        let mut s_1 = Vec::with_capacity(n);
+        let mut s_2 = Vec::with_capacity(n);
+
+        for ring in bytes[Gr::SCALAR_LENGTH..].chunks(ring_length) {
+            let (point, scalars) = ring.split_at(Gr::POINT_LENGTH);
+            C.push(Gr::point_from_bytes(point)?);
+            s_1.push(Gr::scalar_from_bytes(&scalars[..Gr::SCALAR_LENGTH])?);
+            s_2.push(Gr::scalar_from_bytes(&scalars[Gr::SCALAR_LENGTH..])?);
+        }
+
+        Some(GenericRangeProof { e_0: e_0, C: C, s_1: s_1, s_2: s_2, group: PhantomData })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::OsRng;
+
+    use curve25519_dalek::scalar::Scalar;
+
+    use group::Decaf255;
+    use RangeProof;
+    use DEFAULT_PARAMS;
+
+    fn decaf_params() -> GenericParams<Decaf255> {
+        GenericParams::new(DEFAULT_PARAMS.G().basepoint(), *DEFAULT_PARAMS.H())
+    }
+
+    #[test]
+    fn prove_and_verify_over_decaf() {
+        let mut csprng = OsRng::new().unwrap();
+        let params = decaf_params();
+
+        let (proof, commitment, blinding) =
+            GenericRangeProof::create_vartime(16, 13449261, &params, &mut csprng).unwrap();
+        let C = proof.verify(16, &params).unwrap();
+        assert_eq!(C.compress(), commitment.compress());
+
+        let C_hat = &(DEFAULT_PARAMS.G() * &blinding) + &(DEFAULT_PARAMS.H() * &Scalar::from_u64(13449261));
+        assert_eq!(C.compress(), C_hat.compress());
+
+        assert!(proof.verify(15, &params).is_none());
+        assert!(GenericRangeProof::create_vartime(2, 9, &params, &mut csprng).is_none());
+
+        assert!(proof.verify(RANGEPROOF_MAX_N + 1, &params).is_none());
+        assert!(GenericRangeProof::create_vartime(RANGEPROOF_MAX_N + 1, 0, &params, &mut csprng).is_none());
+    }
+
+    #[test]
+    fn decaf_proofs_are_rangeproofs() {
+        let mut csprng = OsRng::new().unwrap();
+        let params = decaf_params();
+
+        let (proof, commitment, _) =
+            GenericRangeProof::create_vartime(16, 13449261, &params, &mut csprng).unwrap();
+        let bytes = proof.to_bytes();
+        assert_eq!(bytes.len(), 32 * (1 + 3 * 16));
+        assert_eq!(bytes.len(), GenericRangeProof::<Decaf255>::encoded_length(16));
+
+        let rangeproof = RangeProof::from_bytes(&bytes).unwrap();
+        let C = rangeproof.verify_with_params(16, &DEFAULT_PARAMS).unwrap();
+        assert_eq!(C.compress(), commitment.compress());
+
+        let decoded: GenericRangeProof<Decaf255> = GenericRangeProof::from_bytes(&bytes).unwrap();
+        assert!(decoded.verify(16, &params).is_some());
+    }
+}
@@ -0,0 +1,147 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! The `G1` group of the BLS12-381 pairing-friendly curve.
+
+use rand::Rng;
+
+use sha2::{Digest, Sha512};
+
+use bls12_381::{G1Affine, G1Projective, Scalar};
+
+use super::RangeProofGroup;
+
+/// The `G1` group of BLS12-381, with points encoded in their 48-byte
+/// compressed form and scalars in 32 little-endian bytes.
+///
+/// Challenges are derived by reducing the SHA-512 hash of their
+/// input modulo the group order, as for `Decaf255`.
+pub enum Bls12381G1 {}
+
+impl RangeProofGroup for Bls12381G1 {
+    type Scalar = Scalar;
+    type Point = G1Projective;
+
+    const POINT_LENGTH: usize = 48;
+    const SCALAR_LENGTH: usize = 32;
+
+    fn identity() -> G1Projective {
+        G1Projective::identity()
+    }
+
+    fn add(A: &G1Projective, B: &G1Projective) -> G1Projective {
+        A + B
+    }
+
+    fn sub(A: &G1Projective, B: &G1Projective) -> G1Projective {
+        A - B
+    }
+
+    fn mul(A: &G1Projective, a: &Scalar) -> G1Projective {
+        A * a
+    }
+
+    fn scalar_from_u64(x: u64) -> Scalar {
+        Scalar::from(x)
+    }
+
+    fn scalar_add(a: &Scalar, b: &Scalar) -> Scalar {
+        a + b
+    }
+
+    fn scalar_mul(a: &Scalar, b: &Scalar) -> Scalar {
+        a * b
+    }
+
+    fn scalar_neg(a: &Scalar) -> Scalar {
+        -a
+    }
+
+    fn scalar_invert(a: &Scalar) -> Option<Scalar> {
+        Option::from(a.invert())
+    }
+
+    fn random_scalar<R: Rng>(rng: &mut R) -> Scalar {
+        let mut bytes = [0u8; 64];
+        rng.fill_bytes(&mut bytes);
+        Scalar::from_bytes_wide(&bytes)
+    }
+
+    fn hash_to_scalar(bytes: &[u8]) -> Scalar {
+        let mut hash = Sha512::default();
+        hash.input(bytes);
+        let mut wide = [0u8; 64];
+        wide.copy_from_slice(hash.result().as_slice());
+        Scalar::from_bytes_wide(&wide)
+    }
+
+    fn point_to_bytes(A: &G1Projective) -> Vec<u8> {
+        G1Affine::from(A).to_compressed().to_vec()
+    }
+
+    fn point_from_bytes(bytes: &[u8]) -> Option<G1Projective> {
+        if bytes.len() != 48 {
+            return None;
+        }
+        let mut compressed = [0u8; 48];
+        compressed.copy_from_slice(bytes);
+        let point: Option<G1Affine> = G1Affine::from_compressed(&compressed).into();
+        point.map(G1Projective::from)
+    }
+
+    fn scalar_to_bytes(a: &Scalar) -> Vec<u8> {
+        a.to_bytes().to_vec()
+    }
+
+    fn scalar_from_bytes(bytes: &[u8]) -> Option<Scalar> {
+        if bytes.len() != 32 {
+            return None;
+        }
+        let mut s = [0u8; 32];
+        s.copy_from_slice(bytes);
+        Scalar::from_bytes(&s).into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::OsRng;
+
+    use generic::{GenericParams, GenericRangeProof};
+
+    // Test generators only: the discrete log of H is known.
+    fn params() -> GenericParams<Bls12381G1> {
+        let G = G1Projective::generator();
+        let H = G1Projective::generator() * Bls12381G1::hash_to_scalar(b"dalek-rangeproofs test H");
+        GenericParams::new(G, H)
+    }
+
+    #[test]
+    fn prove_and_verify_over_bls12_381() {
+        let mut csprng = OsRng::new().unwrap();
+        let params = params();
+
+        let (proof, commitment, blinding) =
+            GenericRangeProof::create_vartime(16, 13449261, &params, &mut csprng).unwrap();
+        let C = proof.verify(16, &params).unwrap();
+        assert_eq!(C, commitment);
+        assert_eq!(C, params.G() * blinding + params.H() * Scalar::from(13449261u64));
+
+        let bytes = proof.to_bytes();
+        assert_eq!(bytes.len(), 32 + 16 * (48 + 64));
+        let decoded: GenericRangeProof<Bls12381G1> = GenericRangeProof::from_bytes(&bytes).unwrap();
+        assert!(decoded.verify(16, &params).is_some());
+        assert!(decoded.verify(15, &params).is_none());
+    }
+}
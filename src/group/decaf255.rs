@@ -0,0 +1,114 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! The Decaf group over Curve25519.
+
+use rand::Rng;
+
+use sha2::Sha512;
+
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::curve::Identity;
+use curve25519_dalek::decaf::DecafPoint;
+use curve25519_dalek::decaf::vartime;
+
+use encoding::{read_point, read_scalar};
+
+use super::RangeProofGroup;
+
+/// The Decaf group over Curve25519, as used by `RangeProof`.
+///
+/// Challenges are derived exactly as in the `challenges` module, so a
+/// `GenericRangeProof<Decaf255>` has the same encoding as, and
+/// verifies as, a `RangeProof`.
+pub enum Decaf255 {}
+
+impl RangeProofGroup for Decaf255 {
+    type Scalar = Scalar;
+    type Point = DecafPoint;
+
+    const POINT_LENGTH: usize = 32;
+    const SCALAR_LENGTH: usize = 32;
+
+    fn identity() -> DecafPoint {
+        DecafPoint::identity()
+    }
+
+    fn add(A: &DecafPoint, B: &DecafPoint) -> DecafPoint {
+        A + B
+    }
+
+    fn sub(A: &DecafPoint, B: &DecafPoint) -> DecafPoint {
+        A - B
+    }
+
+    fn mul(A: &DecafPoint, a: &Scalar) -> DecafPoint {
+        A * a
+    }
+
+    fn double_mul(a: &Scalar, A: &DecafPoint, b: &Scalar, B: &DecafPoint) -> DecafPoint {
+        vartime::k_fold_scalar_mult(&[*a, *b], &[*A, *B])
+    }
+
+    fn scalar_from_u64(x: u64) -> Scalar {
+        Scalar::from_u64(x)
+    }
+
+    fn scalar_add(a: &Scalar, b: &Scalar) -> Scalar {
+        a + b
+    }
+
+    fn scalar_mul(a: &Scalar, b: &Scalar) -> Scalar {
+        a * b
+    }
+
+    fn scalar_neg(a: &Scalar) -> Scalar {
+        -a
+    }
+
+    fn scalar_invert(a: &Scalar) -> Option<Scalar> {
+        if *a == Scalar::zero() {
+            return None;
+        }
+        Some(a.invert())
+    }
+
+    fn random_scalar<R: Rng>(rng: &mut R) -> Scalar {
+        Scalar::random(rng)
+    }
+
+    fn hash_to_scalar(bytes: &[u8]) -> Scalar {
+        Scalar::hash_from_bytes::<Sha512>(bytes)
+    }
+
+    fn point_to_bytes(A: &DecafPoint) -> Vec<u8> {
+        A.compress().as_bytes().to_vec()
+    }
+
+    fn point_from_bytes(bytes: &[u8]) -> Option<DecafPoint> {
+        if bytes.len() != 32 {
+            return None;
+        }
+        read_point(bytes).ok()
+    }
+
+    fn scalar_to_bytes(a: &Scalar) -> Vec<u8> {
+        a.as_bytes().to_vec()
+    }
+
+    fn scalar_from_bytes(bytes: &[u8]) -> Option<Scalar> {
+        if bytes.len() != 32 {
+            return None;
+        }
+        read_scalar(bytes).ok()
+    }
+}
@@ -0,0 +1,176 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! The Decaf group over Curve448.
+//!
+//! Points and scalars are both encoded in 56 bytes, so a proof with
+//! `n` rings is `56(1+3n)` bytes long, or at most
+//! `DECAF448_RANGEPROOF_MAX_LENGTH` bytes.  Since values are `u64`s,
+//! the maximum number of rings is still `RANGEPROOF_MAX_N`.
+
+use rand::Rng;
+
+use sha2::{Digest, Sha512};
+
+use ed448_goldilocks::{CompressedDecaf, DecafPoint, Scalar};
+
+use super::RangeProofGroup;
+use super::super::RANGEPROOF_MAX_N;
+
+/// The length in bytes of an encoded point or scalar.
+pub const DECAF448_ELEMENT_LENGTH: usize = 56;
+
+/// The length in bytes of an encoded proof with `RANGEPROOF_MAX_N` rings.
+pub const DECAF448_RANGEPROOF_MAX_LENGTH: usize =
+    DECAF448_ELEMENT_LENGTH * (1 + 3 * RANGEPROOF_MAX_N);
+
+/// The length of the wide input reduced to a scalar, so that the
+/// result is uniform modulo the 446-bit group order.
+const WIDE_LENGTH: usize = 114;
+
+/// The Decaf group over Curve448.
+///
+/// Challenges are derived by reducing 114 bytes of SHA-512 output,
+/// from two hashes of the input with distinct one-byte prefixes,
+/// modulo the group order.
+pub enum Decaf448 {}
+
+impl RangeProofGroup for Decaf448 {
+    type Scalar = Scalar;
+    type Point = DecafPoint;
+
+    const POINT_LENGTH: usize = DECAF448_ELEMENT_LENGTH;
+    const SCALAR_LENGTH: usize = DECAF448_ELEMENT_LENGTH;
+
+    fn identity() -> DecafPoint {
+        DecafPoint::identity()
+    }
+
+    fn add(A: &DecafPoint, B: &DecafPoint) -> DecafPoint {
+        A + B
+    }
+
+    fn sub(A: &DecafPoint, B: &DecafPoint) -> DecafPoint {
+        A - B
+    }
+
+    fn mul(A: &DecafPoint, a: &Scalar) -> DecafPoint {
+        A * a
+    }
+
+    fn scalar_from_u64(x: u64) -> Scalar {
+        Scalar::from(x)
+    }
+
+    fn scalar_add(a: &Scalar, b: &Scalar) -> Scalar {
+        a + b
+    }
+
+    fn scalar_mul(a: &Scalar, b: &Scalar) -> Scalar {
+        a * b
+    }
+
+    fn scalar_neg(a: &Scalar) -> Scalar {
+        -a
+    }
+
+    fn scalar_invert(a: &Scalar) -> Option<Scalar> {
+        if *a == Scalar::zero() {
+            return None;
+        }
+        Some(a.invert())
+    }
+
+    fn random_scalar<R: Rng>(rng: &mut R) -> Scalar {
+        let mut bytes = [0u8; WIDE_LENGTH];
+        rng.fill_bytes(&mut bytes);
+        Scalar::from_bytes_mod_order_wide(&bytes)
+    }
+
+    fn hash_to_scalar(bytes: &[u8]) -> Scalar {
+        let mut wide = [0u8; WIDE_LENGTH];
+        for (i, chunk) in wide.chunks_mut(64).enumerate() {
+            let mut hash = Sha512::default();
+            hash.input(&[i as u8]);
+            hash.input(bytes);
+            let len = chunk.len();
+            chunk.copy_from_slice(&hash.result().as_slice()[..len]);
+        }
+        Scalar::from_bytes_mod_order_wide(&wide)
+    }
+
+    fn point_to_bytes(A: &DecafPoint) -> Vec<u8> {
+        A.compress().0.to_vec()
+    }
+
+    fn point_from_bytes(bytes: &[u8]) -> Option<DecafPoint> {
+        if bytes.len() != DECAF448_ELEMENT_LENGTH {
+            return None;
+        }
+        let mut p = [0u8; DECAF448_ELEMENT_LENGTH];
+        p.copy_from_slice(bytes);
+        let point = CompressedDecaf(p).decompress()?;
+
+        // Reject any encoding which doesn't recompress to itself.
+        if point.compress().0[..] != p[..] {
+            return None;
+        }
+        Some(point)
+    }
+
+    fn scalar_to_bytes(a: &Scalar) -> Vec<u8> {
+        a.to_bytes().to_vec()
+    }
+
+    fn scalar_from_bytes(bytes: &[u8]) -> Option<Scalar> {
+        if bytes.len() != DECAF448_ELEMENT_LENGTH {
+            return None;
+        }
+        let mut s = [0u8; DECAF448_ELEMENT_LENGTH];
+        s.copy_from_slice(bytes);
+        Scalar::from_canonical_bytes(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::OsRng;
+
+    use ed448_goldilocks::constants::DECAF_BASEPOINT;
+
+    use generic::{GenericParams, GenericRangeProof};
+
+    #[test]
+    fn prove_and_verify_over_decaf448() {
+        let mut csprng = OsRng::new().unwrap();
+
+        // Test generators only: the discrete log of H is known.
+        let G = DECAF_BASEPOINT;
+        let H = &DECAF_BASEPOINT * &Decaf448::hash_to_scalar(b"dalek-rangeproofs test H");
+        let params: GenericParams<Decaf448> = GenericParams::new(G, H);
+
+        let (proof, commitment, _) =
+            GenericRangeProof::create_vartime(16, 13449261, &params, &mut csprng).unwrap();
+        let C = proof.verify(16, &params).unwrap();
+        assert!(C == commitment);
+
+        let bytes = proof.to_bytes();
+        assert_eq!(bytes.len(), 56 * (1 + 3 * 16));
+        assert_eq!(GenericRangeProof::<Decaf448>::encoded_length(RANGEPROOF_MAX_N),
+                   DECAF448_RANGEPROOF_MAX_LENGTH);
+
+        let decoded: GenericRangeProof<Decaf448> = GenericRangeProof::from_bytes(&bytes).unwrap();
+        assert!(decoded.verify(16, &params).is_some());
+    }
+}
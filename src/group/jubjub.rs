@@ -0,0 +1,152 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! The prime-order subgroup of the Jubjub curve.
+//!
+//! Sapling value commitments are Pedersen commitments
+//! `cv = rcv*R + v*V` in this group, so with `G = R` and `H = V` a
+//! `GenericRangeProof<Jubjub>` proves the range of the value in an
+//! existing `cv`, and its commitments can be added to and subtracted
+//! from value commitments directly.
+
+use rand::Rng;
+
+use sha2::{Digest, Sha512};
+
+use jubjub::{Fr, SubgroupPoint};
+
+use group_traits::{Group, GroupEncoding};
+
+use super::RangeProofGroup;
+
+/// The prime-order subgroup of Jubjub, with points and scalars both
+/// encoded in 32 bytes.
+///
+/// Challenges are derived by reducing the SHA-512 hash of their
+/// input modulo the subgroup order, as for `Decaf255`.
+pub enum Jubjub {}
+
+impl RangeProofGroup for Jubjub {
+    type Scalar = Fr;
+    type Point = SubgroupPoint;
+
+    const POINT_LENGTH: usize = 32;
+    const SCALAR_LENGTH: usize = 32;
+
+    fn identity() -> SubgroupPoint {
+        SubgroupPoint::identity()
+    }
+
+    fn add(A: &SubgroupPoint, B: &SubgroupPoint) -> SubgroupPoint {
+        A + B
+    }
+
+    fn sub(A: &SubgroupPoint, B: &SubgroupPoint) -> SubgroupPoint {
+        A - B
+    }
+
+    fn mul(A: &SubgroupPoint, a: &Fr) -> SubgroupPoint {
+        A * a
+    }
+
+    fn scalar_from_u64(x: u64) -> Fr {
+        Fr::from(x)
+    }
+
+    fn scalar_add(a: &Fr, b: &Fr) -> Fr {
+        a + b
+    }
+
+    fn scalar_mul(a: &Fr, b: &Fr) -> Fr {
+        a * b
+    }
+
+    fn scalar_neg(a: &Fr) -> Fr {
+        -a
+    }
+
+    fn scalar_invert(a: &Fr) -> Option<Fr> {
+        Option::from(a.invert())
+    }
+
+    fn random_scalar<R: Rng>(rng: &mut R) -> Fr {
+        let mut bytes = [0u8; 64];
+        rng.fill_bytes(&mut bytes);
+        Fr::from_bytes_wide(&bytes)
+    }
+
+    fn hash_to_scalar(bytes: &[u8]) -> Fr {
+        let mut hash = Sha512::default();
+        hash.input(bytes);
+        let mut wide = [0u8; 64];
+        wide.copy_from_slice(hash.result().as_slice());
+        Fr::from_bytes_wide(&wide)
+    }
+
+    fn point_to_bytes(A: &SubgroupPoint) -> Vec<u8> {
+        A.to_bytes().to_vec()
+    }
+
+    fn point_from_bytes(bytes: &[u8]) -> Option<SubgroupPoint> {
+        if bytes.len() != 32 {
+            return None;
+        }
+        let mut p = [0u8; 32];
+        p.copy_from_slice(bytes);
+        SubgroupPoint::from_bytes(&p).into()
+    }
+
+    fn scalar_to_bytes(a: &Fr) -> Vec<u8> {
+        a.to_bytes().to_vec()
+    }
+
+    fn scalar_from_bytes(bytes: &[u8]) -> Option<Fr> {
+        if bytes.len() != 32 {
+            return None;
+        }
+        let mut s = [0u8; 32];
+        s.copy_from_slice(bytes);
+        Fr::from_bytes(&s).into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::OsRng;
+
+    use generic::{GenericParams, GenericRangeProof};
+
+    #[test]
+    fn prove_and_verify_over_jubjub() {
+        let mut csprng = OsRng::new().unwrap();
+
+        // Test generators only: the discrete log of H is known.
+        let G = SubgroupPoint::generator();
+        let H = G * Jubjub::hash_to_scalar(b"dalek-rangeproofs test H");
+        let params: GenericParams<Jubjub> = GenericParams::new(G, H);
+
+        let (proof, commitment, blinding) =
+            GenericRangeProof::create_vartime(16, 13449261, &params, &mut csprng).unwrap();
+        let C = proof.verify(16, &params).unwrap();
+        assert_eq!(C, commitment);
+
+        // The commitment composes homomorphically with other
+        // commitments to values under the same generators.
+        let other = G * Fr::from(7u64) + H * Fr::from(1000u64);
+        assert_eq!(C + other, G * (blinding + Fr::from(7u64)) + H * Fr::from(13449261u64 + 1000));
+
+        let decoded: GenericRangeProof<Jubjub> = GenericRangeProof::from_bytes(&proof.to_bytes()).unwrap();
+        assert!(decoded.verify(16, &params).is_some());
+    }
+}
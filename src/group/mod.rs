@@ -0,0 +1,102 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Prime-order groups over which rangeproofs can be instantiated.
+//!
+//! `RangeProof` is implemented directly over Decaf, using
+//! `curve25519-dalek`.  The `RangeProofGroup` trait abstracts the
+//! handful of operations the Back-Maxwell construction needs, so that
+//! `generic::GenericRangeProof` can be instantiated over other groups,
+//! each of which is behind its own feature:
+//!
+//! * `Decaf255`, always available, whose proofs are byte-for-byte
+//!   identical to those of `RangeProof`;
+//! * `Bls12381G1`, the `G1` group of BLS12-381, with the
+//!   `bls12_381` feature;
+//! * `Decaf448`, the Decaf group over Curve448, with the `decaf448`
+//!   feature, for a security level of roughly 224 bits;
+//! * `Jubjub`, the prime-order subgroup of the Jubjub curve used by
+//!   Zcash, with the `jubjub` feature.
+
+use rand::Rng;
+
+mod decaf255;
+#[cfg(feature = "bls12_381")]
+mod bls12_381_g1;
+#[cfg(feature = "decaf448")]
+mod decaf448;
+#[cfg(feature = "jubjub")]
+mod jubjub;
+
+pub use self::decaf255::Decaf255;
+#[cfg(feature = "bls12_381")]
+pub use self::bls12_381_g1::Bls12381G1;
+#[cfg(feature = "decaf448")]
+pub use self::decaf448::{Decaf448, DECAF448_ELEMENT_LENGTH, DECAF448_RANGEPROOF_MAX_LENGTH};
+#[cfg(feature = "jubjub")]
+pub use self::jubjub::Jubjub;
+
+/// A prime-order group, with the operations needed to create and
+/// verify rangeproofs over it.
+///
+/// Implementations are marker types; all operations are associated
+/// functions.
+pub trait RangeProofGroup {
+    /// An element of the scalar field.
+    type Scalar: Copy + PartialEq;
+    /// An element of the group.
+    type Point: Copy;
+
+    /// The length in bytes of an encoded point.
+    const POINT_LENGTH: usize;
+    /// The length in bytes of an encoded scalar.
+    const SCALAR_LENGTH: usize;
+
+    /// The identity element.
+    fn identity() -> Self::Point;
+    /// Returns `A + B`.
+    fn add(A: &Self::Point, B: &Self::Point) -> Self::Point;
+    /// Returns `A - B`.
+    fn sub(A: &Self::Point, B: &Self::Point) -> Self::Point;
+    /// Returns `a * A`.
+    fn mul(A: &Self::Point, a: &Self::Scalar) -> Self::Point;
+
+    /// Returns `a * A + b * B`, possibly in variable time.
+    fn double_mul(a: &Self::Scalar, A: &Self::Point, b: &Self::Scalar, B: &Self::Point) -> Self::Point {
+        Self::add(&Self::mul(A, a), &Self::mul(B, b))
+    }
+
+    /// Returns the scalar `x`.
+    fn scalar_from_u64(x: u64) -> Self::Scalar;
+    /// Returns `a + b`.
+    fn scalar_add(a: &Self::Scalar, b: &Self::Scalar) -> Self::Scalar;
+    /// Returns `a * b`.
+    fn scalar_mul(a: &Self::Scalar, b: &Self::Scalar) -> Self::Scalar;
+    /// Returns `-a`.
+    fn scalar_neg(a: &Self::Scalar) -> Self::Scalar;
+    /// Returns `1/a`, or None if `a` is zero.
+    fn scalar_invert(a: &Self::Scalar) -> Option<Self::Scalar>;
+    /// Returns a uniformly random scalar.
+    fn random_scalar<R: Rng>(rng: &mut R) -> Self::Scalar;
+    /// Hash `bytes` to a uniformly distributed scalar.  This is used to
+    /// derive every challenge of a proof.
+    fn hash_to_scalar(bytes: &[u8]) -> Self::Scalar;
+
+    /// Encode a point in `POINT_LENGTH` bytes.
+    fn point_to_bytes(A: &Self::Point) -> Vec<u8>;
+    /// Decode a canonically-encoded point.
+    fn point_from_bytes(bytes: &[u8]) -> Option<Self::Point>;
+    /// Encode a scalar in `SCALAR_LENGTH` bytes.
+    fn scalar_to_bytes(a: &Self::Scalar) -> Vec<u8>;
+    /// Decode a canonically-encoded scalar.
+    fn scalar_from_bytes(bytes: &[u8]) -> Option<Self::Scalar>;
+}
@@ -0,0 +1,129 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Hazardous materials: the low-level ring equations.
+//!
+//! **This module is for researchers building variants of the scheme and
+//! cross-checking it against the paper.  Nothing here is needed to
+//! create or verify rangeproofs, and misusing it, for instance by
+//! reusing a nonce or leaking one, reveals the blinding of a
+//! commitment.**  Nothing here runs in constant time, and this module
+//! is exempt from semver: it is only built with the `hazmat` feature,
+//! and may change in any release.
+//!
+//! In the notation of the paper, the `i`th ring proves that the digit
+//! commitment `C_i = r*G + v*3^i*H` has `v` in `{0,1,2}`.  Writing
+//! `P_j = C_i - j*3^i*H`, the prover knowing `r` for `P_v`
+//!
+//! 1. chooses a nonce `k` and begins the ring with `nonce_point(k)`;
+//! 2. chains the challenges around the ring from index `v+1` with
+//!    `next_challenge`, choosing each `s_j` at random, and closes it
+//!    with `closing_point`;
+//! 3. receives `e_0`, chains from index `0` up to `v`, and closes the
+//!    ring with `closing_scalar(k, e_v, r)`.
+//!
+//! Digits `v = 0` are proven differently, since `C_i` itself closes the
+//! ring: the prover chains from `e_0` with random `s_1` and `s_2`, and
+//! solves `R_i = e_2*C_i` for `C_i = (k/e_2)*G`.
+
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::decaf::{CompressedDecaf, DecafBasepointTable, DecafPoint};
+use curve25519_dalek::decaf::vartime;
+
+use challenges::ring_challenge;
+
+use super::ring_transcript;
+
+/// The point `k*G` which begins a ring at the prover's index.
+pub fn nonce_point(k: &Scalar, G: &DecafBasepointTable) -> DecafPoint {
+    G * k
+}
+
+/// The challenge `e_{j+1} = Hs(s_j*G - e_j*P_j)` following `e_j` in a
+/// ring, where `P_j = C_i - j*3^i*H`.
+pub fn next_challenge(s_j: &Scalar, e_j: &Scalar, P_j: &DecafPoint, G: &DecafBasepointTable) -> Scalar {
+    let P = vartime::k_fold_scalar_mult(&[*s_j, -e_j], &[G.basepoint(), *P_j]);
+    ring_challenge(&P.compress())
+}
+
+/// The point `R_i = e_2*C_i` closing the `i`th ring, from which `e_0`
+/// is derived.
+pub fn closing_point(e_2: &Scalar, C_i: &DecafPoint) -> DecafPoint {
+    C_i * e_2
+}
+
+/// The response `s = k + e*r` closing a ring at the prover's index,
+/// for the nonce `k`, the challenge `e` at that index, and the
+/// discrete log `r` of `P_v` with respect to `G`.
+pub fn closing_scalar(k: &Scalar, e: &Scalar, r: &Scalar) -> Scalar {
+    Scalar::multiply_add(e, r, k)
+}
+
+/// Recompute the challenges of the `i`th ring `(C_i, s_1, s_2)` of a
+/// proof with challenge `e_0`, as the verifier does, given
+/// `mi_H = 3^i*H` and `mi2_H = 2*3^i*H`.
+///
+/// # Returns
+///
+/// The challenges `(e_1, e_2)` and the closing point `R_i`.
+pub fn verify_ring(
+    C_i: &DecafPoint,
+    s_1: &Scalar,
+    s_2: &Scalar,
+    e_0: &Scalar,
+    G: &DecafBasepointTable,
+    mi_H: &DecafPoint,
+    mi2_H: &DecafPoint,
+) -> (Scalar, Scalar, CompressedDecaf) {
+    let transcript = ring_transcript(C_i, s_1, s_2, e_0, G, mi_H, mi2_H);
+
+    (transcript.e_1, transcript.e_2, transcript.R)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::OsRng;
+
+    use challenges::e_0_challenge;
+    use DEFAULT_PARAMS;
+    use RangeProof;
+
+    #[test]
+    fn hand_built_ring_verifies() {
+        let mut csprng = OsRng::new().unwrap();
+
+        // Prove the single digit v = 1 of the value 1, following the
+        // steps in the module documentation.
+        let G = DEFAULT_PARAMS.G();
+        let mi_H = DEFAULT_PARAMS.H_powers().power(0);
+        let mi2_H = DEFAULT_PARAMS.H_powers().double_power(0);
+
+        let r = Scalar::random(&mut csprng);
+        let C = &(G * &r) + mi_H;
+
+        let k = Scalar::random(&mut csprng);
+        let s_2 = Scalar::random(&mut csprng);
+        let e_1 = ring_challenge(&nonce_point(&k, G).compress());
+        let e_2 = next_challenge(&s_2, &e_1, &(&C - mi2_H), G);
+        let R = closing_point(&e_2, &C).compress();
+
+        let e_0 = e_0_challenge(vec![R]);
+        let s_1 = closing_scalar(&k, &e_0, &r);
+
+        assert!(verify_ring(&C, &s_1, &s_2, &e_0, G, mi_H, mi2_H) == (e_1, e_2, R));
+
+        let proof = RangeProof { e_0: e_0, C: vec![C], s_1: vec![s_1], s_2: vec![s_2] };
+        assert_eq!(proof.verify_with_params(1, &DEFAULT_PARAMS).unwrap().compress(), C.compress());
+    }
+}
@@ -0,0 +1,251 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! The interactive protocol underlying the rangeproof.
+//!
+//! A non-interactive rangeproof takes its shared challenge as
+//! `e_0 = e_0_challenge(R_0, ..., R_{n-1})`.  In the interactive
+//! protocol, the verifier supplies `e_0` instead:
+//!
+//! 1. the prover sends an `InteractiveCommitment`, the points `R_i`
+//!    beginning each ring;
+//! 2. the verifier replies with a random challenge `e_0`;
+//! 3. the prover sends an `InteractiveResponse`, the digit commitments
+//!    `C_i` and the responses `s_1,i` and `s_2,i`;
+//! 4. the verifier recomputes each ring and checks that it closes at
+//!    the committed `R_i`.
+//!
+//! This is for embedding the rangeproof in interactive ceremonies and
+//! designated-verifier settings; a transcript of the interactive
+//! protocol doesn't convince anyone but the verifier who chose `e_0`.
+
+use rand::Rng;
+
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::curve::Identity;
+use curve25519_dalek::decaf::{CompressedDecaf, DecafPoint, DecafBasepointTable};
+
+use blinding::SecretBlinding;
+use params::{PowersOfH, RangeProofParams};
+use prover::{blinding_and_commitment, for_each_ring, RingProver};
+use transcript_rng::TranscriptRng;
+
+use super::base3_digits;
+use super::RangeProof;
+use super::RANGEPROOF_MAX_N;
+
+/// The prover's first message: the points `R_i` beginning each ring.
+#[derive(Clone, Debug)]
+pub struct InteractiveCommitment {
+    R: Vec<CompressedDecaf>,
+}
+
+impl InteractiveCommitment {
+    /// The points `R_i` beginning each ring, in order.
+    pub fn R(&self) -> &[CompressedDecaf] {
+        &self.R
+    }
+}
+
+/// The prover's final message, answering the verifier's challenge.
+pub struct InteractiveResponse {
+    pub(crate) C: Vec<DecafPoint>,
+    pub(crate) s_1: Vec<Scalar>,
+    pub(crate) s_2: Vec<Scalar>,
+}
+
+impl InteractiveResponse {
+    /// The commitment to the value, as the sum of the digit commitments.
+    pub fn commitment(&self) -> DecafPoint {
+        self.C.iter().fold(DecafPoint::identity(), |C, C_i| &C + C_i)
+    }
+}
+
+/// The prover's state between committing and responding.
+///
+/// Responding consumes the prover, since answering two challenges with
+/// the same nonces reveals the value.
+pub struct InteractiveProver<'a> {
+    G: &'a DecafBasepointTable,
+    powers: &'a PowersOfH,
+    rings: Vec<RingProver>,
+}
+
+impl<'a> InteractiveProver<'a> {
+    /// Begin an interactive rangeproof for `value` in `[0,3^n]`, in
+    /// variable time.
+    ///
+    /// # Returns
+    ///
+    /// If `n > RANGEPROOF_MAX_N` or `value` is not in the range
+    /// `[0,3^n]`, return None.
+    ///
+    /// Otherwise, returns the prover's state and the commitment to send
+    /// to the verifier.
+    pub fn commit<T: Rng>(
+        n: usize,
+        value: u64,
+        params: &'a RangeProofParams,
+        csprng: &mut T,
+    ) -> Option<(InteractiveProver<'a>, InteractiveCommitment)> {
+        if n > RANGEPROOF_MAX_N {
+            return None;
+        }
+
+        let prover = InteractiveProver::commit_with_powers(n, value, params.G(), params.H_powers(), csprng)?;
+        let commitment = InteractiveCommitment { R: prover.ring_starts() };
+
+        Some((prover, commitment))
+    }
+
+    /// Begin a rangeproof in variable time, given the precomputed
+    /// powers of `H` for `i` in `0..n`.
+    pub(crate) fn commit_with_powers<T: Rng>(
+        n: usize,
+        value: u64,
+        G: &'a DecafBasepointTable,
+        powers: &'a PowersOfH,
+        csprng: &mut T,
+    ) -> Option<InteractiveProver<'a>> {
+        // Derive the nonces from the statement and the value as well as
+        // from csprng, so that a faulty csprng can't leak the value.
+        let csprng = TranscriptRng::for_proof(n, value, G, powers).finalize(csprng);
+
+        // Check that value is in range: all digits above n should be 0
+        let v = base3_digits(value);
+        if v.iter().skip(n).any(|&digit| digit != 0) || n > powers.len() {
+            return None;
+        }
+
+        let mut rings = RingProver::for_digits(v.get(..n)?, &csprng);
+        for_each_ring(&mut rings, |i, ring| {
+            ring.commit_vartime(G, powers.power(i), powers.double_power(i))
+        });
+
+        Some(InteractiveProver { G: G, powers: powers, rings: rings })
+    }
+
+    /// The points `R_i` beginning each ring, as sent to the verifier.
+    pub(crate) fn ring_starts(&self) -> Vec<CompressedDecaf> {
+        self.rings.iter().map(|ring| ring.R.compress()).collect()
+    }
+
+    /// Answer the verifier's challenge `e_0`.
+    ///
+    /// # Returns
+    ///
+    /// A tuple `(response, commitment, blinding)`, where `response` is
+    /// to be sent to the verifier and `commitment = blinding*G + value*H`.
+    pub fn respond(self, e_0: &Scalar) -> (InteractiveResponse, DecafPoint, SecretBlinding) {
+        let InteractiveProver { G, powers, mut rings } = self;
+
+        for_each_ring(&mut rings, |i, ring| {
+            ring.respond_vartime(e_0, G, powers.power(i), powers.double_power(i))
+        });
+
+        let (blinding, commitment) = blinding_and_commitment(&rings);
+        let response = InteractiveResponse {
+            C: rings.iter().map(|ring| ring.C).collect(),
+            s_1: rings.iter().map(|ring| ring.s_1).collect(),
+            s_2: rings.iter().map(|ring| ring.s_2).collect(),
+        };
+
+        (response, commitment, SecretBlinding::new(blinding))
+    }
+}
+
+/// The verifier's state between challenging and checking the response.
+pub struct InteractiveVerifier<'a> {
+    params: &'a RangeProofParams,
+    R: Vec<CompressedDecaf>,
+    e_0: Scalar,
+}
+
+impl<'a> InteractiveVerifier<'a> {
+    /// Receive the prover's `commitment` and choose a random challenge.
+    ///
+    /// # Returns
+    ///
+    /// The verifier's state and the challenge `e_0` to send to the
+    /// prover.
+    pub fn challenge<T: Rng>(
+        commitment: InteractiveCommitment,
+        params: &'a RangeProofParams,
+        csprng: &mut T,
+    ) -> (InteractiveVerifier<'a>, Scalar) {
+        let e_0 = Scalar::random(csprng);
+        (InteractiveVerifier { params: params, R: commitment.R, e_0: e_0 }, e_0)
+    }
+
+    /// Check the prover's `response` for the range `[0,3^n]`.
+    ///
+    /// # Returns
+    ///
+    /// The commitment to the in-range value if the response is valid,
+    /// and None otherwise, including when `n > RANGEPROOF_MAX_N`.
+    #[must_use]
+    pub fn verify(self, n: usize, response: InteractiveResponse) -> Option<DecafPoint> {
+        if n > RANGEPROOF_MAX_N || self.R.len() != n {
+            return None;
+        }
+
+        let proof = RangeProof { e_0: self.e_0, C: response.C, s_1: response.s_1, s_2: response.s_2 };
+        if proof.C.len() != n || proof.s_1.len() != n || proof.s_2.len() != n || !proof.is_canonical() {
+            return None;
+        }
+
+        let G = self.params.G();
+        let powers = self.params.H_powers();
+        for i in 0..n {
+            if proof.recompute_ring(i, G, powers).R != self.R[i] {
+                return None;
+            }
+        }
+
+        Some(proof.commitment_sum())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::OsRng;
+
+    use DEFAULT_PARAMS;
+
+    #[test]
+    fn interactive_protocol() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let (prover, commitment) = InteractiveProver::commit(16, 13449261, &DEFAULT_PARAMS, &mut csprng).unwrap();
+        let (verifier, e_0) = InteractiveVerifier::challenge(commitment, &DEFAULT_PARAMS, &mut csprng);
+        let (response, C, _) = prover.respond(&e_0);
+        assert_eq!(response.commitment().compress(), C.compress());
+        assert_eq!(verifier.verify(16, response).unwrap().compress(), C.compress());
+
+        // A response to a different challenge doesn't verify.
+        let (prover, commitment) = InteractiveProver::commit(16, 13449261, &DEFAULT_PARAMS, &mut csprng).unwrap();
+        let (verifier, _) = InteractiveVerifier::challenge(commitment, &DEFAULT_PARAMS, &mut csprng);
+        let (response, _, _) = prover.respond(&Scalar::random(&mut csprng));
+        assert!(verifier.verify(16, response).is_none());
+
+        assert!(InteractiveProver::commit(4, 81, &DEFAULT_PARAMS, &mut csprng).is_none());
+        assert!(InteractiveProver::commit(RANGEPROOF_MAX_N + 1, 0, &DEFAULT_PARAMS, &mut csprng).is_none());
+
+        // Neither does one checked against a bound larger than any proof.
+        let (prover, commitment) = InteractiveProver::commit(16, 13449261, &DEFAULT_PARAMS, &mut csprng).unwrap();
+        let (verifier, e_0) = InteractiveVerifier::challenge(commitment, &DEFAULT_PARAMS, &mut csprng);
+        let (response, _, _) = prover.respond(&e_0);
+        assert!(verifier.verify(RANGEPROOF_MAX_N + 1, response).is_none());
+    }
+}
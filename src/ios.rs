@@ -0,0 +1,170 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Swift bindings for iOS, generated by UniFFI, with the `uniffi`
+//! feature.
+//!
+//! The interface is defined in `src/dalek_rangeproofs.udl`:
+//!
+//! ```text
+//! createProof(n: UInt8, value: UInt64) throws -> CreatedProof
+//! verifyProof(proof: Data, n: UInt8) throws -> Data
+//! toSelfDescribing(proof: Data) throws -> Data
+//! fromSelfDescribing(bytes: Data) throws -> SelfDescribingProof
+//! ```
+//!
+//! with the same encodings as the Node.js and Android bindings.  The
+//! Swift sources are generated into `bindings/ios` by
+//! `bindings/ios/generate.sh`, from the library built with this feature.
+//! Invalid arguments and proofs which don't decode or verify throw a
+//! `RangeProofFfiError` rather than panicking.
+
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+
+use commitments;
+use entropy::default_rng;
+use params::DEFAULT_PARAMS;
+
+use super::RangeProof;
+use super::RANGEPROOF_MAX_N;
+
+/// The error thrown to Swift.
+#[derive(Debug)]
+pub enum RangeProofFfiError {
+    /// An argument was out of range.
+    InvalidArgument {
+        /// Why the argument was rejected.
+        reason: String,
+    },
+    /// A proof didn't decode or verify.
+    InvalidProof {
+        /// Why the proof was rejected.
+        reason: String,
+    },
+}
+
+impl fmt::Display for RangeProofFfiError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RangeProofFfiError::InvalidArgument { ref reason } =>
+                write!(f, "Invalid argument: {}", reason),
+            RangeProofFfiError::InvalidProof { ref reason } =>
+                write!(f, "Invalid proof: {}", reason),
+        }
+    }
+}
+
+impl Error for RangeProofFfiError {
+    fn description(&self) -> &str {
+        match *self {
+            RangeProofFfiError::InvalidArgument { .. } => "invalid argument",
+            RangeProofFfiError::InvalidProof { .. }    => "invalid proof",
+        }
+    }
+}
+
+fn invalid_proof<E: fmt::Display>(e: E) -> RangeProofFfiError {
+    RangeProofFfiError::InvalidProof { reason: e.to_string() }
+}
+
+/// A newly created proof, as returned by `createProof`.
+pub struct CreatedProof {
+    /// The encoded rangeproof.
+    pub proof: Vec<u8>,
+    /// The compressed commitment.
+    pub commitment: Vec<u8>,
+    /// The secret blinding of the commitment.
+    pub blinding: Vec<u8>,
+}
+
+/// A decoded self-describing proof, as returned by `fromSelfDescribing`.
+pub struct SelfDescribingProof {
+    /// The number of rings, so that the range is `[0,3^n]`.
+    pub n: u8,
+    /// The encoded rangeproof.
+    pub proof: Vec<u8>,
+}
+
+/// Check that `n` is a number of rings which can be proven.
+fn check_n(n: u8) -> Result<usize, RangeProofFfiError> {
+    if n as usize > RANGEPROOF_MAX_N {
+        return Err(RangeProofFfiError::InvalidArgument {
+            reason: format!("n = {} exceeds the maximum of {}", n, RANGEPROOF_MAX_N),
+        });
+    }
+    Ok(n as usize)
+}
+
+/// Construct a rangeproof for `value` in the range `[0,3^n]`.
+pub fn create_proof(n: u8, value: u64) -> Result<CreatedProof, RangeProofFfiError> {
+    let n = check_n(n)?;
+    let output = RangeProof::try_create_with_params(n, value, &DEFAULT_PARAMS, &mut default_rng())
+        .map_err(|e| RangeProofFfiError::InvalidArgument { reason: e.to_string() })?;
+
+    Ok(CreatedProof {
+        proof: output.proof().to_bytes(),
+        commitment: commitments::to_bytes(output.commitment()).to_vec(),
+        blinding: output.blinding().expose_secret().as_bytes().to_vec(),
+    })
+}
+
+/// Verify a rangeproof with `n` rings, returning the compressed
+/// commitment to the in-range value.
+pub fn verify_proof(proof: Vec<u8>, n: u8) -> Result<Vec<u8>, RangeProofFfiError> {
+    let n = check_n(n)?;
+    let proof = RangeProof::from_bytes_exact(&proof, n).map_err(invalid_proof)?;
+    let verified = proof.verify_checked(n, &DEFAULT_PARAMS).map_err(invalid_proof)?;
+
+    Ok(commitments::to_bytes(verified.commitment()).to_vec())
+}
+
+/// Convert an encoded rangeproof to its self-describing encoding.
+pub fn to_self_describing(proof: Vec<u8>) -> Result<Vec<u8>, RangeProofFfiError> {
+    let proof = RangeProof::from_bytes(&proof).map_err(invalid_proof)?;
+
+    proof.to_self_describing_bytes().map_err(invalid_proof)
+}
+
+/// Decode a self-describing rangeproof, returning its number of rings
+/// and its ordinary encoding.
+pub fn from_self_describing(bytes: Vec<u8>) -> Result<SelfDescribingProof, RangeProofFfiError> {
+    let (n, proof) = RangeProof::from_self_describing_bytes(&bytes).map_err(invalid_proof)?;
+    let n = u8::try_from(n).map_err(invalid_proof)?;
+
+    Ok(SelfDescribingProof { n: n, proof: proof.to_bytes() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn create_verify_and_reencode() {
+        let created = create_proof(16, 1000).unwrap();
+        assert_eq!(verify_proof(created.proof.clone(), 16).unwrap(), created.commitment);
+
+        match verify_proof(created.proof.clone(), 15) {
+            Err(RangeProofFfiError::InvalidProof { .. }) => (),
+            _ => panic!("verified with the wrong number of rings"),
+        }
+        match create_proof(42, 0) {
+            Err(RangeProofFfiError::InvalidArgument { .. }) => (),
+            _ => panic!("created a proof with too many rings"),
+        }
+
+        let decoded = from_self_describing(to_self_describing(created.proof.clone()).unwrap()).unwrap();
+        assert_eq!(decoded.n, 16);
+        assert_eq!(decoded.proof, created.proof);
+    }
+}
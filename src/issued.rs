@@ -0,0 +1,220 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Rangeproofs signed by their prover.
+//!
+//! A permissioned asset issuer with identity key `X = x*G` creates the
+//! rangeproof and signs it with a Schnorr signature whose challenge
+//! binds `X`, the number of rings, and the full encoding of the proof.
+//! The result is a single object showing both that the commitment is
+//! in range and who made it.
+//!
+//! The rangeproof's own challenge `e_0` is derived by
+//! `issued_e_0_challenge`, which also commits to `X`, so the rangeproof
+//! can't be stripped from its signature and re-signed under another
+//! key, and doesn't verify as an ordinary rangeproof.
+
+use rand::Rng;
+
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::decaf::{CompressedDecaf, DecafPoint};
+use curve25519_dalek::decaf::vartime;
+
+use blinding::SecretBlinding;
+use challenges::{issued_challenge, issued_e_0_challenge};
+use encoding::{read_point, read_scalar};
+use errors::DecodingError;
+use params::RangeProofParams;
+use transcript_rng::TranscriptRng;
+
+use super::RangeProof;
+
+/// A rangeproof together with its prover's identity key and signature.
+pub struct IssuedRangeProof {
+    proof: RangeProof,
+    issuer: CompressedDecaf,
+    R: DecafPoint,
+    s: Scalar,
+}
+
+impl IssuedRangeProof {
+    /// Construct a rangeproof for `value` in constant time and sign it
+    /// with the identity key `issuer_key`.
+    ///
+    /// # Returns
+    ///
+    /// If `n > RANGEPROOF_MAX_N` or `value` is not in the range
+    /// `[0,3^n]`, return None.
+    ///
+    /// Otherwise, returns `Some((proof, commitment, blinding))`, as for
+    /// `RangeProof::create_with_params`.
+    pub fn create<T: Rng>(
+        n: usize,
+        value: u64,
+        issuer_key: &SecretBlinding,
+        params: &RangeProofParams,
+        csprng: &mut T,
+    ) -> Option<(IssuedRangeProof, DecafPoint, SecretBlinding)> {
+        let X = (params.G() * issuer_key.expose_secret()).compress();
+        let output = RangeProof::create_with_challenge(n, value, params.G(), params.H_powers(),
+                                                       |R| issued_e_0_challenge(&X, R), csprng).ok()?;
+        let (proof, commitment, blinding) = output.into_parts();
+        let proof_bytes = proof.to_bytes();
+
+        let mut rng = TranscriptRng::builder(b"issued")
+            .commit_transcript(b"X", X.as_bytes())
+            .commit_transcript(b"proof", &proof_bytes)
+            .commit_witness(b"x", issuer_key.expose_secret().as_bytes())
+            .finalize(csprng);
+        let k = Scalar::random(&mut rng);
+        let R = params.G() * &k;
+
+        let e = issued_challenge(&X, &R.compress(), n, &proof_bytes);
+        let s = Scalar::multiply_add(&e, issuer_key.expose_secret(), &k);
+
+        Some((IssuedRangeProof { proof: proof, issuer: X, R: R, s: s }, commitment, blinding))
+    }
+
+    /// Verify the signature by the embedded identity key, and then the
+    /// rangeproof with `n` rings.
+    ///
+    /// # Returns
+    ///
+    /// The commitment if both verify, and None otherwise.
+    ///
+    /// # Note
+    ///
+    /// This only shows that the proof was made by *some* key; use
+    /// `verify_issuer` to check that it was made by a particular one.
+    #[must_use]
+    pub fn verify(&self, n: usize, params: &RangeProofParams) -> Option<DecafPoint> {
+        let proof = &self.proof;
+        let X = read_point(self.issuer.as_bytes()).ok()?;
+        let e = issued_challenge(&self.issuer, &self.R.compress(), n, &proof.to_bytes());
+        let R = vartime::k_fold_scalar_mult(&[self.s, -&e], &[params.G().basepoint(), X]);
+        if R.compress() != self.R.compress() {
+            return None;
+        }
+
+        proof.check_with_challenge(n, params.G(), params.H_powers(),
+                                   |R| issued_e_0_challenge(&self.issuer, R)).ok()
+    }
+
+    /// Verify as for `verify`, and also that the proof was signed by
+    /// the identity key `issuer`.
+    #[must_use]
+    pub fn verify_issuer(
+        &self,
+        n: usize,
+        issuer: &CompressedDecaf,
+        params: &RangeProofParams,
+    ) -> Option<DecafPoint> {
+        if self.issuer != *issuer {
+            return None;
+        }
+        self.verify(n, params)
+    }
+
+    /// The rangeproof, whose challenge commits to the issuer, and which
+    /// therefore doesn't verify as an ordinary rangeproof.
+    pub fn proof(&self) -> &RangeProof {
+        &self.proof
+    }
+
+    /// The identity key of the prover.
+    pub fn issuer(&self) -> &CompressedDecaf {
+        &self.issuer
+    }
+
+    /// Encode the proof as `X || R || s || proof`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(self.issuer.as_bytes());
+        bytes.extend_from_slice(self.R.compress().as_bytes());
+        bytes.extend_from_slice(self.s.as_bytes());
+        bytes.extend_from_slice(&self.proof.to_bytes());
+        bytes
+    }
+
+    /// Decode a proof encoded by `to_bytes`.
+    ///
+    /// # Returns
+    ///
+    /// A `DecodingError` if `bytes` is too short, if any of the points
+    /// or scalars aren't canonically encoded, or if the rangeproof
+    /// can't be decoded.
+    pub fn from_bytes(bytes: &[u8]) -> Result<IssuedRangeProof, DecodingError> {
+        if bytes.len() < 96 {
+            return Err(DecodingError::InvalidLength);
+        }
+
+        let X = read_point(&bytes[..32])?;
+
+        Ok(IssuedRangeProof {
+            proof: RangeProof::from_bytes(&bytes[96..])?,
+            issuer: X.compress(),
+            R: read_point(&bytes[32..64])?,
+            s: read_scalar(&bytes[64..96])?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::OsRng;
+
+    use DEFAULT_PARAMS;
+    use RANGEPROOF_MAX_N;
+
+    #[test]
+    fn issue_and_verify() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let issuer_key = SecretBlinding::new(Scalar::random(&mut csprng));
+        let issuer = (DEFAULT_PARAMS.G() * issuer_key.expose_secret()).compress();
+        let other = (DEFAULT_PARAMS.G() * &Scalar::random(&mut csprng)).compress();
+
+        let (proof, commitment, _) =
+            IssuedRangeProof::create(16, 13449261, &issuer_key, &DEFAULT_PARAMS, &mut csprng).unwrap();
+
+        assert_eq!(proof.verify(16, &DEFAULT_PARAMS).unwrap().compress(), commitment.compress());
+        assert!(proof.verify_issuer(16, &issuer, &DEFAULT_PARAMS).is_some());
+        assert!(proof.verify_issuer(16, &other, &DEFAULT_PARAMS).is_none());
+
+        let decoded = IssuedRangeProof::from_bytes(&proof.to_bytes()).unwrap();
+        assert!(decoded.verify_issuer(16, &issuer, &DEFAULT_PARAMS).is_some());
+
+        // Claiming the proof for another issuer invalidates the signature.
+        let mut bytes = proof.to_bytes();
+        bytes[..32].copy_from_slice(other.as_bytes());
+        assert!(IssuedRangeProof::from_bytes(&bytes).unwrap().verify(16, &DEFAULT_PARAMS).is_none());
+
+        // The rangeproof is bound to the issuer, so re-signing it under
+        // another key doesn't verify either.
+        let other_key = SecretBlinding::new(Scalar::random(&mut csprng));
+        let other = (DEFAULT_PARAMS.G() * other_key.expose_secret()).compress();
+        let k = Scalar::random(&mut csprng);
+        let R = DEFAULT_PARAMS.G() * &k;
+        let e = issued_challenge(&other, &R.compress(), 16, &proof.proof().to_bytes());
+        let resigned = IssuedRangeProof {
+            proof: RangeProof::from_bytes(&proof.proof().to_bytes()).unwrap(),
+            issuer: other,
+            R: R,
+            s: Scalar::multiply_add(&e, other_key.expose_secret(), &k),
+        };
+        assert!(resigned.verify(16, &DEFAULT_PARAMS).is_none());
+        assert!(proof.proof().verify_with_params(16, &DEFAULT_PARAMS).is_none());
+        assert!(proof.verify(RANGEPROOF_MAX_N + 1, &DEFAULT_PARAMS).is_none());
+    }
+}
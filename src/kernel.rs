@@ -0,0 +1,185 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Mimblewimble transaction kernels.
+//!
+//! In a Mimblewimble transaction whose outputs each carry a
+//! rangeproof, the values balance exactly when the kernel excess
+//!
+//! ```text
+//! X = Σ C_out - Σ C_in - offset*G
+//! ```
+//!
+//! is a multiple of `G` alone.  The transactors show this by signing
+//! the kernel with `X` as the public key, with a Schnorr signature
+//! whose secret key is `x = Σ r_out - Σ r_in - offset`.
+
+use rand::Rng;
+
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::decaf::{CompressedDecaf, DecafPoint};
+use curve25519_dalek::decaf::vartime;
+
+use blinding::SecretBlinding;
+use challenges::kernel_challenge;
+use commitments;
+use encoding::{read_point, read_scalar};
+use errors::DecodingError;
+use params::RangeProofParams;
+use transcript_rng::TranscriptRng;
+
+/// The length in bytes of an encoded `KernelSignature`.
+pub const KERNEL_SIGNATURE_LENGTH: usize = 64;
+
+/// Compute the kernel excess `Σ outputs - Σ inputs - offset*G`.
+pub fn kernel_excess(
+    outputs: &[DecafPoint],
+    inputs: &[DecafPoint],
+    offset: &Scalar,
+    params: &RangeProofParams,
+) -> DecafPoint {
+    &(&commitments::sum(outputs) - &commitments::sum(inputs)) - &(params.G() * offset)
+}
+
+/// Compute the secret key `Σ outputs - Σ inputs - offset` of the
+/// kernel excess, from the blindings of the outputs and inputs.
+pub fn kernel_excess_blinding(
+    outputs: &[&SecretBlinding],
+    inputs: &[&SecretBlinding],
+    offset: &Scalar,
+) -> SecretBlinding {
+    let mut x = Scalar::zero();
+    for r in outputs.iter() {
+        x += r.expose_secret();
+    }
+    for r in inputs.iter() {
+        x = &x - r.expose_secret();
+    }
+    SecretBlinding::new(&x - offset)
+}
+
+/// A Schnorr signature on a kernel message by the kernel excess.
+pub struct KernelSignature {
+    R: DecafPoint,
+    s: Scalar,
+}
+
+impl KernelSignature {
+    /// Sign `message` with the secret key `excess_blinding` of the
+    /// kernel excess.
+    pub fn sign<T: Rng>(
+        excess_blinding: &SecretBlinding,
+        message: &[u8],
+        params: &RangeProofParams,
+        csprng: &mut T,
+    ) -> KernelSignature {
+        let X = (params.G() * excess_blinding.expose_secret()).compress();
+
+        let mut rng = TranscriptRng::builder(b"kernel")
+            .commit_transcript(b"X", X.as_bytes())
+            .commit_transcript(b"message", message)
+            .commit_witness(b"x", excess_blinding.expose_secret().as_bytes())
+            .finalize(csprng);
+        let k = Scalar::random(&mut rng);
+        let R = params.G() * &k;
+
+        let e = kernel_challenge(&X, &R.compress(), message);
+        let s = Scalar::multiply_add(&e, excess_blinding.expose_secret(), &k);
+
+        KernelSignature { R: R, s: s }
+    }
+
+    /// Verify the signature on `message` by the kernel `excess`.
+    ///
+    /// This is what shows that the transaction balances: only if the
+    /// excess is a multiple of `G` alone can anyone sign with it.
+    #[must_use]
+    pub fn verify(&self, excess: &DecafPoint, message: &[u8], params: &RangeProofParams) -> bool {
+        let e = kernel_challenge(&excess.compress(), &self.R.compress(), message);
+        let R = vartime::k_fold_scalar_mult(&[self.s, -&e], &[params.G().basepoint(), *excess]);
+
+        R.compress() == self.R.compress()
+    }
+
+    /// Encode the signature as `R || s`, in 64 bytes.
+    pub fn to_bytes(&self) -> [u8; KERNEL_SIGNATURE_LENGTH] {
+        let mut bytes = [0u8; KERNEL_SIGNATURE_LENGTH];
+        bytes[..32].copy_from_slice(self.R.compress().as_bytes());
+        bytes[32..].copy_from_slice(self.s.as_bytes());
+        bytes
+    }
+
+    /// Decode a signature encoded by `to_bytes`.
+    ///
+    /// # Returns
+    ///
+    /// A `DecodingError::InvalidLength` if `bytes` isn't 64 bytes long,
+    /// a `DecodingError::InvalidPoint` if `R` isn't canonically
+    /// encoded, or a `DecodingError::NonCanonicalScalar` if `s` isn't.
+    pub fn from_bytes(bytes: &[u8]) -> Result<KernelSignature, DecodingError> {
+        if bytes.len() != KERNEL_SIGNATURE_LENGTH {
+            return Err(DecodingError::InvalidLength);
+        }
+
+        Ok(KernelSignature { R: read_point(&bytes[..32])?, s: read_scalar(&bytes[32..])? })
+    }
+}
+
+/// Returns `true` if the compressed `excess` verifies the signature
+/// on `message`, for kernels stored in compressed form.
+#[must_use]
+pub fn verify_kernel(
+    excess: &CompressedDecaf,
+    signature: &KernelSignature,
+    message: &[u8],
+    params: &RangeProofParams,
+) -> bool {
+    match read_point(excess.as_bytes()) {
+        Ok(X) => signature.verify(&X, message, params),
+        Err(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::OsRng;
+
+    use RangeProof;
+    use DEFAULT_PARAMS;
+
+    #[test]
+    fn balanced_transaction_kernel() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let (_, C_in, r_in) = RangeProof::create_with_params(16, 1000, &DEFAULT_PARAMS, &mut csprng).unwrap().into_parts();
+        let (_, C_out_1, r_out_1) = RangeProof::create_with_params(16, 600, &DEFAULT_PARAMS, &mut csprng).unwrap().into_parts();
+        let (_, C_out_2, r_out_2) = RangeProof::create_with_params(16, 400, &DEFAULT_PARAMS, &mut csprng).unwrap().into_parts();
+        let offset = Scalar::random(&mut csprng);
+
+        let X = kernel_excess(&[C_out_1, C_out_2], &[C_in], &offset, &DEFAULT_PARAMS);
+        let x = kernel_excess_blinding(&[&r_out_1, &r_out_2], &[&r_in], &offset);
+        assert_eq!(X.compress(), (DEFAULT_PARAMS.G() * x.expose_secret()).compress());
+
+        let signature = KernelSignature::sign(&x, b"fee=0", &DEFAULT_PARAMS, &mut csprng);
+        assert!(signature.verify(&X, b"fee=0", &DEFAULT_PARAMS));
+        assert!(!signature.verify(&X, b"fee=1", &DEFAULT_PARAMS));
+
+        let decoded = KernelSignature::from_bytes(&signature.to_bytes()).unwrap();
+        assert!(verify_kernel(&X.compress(), &decoded, b"fee=0", &DEFAULT_PARAMS));
+
+        // An unbalanced transaction has an excess with an H component.
+        let X = kernel_excess(&[C_out_1], &[C_in], &offset, &DEFAULT_PARAMS);
+        assert!(!signature.verify(&X, b"fee=0", &DEFAULT_PARAMS));
+    }
+}
@@ -89,7 +89,7 @@
 //! use dalek_rangeproofs::RangeProof;
 //!
 //! let (proof, commitment, blinding) =
-//!     RangeProof::create(40, value, G, &H, &mut csprng).unwrap();
+//!     RangeProof::create(40, value, G, &H, &mut csprng).unwrap().into_parts();
 //! # }
 //! ```
 //!
@@ -117,7 +117,7 @@
 //! # let value = 134492616741;
 //! # use dalek_rangeproofs::RangeProof;
 //! # let (proof, commitment, blinding)
-//! #     = RangeProof::create(40, value, G, &H, &mut csprng).unwrap();
+//! #     = RangeProof::create(40, value, G, &H, &mut csprng).unwrap().into_parts();
 //! 
 //! let proof_bytes: Vec<u8> = serde_cbor::ser::to_vec_packed(&proof).unwrap();
 //! assert_eq!(proof_bytes.len(), 4125);
@@ -148,7 +148,7 @@
 //! # let value = 134492616741;
 //! use dalek_rangeproofs::RangeProof;
 //! # let (proof, commitment, blinding)
-//! #     = RangeProof::create(40, value, G, &H, &mut csprng).unwrap();
+//! #     = RangeProof::create(40, value, G, &H, &mut csprng).unwrap().into_parts();
 //! # let proof_bytes: Vec<u8> = serde_cbor::to_vec(&proof).unwrap();
 //! let proof: RangeProof = serde_cbor::from_slice(&proof_bytes).unwrap();
 //!
@@ -190,43 +190,282 @@
 //! # let value = 134492616741;
 //! # use dalek_rangeproofs::RangeProof;
 //! # let (proof, commitment, blinding)
-//! #     = RangeProof::create(40, value, G, &H, &mut csprng).unwrap();
+//! #     = RangeProof::create(40, value, G, &H, &mut csprng).unwrap().into_parts();
 //! # let C = proof.verify(40, G, &H).unwrap();
-//! let C_hat = &(G * &blinding) + &(&H * &Scalar::from_u64(value));
+//! let C_hat = &(G * blinding.expose_secret()) + &(&H * &Scalar::from_u64(value));
 //!
 //! assert_eq!(C_hat, C);
 //! # }
 //! ```
-
-#![cfg_attr(feature = "bench", feature(test))]
+//!
+//! # Panics
+//!
+//! Creating and verifying proofs never panics, for any inputs,
+//! including `n > RANGEPROOF_MAX_N` and malformed proofs and encodings
+//! from untrusted sources: they return None or an error instead.  The
+//! same holds for everything configured with an `n`, such as sessions,
+//! verification pools and decoding limits.  With the `no_panic`
+//! feature, the cores of proving and verification are checked not to
+//! panic at link time, which requires building with optimizations,
+//! e.g. `cargo build --release --features no_panic`.
 
 #![allow(non_snake_case)]
 #![deny(missing_docs)]
 
-#[cfg(all(test, feature = "bench"))]
-extern crate test;
-
 extern crate curve25519_dalek;
 extern crate sha2;
 
+#[macro_use]
+extern crate lazy_static;
+
 extern crate rand;
 
+#[cfg(feature = "parallel")]
+extern crate rayon;
+
+#[cfg(feature = "rkyv")]
+extern crate rkyv;
+
+#[cfg(feature = "postcard")]
+extern crate postcard;
+
+#[cfg(feature = "bls12_381")]
+extern crate bls12_381;
+
+#[cfg(feature = "decaf448")]
+extern crate ed448_goldilocks;
+
+#[cfg(feature = "jubjub")]
+extern crate jubjub;
+#[cfg(feature = "jubjub")]
+extern crate group as group_traits;
+
+#[cfg(feature = "bulletproofs")]
+extern crate bulletproofs;
+#[cfg(feature = "bulletproofs")]
+extern crate curve25519_dalek_ng;
+
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(feature = "serde")]
 #[macro_use]
 extern crate serde_derive;
 
+#[cfg(feature = "schemars")]
+#[macro_use]
+extern crate schemars;
+
+#[cfg(any(test, feature = "cbor"))]
+extern crate serde_cbor;
+#[cfg(any(test, feature = "audit"))]
+extern crate serde_json;
+
+#[cfg(feature = "mmap")]
+extern crate memmap2;
+
+#[cfg(feature = "getrandom")]
+extern crate getrandom;
+
+#[cfg(feature = "napi")]
+extern crate napi;
+#[cfg(feature = "napi")]
+extern crate napi_derive;
+
+#[cfg(feature = "jni")]
+extern crate jni;
+
+#[cfg(feature = "uniffi")]
+extern crate uniffi;
+
+#[cfg(feature = "zstd")]
+extern crate zstd;
+
+#[cfg(feature = "framing")]
+extern crate crc32c;
+
+#[cfg(feature = "tokio")]
+extern crate bytes;
+#[cfg(feature = "tokio")]
+extern crate tokio_util;
+
+#[cfg(feature = "sqlx")]
+extern crate sqlx;
+#[cfg(feature = "diesel")]
+#[macro_use]
+extern crate diesel;
+
+#[cfg(feature = "no_panic")]
+extern crate no_panic;
+#[cfg(feature = "no_panic")]
+use no_panic::no_panic;
+
 use rand::Rng;
+use rand::SeedableRng;
+use rand::chacha::ChaChaRng;
 
-use sha2::Sha512;
-use sha2::Digest;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 
 // XXX rewrite curve25519_dalek to have nicer imports.
 use curve25519_dalek::scalar::Scalar;
 use curve25519_dalek::curve::{Identity};
-use curve25519_dalek::decaf::{DecafPoint, DecafBasepointTable};
+use curve25519_dalek::decaf::{CompressedDecaf, DecafPoint, DecafBasepointTable};
 use curve25519_dalek::decaf::vartime;
-use curve25519_dalek::subtle::CTAssignable;
-use curve25519_dalek::subtle::bytes_equal_ct;
-use curve25519_dalek::subtle::byte_is_nonzero;
+
+use challenges::{e_0_challenge, ring_challenge};
+use interactive::InteractiveProver;
+use prover::{blinding_and_commitment, for_each_ring, RingProver};
+
+#[cfg(feature = "rkyv")]
+pub mod archive;
+#[cfg(feature = "audit")]
+pub mod audit;
+pub mod base45;
+pub mod challenges;
+pub mod chunking;
+pub mod commitments;
+#[cfg(feature = "consensus")]
+pub mod consensus;
+pub mod container;
+pub mod ct;
+pub mod entropy;
+pub mod generic;
+pub mod group;
+#[cfg(feature = "hazmat")]
+pub mod hazmat;
+#[cfg(feature = "mmap")]
+pub mod mapped;
+#[cfg(feature = "cbor")]
+pub mod migration;
+
+mod accumulator;
+mod adaptor;
+mod amount_mask;
+#[cfg(feature = "jni")]
+mod android;
+mod assets;
+mod balance;
+mod blinding;
+#[cfg(feature = "bulletproofs")]
+mod bulletproofs_gens;
+#[cfg(feature = "diagnostics")]
+mod diagnostics;
+mod bundle;
+mod cache;
+mod certification;
+#[cfg(feature = "tokio")]
+mod codec;
+#[cfg(feature = "zstd")]
+mod compressed;
+mod deadline;
+#[cfg(feature = "diesel")]
+mod diesel_types;
+mod digit;
+mod disjunctive;
+mod dleq;
+mod elements;
+mod encoding;
+mod epochs;
+mod interactive;
+#[cfg(feature = "uniffi")]
+mod ios;
+mod errors;
+#[cfg(feature = "framing")]
+mod framing;
+mod issued;
+mod kernel;
+#[cfg(feature = "napi")]
+mod node;
+mod offline;
+mod opening;
+mod output;
+mod params;
+mod pool;
+mod prover;
+mod public_value;
+mod receipt;
+mod replay;
+mod ring_count;
+mod rings;
+#[cfg(feature = "schemars")]
+mod schema;
+mod session;
+mod signed;
+#[cfg(feature = "sqlx")]
+mod sqlx_types;
+#[cfg(feature = "diagnostics")]
+mod trace;
+mod transcript_rng;
+mod tree;
+mod two_sided;
+mod verification;
+mod weighted_sum;
+
+#[cfg(feature = "serde")]
+pub mod serialization;
+
+pub use accumulator::CommitmentAccumulator;
+pub use adaptor::{AdaptorPoint, AdaptorRangeProof};
+pub use amount_mask::{ecdh_shared_secret, AmountKey, MaskedOutput, TransactionKey};
+pub use assets::{asset_generator, AssetParamsCache};
+pub use balance::{balancing_blinding, balancing_opening, verify_balance, verify_conservation};
+pub use balance::{BalanceTerm, ExplicitCommitment};
+pub use blinding::SecretBlinding;
+pub use bundle::ProofBundle;
+pub use cache::VerificationCache;
+pub use certification::{Certificate, CertificationIssuer, CertificationRequest, CertificationUser};
+pub use certification::CERTIFICATE_LENGTH;
+#[cfg(feature = "tokio")]
+pub use codec::RangeProofCodec;
+#[cfg(feature = "zstd")]
+pub use compressed::{compressed_container_reader, CompressedContainerWriter, DEFAULT_COMPRESSION_LEVEL};
+#[cfg(feature = "diagnostics")]
+pub use diagnostics::{RingDiagnostics, VerificationDiagnostics};
+#[cfg(feature = "diagnostics")]
+pub use trace::{Trace, TraceEvent, TraceMismatch};
+pub use deadline::{BatchAborted, Cancellation};
+pub use digit::DigitProof;
+pub use disjunctive::DisjunctiveRangeProof;
+pub use dleq::{DleqProof, DLEQ_PROOF_LENGTH};
+pub use elements::{ElementsRangeProof, ELEMENTS_MAX_EXP};
+pub use encoding::{DecodingLimits, RANGEPROOF_BASE};
+pub use epochs::{epoch_generator, EpochParams};
+pub use errors::{CapError, ChunkingError, CodecError, ConsensusError, ContainerError, DecodingError, OutOfRange};
+pub use errors::{RangeProofError, ValueOverflow, VerifyError};
+#[cfg(feature = "framing")]
+pub use framing::{split_frame, FRAME_HEADER_LENGTH};
+pub use interactive::{InteractiveCommitment, InteractiveProver, InteractiveResponse, InteractiveVerifier};
+pub use issued::IssuedRangeProof;
+pub use kernel::{kernel_excess, kernel_excess_blinding, verify_kernel, KernelSignature, KERNEL_SIGNATURE_LENGTH};
+pub use offline::RangeProofPrecomputation;
+pub use commitments::{CompressedCommitment, PedersenCommitment};
+pub use opening::{verify_openings, Opening, SplitProof};
+pub use output::ProverOutput;
+pub use params::{PowersOfH, RangeProofParams, DEFAULT_PARAMS};
+pub use pool::{Ticket, VerificationPool, VerificationStatus};
+pub use public_value::{PublicValueProof, PUBLIC_VALUE_PROOF_LENGTH};
+pub use receipt::{ReceiptSigner, VerificationReceipt, RECEIPT_LENGTH};
+pub use replay::ReplayFilter;
+pub use ring_count::RingCount;
+pub use rings::{Inspection, Ring, Rings};
+pub use session::{CappedProver, Prover, Verifier};
+pub use signed::{SignedRangeProof, SIGNED_RANGEPROOF_MAX_N};
+pub use transcript_rng::{TranscriptRng, TranscriptRngBuilder};
+pub use tree::TreeRangeProof;
+pub use two_sided::TwoSidedRangeProof;
+pub use verification::{VerifiedCommitment, VerifiedProof};
+pub use weighted_sum::WeightedSumRangeProof;
+#[cfg(feature = "postcard")]
+pub use serialization::{commitment_from_postcard, commitment_to_postcard};
+
+// The UniFFI scaffolding must be included at the crate root, and refers
+// to the exported functions and types by name.
+#[cfg(feature = "uniffi")]
+use ios::{create_proof, from_self_describing, to_self_describing, verify_proof};
+#[cfg(feature = "uniffi")]
+use ios::{CreatedProof, RangeProofFfiError, SelfDescribingProof};
+#[cfg(feature = "uniffi")]
+uniffi::include_scaffolding!("dalek_rangeproofs");
 
 /// A Back-Maxwell rangeproof, which proves in zero knowledge that a
 /// number is in a range `[0,m^n]`.  We hardcode `m = 3` as this is
@@ -234,11 +473,17 @@ use curve25519_dalek::subtle::byte_is_nonzero;
 ///
 /// The size of the proof and the cost of verification are
 /// proportional to `n`.
-#[derive(Serialize, Deserialize)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "diesel", derive(AsExpression, FromSqlRow))]
+#[cfg_attr(feature = "diesel", diesel(sql_type = diesel::sql_types::Binary))]
 pub struct RangeProof {
+    #[cfg_attr(feature = "serde", serde(with = "serialization::scalar"))]
     e_0: Scalar,
+    #[cfg_attr(feature = "serde", serde(with = "serialization::points"))]
     C: Vec<DecafPoint>,
+    #[cfg_attr(feature = "serde", serde(with = "serialization::scalars"))]
     s_1: Vec<Scalar>,
+    #[cfg_attr(feature = "serde", serde(with = "serialization::scalars"))]
     s_2: Vec<Scalar>,
 }
 
@@ -247,80 +492,239 @@ pub struct RangeProof {
 /// for `u64`s, and 3^41 is the least power of 3 greater than `2^64`.
 pub const RANGEPROOF_MAX_N: usize = 41;
 
+/// The least number of proofs for which `create_many` builds a
+/// basepoint table for each power of `H`.  Below this, building the
+/// tables costs more than using them saves.
+const CREATE_MANY_TABLES_MIN_BATCH: usize = 64;
+
 impl RangeProof {
     /// Verify the rangeproof, returning a Pedersen commitment to the
     /// in-range value if successful.
+    ///
+    /// # Returns
+    ///
+    /// None if the proof doesn't verify, including if
+    /// `n > RANGEPROOF_MAX_N`.
+    #[must_use]
     pub fn verify(
         &self,
         n: usize,
         G: &DecafBasepointTable,
         H: &DecafPoint,
     ) -> Option<DecafPoint> {
-        // Calling verify with n out of bounds is a programming error.
         if n > RANGEPROOF_MAX_N {
-            panic!("Error: called create_vartime with too large bound 3^n, n = {}", n);
+            return None;
+        }
+
+        self.verify_with_powers(n, G, &PowersOfH::with_len(n, H))
+    }
+
+    /// Verify the rangeproof using precomputed `params`, returning a
+    /// Pedersen commitment to the in-range value if successful.
+    #[must_use]
+    pub fn verify_with_params(
+        &self,
+        n: usize,
+        params: &RangeProofParams,
+    ) -> Option<DecafPoint> {
+        if n > RANGEPROOF_MAX_N {
+            return None;
+        }
+
+        self.verify_with_powers(n, params.G(), params.H_powers())
+    }
+
+    /// Verify the rangeproof for whatever number of rings it has,
+    /// returning `n` along with a Pedersen commitment to the value in
+    /// `[0,3^n]` if successful.
+    ///
+    /// # Note
+    ///
+    /// The range is chosen by the prover.  Callers must check the
+    /// returned `n` against their policy before accepting the value as
+    /// in range.
+    #[must_use]
+    pub fn verify_auto(
+        &self,
+        G: &DecafBasepointTable,
+        H: &DecafPoint,
+    ) -> Option<(usize, DecafPoint)> {
+        let n = self.C.len();
+        if n > RANGEPROOF_MAX_N || n != self.s_1.len() || n != self.s_2.len() {
+            return None;
+        }
+
+        self.verify_with_powers(n, G, &PowersOfH::with_len(n, H)).map(|C| (n, C))
+    }
+
+    /// Verify the rangeproof, and check that it proves the commitment
+    /// with the compressed encoding `bytes`.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the proof is valid and is for the given commitment,
+    /// and `false` otherwise, including if `bytes` isn't a valid
+    /// encoding of a point or `n > RANGEPROOF_MAX_N`.
+    #[must_use]
+    pub fn verify_with_compressed_commitment(
+        &self,
+        n: usize,
+        G: &DecafBasepointTable,
+        H: &DecafPoint,
+        bytes: &[u8; 32],
+    ) -> bool {
+        let commitment = match CompressedDecaf(*bytes).decompress() {
+            Some(commitment) => commitment,
+            None => return false,
+        };
+
+        match self.verify(n, G, H) {
+            Some(C) => C == commitment,
+            None => false,
+        }
+    }
+
+    /// Compute `Σ C_i`, the commitment which this rangeproof claims
+    /// to prove, *without verifying the proof*.
+    ///
+    /// # Note
+    ///
+    /// The returned commitment is UNVERIFIED: anyone can construct a
+    /// "proof" whose ring commitments sum to any point they like.  It
+    /// is only suitable for cheap structural bookkeeping, such as a
+    /// preliminary balance check before a transaction is fully
+    /// verified, and must never be relied upon in place of `verify`.
+    pub fn commitment_sum(&self) -> DecafPoint {
+        let mut C = DecafPoint::identity();
+        for C_i in self.C.iter() {
+            C = &C + C_i;
         }
-        
+        C
+    }
+
+    /// Verify the rangeproof, given the precomputed powers of `H` for
+    /// `i` in `0..n`.
+    fn verify_with_powers(
+        &self,
+        n: usize,
+        G: &DecafBasepointTable,
+        powers: &PowersOfH,
+    ) -> Option<DecafPoint> {
+        self.check_with_powers(n, G, powers).ok()
+    }
+
+    /// Verify the rangeproof, given the precomputed powers of `H` for
+    /// `i` in `0..n`, returning the reason for any failure.
+    ///
+    /// This can't panic, whatever the proof, and with the `no_panic`
+    /// feature this is checked at link time.
+    #[cfg_attr(feature = "no_panic", no_panic)]
+    fn check_with_powers(
+        &self,
+        n: usize,
+        G: &DecafBasepointTable,
+        powers: &PowersOfH,
+    ) -> Result<DecafPoint, VerifyError> {
+        self.check_with_challenge(n, G, powers, |R| e_0_challenge(R.iter().cloned()))
+    }
+
+    /// Verify the rangeproof, as for `check_with_powers`, checking `e_0`
+    /// against the challenge derived from the `R_i` with `challenge`.
+    fn check_with_challenge<F: Fn(&[CompressedDecaf]) -> Scalar>(
+        &self,
+        n: usize,
+        G: &DecafBasepointTable,
+        powers: &PowersOfH,
+        challenge: F,
+    ) -> Result<DecafPoint, VerifyError> {
+        self.check_with_adaptor(n, G, powers, None, challenge)
+    }
+
+    /// Verify the rangeproof, as for `check_with_challenge`, with the
+    /// point hashed to `e_1` in the first ring offset by the `adaptor`
+    /// point, if any.
+    fn check_with_adaptor<F: Fn(&[CompressedDecaf]) -> Scalar>(
+        &self,
+        n: usize,
+        G: &DecafBasepointTable,
+        powers: &PowersOfH,
+        adaptor: Option<&DecafPoint>,
+        challenge: F,
+    ) -> Result<DecafPoint, VerifyError> {
         // If the lengths of any of the arrays don't match, the proof
         // is malformed.
         if n != self.C.len() {
-            return None;
+            return Err(VerifyError::WrongRingCount);
         } else if n != self.s_1.len() {
-            return None;
+            return Err(VerifyError::WrongRingCount);
         } else if n != self.s_2.len() {
-            return None;
+            return Err(VerifyError::WrongRingCount);
         }
-        
-        let mut e_0_hash = Sha512::default();
-        let mut C = DecafPoint::identity();
-        // mi_H = m^i * H = 3^i * H in the loop below
-        let mut mi_H = *H;
 
-        for i in 0..n {
-            let mi2_H = &mi_H + &mi_H;
+        // The powers are always computed for at least n rings, so this
+        // only rejects n > RANGEPROOF_MAX_N and guards the iteration
+        // below.
+        let mut R = [CompressedDecaf([0u8; 32]); RANGEPROOF_MAX_N];
+        if n > R.len() || n > powers.len() {
+            return Err(VerifyError::WrongRingCount);
+        }
 
-            let Ci_minus_miH = &self.C[i] - &mi_H;
-            let P = vartime::k_fold_scalar_mult(&[self.s_1[i], -&self.e_0],
-                                                &[G.basepoint(), Ci_minus_miH]);
-            let ei_1 = Scalar::hash_from_bytes::<Sha512>(P.compress().as_bytes());
+        // If any of the scalars aren't reduced, the proof is malleable.
+        if !self.is_canonical() {
+            return Err(VerifyError::NonCanonicalScalar);
+        }
 
-            let Ci_minus_2miH = &self.C[i] - &mi2_H;
-            let P = vartime::k_fold_scalar_mult(&[self.s_2[i], -&ei_1],
-                                                &[G.basepoint(), Ci_minus_2miH]);
-            let ei_2 = Scalar::hash_from_bytes::<Sha512>(P.compress().as_bytes());
+        let mut C = DecafPoint::identity();
+        let rings = self.C.iter().zip(self.s_1.iter()).zip(self.s_2.iter());
 
-            let Ri = &self.C[i] * &ei_2;
-            e_0_hash.input(Ri.compress().as_bytes());
-            C = &C + &self.C[i];
+        // Only the first ring is offset by the adaptor point.
+        let identity = DecafPoint::identity();
+        let mut T = adaptor.unwrap_or(&identity);
 
-            // Set mi_H <-- 3*m_iH, so that mi_H is always 3^i * H in the loop
-            mi_H = &mi_H + &mi2_H;
+        for (((C_i, s_1), s_2), ((mi_H, mi2_H), R_i)) in rings.zip(powers.iter().zip(R.iter_mut())) {
+            *R_i = adapted_ring_transcript(C_i, s_1, s_2, &self.e_0, G, mi_H, mi2_H, T).R;
+            C = &C + C_i;
+            T = &identity;
         }
 
-        let e_0_hat = Scalar::from_hash(e_0_hash);
+        let e_0_hat = challenge(R.get(..n).ok_or(VerifyError::WrongRingCount)?);
 
         if e_0_hat == self.e_0 {
-            return Some(C);
+            return Ok(C);
         } else {
-            return None;
+            return Err(VerifyError::InvalidProof);
         }
     }
 
+    /// Recompute the transcript of the `i`-th ring, as the verifier
+    /// does.
+    fn recompute_ring(
+        &self,
+        i: usize,
+        G: &DecafBasepointTable,
+        powers: &PowersOfH,
+    ) -> RingTranscript {
+        ring_transcript(&self.C[i], &self.s_1[i], &self.s_2[i], &self.e_0,
+                        G, powers.power(i), powers.double_power(i))
+    }
+
     /// Construct a rangeproof for `value`, in variable time.
     ///
     /// # Inputs
     ///
-    /// * `n`, so that the range is `[0,3^n]` with `n < RANGEPROOF_MAX_N`;
+    /// * `n`, so that the range is `[0,3^n]` with `n <= RANGEPROOF_MAX_N`;
     /// * The `value` to prove within range `[0,3^n]`;
     /// * `csprng`, an implementation of `rand::Rng`, which should be
     /// cryptographically secure.
     ///
     /// # Returns
     ///
-    /// If `value` is not in the range `[0,3^n]`, return None.
+    /// If `n > RANGEPROOF_MAX_N` or `value` is not in the range
+    /// `[0,3^n]`, return None.
     /// 
-    /// Otherwise, returns `Some((proof, commitment, blinding))`, where:
-    /// `proof` is the rangeproof, and `commitment = blinding*G + value*H`.
+    /// Otherwise, returns a `ProverOutput` holding the rangeproof, the
+    /// commitment `commitment = blinding*G + value*H`, and its secret
+    /// `blinding`.
     ///
     /// Only the `RangeProof` should be sent to the verifier.  The
     /// commitment and blinding are for the use of the prover.
@@ -329,118 +733,32 @@ impl RangeProof {
         value: u64,
         G: &DecafBasepointTable,
         H: &DecafPoint,
-        mut csprng: &mut T,
-    ) -> Option<(RangeProof, DecafPoint, Scalar)> {
-        // Calling verify with n out of bounds is a programming error.
-        if n > RANGEPROOF_MAX_N {
-            panic!("Error: called create_vartime with too large bound 3^n, n = {}", n);
-        }
-
-        // Check that value is in range: all digits above n should be 0
-        let v = base3_digits(value);
-        for i in n..41 {
-            if v[i] != 0 { return None; }
-        }
-
-        let mut R = vec![DecafPoint::identity(); n];
-        let mut C = vec![DecafPoint::identity(); n];
-        let mut k   = vec![Scalar::zero(); n];
-        let mut r   = vec![Scalar::zero(); n];
-        let mut s_1 = vec![Scalar::zero(); n];
-        let mut s_2 = vec![Scalar::zero(); n];
-        let mut e_1 = vec![Scalar::zero(); n];
-        let mut e_2 = vec![Scalar::zero(); n];
-
-        let mut mi_H = *H;
-        for i in 0..n {
-            let mi2_H = &mi_H + &mi_H;
-            k[i] = Scalar::random(&mut csprng);
-
-            if v[i] == 0 {
-                R[i] = G * &k[i];
-            } else if v[i] == 1 {
-                // Commitment to i-th digit is r^i G + 1 * m^i H
-                r[i] = Scalar::random(&mut csprng);
-                C[i] = &(G * &r[i]) + &mi_H;
-                // Begin at index 1 in the ring, choosing random e_1
-                let P = G * &k[i];
-                e_1[i] = Scalar::hash_from_bytes::<Sha512>(P.compress().as_bytes());
-                // Choose random scalar for s_2
-                s_2[i] = Scalar::random(&mut csprng);
-                // Compute e_2 = Hash(s_2^i G - e_1^i (C^i - 2m^i H) )
-                let Ci_minus_mi2H = &C[i] - &mi2_H;
-                let P = vartime::k_fold_scalar_mult(&[s_2[i],       -&e_1[i]],
-                                                    &[G.basepoint(), Ci_minus_mi2H]);
-                e_2[i] = Scalar::hash_from_bytes::<Sha512>(P.compress().as_bytes());
-
-                R[i] = &C[i] * &e_2[i];
-            } else if v[i] == 2 {
-                // Commitment to i-th digit is r^i G + 2 * m^i H
-                r[i] = Scalar::random(&mut csprng);
-                C[i] = &(G * &r[i]) + &mi2_H;
-                // Begin at index 2 in the ring, choosing random e_2
-                let P = G * &k[i];
-                e_2[i] = Scalar::hash_from_bytes::<Sha512>(P.compress().as_bytes());
-
-                R[i] = &C[i] * &e_2[i];
-            } else {
-                panic!("Invalid digit {}", v[i]);
-            }
+        csprng: &mut T,
+    ) -> Option<ProverOutput> {
+        RangeProof::try_create_vartime(n, value, G, H, csprng).ok()
+    }
 
-            // Set mi_H <- 3 * mi_H so that mi_H = m^i H in the loop
-            mi_H = &mi2_H + &mi_H;
-        }
+    /// Construct a rangeproof in variable time, given the precomputed
+    /// powers of `H` for `i` in `0..n`.
+    fn create_vartime_with_powers<T: Rng>(
+        n: usize,
+        value: u64,
+        G: &DecafBasepointTable,
+        powers: &PowersOfH,
+        csprng: &mut T,
+    ) -> Result<ProverOutput, RangeProofError> {
+        RangeProof::check_n(n)?;
+        RangeProof::check_range(n, value)?;
+        let prover = InteractiveProver::commit_with_powers(n, value, G, powers, csprng)
+            .ok_or(RangeProofError::InvalidRingCount(n))?;
 
         // Compute e_0 = Hash( R^0 || ... || R^{n-1} )
-        let mut e_0_hash = Sha512::default();
-        for i in 0..n {
-            e_0_hash.input(R[i].compress().as_bytes());
-        }
-        let e_0 = Scalar::from_hash(e_0_hash);
-
-        let mut mi_H = *H;
-        for i in 0..n {
-            let mi2_H = &mi_H + &mi_H;
-            if v[i] == 0 {
-                let k_1 = Scalar::random(&mut csprng);
-                let P = vartime::k_fold_scalar_mult(&[k_1, e_0], &[G.basepoint(), mi_H]);
-                e_1[i] = Scalar::hash_from_bytes::<Sha512>(P.compress().as_bytes());
-
-                let k_2 = Scalar::random(&mut csprng);
-                let P = vartime::k_fold_scalar_mult(&[k_2, e_1[i]], &[G.basepoint(), mi2_H]);
-                e_2[i] = Scalar::hash_from_bytes::<Sha512>(P.compress().as_bytes());
-
-                let e_2_inv = e_2[i].invert();
-                r[i] = &e_2_inv * &k[i];
-                C[i] = G * &r[i];
-
-                s_1[i] = &k_1 + &(&e_0    * &(&k[i] * &e_2_inv));
-                s_2[i] = &k_2 + &(&e_1[i] * &(&k[i] * &e_2_inv));
-            } else if v[i] == 1 {
-                s_1[i] = Scalar::multiply_add(&e_0, &r[i], &k[i]);
-            } else if v[i] == 2 {
-                s_1[i] = Scalar::random(&mut csprng);
-                // Compute e_1^i = Hash(s_1^i G - e_0^i (C^i - 1 m^i H) )
-                let Ci_minus_miH = &C[i] - &mi_H;
-                let P = vartime::k_fold_scalar_mult(&[s_1[i],        -&e_0],
-                                                    &[G.basepoint(), Ci_minus_miH]);
-                e_1[i] = Scalar::hash_from_bytes::<Sha512>(P.compress().as_bytes());
-                s_2[i] = Scalar::multiply_add(&e_1[i], &r[i], &k[i]);
-            }
-            // Set mi_H <-- 3*m_iH, so that mi_H is always 3^i * H in the loop
-            mi_H = &mi_H + &mi2_H;
-        }
+        let e_0 = e_0_challenge(prover.ring_starts());
 
-        let mut blinding = Scalar::zero();
-        let mut commitment = DecafPoint::identity();
-        for i in 0..n {
-            blinding += &r[i];
-            // XXX implement AddAssign for ExtendedPoint
-            commitment = &commitment + &C[i];
-        }
+        let (response, commitment, blinding) = prover.respond(&e_0);
 
-        Some((
-            RangeProof{e_0: e_0, C: C, s_1: s_1, s_2: s_2},
+        Ok(ProverOutput::new(
+            RangeProof{e_0: e_0, C: response.C, s_1: response.s_1, s_2: response.s_2},
             commitment,
             blinding,
         ))
@@ -453,184 +771,438 @@ impl RangeProof {
     ///
     /// # Inputs
     ///
-    /// * `n`, so that the range is `[0,3^n]` with `n < RANGEPROOF_MAX_N`;
+    /// * `n`, so that the range is `[0,3^n]` with `n <= RANGEPROOF_MAX_N`;
     /// * The `value` to prove within range `[0,3^n]`;
     /// * `csprng`, an implementation of `rand::Rng`, which should be
     /// cryptographically secure.
     ///
     /// # Returns
     ///
-    /// If `value` is not in the range `[0,3^n]`, return None.
+    /// If `n > RANGEPROOF_MAX_N` or `value` is not in the range
+    /// `[0,3^n]`, return None.
     ///
     /// Note that this function is designed to execute in constant
     /// time for all *valid* inputs.  Passing an out-of-range `value`
     /// will cause it to return `None` early.
     /// 
-    /// Otherwise, returns `Some((proof, commitment, blinding))`, where:
-    /// `proof` is the rangeproof, and `commitment = blinding*G + value*H`.
+    /// Otherwise, returns a `ProverOutput` holding the rangeproof, the
+    /// commitment `commitment = blinding*G + value*H`, and its secret
+    /// `blinding`.
     ///
     /// Only the `RangeProof` should be sent to the verifier.  The
     /// commitment and blinding are for the use of the prover.
     ///
     /// # Note
     ///
-    /// Even when passing a deterministic CSPRNG generated with identical seeds,
-    /// e.g. two instances of `rand::chacha::ChaChaRng::new_unseeded()`, and
-    /// seeking to prove the same `value`, one cannot expect the `RangeProofs`
-    /// generated with `RangeProof::create_vartime()` and `RangeProof::create()`
-    /// to be identical.  The values in the eventual proofs will differ, since
-    /// this constant time version makes additional calls to the `csprng` which
-    /// are thrown away in some conditions.
+    /// Both this function and `RangeProof::create_vartime()` draw the
+    /// nonces for each ring from its own substream of a `TranscriptRng`
+    /// bound to `n`, `value` and the basepoints as well as to `csprng`.
+    /// Even so, given identically seeded `csprng`s, the `RangeProof`s
+    /// they generate differ, since this constant time version draws
+    /// nonces from each substream which are thrown away for some
+    /// digits.
     pub fn create<T: Rng>(
         n: usize,
         value: u64,
         G: &DecafBasepointTable,
         H: &DecafPoint,
-        mut csprng: &mut T,
-    ) -> Option<(RangeProof, DecafPoint, Scalar)> {
-        // Calling verify with n out of bounds is a programming error.
-        if n > RANGEPROOF_MAX_N {
-            panic!("Error: called create_vartime with too large bound 3^n, n = {}", n);
+        csprng: &mut T,
+    ) -> Option<ProverOutput> {
+        RangeProof::try_create(n, value, G, H, csprng).ok()
+    }
+
+    /// Construct a rangeproof in constant time, given the precomputed
+    /// powers of `H` for `i` in `0..n`.
+    fn create_with_powers<T: Rng>(
+        n: usize,
+        value: u64,
+        G: &DecafBasepointTable,
+        powers: &PowersOfH,
+        csprng: &mut T,
+    ) -> Result<ProverOutput, RangeProofError> {
+        RangeProof::create_with_challenge(n, value, G, powers, |R| e_0_challenge(R.iter().cloned()), csprng)
+    }
+
+    /// Construct a rangeproof in constant time, as for
+    /// `create_with_powers`, deriving `e_0` from the `R_i` with
+    /// `challenge`.
+    fn create_with_challenge<T: Rng, F: Fn(&[CompressedDecaf]) -> Scalar>(
+        n: usize,
+        value: u64,
+        G: &DecafBasepointTable,
+        powers: &PowersOfH,
+        challenge: F,
+        csprng: &mut T,
+    ) -> Result<ProverOutput, RangeProofError> {
+        RangeProof::create_with_adaptor(n, value, G, powers, None, challenge, csprng)
+    }
+
+    /// Construct a rangeproof in constant time, as for
+    /// `create_with_challenge`, with the point hashed to `e_1` in the
+    /// first ring offset by the `adaptor` point, if any.
+    ///
+    /// The `s_1` of the first ring of the resulting proof is then
+    /// `t` less than that of a proof which verifies, where `T = t*G`.
+    fn create_with_adaptor<T: Rng, F: Fn(&[CompressedDecaf]) -> Scalar>(
+        n: usize,
+        value: u64,
+        G: &DecafBasepointTable,
+        powers: &PowersOfH,
+        adaptor: Option<&DecafPoint>,
+        challenge: F,
+        csprng: &mut T,
+    ) -> Result<ProverOutput, RangeProofError> {
+        RangeProof::check_n(n)?;
+        RangeProof::check_range(n, value)?;
+
+        // Derive the nonces from the statement and the value as well as
+        // from csprng, so that a faulty csprng can't leak the value.
+        let mut builder = TranscriptRng::for_proof(n, value, G, powers);
+        if let Some(point) = adaptor {
+            builder = builder.commit_transcript(b"T", point.compress().as_bytes());
         }
+        let csprng = builder.finalize(csprng);
 
-        // Check that value is in range: all digits above N should be 0
+        // The digits above n are 0, since value is in range.
         let v = base3_digits(value);
-        for i in n..41 {
-            if v[i] != 0 { return None; }
+        let digits = match v.get(..n) {
+            Some(digits) if n <= powers.len() => digits,
+            _ => return Err(RangeProofError::InvalidRingCount(n)),
+        };
+
+        let mut rings = RingProver::for_digits(digits, &csprng);
+        if let (Some(point), Some(ring)) = (adaptor, rings.first_mut()) {
+            ring.T = *point;
         }
 
-        let mut R = vec![DecafPoint::identity(); n];
-        let mut C = vec![DecafPoint::identity(); n];
-        let mut k   = vec![Scalar::zero(); n];
-        let mut r   = vec![Scalar::zero(); n];
-        let mut s_1 = vec![Scalar::zero(); n];
-        let mut s_2 = vec![Scalar::zero(); n];
-        let mut e_1 = vec![Scalar::zero(); n];
-        let mut e_2 = vec![Scalar::zero(); n];
-
-        let mut mi_H = *H;
-        let mut P: DecafPoint;
-
-        for i in 0..n {
-            debug_assert!(v[i] == 0 || v[i] == 1 || v[i] == 2);
+        for_each_ring(&mut rings, |i, ring| {
+            ring.commit_ct(G, powers.power(i), powers.double_power(i))
+        });
 
-            let mi2_H: DecafPoint = &mi_H + &mi_H;
+        // Compute e_0 = Hash( R^0 || ... || R^{n-1} )
+        let R: Vec<CompressedDecaf> = rings.iter().map(|ring| ring.R.compress()).collect();
+        let e_0 = challenge(&R);
+
+        for_each_ring(&mut rings, |i, ring| {
+            ring.respond_ct(&e_0, G, powers.power(i), powers.double_power(i), powers.tables(i))
+        });
+
+        let (blinding, commitment) = blinding_and_commitment(&rings);
+
+        Ok(ProverOutput::new(
+            RangeProof{
+                e_0: e_0,
+                C: rings.iter().map(|ring| ring.C).collect(),
+                s_1: rings.iter().map(|ring| ring.s_1).collect(),
+                s_2: rings.iter().map(|ring| ring.s_2).collect(),
+            },
+            commitment,
+            SecretBlinding::new(blinding),
+        ))
+    }
 
-            k[i] = Scalar::random(&mut csprng);
+    /// Construct a rangeproof for `value` in variable time, using
+    /// precomputed `params`.
+    ///
+    /// This is identical to `RangeProof::create_vartime`, except that
+    /// the powers of `H` are taken from `params` rather than being
+    /// recomputed.
+    pub fn create_vartime_with_params<T: Rng>(
+        n: usize,
+        value: u64,
+        params: &RangeProofParams,
+        csprng: &mut T,
+    ) -> Option<ProverOutput> {
+        RangeProof::try_create_vartime_with_params(n, value, params, csprng).ok()
+    }
 
-            // Commitment to i-th digit is r^i G + (v^1 * m^i H)
-            let maybe_ri: Scalar = Scalar::random(&mut csprng);
-            r[i].conditional_assign(&maybe_ri, byte_is_nonzero(v[i]));
+    /// Construct a rangeproof for `value` in constant time, using
+    /// precomputed `params`.
+    ///
+    /// This is identical to `RangeProof::create`, except that the
+    /// powers of `H` are taken from `params` rather than being
+    /// recomputed.
+    pub fn create_with_params<T: Rng>(
+        n: usize,
+        value: u64,
+        params: &RangeProofParams,
+        csprng: &mut T,
+    ) -> Option<ProverOutput> {
+        RangeProof::try_create_with_params(n, value, params, csprng).ok()
+    }
 
-            let mut which_mi_H: DecafPoint = mi_H;  // is a copy
-            which_mi_H.conditional_assign(&mi2_H, bytes_equal_ct(v[i], 2u8));
+    /// Construct a rangeproof in constant time, as for
+    /// `create_with_params`, using the default source of entropy
+    /// described in the `entropy` module.
+    ///
+    /// # Panics
+    ///
+    /// If the source of entropy fails.
+    #[cfg(any(feature = "std", feature = "getrandom"))]
+    pub fn create_with_os_rng(
+        n: usize,
+        value: u64,
+        params: &RangeProofParams,
+    ) -> Option<ProverOutput> {
+        let mut csprng = entropy::default_rng();
 
-            let maybe_Ci: DecafPoint = &(G * &r[i]) + &which_mi_H;
-            C[i].conditional_assign(&maybe_Ci, byte_is_nonzero(v[i]));
+        RangeProof::create_with_params(n, value, params, &mut csprng)
+    }
 
-            P = &k[i] * G;
+    /// Construct a rangeproof for `value` in constant time, as for
+    /// `create`, but explain why it can't be constructed.
+    ///
+    /// # Returns
+    ///
+    /// A `RangeProofError::InvalidRingCount` if `n > RANGEPROOF_MAX_N`,
+    /// or a `RangeProofError::OutOfRange` carrying the largest value
+    /// which fits in `n` rings and the least `n` which would fit
+    /// `value`, if `value` is not in the range `[0,3^n]`.
+    ///
+    /// Otherwise, returns `Ok(output)`, exactly as for `create`.
+    pub fn try_create<T: Rng>(
+        n: usize,
+        value: u64,
+        G: &DecafBasepointTable,
+        H: &DecafPoint,
+        csprng: &mut T,
+    ) -> Result<ProverOutput, RangeProofError> {
+        RangeProof::check_n(n)?;
 
-            // Begin at index 1 in the ring, choosing random e_{v^i}
-            let mut maybe_ei = Scalar::hash_from_bytes::<Sha512>(P.compress().as_bytes());
-            e_1[i].conditional_assign(&maybe_ei, bytes_equal_ct(v[i], 1u8));
-            e_2[i].conditional_assign(&maybe_ei, bytes_equal_ct(v[i], 2u8));
+        RangeProof::create_with_powers(n, value, G, &PowersOfH::with_len(n, H), csprng)
+    }
 
-            // Choose random scalar for s_2
-            let maybe_s2: Scalar = Scalar::random(&mut csprng);
-            s_2[i].conditional_assign(&maybe_s2, bytes_equal_ct(v[i], 1u8));
+    /// Construct a rangeproof for `value` in variable time, as for
+    /// `create_vartime`, but explain why it can't be constructed.
+    ///
+    /// # Returns
+    ///
+    /// As for `try_create`.
+    pub fn try_create_vartime<T: Rng>(
+        n: usize,
+        value: u64,
+        G: &DecafBasepointTable,
+        H: &DecafPoint,
+        csprng: &mut T,
+    ) -> Result<ProverOutput, RangeProofError> {
+        RangeProof::check_n(n)?;
 
-            // Compute e_2 = Hash(s_2^i G - e_1^i (C^i - 2m^i H) )
-            P = &(&s_2[i] * G) - &(&e_1[i] * &(&C[i] - &mi2_H));
-            maybe_ei = Scalar::hash_from_bytes::<Sha512>(P.compress().as_bytes());
-            e_2[i].conditional_assign(&maybe_ei, bytes_equal_ct(v[i], 1u8));
+        RangeProof::create_vartime_with_powers(n, value, G, &PowersOfH::with_len(n, H), csprng)
+    }
 
-            // Compute R^i = k^i G            iff  v^i == 0, otherwise
-            //         R^i = e_2^i * C^i
-            R[i] = &k[i] * G;
+    /// Construct a rangeproof for `value` in constant time, as for
+    /// `create_with_params`, but explain why it can't be constructed.
+    ///
+    /// # Returns
+    ///
+    /// As for `try_create`.
+    pub fn try_create_with_params<T: Rng>(
+        n: usize,
+        value: u64,
+        params: &RangeProofParams,
+        csprng: &mut T,
+    ) -> Result<ProverOutput, RangeProofError> {
+        RangeProof::create_with_powers(n, value, params.G(), params.H_powers(), csprng)
+    }
 
-            let maybe_Ri: DecafPoint = &e_2[i] * &C[i];
-            R[i].conditional_assign(&maybe_Ri, byte_is_nonzero(v[i]));
+    /// Construct a rangeproof for `value` in variable time, as for
+    /// `create_vartime_with_params`, but explain why it can't be
+    /// constructed.
+    ///
+    /// # Returns
+    ///
+    /// As for `try_create`.
+    pub fn try_create_vartime_with_params<T: Rng>(
+        n: usize,
+        value: u64,
+        params: &RangeProofParams,
+        csprng: &mut T,
+    ) -> Result<ProverOutput, RangeProofError> {
+        RangeProof::create_vartime_with_powers(n, value, params.G(), params.H_powers(), csprng)
+    }
 
-            // Multiply mi_H by m (a.k.a. m == 3)
-            mi_H = &mi2_H + &mi_H;
+    /// Check that `n` is a number of rings which can be proven.
+    ///
+    /// # Returns
+    ///
+    /// A `RangeProofError::InvalidRingCount` if `n > RANGEPROOF_MAX_N`.
+    pub fn check_n(n: usize) -> Result<(), RangeProofError> {
+        if n > RANGEPROOF_MAX_N {
+            return Err(RangeProofError::InvalidRingCount(n));
         }
+        Ok(())
+    }
 
-        // Compute e_0 = Hash( R^0 || ... || R^{n-1} )
-        let mut e_0_hash = Sha512::default();
-        for i in 0..n {
-            e_0_hash.input(R[i].compress().as_bytes());  // XXX new digest API for 0.5.x
+    /// Check whether `value` can be proven in range with `n` rings.
+    ///
+    /// # Returns
+    ///
+    /// An `OutOfRange` error if `value` exceeds `RangeProof::max_value(n)`.
+    pub fn check_range(n: usize, value: u64) -> Result<(), OutOfRange> {
+        let max = RangeProof::max_value(n);
+        if value <= max {
+            return Ok(());
         }
-        let e_0 = Scalar::from_hash(e_0_hash);
-
-        let mut mi_H = *H;
-
-        for i in 0..n {
-            debug_assert!(v[i] == 0 || v[i] == 1 || v[i] == 2);
-
-            let mi2_H = &mi_H + &mi_H;
 
-            let mut k_1 = Scalar::zero();
-            let maybe_k1: Scalar = Scalar::random(&mut csprng);
-            k_1.conditional_assign(&maybe_k1, bytes_equal_ct(v[i], 0u8));
-
-            P = &(&k_1 * G) + &(&e_0 * &mi_H);
-            let maybe_e_1 = Scalar::hash_from_bytes::<Sha512>(P.compress().as_bytes());
-            e_1[i].conditional_assign(&maybe_e_1, bytes_equal_ct(v[i], 0u8));
-
-            let mut k_2 = Scalar::zero();
-            let maybe_k2: Scalar = Scalar::random(&mut csprng);
-            k_2.conditional_assign(&maybe_k2, bytes_equal_ct(v[i], 0u8));
-
-            P = &(&k_2 * &G.basepoint()) + &(&e_1[i] * &mi2_H);
-            let maybe_e_2 = Scalar::hash_from_bytes::<Sha512>(P.compress().as_bytes()); // XXX API
-            e_2[i].conditional_assign(&maybe_e_2, bytes_equal_ct(v[i], 0u8));
+        Err(OutOfRange { value: value, n: n, max: max, min_n: RangeProof::min_n(value) })
+    }
 
-            let e_2_inv = e_2[i].invert();  // XXX only used in v[i]==0, check what the optimiser is doing
-            let maybe_r_i = &e_2_inv * &k[i];
-            r[i].conditional_assign(&maybe_r_i, bytes_equal_ct(v[i], 0u8));
+    /// The largest value which can be proven in range with `n` rings,
+    /// that is, `3^n - 1` (or `u64::MAX` when `3^n` exceeds a `u64`).
+    pub fn max_value(n: usize) -> u64 {
+        if n >= RANGEPROOF_MAX_N {
+            return u64::max_value();
+        }
+        3u64.pow(n as u32) - 1
+    }
 
-            let maybe_C_i = G * &r[i];
-            C[i].conditional_assign(&maybe_C_i, bytes_equal_ct(v[i], 0u8));
+    /// The least number of rings `n` for which `value` can be proven in
+    /// range, that is, the number of base 3 digits of `value`.
+    pub fn min_n(value: u64) -> usize {
+        let digits = base3_digits(value);
+        (0..RANGEPROOF_MAX_N).rev().find(|&i| digits[i] != 0).map_or(0, |i| i + 1)
+    }
 
-            let mut maybe_s_1 = &k_1 + &(&e_0 * &(&k[i] * &e_2_inv));  // XXX reuse k[i] * e_2_inv
-            s_1[i].conditional_assign(&maybe_s_1, bytes_equal_ct(v[i], 0u8));
-            maybe_s_1 = Scalar::multiply_add(&e_0, &r[i], &k[i]);
-            s_1[i].conditional_assign(&maybe_s_1, bytes_equal_ct(v[i], 1u8));
-            maybe_s_1 = Scalar::random(&mut csprng);
-            s_1[i].conditional_assign(&maybe_s_1, bytes_equal_ct(v[i], 2u8));
+    /// Construct rangeproofs for each of the `values`, in constant time.
+    ///
+    /// The table of `3^i * H` is computed once and shared read-only
+    /// between all of the proofs, along with the basepoint table `G`.
+    /// For large batches, a basepoint table for each `3^i * H` and
+    /// `2 * 3^i * H` is also built once and shared.  When the
+    /// `parallel` feature is enabled, the proofs are created on
+    /// `rayon`'s work-stealing thread pool; otherwise they are created
+    /// sequentially.
+    ///
+    /// # Inputs
+    ///
+    /// * `n`, so that the range is `[0,3^n]` with `n <= RANGEPROOF_MAX_N`;
+    /// * The `values` to prove within range `[0,3^n]`;
+    /// * `csprng`, an implementation of `rand::Rng`, which should be
+    /// cryptographically secure.  It is used to seed an independent
+    /// `ChaChaRng` for each proof, so that no RNG state is shared
+    /// between threads.
+    ///
+    /// # Returns
+    ///
+    /// If `n > RANGEPROOF_MAX_N` or any of the `values` is not in the
+    /// range `[0,3^n]`, return None.
+    ///
+    /// Otherwise, returns `Some(outputs)`, where `outputs[j]` is the
+    /// `ProverOutput` for `values[j]`, exactly as returned by
+    /// `RangeProof::create`.
+    pub fn create_many<T: Rng>(
+        n: usize,
+        values: &[u64],
+        G: &DecafBasepointTable,
+        H: &DecafPoint,
+        csprng: &mut T,
+    ) -> Option<Vec<ProverOutput>> {
+        if n > RANGEPROOF_MAX_N {
+            return None;
+        }
 
-            // Compute e_1^i = Hash(s_1^i G - e_0^i (C^i - 1 m^i H) )
-            let Ci_minus_miH = &C[i] - &mi_H;  // XXX only used in v[i]==2, check optimiser
+        RangeProof::create_many_with_powers(n, values, G, &PowersOfH::with_len(n, H), csprng)
+    }
 
-            P = &(&s_1[i] * &G.basepoint()) - &(&e_0 * &Ci_minus_miH);
-            let maybe_e_1 = Scalar::hash_from_bytes::<Sha512>(P.compress().as_bytes());
-            e_1[i].conditional_assign(&maybe_e_1, bytes_equal_ct(v[i], 2u8));
+    /// Construct rangeproofs for each of the `values` in constant time,
+    /// given the precomputed powers of `H` for `i` in `0..n`.
+    fn create_many_with_powers<T: Rng>(
+        n: usize,
+        values: &[u64],
+        G: &DecafBasepointTable,
+        powers: &PowersOfH,
+        csprng: &mut T,
+    ) -> Option<Vec<ProverOutput>> {
+        let tables;
+        let powers = if values.len() >= CREATE_MANY_TABLES_MIN_BATCH {
+            tables = powers.with_tables(n);
+            &tables
+        } else {
+            powers
+        };
 
-            let mut maybe_s_2 = &k_2 + &(&e_1[i] * &(&k[i] * &e_2_inv));  // XXX reuse k[i] * e_2_inv
-            s_2[i].conditional_assign(&maybe_s_2, bytes_equal_ct(v[i], 0u8));
-            maybe_s_2 = Scalar::multiply_add(&e_1[i], &r[i], &k[i]);
-            s_2[i].conditional_assign(&maybe_s_2, bytes_equal_ct(v[i], 2u8));
+        let seeds: Vec<[u32; 8]> = values.iter().map(|_| {
+            let mut seed = [0u32; 8];
+            for word in seed.iter_mut() {
+                *word = csprng.next_u32();
+            }
+            seed
+        }).collect();
+
+        let prove = |(value, seed): (&u64, &[u32; 8])| {
+            let mut rng = ChaChaRng::from_seed(&seed[..]);
+            RangeProof::create_with_powers(n, *value, G, powers, &mut rng).ok()
+        };
+
+        #[cfg(feature = "parallel")]
+        let outputs: Vec<Option<ProverOutput>> =
+            values.par_iter().zip(seeds.par_iter()).map(prove).collect();
+        #[cfg(not(feature = "parallel"))]
+        let outputs: Vec<Option<ProverOutput>> =
+            values.iter().zip(seeds.iter()).map(prove).collect();
+
+        outputs.into_iter().collect()
+    }
+}
 
-            // Set mi_H <-- 3*m_iH, so that mi_H is always 3^i * H in the loop
-            mi_H = &mi_H + &mi2_H;
-        }
+/// The values hashed and derived while verifying a single ring.
+#[cfg_attr(not(any(feature = "diagnostics", feature = "audit")), allow(dead_code))]
+struct RingTranscript {
+    /// The point hashed to `e_1,i`.
+    P_1: CompressedDecaf,
+    /// The challenge at index 1 of the ring.
+    e_1: Scalar,
+    /// The point hashed to `e_2,i`.
+    P_2: CompressedDecaf,
+    /// The challenge at index 2 of the ring.
+    e_2: Scalar,
+    /// The point `R_i = e_2,i * C_i` closing the ring.
+    R: CompressedDecaf,
+}
 
-        let mut blinding = Scalar::zero();
-        let mut commitment = DecafPoint::identity();
-        for i in 0..n {
-            blinding += &r[i];
-            // XXX implement AddAssign for ExtendedPoint
-            commitment = &commitment + &C[i];
-        }
+/// Recompute the transcript of the `i`-th ring `(C_i, s_1,i, s_2,i)`
+/// of a proof with challenge `e_0`, as the verifier does, given
+/// `mi_H = 3^i * H` and `mi2_H = 2 * 3^i * H`.
+#[cfg_attr(feature = "no_panic", no_panic)]
+fn ring_transcript(
+    C_i: &DecafPoint,
+    s_1: &Scalar,
+    s_2: &Scalar,
+    e_0: &Scalar,
+    G: &DecafBasepointTable,
+    mi_H: &DecafPoint,
+    mi2_H: &DecafPoint,
+) -> RingTranscript {
+    adapted_ring_transcript(C_i, s_1, s_2, e_0, G, mi_H, mi2_H, &DecafPoint::identity())
+}
 
-        Some((
-            RangeProof{ e_0: e_0, C: C, s_1: s_1, s_2: s_2},
-            commitment,
-            blinding,
-        ))
-    }
+/// Recompute the transcript of a ring, as for `ring_transcript`, with
+/// the point hashed to `e_1,i` offset by the adaptor point `T`.
+#[cfg_attr(feature = "no_panic", no_panic)]
+fn adapted_ring_transcript(
+    C_i: &DecafPoint,
+    s_1: &Scalar,
+    s_2: &Scalar,
+    e_0: &Scalar,
+    G: &DecafBasepointTable,
+    mi_H: &DecafPoint,
+    mi2_H: &DecafPoint,
+    T: &DecafPoint,
+) -> RingTranscript {
+    let Ci_minus_miH = C_i - mi_H;
+    let P_1 = (&vartime::k_fold_scalar_mult(&[*s_1, -e_0],
+                                            &[G.basepoint(), Ci_minus_miH]) + T).compress();
+    let ei_1 = ring_challenge(&P_1);
+
+    let Ci_minus_2miH = C_i - mi2_H;
+    let P_2 = vartime::k_fold_scalar_mult(&[*s_2, -&ei_1],
+                                          &[G.basepoint(), Ci_minus_2miH]).compress();
+    let ei_2 = ring_challenge(&P_2);
+
+    RingTranscript { P_1: P_1, e_1: ei_1, P_2: P_2, e_2: ei_2, R: (C_i * &ei_2).compress() }
 }
 
+#[cfg_attr(feature = "no_panic", no_panic)]
 fn base3_digits(mut x: u64) -> [u8; 41] {
     let mut digits = [0u8; 41];
     for i in 0..41 {
@@ -688,7 +1260,7 @@ mod tests {
         let n = 16;
         let value = 13449261;
         let (proof, commitment, blinding) =
-            RangeProof::create_vartime(n, value, G, &H, &mut csprng).unwrap();
+            RangeProof::create_vartime(n, value, G, &H, &mut csprng).unwrap().into_parts();
 
         let C_option = proof.verify(n, G, &H);
         assert!(C_option.is_some());
@@ -696,7 +1268,7 @@ mod tests {
         assert!(proof.verify(2, G, &H).is_none());
 
         let C = C_option.unwrap();
-        let C_hat = &(G * &blinding) + &(&H * &Scalar::from_u64(value));
+        let C_hat = &(G * blinding.expose_secret()) + &(&H * &Scalar::from_u64(value));
 
         assert_eq!(C.compress(), C_hat.compress());
         assert_eq!(commitment.compress(), C_hat.compress());
@@ -712,7 +1284,7 @@ mod tests {
         let n = 16;
         let value = 13449261;
         let (proof, commitment, blinding) =
-            RangeProof::create(n, value, G, &H, &mut csprng).unwrap();
+            RangeProof::create(n, value, G, &H, &mut csprng).unwrap().into_parts();
 
         let C_option = proof.verify(n, G, &H);
         assert!(C_option.is_some());
@@ -720,60 +1292,150 @@ mod tests {
         assert!(proof.verify(2, G, &H).is_none());
 
         let C = C_option.unwrap();
-        let C_hat = &(G * &blinding) + &(&H * &Scalar::from_u64(value));
+        let C_hat = &(G * blinding.expose_secret()) + &(&H * &Scalar::from_u64(value));
 
         assert_eq!(C.compress(), C_hat.compress());
         assert_eq!(commitment.compress(), C_hat.compress());
     }
-}
 
-#[cfg(all(test, feature = "bench"))]
-mod bench {
-    use test::Bencher;
-    use super::*;
+    #[test]
+    fn prove_and_verify_with_default_params() {
+        let mut csprng = OsRng::new().unwrap();
 
-    use rand::OsRng;
-    use sha2::Sha256;
-    use curve25519_dalek::constants as dalek_constants;
+        let n = 16;
+        let value = 13449261;
+        let (proof, commitment, _) =
+            RangeProof::create_with_params(n, value, &DEFAULT_PARAMS, &mut csprng).unwrap().into_parts();
+        assert_eq!(proof.verify_with_params(n, &DEFAULT_PARAMS).unwrap().compress(),
+                   commitment.compress());
+
+        let (proof, commitment, _) =
+            RangeProof::create_vartime_with_params(n, value, &DEFAULT_PARAMS, &mut csprng).unwrap().into_parts();
+        assert_eq!(proof.verify(n, DEFAULT_PARAMS.G(), DEFAULT_PARAMS.H()).unwrap().compress(),
+                   commitment.compress());
+    }
 
-    #[bench]
-    fn verify(b: &mut Bencher) {
+    #[test]
+    fn verify_auto_infers_n() {
         let G = &dalek_constants::DECAF_ED25519_BASEPOINT_TABLE;
         let H = DecafPoint::hash_from_bytes::<Sha256>(G.basepoint().compress().as_bytes());
 
         let mut csprng = OsRng::new().unwrap();
 
-        let value = 1666;
-        let (proof, _, _) = RangeProof::create_vartime(
-            RANGEPROOF_MAX_N, value, G, &H, &mut csprng).unwrap();
+        let (mut proof, commitment, _) = RangeProof::create(16, 13449261, G, &H, &mut csprng).unwrap().into_parts();
+        let (n, C) = proof.verify_auto(G, &H).unwrap();
+        assert_eq!(n, 16);
+        assert_eq!(C.compress(), commitment.compress());
 
-        b.iter(|| proof.verify(RANGEPROOF_MAX_N, G, &H));
+        proof.s_2.pop();
+        assert!(proof.verify_auto(G, &H).is_none());
     }
 
-    #[bench]
-    fn prove_vartime(b: &mut Bencher) {
+    #[test]
+    fn verify_against_compressed_commitment() {
         let G = &dalek_constants::DECAF_ED25519_BASEPOINT_TABLE;
         let H = DecafPoint::hash_from_bytes::<Sha256>(G.basepoint().compress().as_bytes());
 
         let mut csprng = OsRng::new().unwrap();
 
-        let value = 1666;
-        b.iter(|| RangeProof::create_vartime(RANGEPROOF_MAX_N, value, G, &H, &mut csprng));
+        let n = 16;
+        let (proof, commitment, _) = RangeProof::create(n, 13449261, G, &H, &mut csprng).unwrap().into_parts();
+
+        assert!(proof.verify_with_compressed_commitment(n, G, &H, commitment.compress().as_bytes()));
+        assert!(!proof.verify_with_compressed_commitment(n, G, &H, H.compress().as_bytes()));
+        assert!(!proof.verify_with_compressed_commitment(n, G, &H, &[0xff; 32]));
+    }
+
+    #[test]
+    fn commitment_sum_matches_verified_commitment() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let n = 16;
+        let (proof, commitment, _) =
+            RangeProof::create_with_params(n, 13449261, &DEFAULT_PARAMS, &mut csprng).unwrap().into_parts();
+
+        assert_eq!(proof.commitment_sum().compress(), commitment.compress());
+        assert_eq!(proof.verify_with_params(n, &DEFAULT_PARAMS).unwrap().compress(),
+                   proof.commitment_sum().compress());
+    }
+
+    #[test]
+    #[cfg(any(feature = "std", feature = "getrandom"))]
+    fn prove_with_os_rng() {
+        let n = 16;
+        let (proof, commitment, _) =
+            RangeProof::create_with_os_rng(n, 13449261, &DEFAULT_PARAMS).unwrap().into_parts();
+
+        assert_eq!(proof.verify_with_params(n, &DEFAULT_PARAMS).unwrap().compress(),
+                   commitment.compress());
+    }
+
+    #[test]
+    fn out_of_range_error() {
+        let mut csprng = OsRng::new().unwrap();
+
+        assert_eq!(RangeProof::max_value(0), 0);
+        assert_eq!(RangeProof::max_value(4), 80);
+        assert_eq!(RangeProof::max_value(RANGEPROOF_MAX_N), u64::max_value());
+        assert_eq!(RangeProof::min_n(0), 0);
+        assert_eq!(RangeProof::min_n(80), 4);
+        assert_eq!(RangeProof::min_n(81), 5);
+        assert_eq!(RangeProof::min_n(u64::max_value()), RANGEPROOF_MAX_N);
+
+        let err = RangeProof::try_create_with_params(4, 81, &DEFAULT_PARAMS, &mut csprng).err().unwrap();
+        assert_eq!(err, RangeProofError::OutOfRange(OutOfRange { value: 81, n: 4, max: 80, min_n: 5 }));
+
+        let (proof, commitment, _) =
+            RangeProof::try_create_with_params(4, 80, &DEFAULT_PARAMS, &mut csprng).unwrap().into_parts();
+        assert_eq!(proof.verify_with_params(4, &DEFAULT_PARAMS).unwrap().compress(),
+                   commitment.compress());
+    }
+
+    #[test]
+    fn too_many_rings_is_an_error() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let n = RANGEPROOF_MAX_N + 1;
+        assert!(RangeProof::create_with_params(n, 0, &DEFAULT_PARAMS, &mut csprng).is_none());
+        assert!(RangeProof::create_vartime_with_params(n, 0, &DEFAULT_PARAMS, &mut csprng).is_none());
+        assert_eq!(RangeProof::try_create_with_params(n, 0, &DEFAULT_PARAMS, &mut csprng).err(),
+                   Some(RangeProofError::InvalidRingCount(n)));
+        assert!(RangeProof::create_many(n, &[0], DEFAULT_PARAMS.G(), DEFAULT_PARAMS.H(), &mut csprng).is_none());
+
+        let proof = RangeProof::create_with_params(4, 80, &DEFAULT_PARAMS, &mut csprng).unwrap().into_proof();
+        assert!(proof.verify(n, DEFAULT_PARAMS.G(), DEFAULT_PARAMS.H()).is_none());
+        assert!(proof.verify_with_params(n, &DEFAULT_PARAMS).is_none());
+        assert_eq!(proof.verify_checked(n, &DEFAULT_PARAMS).err(), Some(VerifyError::WrongRingCount));
     }
 
-    #[bench]
-    fn prove_ct(b: &mut Bencher) {
+    #[test]
+    fn prove_and_verify_many() {
         let G = &dalek_constants::DECAF_ED25519_BASEPOINT_TABLE;
         let H = DecafPoint::hash_from_bytes::<Sha256>(G.basepoint().compress().as_bytes());
 
         let mut csprng = OsRng::new().unwrap();
 
-        let value = 1666;
-        b.iter(|| RangeProof::create(RANGEPROOF_MAX_N, value, G, &H, &mut csprng));
-    }
+        let n = 16;
+        let values = [0, 1, 2, 13449261, 43046720];
+        let outputs = RangeProof::create_many(n, &values, G, &H, &mut csprng).unwrap();
+        assert_eq!(outputs.len(), values.len());
+
+        for (&value, output) in values.iter().zip(outputs.iter()) {
+            let C = output.proof().verify(n, G, &H).unwrap();
+            let C_hat = &(G * output.blinding().expose_secret()) + &(&H * &Scalar::from_u64(value));
 
-    #[bench]
-    fn bench_base3_digits(b: &mut Bencher) {
-        b.iter(|| base3_digits(10352669767914021650) );
+            assert_eq!(C.compress(), C_hat.compress());
+            assert_eq!(output.commitment().compress(), C_hat.compress());
+        }
+
+        assert!(RangeProof::create_many(n, &[1, 43046721], G, &H, &mut csprng).is_none());
+
+        // A batch large enough to use basepoint tables for the powers of H.
+        let n = 4;
+        let values: Vec<u64> = (0..CREATE_MANY_TABLES_MIN_BATCH as u64).map(|j| j % 81).collect();
+        let outputs = RangeProof::create_many(n, &values, G, &H, &mut csprng).unwrap();
+        for output in outputs.iter() {
+            assert_eq!(output.proof().verify(n, G, &H).unwrap().compress(), output.commitment().compress());
+        }
     }
 }
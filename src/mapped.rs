@@ -0,0 +1,224 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Memory-mapped containers of rangeproofs.
+//!
+//! A `MappedContainer` maps a container file, in the format of the
+//! `container` module, into memory, and yields a `RangeProofRef`
+//! borrowing each entry's bytes in place.  Nothing is copied or decoded
+//! until an entry is verified, so that indexers re-scanning historical
+//! data only pay for the entries they look at.
+
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+use memmap2::Mmap;
+
+use curve25519_dalek::decaf::DecafPoint;
+
+use container::{parse_header, CONTAINER_HEADER_LENGTH};
+use encoding::{read_point, RING_LENGTH};
+use errors::{ContainerError, DecodingError, RangeProofError, VerifyError};
+use params::RangeProofParams;
+
+use super::RangeProof;
+use super::RANGEPROOF_MAX_N;
+
+/// A borrowed, undecoded entry of a container.
+#[derive(Clone, Copy, Debug)]
+pub struct RangeProofRef<'a> {
+    params_id: &'a [u8; 32],
+    n: usize,
+    commitment: &'a [u8],
+    proof: &'a [u8],
+}
+
+impl<'a> RangeProofRef<'a> {
+    /// The number of rings, so that the proof is for the range `[0,3^n]`.
+    pub fn n(&self) -> usize {
+        self.n
+    }
+
+    /// The encoding of the claimed commitment.
+    pub fn commitment_bytes(&self) -> &'a [u8] {
+        self.commitment
+    }
+
+    /// The encoding of the rangeproof, as by `RangeProof::to_bytes`.
+    pub fn proof_bytes(&self) -> &'a [u8] {
+        self.proof
+    }
+
+    /// Decode the claimed commitment.
+    pub fn commitment(&self) -> Result<DecafPoint, DecodingError> {
+        read_point(self.commitment)
+    }
+
+    /// Decode the rangeproof.
+    pub fn to_proof(&self) -> Result<RangeProof, DecodingError> {
+        RangeProof::from_bytes_exact(self.proof, self.n)
+    }
+
+    /// Decode and verify the rangeproof, and check that it proves the
+    /// claimed commitment, as for `ContainerEntry::verify`.
+    ///
+    /// # Returns
+    ///
+    /// A `VerifyError::ParamsMismatch` if the container wasn't made
+    /// with `params`, a `RangeProofError::Decoding` if the entry can't
+    /// be decoded, the reason the proof doesn't verify as for
+    /// `verify_checked`, or a `VerifyError::CommitmentMismatch` if it
+    /// proves another commitment than the claimed one.
+    pub fn verify(&self, params: &RangeProofParams) -> Result<(), RangeProofError> {
+        if self.params_id != params.id() {
+            return Err(VerifyError::ParamsMismatch.into());
+        }
+
+        let C = self.commitment()?;
+        let proof = self.to_proof()?;
+        if proof.verify_checked(self.n, params)?.commitment() != &C {
+            return Err(VerifyError::CommitmentMismatch.into());
+        }
+        Ok(())
+    }
+}
+
+/// A container file mapped into memory.
+pub struct MappedContainer {
+    map: Mmap,
+    params_id: [u8; 32],
+}
+
+impl MappedContainer {
+    /// Map the container file at `path` and check its header.
+    ///
+    /// # Returns
+    ///
+    /// A `ContainerError::Io` if the file can't be opened or mapped, or
+    /// is shorter than a header, or the errors of `ContainerReader::new`
+    /// for an invalid header.
+    ///
+    /// # Note
+    ///
+    /// The file must not be modified while it is mapped; this is the
+    /// usual caveat of memory-mapping, which can't be checked here.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<MappedContainer, ContainerError> {
+        let file = File::open(path)?;
+        let map = unsafe { Mmap::map(&file)? };
+
+        if map.len() < CONTAINER_HEADER_LENGTH {
+            return Err(ContainerError::Io(io::Error::new(io::ErrorKind::UnexpectedEof,
+                                                         "container shorter than its header")));
+        }
+        let mut header = [0u8; CONTAINER_HEADER_LENGTH];
+        header.copy_from_slice(&map[..CONTAINER_HEADER_LENGTH]);
+        let params_id = parse_header(&header)?;
+
+        Ok(MappedContainer { map: map, params_id: params_id })
+    }
+
+    /// The identifier of the parameters the proofs were made with,
+    /// which should be compared against `RangeProofParams::id`.
+    pub fn params_id(&self) -> &[u8; 32] {
+        &self.params_id
+    }
+
+    /// Iterate over the entries of the container, in order.
+    pub fn entries(&self) -> MappedEntries {
+        MappedEntries { params_id: &self.params_id, bytes: &self.map[CONTAINER_HEADER_LENGTH..] }
+    }
+}
+
+/// An iterator over the entries of a `MappedContainer`.
+///
+/// A malformed entry is reported as an error, after which the iterator
+/// ends, since the position of the next entry is unknown.
+pub struct MappedEntries<'a> {
+    params_id: &'a [u8; 32],
+    bytes: &'a [u8],
+}
+
+impl<'a> Iterator for MappedEntries<'a> {
+    type Item = Result<RangeProofRef<'a>, ContainerError>;
+
+    fn next(&mut self) -> Option<Result<RangeProofRef<'a>, ContainerError>> {
+        if self.bytes.is_empty() {
+            return None;
+        }
+
+        let n = self.bytes[0] as usize;
+        let length = 1 + 32 + 32 + RING_LENGTH * n;
+        if n > RANGEPROOF_MAX_N || self.bytes.len() < length {
+            self.bytes = &[];
+            return Some(Err(ContainerError::Decoding(DecodingError::InvalidLength)));
+        }
+
+        let entry = RangeProofRef {
+            params_id: self.params_id,
+            n: n,
+            commitment: &self.bytes[1..33],
+            proof: &self.bytes[33..length],
+        };
+        self.bytes = &self.bytes[length..];
+
+        Some(Ok(entry))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::env;
+    use std::fs;
+    use std::io::Write;
+
+    use rand::{OsRng, Rng};
+
+    use container::ContainerWriter;
+    use DEFAULT_PARAMS;
+
+    #[test]
+    fn map_and_verify_container() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let mut writer = ContainerWriter::new(Vec::new(), &DEFAULT_PARAMS).unwrap();
+        for &(n, value) in [(4, 42), (16, 13449261), (1, 2)].iter() {
+            let (proof, commitment, _) =
+                RangeProof::create_with_params(n, value, &DEFAULT_PARAMS, &mut csprng).unwrap().into_parts();
+            writer.write(&commitment, &proof).unwrap();
+        }
+        let bytes = writer.finish().unwrap();
+
+        let path = env::temp_dir().join(format!("dalek-rangeproofs-mapped-{}", csprng.next_u64()));
+        fs::File::create(&path).unwrap().write_all(&bytes[..bytes.len() - 1]).unwrap();
+
+        let container = MappedContainer::open(&path).unwrap();
+        assert_eq!(container.params_id(), DEFAULT_PARAMS.id());
+
+        let entries: Vec<_> = container.entries().collect();
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].as_ref().unwrap().n(), 4);
+        assert!(entries[0].as_ref().unwrap().verify(&DEFAULT_PARAMS).is_ok());
+        assert!(entries[1].as_ref().unwrap().verify(&DEFAULT_PARAMS).is_ok());
+
+        // The entries were made with other parameters than these.
+        let other_params = RangeProofParams::new(DEFAULT_PARAMS.H(), &DEFAULT_PARAMS.G().basepoint());
+        assert_eq!(entries[0].as_ref().unwrap().verify(&other_params).err(),
+                   Some(RangeProofError::Verify(VerifyError::ParamsMismatch)));
+        // The last entry was truncated.
+        assert!(entries[2].is_err());
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,78 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Migrating stored rangeproofs between `serde_cbor` and the compact
+//! encoding produced by `RangeProof::to_bytes`.
+//!
+//! Neither direction requires re-proving: both encodings contain
+//! exactly the same proof, and the conversion is checked by fully
+//! decoding the proof, so that a successfully migrated proof is
+//! always canonical.
+
+use serde_cbor;
+
+use errors::DecodingError;
+
+use super::RangeProof;
+
+/// Re-encode a `serde_cbor`-encoded rangeproof (packed or not) in the
+/// compact encoding.
+///
+/// # Returns
+///
+/// A `DecodingError::InvalidCbor` if `cbor` is not a valid CBOR
+/// encoding of a rangeproof, including if it has trailing data.
+pub fn cbor_to_compact(cbor: &[u8]) -> Result<Vec<u8>, DecodingError> {
+    let proof: RangeProof = serde_cbor::from_slice(cbor).map_err(|_| DecodingError::InvalidCbor)?;
+
+    Ok(proof.to_bytes())
+}
+
+/// Re-encode a compact rangeproof in packed `serde_cbor` form, as in
+/// the crate documentation.
+///
+/// # Returns
+///
+/// A `DecodingError` if `bytes` is not a valid compact encoding, as
+/// for `RangeProof::from_bytes`.
+pub fn compact_to_cbor(bytes: &[u8]) -> Result<Vec<u8>, DecodingError> {
+    let proof = RangeProof::from_bytes(bytes)?;
+
+    serde_cbor::ser::to_vec_packed(&proof).map_err(|_| DecodingError::InvalidCbor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::OsRng;
+
+    use DEFAULT_PARAMS;
+
+    #[test]
+    fn cbor_compact_roundtrip() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let proof =
+            RangeProof::create_with_params(16, 13449261, &DEFAULT_PARAMS, &mut csprng).unwrap().into_proof();
+
+        let cbor = serde_cbor::to_vec(&proof).unwrap();
+        let compact = cbor_to_compact(&cbor).unwrap();
+        assert_eq!(compact, proof.to_bytes());
+
+        let packed = compact_to_cbor(&compact).unwrap();
+        assert_eq!(packed, serde_cbor::ser::to_vec_packed(&proof).unwrap());
+        assert_eq!(cbor_to_compact(&packed).unwrap(), compact);
+
+        assert_eq!(cbor_to_compact(&compact).err(), Some(DecodingError::InvalidCbor));
+    }
+}
@@ -0,0 +1,123 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Node.js bindings, with the `napi` feature.
+//!
+//! The crate builds as a native addon exporting:
+//!
+//! ```text
+//! createProof(n: number, value: bigint): { proof, commitment, blinding }
+//! verifyProof(proof: Buffer, n: number): Buffer
+//! toSelfDescribing(proof: Buffer): Buffer
+//! fromSelfDescribing(bytes: Buffer): { n, proof }
+//! ```
+//!
+//! where proofs are encoded as by `RangeProof::to_bytes`, commitments as
+//! compressed Decaf points, and blindings as 32-byte scalars, all with
+//! the default parameters.  Invalid inputs and proofs which don't verify
+//! throw an `Error` with the reason, rather than panicking, which would
+//! abort the Node.js process.
+//!
+//! The `blinding` returned by `createProof` opens the commitment, so it
+//! must be kept as secret as any other key material.
+
+use std::convert::TryFrom;
+
+use napi::bindgen_prelude::{BigInt, Buffer};
+use napi::{Error, Result};
+use napi_derive::napi;
+
+use commitments;
+use entropy::default_rng;
+use params::DEFAULT_PARAMS;
+
+use super::RangeProof;
+use super::RANGEPROOF_MAX_N;
+
+/// A newly created proof, as returned by `createProof`.
+#[napi(object)]
+pub struct ProofOutput {
+    /// The encoded rangeproof.
+    pub proof: Buffer,
+    /// The compressed commitment.
+    pub commitment: Buffer,
+    /// The secret blinding of the commitment.
+    pub blinding: Buffer,
+}
+
+/// A decoded self-describing proof, as returned by `fromSelfDescribing`.
+#[napi(object)]
+pub struct SelfDescribingProof {
+    /// The number of rings, so that the range is `[0,3^n]`.
+    pub n: u32,
+    /// The encoded rangeproof.
+    pub proof: Buffer,
+}
+
+/// Check that `n` is a number of rings which can be proven.
+fn check_n(n: u32) -> Result<usize> {
+    if n as usize > RANGEPROOF_MAX_N {
+        return Err(Error::from_reason(format!("n = {} exceeds the maximum of {}", n, RANGEPROOF_MAX_N)));
+    }
+    Ok(n as usize)
+}
+
+/// Construct a rangeproof for `value` in the range `[0,3^n]`.
+#[napi(js_name = "createProof")]
+pub fn create_proof(n: u32, value: BigInt) -> Result<ProofOutput> {
+    let n = check_n(n)?;
+    let (signed, value, lossless) = value.get_u64();
+    if signed || !lossless {
+        return Err(Error::from_reason("value must be an unsigned 64-bit integer"));
+    }
+
+    let output = RangeProof::try_create_with_params(n, value, &DEFAULT_PARAMS, &mut default_rng())
+        .map_err(|e| Error::from_reason(e.to_string()))?;
+
+    Ok(ProofOutput {
+        proof: output.proof().to_bytes().into(),
+        commitment: commitments::to_bytes(output.commitment()).to_vec().into(),
+        blinding: output.blinding().expose_secret().as_bytes().to_vec().into(),
+    })
+}
+
+/// Verify a rangeproof with `n` rings, returning the compressed
+/// commitment to the in-range value.
+#[napi(js_name = "verifyProof")]
+pub fn verify_proof(proof: Buffer, n: u32) -> Result<Buffer> {
+    let n = check_n(n)?;
+    let proof = RangeProof::from_bytes_exact(&proof, n).map_err(|e| Error::from_reason(e.to_string()))?;
+    let verified = proof.verify_checked(n, &DEFAULT_PARAMS).map_err(|e| Error::from_reason(e.to_string()))?;
+
+    Ok(commitments::to_bytes(verified.commitment()).to_vec().into())
+}
+
+/// Convert an encoded rangeproof to its self-describing encoding.
+#[napi(js_name = "toSelfDescribing")]
+pub fn to_self_describing(proof: Buffer) -> Result<Buffer> {
+    let proof = RangeProof::from_bytes(&proof).map_err(|e| Error::from_reason(e.to_string()))?;
+
+    let bytes = proof.to_self_describing_bytes().map_err(|e| Error::from_reason(e.to_string()))?;
+
+    Ok(bytes.into())
+}
+
+/// Decode a self-describing rangeproof, returning its number of rings
+/// and its ordinary encoding.
+#[napi(js_name = "fromSelfDescribing")]
+pub fn from_self_describing(bytes: Buffer) -> Result<SelfDescribingProof> {
+    let (n, proof) = RangeProof::from_self_describing_bytes(&bytes)
+        .map_err(|e| Error::from_reason(e.to_string()))?;
+    let n = u32::try_from(n).map_err(|e| Error::from_reason(e.to_string()))?;
+
+    Ok(SelfDescribingProof { n: n, proof: proof.to_bytes().into() })
+}
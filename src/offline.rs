@@ -0,0 +1,318 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Splitting rangeproof creation into an offline and an online phase.
+//!
+//! Every ring of the proof needs a handful of random scalars and their
+//! multiples of `G`, none of which depend on the value being proven.
+//! A `RangeProofPrecomputation` computes these ahead of time, e.g.
+//! while a signer is idle, so that `RangeProof::create_online` only
+//! needs to do the work which actually depends on the value.
+//!
+//! The random scalars for the `i`th ring are drawn from the `i`th
+//! substream of a `TranscriptRng` bound to `n` and the parameters, and
+//! are overwritten with zeroes when the precomputation is dropped.
+
+use rand::Rng;
+
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::curve::Identity;
+use curve25519_dalek::decaf::{DecafPoint, DecafBasepointTable};
+
+use blinding::{zeroize, SecretBlinding};
+use challenges::{e_0_challenge, ring_challenge};
+use errors::{OutOfRange, RangeProofError};
+use output::ProverOutput;
+use transcript_rng::TranscriptRng;
+
+use super::RangeProof;
+use super::RangeProofParams;
+use super::base3_digits;
+
+/// The value-independent random material for a single ring.
+struct RingPrecomputation {
+    k: Scalar,
+    K: DecafPoint,
+    r: Scalar,
+    rG: DecafPoint,
+    a: Scalar,
+    A: DecafPoint,
+    b: Scalar,
+    B: DecafPoint,
+}
+
+impl RingPrecomputation {
+    fn new<T: Rng>(G: &DecafBasepointTable, mut csprng: &mut T) -> RingPrecomputation {
+        let k = Scalar::random(&mut csprng);
+        let r = Scalar::random(&mut csprng);
+        let a = Scalar::random(&mut csprng);
+        let b = Scalar::random(&mut csprng);
+
+        RingPrecomputation {
+            K: G * &k,
+            rG: G * &r,
+            A: G * &a,
+            B: G * &b,
+            k: k,
+            r: r,
+            a: a,
+            b: b,
+        }
+    }
+}
+
+impl Drop for RingPrecomputation {
+    fn drop(&mut self) {
+        zeroize(&mut self.k.0);
+        zeroize(&mut self.r.0);
+        zeroize(&mut self.a.0);
+        zeroize(&mut self.b.0);
+    }
+}
+
+/// The value-independent part of a rangeproof for the range `[0,3^n]`.
+///
+/// A `RangeProofPrecomputation` contains secret nonces, and must be
+/// used for at most one proof: reusing it would reveal the blinding
+/// factors of both proofs.  For this reason it is neither `Clone` nor
+/// serializable, and `RangeProof::create_online` consumes it.
+pub struct RangeProofPrecomputation {
+    n: usize,
+    params_id: [u8; 32],
+    rings: Vec<RingPrecomputation>,
+}
+
+impl RangeProofPrecomputation {
+    /// Precompute the value-independent material for a rangeproof over
+    /// `[0,3^n]`, using the basepoint `G` from `params`.
+    ///
+    /// This costs four fixed-base scalar multiplications per ring.
+    ///
+    /// # Returns
+    ///
+    /// A `RangeProofError::InvalidRingCount` if `n > RANGEPROOF_MAX_N`.
+    pub fn new<T: Rng>(
+        n: usize,
+        params: &RangeProofParams,
+        csprng: &mut T,
+    ) -> Result<RangeProofPrecomputation, RangeProofError> {
+        RangeProof::check_n(n)?;
+
+        let rng = TranscriptRng::builder(b"offline")
+            .commit_transcript(b"n", &[n as u8])
+            .commit_transcript(b"params", params.id())
+            .finalize(csprng);
+
+        let mut rings = Vec::with_capacity(n);
+        for i in 0..n {
+            rings.push(RingPrecomputation::new(params.G(), &mut rng.substream(i as u64)));
+        }
+
+        Ok(RangeProofPrecomputation { n: n, params_id: *params.id(), rings: rings })
+    }
+
+    /// The `n` for which this precomputation was made, so that the
+    /// resulting proof is for the range `[0,3^n]`.
+    pub fn n(&self) -> usize {
+        self.n
+    }
+}
+
+impl RangeProof {
+    /// Construct a rangeproof for `value` from a `precomputation`, in
+    /// variable time.
+    ///
+    /// No fixed-base scalar multiplications by `G` are needed except
+    /// for the rings whose digit is `0`.
+    ///
+    /// # Returns
+    ///
+    /// A `RangeProofError::PrecomputationMismatch` if the `params`
+    /// aren't those the `precomputation` was made with, and a
+    /// `RangeProofError::OutOfRange` if `value` is not in the range
+    /// `[0,3^n]`.  The `precomputation` is consumed either way.
+    ///
+    /// Otherwise, returns a `ProverOutput`, as for `RangeProof::create`.
+    ///
+    /// # Note
+    ///
+    /// Like `RangeProof::create_vartime`, this function branches on the
+    /// digits of `value`.
+    pub fn create_online(
+        precomputation: RangeProofPrecomputation,
+        value: u64,
+        params: &RangeProofParams,
+    ) -> Result<ProverOutput, RangeProofError> {
+        if precomputation.params_id != *params.id() {
+            return Err(RangeProofError::PrecomputationMismatch);
+        }
+        let n = precomputation.n;
+        RangeProof::check_range(n, value)?;
+
+        let rings = precomputation.rings;
+        let G = params.G();
+        let powers = params.H_powers();
+        if n > powers.len() {
+            return Err(RangeProofError::InvalidRingCount(n));
+        }
+
+        // The digits above n are 0, since value is in range.
+        let v = base3_digits(value);
+        let digits = match v.get(..n) {
+            Some(digits) => digits,
+            None => return Err(RangeProofError::InvalidRingCount(n)),
+        };
+
+        let mut R = Vec::with_capacity(n);
+        let mut C = Vec::with_capacity(n);
+        let mut r = Vec::with_capacity(n);
+
+        for (i, (ring, &digit)) in rings.iter().zip(digits.iter()).enumerate() {
+            let mi_H = powers.power(i);
+            let mi2_H = powers.double_power(i);
+
+            match digit {
+                0 => {
+                    R.push(ring.K);
+                    C.push(DecafPoint::identity());
+                    r.push(Scalar::zero());
+                }
+                1 => {
+                    // Commitment to i-th digit is r^i G + 1 * m^i H
+                    let C_i = &ring.rG + mi_H;
+                    // Begin at index 1 in the ring, with e_1 = Hash(k^i G)
+                    let e_1_i = ring_challenge(&ring.K.compress());
+                    // Compute e_2 = Hash(s_2^i G - e_1^i (C^i - 2m^i H) ), with s_2^i = a^i
+                    let P = &ring.A - &(&(&C_i - mi2_H) * &e_1_i);
+                    let e_2_i = ring_challenge(&P.compress());
+
+                    R.push(&C_i * &e_2_i);
+                    C.push(C_i);
+                    r.push(ring.r);
+                }
+                2 => {
+                    // Commitment to i-th digit is r^i G + 2 * m^i H
+                    let C_i = &ring.rG + mi2_H;
+                    // Begin at index 2 in the ring, with e_2 = Hash(k^i G)
+                    let e_2_i = ring_challenge(&ring.K.compress());
+
+                    R.push(&C_i * &e_2_i);
+                    C.push(C_i);
+                    r.push(ring.r);
+                }
+                _ => {
+                    return Err(RangeProofError::OutOfRange(OutOfRange {
+                        value: value,
+                        n: n,
+                        max: RangeProof::max_value(n),
+                        min_n: RangeProof::min_n(value),
+                    }));
+                }
+            }
+        }
+
+        // Compute e_0 = Hash( R^0 || ... || R^{n-1} )
+        let e_0 = e_0_challenge(R.iter().map(|R_i| R_i.compress()));
+
+        let mut s_1 = Vec::with_capacity(n);
+        let mut s_2 = Vec::with_capacity(n);
+
+        for (i, (ring, &digit)) in rings.iter().zip(digits.iter()).enumerate() {
+            let mi_H = powers.power(i);
+            let mi2_H = powers.double_power(i);
+
+            if digit == 0 {
+                // Use k_1 = a^i and k_2 = b^i
+                let P = &ring.A + &(mi_H * &e_0);
+                let e_1_i = ring_challenge(&P.compress());
+
+                let P = &ring.B + &(mi2_H * &e_1_i);
+                let e_2_i = ring_challenge(&P.compress());
+
+                let k_e_2_inv = &ring.k * &e_2_i.invert();
+                r[i] = k_e_2_inv;
+                C[i] = G * &r[i];
+
+                s_1.push(&ring.a + &(&e_0   * &k_e_2_inv));
+                s_2.push(&ring.b + &(&e_1_i * &k_e_2_inv));
+            } else if digit == 1 {
+                s_1.push(Scalar::multiply_add(&e_0, &r[i], &ring.k));
+                s_2.push(ring.a);
+            } else {
+                // Compute e_1^i = Hash(s_1^i G - e_0^i (C^i - 1 m^i H) ), with s_1^i = a^i
+                let P = &ring.A - &(&(&C[i] - mi_H) * &e_0);
+                let e_1_i = ring_challenge(&P.compress());
+                s_1.push(ring.a);
+                s_2.push(Scalar::multiply_add(&e_1_i, &r[i], &ring.k));
+            }
+        }
+
+        let mut blinding = Scalar::zero();
+        let mut commitment = DecafPoint::identity();
+        for (r_i, C_i) in r.iter_mut().zip(C.iter()) {
+            blinding += &*r_i;
+            zeroize(&mut r_i.0);
+            // XXX implement AddAssign for ExtendedPoint
+            commitment = &commitment + C_i;
+        }
+
+        Ok(ProverOutput::new(
+            RangeProof{ e_0: e_0, C: C, s_1: s_1, s_2: s_2 },
+            commitment,
+            SecretBlinding::new(blinding),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::OsRng;
+
+    use DEFAULT_PARAMS;
+
+    #[test]
+    fn precompute_then_prove_and_verify() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let n = 16;
+        for &value in [0u64, 1, 2, 13449261, 43046720].iter() {
+            let precomputation = RangeProofPrecomputation::new(n, &DEFAULT_PARAMS, &mut csprng).unwrap();
+            assert_eq!(precomputation.n(), n);
+
+            let (proof, commitment, blinding) =
+                RangeProof::create_online(precomputation, value, &DEFAULT_PARAMS).unwrap().into_parts();
+
+            let C = proof.verify_with_params(n, &DEFAULT_PARAMS).unwrap();
+            let C_hat = &(DEFAULT_PARAMS.G() * blinding.expose_secret())
+                + &(DEFAULT_PARAMS.H() * &Scalar::from_u64(value));
+
+            assert_eq!(C.compress(), C_hat.compress());
+            assert_eq!(commitment.compress(), C_hat.compress());
+        }
+
+        let precomputation = RangeProofPrecomputation::new(n, &DEFAULT_PARAMS, &mut csprng).unwrap();
+        match RangeProof::create_online(precomputation, 43046721, &DEFAULT_PARAMS) {
+            Err(RangeProofError::OutOfRange(e)) => assert_eq!(e.min_n, 17),
+            _ => panic!("created a proof for an out-of-range value"),
+        }
+
+        let other_params = RangeProofParams::new(DEFAULT_PARAMS.H(), &DEFAULT_PARAMS.G().basepoint());
+        let precomputation = RangeProofPrecomputation::new(n, &other_params, &mut csprng).unwrap();
+        assert_eq!(RangeProof::create_online(precomputation, 42, &DEFAULT_PARAMS).err(),
+                   Some(RangeProofError::PrecomputationMismatch));
+
+        assert_eq!(RangeProofPrecomputation::new(42, &DEFAULT_PARAMS, &mut csprng).err(),
+                   Some(RangeProofError::InvalidRingCount(42)));
+    }
+}
@@ -0,0 +1,453 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Openings of commitments, and splitting them into parts.
+//!
+//! Spending a committed amount and making change means replacing one
+//! commitment by several whose values sum to the original, each with a
+//! rangeproof, and whose blindings also sum to the original's, so that
+//! the commitments balance: `C = Σ C_j`.
+//!
+//! A rangeproof chooses its own blinding, so each part carries the
+//! public difference `offset_j = b_j - r_j` between the blinding `b_j`
+//! of its opening and the blinding `r_j` of its rangeproof, and its
+//! commitment is the proof's commitment plus `offset_j*G`.  Since each
+//! `r_j` is uniformly random, the offsets reveal nothing about the
+//! `b_j`.
+//!
+//! Openings sum into an `Option<Opening>`, which is None if the values
+//! overflow a `u64`.  Wallets composing many openings should use
+//! `checked_add` and `checked_sum`, which sum the values as `u128`s and
+//! fail if the sum exceeds what can be proven in range.
+//!
+//! Auditors re-checking many revealed openings can use
+//! `verify_openings`, which checks them all with a single multiscalar
+//! multiplication.
+
+use std::iter;
+use std::iter::Sum;
+
+use rand::Rng;
+
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::curve::Identity;
+use curve25519_dalek::decaf::DecafPoint;
+use curve25519_dalek::decaf::vartime;
+
+use blinding::SecretBlinding;
+use commitments;
+use ct;
+use errors::{RangeProofError, ValueOverflow};
+use params::RangeProofParams;
+
+use super::RangeProof;
+use super::RANGEPROOF_MAX_N;
+
+/// The opening `(value, blinding)` of a Pedersen commitment
+/// `blinding*G + value*H`.
+#[derive(Debug)]
+pub struct Opening {
+    value: u64,
+    blinding: SecretBlinding,
+}
+
+/// A rangeproof for one part of a split opening.
+pub struct SplitProof {
+    proof: RangeProof,
+    offset: Scalar,
+}
+
+impl Opening {
+    /// Construct the opening of a commitment to `value` with `blinding`.
+    pub fn new(value: u64, blinding: SecretBlinding) -> Opening {
+        Opening { value: value, blinding: blinding }
+    }
+
+    /// The committed value.
+    pub fn value(&self) -> u64 {
+        self.value
+    }
+
+    /// The blinding factor.
+    pub fn blinding(&self) -> &SecretBlinding {
+        &self.blinding
+    }
+
+    /// The commitment `blinding*G + value*H`.
+    pub fn commitment(&self, params: &RangeProofParams) -> DecafPoint {
+        commitments::commit(self.value, &self.blinding, params)
+    }
+
+    /// Check that this is the opening of `commitment`, in constant time.
+    ///
+    /// Unlike comparing `commitment(params)` with `==`, this takes the
+    /// same time whether or not the opening matches, so an auditor
+    /// checking secret openings doesn't leak through its timing which
+    /// of them matched.  Only the result should be revealed.
+    #[must_use]
+    pub fn verify_against(&self, commitment: &DecafPoint, params: &RangeProofParams) -> bool {
+        ct::points_equal(&self.commitment(params), commitment) == 1
+    }
+
+    /// Add `other` to this opening, checking that the sum of the values
+    /// can be proven in range `[0,3^n]`.
+    ///
+    /// # Returns
+    ///
+    /// As for `checked_sum`.
+    pub fn checked_add(&self, other: &Opening, n: usize) -> Result<Opening, RangeProofError> {
+        Opening::checked_sum(iter::once(self).chain(iter::once(other)), n)
+    }
+
+    /// Sum the `openings`, checking that the sum of the values can be
+    /// proven in range `[0,3^n]`.
+    ///
+    /// The values are summed as `u128`s, which can't overflow, so the
+    /// error reports the true sum however many openings there are.
+    ///
+    /// # Returns
+    ///
+    /// A `RangeProofError::InvalidRingCount` if `n > RANGEPROOF_MAX_N`,
+    /// or a `RangeProofError::ValueOverflow` if the sum of the values
+    /// exceeds `RangeProof::max_value(n)`.
+    pub fn checked_sum<'a, I>(openings: I, n: usize) -> Result<Opening, RangeProofError>
+        where I: IntoIterator<Item = &'a Opening>
+    {
+        RangeProof::check_n(n)?;
+
+        let mut sum = 0u128;
+        let mut blinding = Scalar::zero();
+        for opening in openings {
+            sum += opening.value as u128;
+            blinding = &blinding + opening.blinding.expose_secret();
+        }
+
+        let max = RangeProof::max_value(n);
+        if sum > max as u128 {
+            return Err(ValueOverflow { sum: sum, n: n, max: max }.into());
+        }
+        Ok(Opening::new(sum as u64, SecretBlinding::new(blinding)))
+    }
+
+    /// Split the opening into openings of the `parts`, with a
+    /// rangeproof for each, in constant time.
+    ///
+    /// # Inputs
+    ///
+    /// * `n`, so that each part is proven in the range `[0,3^n]`;
+    /// * The `parts`, which must sum to the value of this opening;
+    /// * The precomputed `params`;
+    /// * `csprng`, an implementation of `rand::Rng`, which should be
+    /// cryptographically secure.
+    ///
+    /// # Returns
+    ///
+    /// If `n > RANGEPROOF_MAX_N`, or `parts` is empty, doesn't sum to
+    /// the value, or any part isn't in the range `[0,3^n]`, return None.
+    ///
+    /// Otherwise, returns an opening and a `SplitProof` for each part,
+    /// in order, where the blindings of the openings sum to the
+    /// blinding of this opening.
+    pub fn split<T: Rng>(
+        &self,
+        n: usize,
+        parts: &[u64],
+        params: &RangeProofParams,
+        csprng: &mut T,
+    ) -> Option<Vec<(Opening, SplitProof)>> {
+        if n > RANGEPROOF_MAX_N {
+            return None;
+        }
+
+        let mut sum = 0u64;
+        for part in parts.iter() {
+            sum = sum.checked_add(*part)?;
+        }
+        if parts.is_empty() || sum != self.value {
+            return None;
+        }
+
+        prove_parts(n, parts, &self.blinding, params, csprng)
+    }
+}
+
+impl RangeProof {
+    /// Construct rangeproofs for each of the `values`, in constant
+    /// time, whose blindings sum to `target_blinding_sum`.
+    ///
+    /// Passing the sum of the blindings of a transaction's inputs (less
+    /// those of any other outputs) gives outputs whose commitments
+    /// balance the inputs, as for `verify_balance`, provided the values
+    /// do too.
+    ///
+    /// # Returns
+    ///
+    /// If `n > RANGEPROOF_MAX_N`, `values` is empty or any value isn't
+    /// in the range `[0,3^n]`, return None.
+    ///
+    /// Otherwise, returns an opening and a `SplitProof` for each value,
+    /// in order, where the blindings of the openings sum to
+    /// `target_blinding_sum`.
+    pub fn create_batch_balanced<T: Rng>(
+        values: &[u64],
+        n: usize,
+        target_blinding_sum: &SecretBlinding,
+        params: &RangeProofParams,
+        csprng: &mut T,
+    ) -> Option<Vec<(Opening, SplitProof)>> {
+        if n > RANGEPROOF_MAX_N || values.is_empty() {
+            return None;
+        }
+
+        prove_parts(n, values, target_blinding_sum, params, csprng)
+    }
+}
+
+/// Construct an opening and a `SplitProof` for each of the `parts`,
+/// with random blindings summing to `blinding`.
+fn prove_parts<T: Rng>(
+    n: usize,
+    parts: &[u64],
+    blinding: &SecretBlinding,
+    params: &RangeProofParams,
+    csprng: &mut T,
+) -> Option<Vec<(Opening, SplitProof)>> {
+    let mut remaining = *blinding.expose_secret();
+    let mut outputs = Vec::with_capacity(parts.len());
+
+    for (j, &part) in parts.iter().enumerate() {
+        let blinding = if j + 1 == parts.len() {
+            remaining
+        } else {
+            let b_j = Scalar::random(csprng);
+            remaining = &remaining - &b_j;
+            b_j
+        };
+
+        let (proof, _, r_j) = RangeProof::create_with_params(n, part, params, csprng)?.into_parts();
+        let offset = &blinding - r_j.expose_secret();
+
+        outputs.push((
+            Opening::new(part, SecretBlinding::new(blinding)),
+            SplitProof { proof: proof, offset: offset },
+        ));
+    }
+
+    Some(outputs)
+}
+
+/// Sum openings, so that the sum opens the sum of their commitments,
+/// or None if the sum of the values overflows a `u64`.
+///
+/// Use `Opening::checked_sum` to also check that the sum can be proven
+/// in range.
+impl<'a> Sum<&'a Opening> for Option<Opening> {
+    fn sum<I: Iterator<Item = &'a Opening>>(iter: I) -> Option<Opening> {
+        let mut value = 0u64;
+        let mut blinding = Scalar::zero();
+        for opening in iter {
+            value = value.checked_add(opening.value)?;
+            blinding = &blinding + opening.blinding.expose_secret();
+        }
+        Some(Opening::new(value, SecretBlinding::new(blinding)))
+    }
+}
+
+/// Sum openings, as for `Sum<&Opening>`.
+impl Sum<Opening> for Option<Opening> {
+    fn sum<I: Iterator<Item = Opening>>(iter: I) -> Option<Opening> {
+        let openings: Vec<Opening> = iter.collect();
+        openings.iter().sum()
+    }
+}
+
+impl SplitProof {
+    /// Verify the rangeproof, returning the commitment to the part if
+    /// successful.
+    #[must_use]
+    pub fn verify(&self, n: usize, params: &RangeProofParams) -> Option<DecafPoint> {
+        self.proof.verify_with_params(n, params)
+            .map(|C| &C + &(params.G() * &self.offset))
+    }
+
+    /// The underlying rangeproof.
+    pub fn proof(&self) -> &RangeProof {
+        &self.proof
+    }
+
+    /// The public difference between the blinding of the part's
+    /// opening and that of its rangeproof.
+    pub fn offset(&self) -> &Scalar {
+        &self.offset
+    }
+}
+
+/// Check that each commitment `C_j` opens to `(v_j, b_j)`, i.e. that
+/// `C_j = b_j*G + v_j*H`, for each `(C_j, v_j, b_j)` in `openings`.
+///
+/// Rather than checking each opening with two scalar multiplications,
+/// this checks the random linear combination
+///
+/// ```text
+/// Σ z_j*C_j - (Σ z_j*b_j)*G - (Σ z_j*v_j)*H = 0
+/// ```
+///
+/// with scalars `z_j` from `csprng`, in a single multiscalar
+/// multiplication.  If any opening is wrong, the check fails except
+/// with negligible probability.  Since it's in variable time, it
+/// should only be used for openings which are already public.
+#[must_use]
+pub fn verify_openings<T: Rng>(
+    openings: &[(DecafPoint, u64, Scalar)],
+    params: &RangeProofParams,
+    csprng: &mut T,
+) -> bool {
+    let mut scalars = Vec::with_capacity(openings.len() + 2);
+    let mut points = Vec::with_capacity(openings.len() + 2);
+    let mut b = Scalar::zero();
+    let mut v = Scalar::zero();
+
+    for &(ref C, value, ref blinding) in openings.iter() {
+        let z = Scalar::random(csprng);
+        b = Scalar::multiply_add(&z, blinding, &b);
+        v = Scalar::multiply_add(&z, &Scalar::from_u64(value), &v);
+        scalars.push(z);
+        points.push(*C);
+    }
+    scalars.push(-&b);
+    points.push(params.G().basepoint());
+    scalars.push(-&v);
+    points.push(*params.H());
+
+    let sum = vartime::k_fold_scalar_mult(&scalars, &points);
+    sum.compress() == DecafPoint::identity().compress()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::OsRng;
+
+    use balance::verify_balance;
+    use DEFAULT_PARAMS;
+
+    #[test]
+    fn verify_against_commitment() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let (_, C, blinding) =
+            RangeProof::create_with_params(8, 42, &DEFAULT_PARAMS, &mut csprng).unwrap().into_parts();
+        let opening = Opening::new(42, blinding);
+        assert!(opening.verify_against(&C, &DEFAULT_PARAMS));
+
+        let wrong_value = Opening::new(43, SecretBlinding::new(*opening.blinding().expose_secret()));
+        assert!(!wrong_value.verify_against(&C, &DEFAULT_PARAMS));
+    }
+
+    #[test]
+    fn split_into_payment_and_change() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let (_, C, blinding) =
+            RangeProof::create_with_params(16, 1000, &DEFAULT_PARAMS, &mut csprng).unwrap().into_parts();
+        let opening = Opening::new(1000, blinding);
+        assert_eq!(opening.commitment(&DEFAULT_PARAMS).compress(), C.compress());
+
+        let outputs = opening.split(16, &[600, 0, 400], &DEFAULT_PARAMS, &mut csprng).unwrap();
+        let mut sum = DecafPoint::identity();
+        for &(ref child, ref proof) in outputs.iter() {
+            let C_j = proof.verify(16, &DEFAULT_PARAMS).unwrap();
+            assert_eq!(C_j.compress(), child.commitment(&DEFAULT_PARAMS).compress());
+            sum = &sum + &C_j;
+        }
+        assert_eq!(sum.compress(), C.compress());
+        assert_eq!(outputs.iter().map(|output| output.0.value()).collect::<Vec<_>>(), vec![600, 0, 400]);
+
+        assert!(opening.split(16, &[600, 399], &DEFAULT_PARAMS, &mut csprng).is_none());
+        assert!(opening.split(16, &[], &DEFAULT_PARAMS, &mut csprng).is_none());
+        assert!(opening.split(16, &[u64::max_value(), 1001], &DEFAULT_PARAMS, &mut csprng).is_none());
+        assert!(opening.split(RANGEPROOF_MAX_N + 1, &[1000], &DEFAULT_PARAMS, &mut csprng).is_none());
+    }
+
+    #[test]
+    fn create_batch_balancing_inputs() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let inputs: Vec<Opening> = [700, 300].iter().map(|&value| {
+            Opening::new(value, SecretBlinding::new(Scalar::random(&mut csprng)))
+        }).collect();
+        let total: Opening = inputs.iter().sum::<Option<Opening>>().unwrap();
+
+        let outputs = RangeProof::create_batch_balanced(&[250, 750], 16, total.blinding(),
+                                                        &DEFAULT_PARAMS, &mut csprng).unwrap();
+        let mut commitments = Vec::new();
+        for &(ref opening, ref proof) in outputs.iter() {
+            let C_j = proof.verify(16, &DEFAULT_PARAMS).unwrap();
+            assert_eq!(C_j.compress(), opening.commitment(&DEFAULT_PARAMS).compress());
+            commitments.push(C_j);
+        }
+        let C_in: Vec<DecafPoint> = inputs.iter().map(|input| input.commitment(&DEFAULT_PARAMS)).collect();
+        assert!(verify_balance(&C_in, &commitments));
+
+        assert!(RangeProof::create_batch_balanced(&[], 16, total.blinding(), &DEFAULT_PARAMS, &mut csprng).is_none());
+        assert!(RangeProof::create_batch_balanced(&[81], 4, total.blinding(), &DEFAULT_PARAMS, &mut csprng).is_none());
+        assert!(RangeProof::create_batch_balanced(&[81], RANGEPROOF_MAX_N + 1, total.blinding(),
+                                                  &DEFAULT_PARAMS, &mut csprng).is_none());
+    }
+
+    #[test]
+    fn checked_sum_of_openings() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let a = Opening::new(40, SecretBlinding::new(Scalar::random(&mut csprng)));
+        let b = Opening::new(40, SecretBlinding::new(Scalar::random(&mut csprng)));
+        let sum = a.checked_add(&b, 4).unwrap();
+        assert_eq!(sum.value(), 80);
+        assert_eq!(sum.commitment(&DEFAULT_PARAMS).compress(),
+                   (&a.commitment(&DEFAULT_PARAMS) + &b.commitment(&DEFAULT_PARAMS)).compress());
+
+        // 3^4 - 1 = 80 is the largest value provable with 4 rings.
+        let c = Opening::new(1, SecretBlinding::new(Scalar::zero()));
+        assert_eq!(Opening::checked_sum(&[a, b, c], 4).err(),
+                   Some(RangeProofError::ValueOverflow(ValueOverflow { sum: 81, n: 4, max: 80 })));
+
+        let big = Opening::new(u64::max_value(), SecretBlinding::new(Scalar::zero()));
+        match big.checked_add(&big, RANGEPROOF_MAX_N) {
+            Err(RangeProofError::ValueOverflow(error)) => assert_eq!(error.sum, 2 * u64::max_value() as u128),
+            _ => panic!("expected the sum to overflow"),
+        }
+
+        assert_eq!(big.checked_add(&big, RANGEPROOF_MAX_N + 1).err(),
+                   Some(RangeProofError::InvalidRingCount(RANGEPROOF_MAX_N + 1)));
+    }
+
+    #[test]
+    fn batch_verify_openings() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let mut openings = Vec::new();
+        for value in 0..20 {
+            let (_, C, blinding) =
+                RangeProof::create_with_params(8, value, &DEFAULT_PARAMS, &mut csprng).unwrap().into_parts();
+            openings.push((C, value, *blinding.expose_secret()));
+        }
+        assert!(verify_openings(&openings, &DEFAULT_PARAMS, &mut csprng));
+        assert!(verify_openings(&[], &DEFAULT_PARAMS, &mut csprng));
+
+        openings[7].1 += 1;
+        assert!(!verify_openings(&openings, &DEFAULT_PARAMS, &mut csprng));
+        openings[7].1 -= 1;
+        openings[3].2 = Scalar::one();
+        assert!(!verify_openings(&openings, &DEFAULT_PARAMS, &mut csprng));
+    }
+}
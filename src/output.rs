@@ -0,0 +1,103 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! The output of creating a rangeproof.
+
+use std::fmt;
+
+use curve25519_dalek::decaf::DecafPoint;
+
+use blinding::SecretBlinding;
+use commitments::PedersenCommitment;
+
+use super::RangeProof;
+
+/// A newly created rangeproof, together with the commitment it proves
+/// and the commitment's secret blinding.
+///
+/// Only the proof should be sent to the verifier.  The commitment and
+/// blinding are for the use of the prover, and the blinding must be
+/// kept secret, since anyone who learns it can open the commitment.
+pub struct ProverOutput {
+    proof: RangeProof,
+    commitment: DecafPoint,
+    blinding: SecretBlinding,
+}
+
+impl ProverOutput {
+    pub(crate) fn new(proof: RangeProof, commitment: DecafPoint, blinding: SecretBlinding) -> ProverOutput {
+        ProverOutput { proof: proof, commitment: commitment, blinding: blinding }
+    }
+
+    /// The rangeproof.
+    pub fn proof(&self) -> &RangeProof {
+        &self.proof
+    }
+
+    /// The commitment `blinding*G + value*H` the rangeproof proves is
+    /// to a value in range.
+    pub fn commitment(&self) -> &DecafPoint {
+        &self.commitment
+    }
+
+    /// The commitment the rangeproof proves, as a `PedersenCommitment`.
+    pub fn pedersen_commitment(&self) -> PedersenCommitment {
+        PedersenCommitment::new(self.commitment)
+    }
+
+    /// The secret blinding of the commitment.
+    pub fn blinding(&self) -> &SecretBlinding {
+        &self.blinding
+    }
+
+    /// Consume the output, returning only the rangeproof.  The
+    /// blinding is zeroed.
+    pub fn into_proof(self) -> RangeProof {
+        self.proof
+    }
+
+    /// Consume the output, returning `(proof, commitment, blinding)`.
+    pub fn into_parts(self) -> (RangeProof, DecafPoint, SecretBlinding) {
+        (self.proof, self.commitment, self.blinding)
+    }
+}
+
+impl fmt::Debug for ProverOutput {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ProverOutput {{ commitment: {:?}, blinding: {:?}, .. }}",
+               self.commitment.compress(), self.blinding)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::OsRng;
+
+    use DEFAULT_PARAMS;
+
+    #[test]
+    fn output_parts_and_redaction() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let output = RangeProof::create_with_params(8, 42, &DEFAULT_PARAMS, &mut csprng).unwrap();
+        assert!(format!("{:?}", output).contains("[REDACTED]"));
+        assert_eq!(output.proof().verify_with_params(8, &DEFAULT_PARAMS).unwrap().compress(),
+                   output.commitment().compress());
+
+        let C = output.commitment().compress();
+        let (proof, commitment, _) = output.into_parts();
+        assert_eq!(commitment.compress(), C);
+        assert_eq!(proof.verify_with_params(8, &DEFAULT_PARAMS).unwrap().compress(), C);
+    }
+}
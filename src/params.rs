@@ -0,0 +1,252 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Precomputed generators for creating and verifying rangeproofs.
+
+use std::iter;
+use std::slice;
+
+use sha2::{Digest, Sha256, Sha512};
+
+use curve25519_dalek::constants as dalek_constants;
+use curve25519_dalek::decaf::{DecafPoint, DecafBasepointTable};
+
+use encoding::read_point;
+use errors::DecodingError;
+
+use super::RANGEPROOF_MAX_N;
+
+/// The powers `3^i * H` and `2 * 3^i * H` of the value basepoint `H`,
+/// for `i < RANGEPROOF_MAX_N`.
+///
+/// The prover and the verifier both need `m^i * H` and `2 * m^i * H`
+/// for every ring `i`; precomputing them saves two point additions
+/// per ring on every call.
+pub struct PowersOfH {
+    single: Vec<DecafPoint>,
+    double: Vec<DecafPoint>,
+    tables: Vec<(DecafBasepointTable, DecafBasepointTable)>,
+}
+
+impl PowersOfH {
+    /// Precompute the powers of `H` for every `i < RANGEPROOF_MAX_N`.
+    pub fn new(H: &DecafPoint) -> PowersOfH {
+        PowersOfH::with_len(RANGEPROOF_MAX_N, H)
+    }
+
+    /// Precompute the powers of `H` for `i < n` only.
+    pub(crate) fn with_len(n: usize, H: &DecafPoint) -> PowersOfH {
+        let mut single = Vec::with_capacity(n);
+        let mut double = Vec::with_capacity(n);
+        let mut mi_H = *H;
+
+        for _ in 0..n {
+            let mi2_H = &mi_H + &mi_H;
+            single.push(mi_H);
+            double.push(mi2_H);
+            // Set mi_H <-- 3*m_iH, so that mi_H is always 3^i * H in the loop
+            mi_H = &mi_H + &mi2_H;
+        }
+
+        PowersOfH { single: single, double: double, tables: Vec::new() }
+    }
+
+    /// Copy the powers for `i < n`, along with a basepoint table for
+    /// each of them, for a batch of proofs which multiplies by the same
+    /// powers many times.
+    pub(crate) fn with_tables(&self, n: usize) -> PowersOfH {
+        let n = n.min(self.len());
+        let tables = self.iter().take(n).map(|(mi_H, mi2_H)| {
+            (DecafBasepointTable::create(mi_H), DecafBasepointTable::create(mi2_H))
+        }).collect();
+
+        PowersOfH { single: self.single[..n].to_vec(), double: self.double[..n].to_vec(), tables: tables }
+    }
+
+    /// The basepoint tables for `3^i * H` and `2 * 3^i * H`, if they
+    /// were built by `with_tables`.
+    pub(crate) fn tables(&self, i: usize) -> Option<(&DecafBasepointTable, &DecafBasepointTable)> {
+        self.tables.get(i).map(|&(ref single, ref double)| (single, double))
+    }
+
+    /// The number of precomputed powers.
+    pub fn len(&self) -> usize {
+        self.single.len()
+    }
+
+    /// Iterate over the pairs `(3^i * H, 2 * 3^i * H)`, in order of
+    /// increasing `i`.
+    pub(crate) fn iter(&self) -> iter::Zip<slice::Iter<DecafPoint>, slice::Iter<DecafPoint>> {
+        self.single.iter().zip(self.double.iter())
+    }
+
+    /// Returns `3^i * H`.
+    ///
+    /// # Panics
+    ///
+    /// If `i >= self.len()`.
+    pub fn power(&self, i: usize) -> &DecafPoint {
+        &self.single[i]
+    }
+
+    /// Returns `2 * 3^i * H`.
+    ///
+    /// # Panics
+    ///
+    /// If `i >= self.len()`.
+    pub fn double_power(&self, i: usize) -> &DecafPoint {
+        &self.double[i]
+    }
+}
+
+/// A set of orthogonal basepoints `G` and `H`, along with the
+/// precomputations needed to use them efficiently.
+///
+/// Constructing a `RangeProofParams` builds two basepoint tables and
+/// the `PowersOfH` for every `i < RANGEPROOF_MAX_N`, which is
+/// considerably more expensive than creating or verifying a single
+/// proof.  Applications should construct their parameters once and
+/// reuse them, or simply use `DEFAULT_PARAMS`.
+pub struct RangeProofParams {
+    G: DecafBasepointTable,
+    H: DecafPoint,
+    H_table: DecafBasepointTable,
+    H_powers: PowersOfH,
+    id: [u8; 32],
+}
+
+impl RangeProofParams {
+    /// Precompute parameters for the basepoints `G` and `H`.
+    ///
+    /// The discrete logarithm of `H` with respect to `G` must be
+    /// unknown, otherwise the resulting commitments are not binding.
+    pub fn new(G: &DecafPoint, H: &DecafPoint) -> RangeProofParams {
+        let mut h = Sha512::default();
+        h.input(b"dalek-rangeproofs params");
+        h.input(G.compress().as_bytes());
+        h.input(H.compress().as_bytes());
+        let mut id = [0u8; 32];
+        id.copy_from_slice(&h.result()[..32]);
+
+        RangeProofParams {
+            G: DecafBasepointTable::create(G),
+            H: *H,
+            H_table: DecafBasepointTable::create(H),
+            H_powers: PowersOfH::new(H),
+            id: id,
+        }
+    }
+
+    /// Precompute parameters for the basepoint `G`, choosing the
+    /// second basepoint as `H = Hash(G)`.
+    pub fn from_basepoint(G: &DecafPoint) -> RangeProofParams {
+        let H = DecafPoint::hash_from_bytes::<Sha256>(G.compress().as_bytes());
+
+        RangeProofParams::new(G, &H)
+    }
+
+    /// Precompute parameters for the basepoints `G` and `H` given in
+    /// compressed form, e.g. as exported by another proof system using
+    /// the same group.
+    ///
+    /// # Returns
+    ///
+    /// A `DecodingError::InvalidPoint` if either encoding isn't a
+    /// canonically-encoded point.
+    pub fn from_compressed_generators(G: &[u8; 32], H: &[u8; 32]) -> Result<RangeProofParams, DecodingError> {
+        Ok(RangeProofParams::new(&read_point(G)?, &read_point(H)?))
+    }
+
+    /// The compressed basepoints `(G, H)`, as accepted by
+    /// `from_compressed_generators`.
+    pub fn compressed_generators(&self) -> ([u8; 32], [u8; 32]) {
+        (*self.G.basepoint().compress().as_bytes(), *self.H.compress().as_bytes())
+    }
+
+    /// The table for the blinding basepoint `G`.
+    pub fn G(&self) -> &DecafBasepointTable {
+        &self.G
+    }
+
+    /// The value basepoint `H`.
+    pub fn H(&self) -> &DecafPoint {
+        &self.H
+    }
+
+    /// The table for the value basepoint `H`.
+    pub fn H_table(&self) -> &DecafBasepointTable {
+        &self.H_table
+    }
+
+    /// The precomputed powers of `H`.
+    pub fn H_powers(&self) -> &PowersOfH {
+        &self.H_powers
+    }
+
+    /// A 32-byte identifier for these parameters, computed by hashing
+    /// `G` and `H`, so that encodings of proofs can refer to the
+    /// parameters they were made with.
+    pub fn id(&self) -> &[u8; 32] {
+        &self.id
+    }
+}
+
+lazy_static! {
+    /// The standard parameters, with `G` the Decaf coset containing
+    /// the ed25519 basepoint and `H = Sha256(G)`, as in the crate
+    /// documentation.
+    pub static ref DEFAULT_PARAMS: RangeProofParams =
+        RangeProofParams::from_basepoint(&dalek_constants::DECAF_ED25519_BASEPOINT_TABLE.basepoint());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_params_match_documented_generators() {
+        let G = &dalek_constants::DECAF_ED25519_BASEPOINT_TABLE;
+        let H = DecafPoint::hash_from_bytes::<Sha256>(G.basepoint().compress().as_bytes());
+
+        assert_eq!(DEFAULT_PARAMS.G().basepoint().compress(), G.basepoint().compress());
+        assert_eq!(DEFAULT_PARAMS.H().compress(), H.compress());
+        assert_eq!(DEFAULT_PARAMS.H_table().basepoint().compress(), H.compress());
+        assert_eq!(DEFAULT_PARAMS.H_powers().len(), RANGEPROOF_MAX_N);
+
+        assert_eq!(RangeProofParams::new(&G.basepoint(), &H).id(), DEFAULT_PARAMS.id());
+        assert!(RangeProofParams::new(&H, &G.basepoint()).id() != DEFAULT_PARAMS.id());
+    }
+
+    #[test]
+    fn compressed_generators_roundtrip() {
+        let (G, H) = DEFAULT_PARAMS.compressed_generators();
+        let params = RangeProofParams::from_compressed_generators(&G, &H).unwrap();
+        assert_eq!(params.id(), DEFAULT_PARAMS.id());
+
+        let mut bad = H;
+        bad[31] |= 0x80;
+        assert!(RangeProofParams::from_compressed_generators(&G, &bad).is_err());
+    }
+
+    #[test]
+    fn powers_of_H() {
+        let H = DEFAULT_PARAMS.H();
+        let powers = PowersOfH::new(H);
+
+        let mut mi_H = *H;
+        for i in 0..RANGEPROOF_MAX_N {
+            assert_eq!(powers.power(i).compress(), mi_H.compress());
+            assert_eq!(powers.double_power(i).compress(), (&mi_H + &mi_H).compress());
+            mi_H = &(&mi_H + &mi_H) + &mi_H;
+        }
+    }
+}
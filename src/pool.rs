@@ -0,0 +1,240 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Verifying rangeproofs in the background.
+//!
+//! A mempool admission pipeline wants to hand off proofs as they
+//! arrive and learn the results later, without blocking on each one.
+//! A `VerificationPool` owns a set of worker threads which verify
+//! submitted proofs in order of submission; each submission returns a
+//! `Ticket` whose status can be polled, and may also carry a callback
+//! which the worker calls with the result.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::thread::{self, JoinHandle};
+
+use errors::VerifyError;
+use params::RangeProofParams;
+use verification::VerifiedCommitment;
+
+use super::RangeProof;
+use super::RANGEPROOF_MAX_N;
+
+/// The result of verifying a proof.
+type VerifyResult = Result<VerifiedCommitment, VerifyError>;
+
+/// A callback to be called with the result of verifying a proof.
+type Callback = Box<FnOnce(VerifyResult) + Send>;
+
+/// A proof submitted to the pool, with its ticket and callback.
+struct Job {
+    ticket: u64,
+    proof: RangeProof,
+    callback: Option<Callback>,
+}
+
+/// A handle for a proof submitted to a `VerificationPool`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Ticket(u64);
+
+/// The status of a submitted proof.
+#[derive(Clone, Copy, Debug)]
+pub enum VerificationStatus {
+    /// The proof hasn't been verified yet.
+    Pending,
+    /// The proof has been verified, with this result.
+    Done(Result<VerifiedCommitment, VerifyError>),
+    /// The ticket isn't known to the pool, e.g. because its result was
+    /// already taken.
+    Unknown,
+}
+
+/// A pool of worker threads verifying rangeproofs for the range
+/// `[0,3^n]`.
+pub struct VerificationPool {
+    sender: Option<Sender<Job>>,
+    workers: Vec<JoinHandle<()>>,
+    results: Arc<Mutex<HashMap<u64, Option<VerifyResult>>>>,
+    next_ticket: u64,
+}
+
+impl VerificationPool {
+    /// Start a pool of `threads` workers verifying proofs with `n` rings
+    /// and the given `params`.
+    ///
+    /// # Returns
+    ///
+    /// The pool, or None if `threads` is zero or `n > RANGEPROOF_MAX_N`.
+    pub fn new(threads: usize, n: usize, params: &'static RangeProofParams) -> Option<VerificationPool> {
+        if n > RANGEPROOF_MAX_N || threads == 0 {
+            return None;
+        }
+
+        let (sender, receiver) = channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        let results = Arc::new(Mutex::new(HashMap::new()));
+
+        let workers = (0..threads).map(|_| {
+            let receiver = receiver.clone();
+            let results = results.clone();
+            thread::spawn(move || worker(n, params, &receiver, &results))
+        }).collect();
+
+        Some(VerificationPool { sender: Some(sender), workers: workers, results: results, next_ticket: 0 })
+    }
+
+    /// Submit a proof for verification.
+    pub fn submit(&mut self, proof: RangeProof) -> Ticket {
+        self.enqueue(proof, None)
+    }
+
+    /// Submit a proof for verification, calling `callback` with the
+    /// result on the worker thread once it's verified.
+    pub fn submit_with_callback<F>(&mut self, proof: RangeProof, callback: F) -> Ticket
+        where F: FnOnce(Result<VerifiedCommitment, VerifyError>) + Send + 'static
+    {
+        self.enqueue(proof, Some(Box::new(callback)))
+    }
+
+    fn enqueue(&mut self, proof: RangeProof, callback: Option<Callback>) -> Ticket {
+        let ticket = self.next_ticket;
+        self.next_ticket += 1;
+        self.results.lock().unwrap().insert(ticket, None);
+
+        let job = Job { ticket: ticket, proof: proof, callback: callback };
+        // The workers only stop once the sender is dropped.
+        self.sender.as_ref().unwrap().send(job).unwrap();
+
+        Ticket(ticket)
+    }
+
+    /// The status of the proof submitted with `ticket`, without
+    /// blocking.
+    pub fn status(&self, ticket: Ticket) -> VerificationStatus {
+        match self.results.lock().unwrap().get(&ticket.0) {
+            Some(&Some(result)) => VerificationStatus::Done(result),
+            Some(&None) => VerificationStatus::Pending,
+            None => VerificationStatus::Unknown,
+        }
+    }
+
+    /// Take the result for `ticket` if it's done, so that the pool no
+    /// longer stores it.
+    pub fn take(&self, ticket: Ticket) -> Option<Result<VerifiedCommitment, VerifyError>> {
+        let mut results = self.results.lock().unwrap();
+        let result = match results.get(&ticket.0) {
+            Some(&Some(result)) => result,
+            _ => return None,
+        };
+        results.remove(&ticket.0);
+        Some(result)
+    }
+
+    /// The number of submitted proofs not yet verified.
+    pub fn pending(&self) -> usize {
+        self.results.lock().unwrap().values().filter(|result| result.is_none()).count()
+    }
+
+    /// Wait for every submitted proof to be verified, then stop the
+    /// workers.
+    pub fn join(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        self.sender.take();
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl Drop for VerificationPool {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+/// Verify jobs from `receiver` until the pool's sender is dropped.
+fn worker(
+    n: usize,
+    params: &RangeProofParams,
+    receiver: &Mutex<Receiver<Job>>,
+    results: &Mutex<HashMap<u64, Option<VerifyResult>>>,
+) {
+    loop {
+        let job = match receiver.lock().unwrap().recv() {
+            Ok(job) => job,
+            Err(_) => return,
+        };
+
+        let result = job.proof.verify_checked(n, params);
+        if let Some(slot) = results.lock().unwrap().get_mut(&job.ticket) {
+            *slot = Some(result);
+        }
+        if let Some(callback) = job.callback {
+            callback(result);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::mpsc;
+
+    use rand::OsRng;
+
+    use DEFAULT_PARAMS;
+
+    #[test]
+    fn pool_verifies_in_background() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let mut pool = VerificationPool::new(2, 8, &DEFAULT_PARAMS).unwrap();
+        let (proof, commitment, _) =
+            RangeProof::create_with_params(8, 42, &DEFAULT_PARAMS, &mut csprng).unwrap().into_parts();
+        let bad =
+            RangeProof::create_with_params(4, 42, &DEFAULT_PARAMS, &mut csprng).unwrap().into_proof();
+
+        let (tx, rx) = mpsc::channel();
+        let good = pool.submit_with_callback(proof, move |result| tx.send(result).unwrap());
+        let result = rx.recv().unwrap();
+        assert_eq!(result.unwrap().commitment().compress(), commitment.compress());
+
+        let bad = pool.submit(bad);
+        match pool.status(good) {
+            VerificationStatus::Done(result) => assert!(result.is_ok()),
+            _ => panic!("expected the proof to be verified"),
+        }
+        assert!(pool.take(good).is_some());
+        match pool.status(good) {
+            VerificationStatus::Unknown => (),
+            _ => panic!("expected the result to have been taken"),
+        }
+
+        while pool.pending() > 0 {
+            thread::yield_now();
+        }
+        assert_eq!(pool.take(bad).unwrap().err(), Some(VerifyError::WrongRingCount));
+        pool.join();
+    }
+
+    #[test]
+    fn pool_rejects_bad_configuration() {
+        assert!(VerificationPool::new(0, 8, &DEFAULT_PARAMS).is_none());
+        assert!(VerificationPool::new(2, RANGEPROOF_MAX_N + 1, &DEFAULT_PARAMS).is_none());
+    }
+}
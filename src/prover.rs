@@ -0,0 +1,295 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! The prover's computation for a single ring.
+//!
+//! Each ring draws its nonces from its own substream of the proof's
+//! `TranscriptRng`, so that the rings are independent of one another.
+//! When the `parallel` feature is enabled the rings are proven on
+//! `rayon`'s thread pool, and the proof is identical to the one proven
+//! sequentially from the same RNG.
+//!
+//! Nothing here can panic, whatever the digit, and with the `no_panic`
+//! feature this is checked at link time.
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+#[cfg(feature = "no_panic")]
+use no_panic::no_panic;
+
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::curve::Identity;
+use curve25519_dalek::decaf::{DecafPoint, DecafBasepointTable};
+use curve25519_dalek::decaf::vartime;
+
+use blinding::zeroize;
+use challenges::ring_challenge;
+use ct::{digit_equals, digit_is_nonzero, digit_multiple_of_H, CTAssignable};
+use transcript_rng::TranscriptRng;
+
+/// The prover's state for the `i`th ring, proving the digit `v`.
+///
+/// The point hashed to `e_1` is offset by the adaptor point `T`, which
+/// is the identity except in the first ring of an adaptor rangeproof.
+///
+/// The secret nonce `k` and blinding `r` are overwritten with zeroes
+/// when it is dropped.
+pub(crate) struct RingProver {
+    v: u8,
+    pub(crate) T: DecafPoint,
+    pub(crate) k: Scalar,
+    pub(crate) r: Scalar,
+    pub(crate) C: DecafPoint,
+    pub(crate) R: DecafPoint,
+    pub(crate) e_1: Scalar,
+    pub(crate) e_2: Scalar,
+    pub(crate) s_1: Scalar,
+    pub(crate) s_2: Scalar,
+    rng: TranscriptRng,
+}
+
+impl RingProver {
+    /// Begin proving the `digits` of a value, drawing the nonces for
+    /// the `i`th ring from the `i`th substream of `rng`.
+    pub(crate) fn for_digits(digits: &[u8], rng: &TranscriptRng) -> Vec<RingProver> {
+        digits.iter().enumerate().map(|(i, &v)| RingProver {
+            v: v,
+            T: DecafPoint::identity(),
+            k: Scalar::zero(),
+            r: Scalar::zero(),
+            C: DecafPoint::identity(),
+            R: DecafPoint::identity(),
+            e_1: Scalar::zero(),
+            e_2: Scalar::zero(),
+            s_1: Scalar::zero(),
+            s_2: Scalar::zero(),
+            rng: rng.substream(i as u64),
+        }).collect()
+    }
+
+    /// Compute the point `R_i` beginning the ring, in variable time.
+    #[cfg_attr(feature = "no_panic", no_panic)]
+    pub(crate) fn commit_vartime(&mut self, G: &DecafBasepointTable, mi_H: &DecafPoint, mi2_H: &DecafPoint) {
+        self.k = Scalar::random(&mut self.rng);
+
+        if self.v == 0 {
+            self.R = G * &self.k;
+        } else if self.v == 1 {
+            // Commitment to i-th digit is r^i G + 1 * m^i H
+            self.r = Scalar::random(&mut self.rng);
+            self.C = &(G * &self.r) + mi_H;
+            // Begin at index 1 in the ring, choosing random e_1
+            let P = &(G * &self.k) + &self.T;
+            self.e_1 = ring_challenge(&P.compress());
+            // Choose random scalar for s_2
+            self.s_2 = Scalar::random(&mut self.rng);
+            // Compute e_2 = Hash(s_2^i G - e_1^i (C^i - 2m^i H) )
+            let Ci_minus_mi2H = &self.C - mi2_H;
+            let P = vartime::k_fold_scalar_mult(&[self.s_2,     -&self.e_1],
+                                                &[G.basepoint(), Ci_minus_mi2H]);
+            self.e_2 = ring_challenge(&P.compress());
+
+            self.R = &self.C * &self.e_2;
+        } else {
+            debug_assert!(self.v == 2, "Invalid digit {}", self.v);
+            // Commitment to i-th digit is r^i G + 2 * m^i H
+            self.r = Scalar::random(&mut self.rng);
+            self.C = &(G * &self.r) + mi2_H;
+            // Begin at index 2 in the ring, choosing random e_2
+            let P = G * &self.k;
+            self.e_2 = ring_challenge(&P.compress());
+
+            self.R = &self.C * &self.e_2;
+        }
+    }
+
+    /// Close the ring with the shared challenge `e_0`, in variable time.
+    #[cfg_attr(feature = "no_panic", no_panic)]
+    pub(crate) fn respond_vartime(
+        &mut self,
+        e_0: &Scalar,
+        G: &DecafBasepointTable,
+        mi_H: &DecafPoint,
+        mi2_H: &DecafPoint,
+    ) {
+        if self.v == 0 {
+            let k_1 = Scalar::random(&mut self.rng);
+            let P = &vartime::k_fold_scalar_mult(&[k_1, *e_0], &[G.basepoint(), *mi_H]) + &self.T;
+            self.e_1 = ring_challenge(&P.compress());
+
+            let k_2 = Scalar::random(&mut self.rng);
+            let P = vartime::k_fold_scalar_mult(&[k_2, self.e_1], &[G.basepoint(), *mi2_H]);
+            self.e_2 = ring_challenge(&P.compress());
+
+            let e_2_inv = self.e_2.invert();
+            self.r = &e_2_inv * &self.k;
+            self.C = G * &self.r;
+
+            self.s_1 = &k_1 + &(e_0       * &(&self.k * &e_2_inv));
+            self.s_2 = &k_2 + &(&self.e_1 * &(&self.k * &e_2_inv));
+        } else if self.v == 1 {
+            self.s_1 = Scalar::multiply_add(e_0, &self.r, &self.k);
+        } else if self.v == 2 {
+            self.s_1 = Scalar::random(&mut self.rng);
+            // Compute e_1^i = Hash(s_1^i G - e_0^i (C^i - 1 m^i H) )
+            let Ci_minus_miH = &self.C - mi_H;
+            let P = &vartime::k_fold_scalar_mult(&[self.s_1,      -e_0],
+                                                 &[G.basepoint(), Ci_minus_miH]) + &self.T;
+            self.e_1 = ring_challenge(&P.compress());
+            self.s_2 = Scalar::multiply_add(&self.e_1, &self.r, &self.k);
+        }
+    }
+
+    /// Compute the point `R_i` beginning the ring, in constant time.
+    #[cfg_attr(feature = "no_panic", no_panic)]
+    pub(crate) fn commit_ct(&mut self, G: &DecafBasepointTable, mi_H: &DecafPoint, mi2_H: &DecafPoint) {
+        let v = self.v;
+        debug_assert!(v == 0 || v == 1 || v == 2);
+
+        self.k = Scalar::random(&mut self.rng);
+
+        // Commitment to i-th digit is r^i G + (v^1 * m^i H)
+        let maybe_ri: Scalar = Scalar::random(&mut self.rng);
+        self.r.conditional_assign(&maybe_ri, digit_is_nonzero(v));
+
+        let which_mi_H: DecafPoint = digit_multiple_of_H(v, mi_H, mi2_H);
+
+        let maybe_Ci: DecafPoint = &(G * &self.r) + &which_mi_H;
+        self.C.conditional_assign(&maybe_Ci, digit_is_nonzero(v));
+
+        let mut P: DecafPoint = &self.k * G;
+
+        // Begin at index v^i in the ring, choosing random e_{v^i}
+        let mut maybe_ei = ring_challenge(&(&P + &self.T).compress());
+        self.e_1.conditional_assign(&maybe_ei, digit_equals(v, 1u8));
+        maybe_ei = ring_challenge(&P.compress());
+        self.e_2.conditional_assign(&maybe_ei, digit_equals(v, 2u8));
+
+        // Choose random scalar for s_2
+        let maybe_s2: Scalar = Scalar::random(&mut self.rng);
+        self.s_2.conditional_assign(&maybe_s2, digit_equals(v, 1u8));
+
+        // Compute e_2 = Hash(s_2^i G - e_1^i (C^i - 2m^i H) )
+        P = &(&self.s_2 * G) - &(&self.e_1 * &(&self.C - mi2_H));
+        maybe_ei = ring_challenge(&P.compress());
+        self.e_2.conditional_assign(&maybe_ei, digit_equals(v, 1u8));
+
+        // Compute R^i = k^i G            iff  v^i == 0, otherwise
+        //         R^i = e_2^i * C^i
+        self.R = &self.k * G;
+
+        let maybe_Ri: DecafPoint = &self.e_2 * &self.C;
+        self.R.conditional_assign(&maybe_Ri, digit_is_nonzero(v));
+    }
+
+    /// Close the ring with the shared challenge `e_0`, in constant time.
+    ///
+    /// If `tables` holds basepoint tables for `mi_H` and `mi2_H`, they
+    /// are used to multiply by them.
+    #[cfg_attr(feature = "no_panic", no_panic)]
+    pub(crate) fn respond_ct(
+        &mut self,
+        e_0: &Scalar,
+        G: &DecafBasepointTable,
+        mi_H: &DecafPoint,
+        mi2_H: &DecafPoint,
+        tables: Option<(&DecafBasepointTable, &DecafBasepointTable)>,
+    ) {
+        let v = self.v;
+        debug_assert!(v == 0 || v == 1 || v == 2);
+
+        let mut k_1 = Scalar::zero();
+        let maybe_k1: Scalar = Scalar::random(&mut self.rng);
+        k_1.conditional_assign(&maybe_k1, digit_equals(v, 0u8));
+
+        let e_0_mi_H = match tables {
+            Some((mi_H_table, _)) => mi_H_table * e_0,
+            None => e_0 * mi_H,
+        };
+        let mut P: DecafPoint = &(&(&k_1 * G) + &e_0_mi_H) + &self.T;
+        let maybe_e_1 = ring_challenge(&P.compress());
+        self.e_1.conditional_assign(&maybe_e_1, digit_equals(v, 0u8));
+
+        let mut k_2 = Scalar::zero();
+        let maybe_k2: Scalar = Scalar::random(&mut self.rng);
+        k_2.conditional_assign(&maybe_k2, digit_equals(v, 0u8));
+
+        let e_1_mi2_H = match tables {
+            Some((_, mi2_H_table)) => mi2_H_table * &self.e_1,
+            None => &self.e_1 * mi2_H,
+        };
+        P = &(&k_2 * &G.basepoint()) + &e_1_mi2_H;
+        let maybe_e_2 = ring_challenge(&P.compress()); // XXX API
+        self.e_2.conditional_assign(&maybe_e_2, digit_equals(v, 0u8));
+
+        let e_2_inv = self.e_2.invert();  // XXX only used in v[i]==0, check what the optimiser is doing
+        let maybe_r_i = &e_2_inv * &self.k;
+        self.r.conditional_assign(&maybe_r_i, digit_equals(v, 0u8));
+
+        let maybe_C_i = G * &self.r;
+        self.C.conditional_assign(&maybe_C_i, digit_equals(v, 0u8));
+
+        let mut maybe_s_1 = &k_1 + &(e_0 * &(&self.k * &e_2_inv));  // XXX reuse k[i] * e_2_inv
+        self.s_1.conditional_assign(&maybe_s_1, digit_equals(v, 0u8));
+        maybe_s_1 = Scalar::multiply_add(e_0, &self.r, &self.k);
+        self.s_1.conditional_assign(&maybe_s_1, digit_equals(v, 1u8));
+        maybe_s_1 = Scalar::random(&mut self.rng);
+        self.s_1.conditional_assign(&maybe_s_1, digit_equals(v, 2u8));
+
+        // Compute e_1^i = Hash(s_1^i G - e_0^i (C^i - 1 m^i H) )
+        let Ci_minus_miH = &self.C - mi_H;  // XXX only used in v[i]==2, check optimiser
+
+        P = &(&(&self.s_1 * &G.basepoint()) - &(e_0 * &Ci_minus_miH)) + &self.T;
+        let maybe_e_1 = ring_challenge(&P.compress());
+        self.e_1.conditional_assign(&maybe_e_1, digit_equals(v, 2u8));
+
+        let mut maybe_s_2 = &k_2 + &(&self.e_1 * &(&self.k * &e_2_inv));  // XXX reuse k[i] * e_2_inv
+        self.s_2.conditional_assign(&maybe_s_2, digit_equals(v, 0u8));
+        maybe_s_2 = Scalar::multiply_add(&self.e_1, &self.r, &self.k);
+        self.s_2.conditional_assign(&maybe_s_2, digit_equals(v, 2u8));
+    }
+}
+
+impl Drop for RingProver {
+    fn drop(&mut self) {
+        zeroize(&mut self.k.0);
+        zeroize(&mut self.r.0);
+    }
+}
+
+/// Apply `f` to each ring, with its index, on `rayon`'s thread pool
+/// when the `parallel` feature is enabled and sequentially otherwise.
+pub(crate) fn for_each_ring<F>(rings: &mut [RingProver], f: F)
+    where F: Fn(usize, &mut RingProver) + Sync + Send
+{
+    #[cfg(feature = "parallel")]
+    rings.par_iter_mut().enumerate().for_each(|(i, ring)| f(i, ring));
+    #[cfg(not(feature = "parallel"))]
+    for (i, ring) in rings.iter_mut().enumerate() {
+        f(i, ring);
+    }
+}
+
+/// Returns the sums of the blindings and of the digit commitments of
+/// the `rings`.
+#[cfg_attr(feature = "no_panic", no_panic)]
+pub(crate) fn blinding_and_commitment(rings: &[RingProver]) -> (Scalar, DecafPoint) {
+    let mut blinding = Scalar::zero();
+    let mut commitment = DecafPoint::identity();
+    for ring in rings.iter() {
+        blinding += &ring.r;
+        // XXX implement AddAssign for ExtendedPoint
+        commitment = &commitment + &ring.C;
+    }
+    (blinding, commitment)
+}
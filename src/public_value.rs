@@ -0,0 +1,157 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Proofs that a commitment opens to a public value.
+//!
+//! A commitment `C = r*G + v*H` opens to the public value `v` exactly
+//! when `C - v*H` is a multiple of `G` alone, so a Schnorr proof of
+//! knowledge of `r` with respect to `G` proves the opening without
+//! revealing the blinding.  This is useful alongside rangeproofs when
+//! some of the amounts in a transaction are explicit.
+
+#[cfg(feature = "schemars")]
+use schemars;
+
+use rand::Rng;
+
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::decaf::DecafPoint;
+use curve25519_dalek::decaf::vartime;
+
+use blinding::SecretBlinding;
+use challenges::public_value_challenge;
+use commitments;
+use encoding::read_scalar;
+use errors::DecodingError;
+use params::RangeProofParams;
+use transcript_rng::TranscriptRng;
+
+/// The length in bytes of an encoded `PublicValueProof`.
+pub const PUBLIC_VALUE_PROOF_LENGTH: usize = 64;
+
+/// A proof that a commitment opens to a public value.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(deny_unknown_fields))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct PublicValueProof {
+    #[cfg_attr(feature = "serde", serde(with = "::serialization::scalar"))]
+    #[cfg_attr(feature = "schemars", schemars(with = "::schema::Hex32"))]
+    c: Scalar,
+    #[cfg_attr(feature = "serde", serde(with = "::serialization::scalar"))]
+    #[cfg_attr(feature = "schemars", schemars(with = "::schema::Hex32"))]
+    s: Scalar,
+}
+
+impl PublicValueProof {
+    /// Prove that `commitment = blinding*G + value*H` opens to `value`.
+    ///
+    /// # Note
+    ///
+    /// The caller is responsible for `commitment` actually being
+    /// `blinding*G + value*H`; otherwise the proof won't verify.
+    pub fn create<T: Rng>(
+        commitment: &DecafPoint,
+        value: u64,
+        blinding: &SecretBlinding,
+        params: &RangeProofParams,
+        csprng: &mut T,
+    ) -> PublicValueProof {
+        let C = commitment.compress();
+
+        let mut rng = TranscriptRng::builder(b"public value")
+            .commit_transcript(b"C", C.as_bytes())
+            .commit_transcript(b"value", &value.to_le_bytes())
+            .commit_witness(b"blinding", blinding.expose_secret().as_bytes())
+            .finalize(csprng);
+        let k = Scalar::random(&mut rng);
+        let K = params.G() * &k;
+
+        let c = public_value_challenge(&C, value, &K.compress());
+        let s = Scalar::multiply_add(&c, blinding.expose_secret(), &k);
+
+        PublicValueProof { c: c, s: s }
+    }
+
+    /// Verify that `commitment` opens to `value`.
+    #[must_use]
+    pub fn verify(&self, commitment: &DecafPoint, value: u64, params: &RangeProofParams) -> bool {
+        let X = commitments::sub_value(commitment, value, params);
+        let K = vartime::k_fold_scalar_mult(&[self.s, -&self.c], &[params.G().basepoint(), X]);
+
+        public_value_challenge(&commitment.compress(), value, &K.compress()) == self.c
+    }
+
+    /// Encode the proof as `c || s`, in 64 bytes.
+    pub fn to_bytes(&self) -> [u8; PUBLIC_VALUE_PROOF_LENGTH] {
+        let mut bytes = [0u8; PUBLIC_VALUE_PROOF_LENGTH];
+        bytes[..32].copy_from_slice(self.c.as_bytes());
+        bytes[32..].copy_from_slice(self.s.as_bytes());
+        bytes
+    }
+
+    /// Decode a proof encoded by `to_bytes`.
+    ///
+    /// # Returns
+    ///
+    /// A `DecodingError::InvalidLength` if `bytes` isn't 64 bytes long,
+    /// or a `DecodingError::NonCanonicalScalar` if either scalar isn't
+    /// canonically encoded.
+    pub fn from_bytes(bytes: &[u8]) -> Result<PublicValueProof, DecodingError> {
+        if bytes.len() != PUBLIC_VALUE_PROOF_LENGTH {
+            return Err(DecodingError::InvalidLength);
+        }
+
+        Ok(PublicValueProof { c: read_scalar(&bytes[..32])?, s: read_scalar(&bytes[32..])? })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::OsRng;
+    #[cfg(feature = "serde")]
+    use serde_json;
+
+    use RangeProof;
+    use DEFAULT_PARAMS;
+
+    #[test]
+    fn prove_and_verify_public_value() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let (_, commitment, blinding) =
+            RangeProof::create_with_params(16, 13449261, &DEFAULT_PARAMS, &mut csprng).unwrap().into_parts();
+
+        let proof = PublicValueProof::create(&commitment, 13449261, &blinding, &DEFAULT_PARAMS, &mut csprng);
+        assert!(proof.verify(&commitment, 13449261, &DEFAULT_PARAMS));
+        assert!(!proof.verify(&commitment, 13449262, &DEFAULT_PARAMS));
+
+        let decoded = PublicValueProof::from_bytes(&proof.to_bytes()).unwrap();
+        assert!(decoded.verify(&commitment, 13449261, &DEFAULT_PARAMS));
+        assert_eq!(PublicValueProof::from_bytes(&[0u8; 63]).err(), Some(DecodingError::InvalidLength));
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn public_value_proof_serde_roundtrip() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let (_, commitment, blinding) =
+            RangeProof::create_with_params(16, 13449261, &DEFAULT_PARAMS, &mut csprng).unwrap().into_parts();
+        let proof = PublicValueProof::create(&commitment, 13449261, &blinding, &DEFAULT_PARAMS, &mut csprng);
+
+        let json = serde_json::to_string(&proof).unwrap();
+        let decoded: PublicValueProof = serde_json::from_str(&json).unwrap();
+        assert!(decoded.verify(&commitment, 13449261, &DEFAULT_PARAMS));
+    }
+}
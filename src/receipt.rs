@@ -0,0 +1,280 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Signed receipts of verification.
+//!
+//! A verification farm with identity key `X = x*G` verifies rangeproofs
+//! on behalf of downstream services, and for each valid proof emits a
+//! `VerificationReceipt` stating that the proof with id `RangeProof::id`
+//! was verified with `n` rings under the parameters with id
+//! `RangeProofParams::id` at some height, along with the commitment it
+//! proved.  The receipt is signed with a Schnorr signature by `X`, so
+//! a service which trusts the farm can check it with two scalar
+//! multiplications rather than re-verifying the proof.
+
+use rand::Rng;
+
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::decaf::{CompressedDecaf, DecafPoint};
+use curve25519_dalek::decaf::vartime;
+
+use blinding::SecretBlinding;
+use challenges::receipt_challenge;
+use encoding::{read_point, read_scalar};
+use errors::{DecodingError, VerifyError};
+use params::RangeProofParams;
+use transcript_rng::TranscriptRng;
+
+use super::RangeProof;
+use super::RANGEPROOF_MAX_N;
+
+/// The length in bytes of the statement signed by a receipt,
+/// `proof_id || params_id || n || height || C`.
+const STATEMENT_LENGTH: usize = 32 + 32 + 1 + 8 + 32;
+
+/// The length in bytes of an encoded `VerificationReceipt`.
+pub const RECEIPT_LENGTH: usize = STATEMENT_LENGTH + 32 + 32 + 32;
+
+/// A signed statement that a rangeproof verified.
+#[derive(Clone, Copy, Debug)]
+pub struct VerificationReceipt {
+    proof_id: [u8; 32],
+    params_id: [u8; 32],
+    n: u8,
+    height: u64,
+    commitment: CompressedDecaf,
+    verifier: CompressedDecaf,
+    R: CompressedDecaf,
+    s: Scalar,
+}
+
+/// A verifier which signs receipts for the proofs it verifies.
+pub struct ReceiptSigner<'a> {
+    params: &'a RangeProofParams,
+    key: SecretBlinding,
+    X: CompressedDecaf,
+}
+
+impl<'a> ReceiptSigner<'a> {
+    /// Construct a signer verifying proofs with `params` and signing
+    /// receipts with the identity key `key`.
+    pub fn new(key: SecretBlinding, params: &'a RangeProofParams) -> ReceiptSigner<'a> {
+        let X = (params.G() * key.expose_secret()).compress();
+
+        ReceiptSigner { params: params, key: key, X: X }
+    }
+
+    /// The identity key of the signer, with which receipts verify.
+    pub fn identity(&self) -> &CompressedDecaf {
+        &self.X
+    }
+
+    /// Verify `proof` with `n` rings and, if it verifies, sign a
+    /// receipt for it at `height`.
+    ///
+    /// # Returns
+    ///
+    /// The receipt, or the reason the rangeproof failed to verify.
+    pub fn verify<T: Rng>(
+        &self,
+        n: usize,
+        proof: &RangeProof,
+        height: u64,
+        csprng: &mut T,
+    ) -> Result<VerificationReceipt, VerifyError> {
+        let commitment = proof.verify_checked(n, self.params)?.commitment().compress();
+
+        let mut receipt = VerificationReceipt {
+            proof_id: proof.id(),
+            params_id: *self.params.id(),
+            n: n as u8,
+            height: height,
+            commitment: commitment,
+            verifier: self.X,
+            R: CompressedDecaf([0u8; 32]),
+            s: Scalar::zero(),
+        };
+        let statement = receipt.statement();
+
+        let mut rng = TranscriptRng::builder(b"receipt")
+            .commit_transcript(b"X", self.X.as_bytes())
+            .commit_transcript(b"statement", &statement)
+            .commit_witness(b"x", self.key.expose_secret().as_bytes())
+            .finalize(csprng);
+        let k = Scalar::random(&mut rng);
+        receipt.R = (self.params.G() * &k).compress();
+
+        let e = receipt_challenge(&self.X, &receipt.R, &statement);
+        receipt.s = Scalar::multiply_add(&e, self.key.expose_secret(), &k);
+
+        Ok(receipt)
+    }
+}
+
+impl VerificationReceipt {
+    /// The encoding `proof_id || params_id || n || height || C` of the
+    /// statement the receipt signs.
+    fn statement(&self) -> [u8; STATEMENT_LENGTH] {
+        let mut bytes = [0u8; STATEMENT_LENGTH];
+        bytes[..32].copy_from_slice(&self.proof_id);
+        bytes[32..64].copy_from_slice(&self.params_id);
+        bytes[64] = self.n;
+        bytes[65..73].copy_from_slice(&self.height.to_le_bytes());
+        bytes[73..].copy_from_slice(self.commitment.as_bytes());
+        bytes
+    }
+
+    /// Check that the receipt was signed by the identity key
+    /// `verifier`, for a proof verified with `params`.
+    ///
+    /// # Returns
+    ///
+    /// The commitment the proof was verified for if the receipt
+    /// checks, and None otherwise.
+    #[must_use]
+    pub fn check(&self, verifier: &CompressedDecaf, params: &RangeProofParams) -> Option<DecafPoint> {
+        if self.verifier != *verifier || self.params_id != *params.id() {
+            return None;
+        }
+
+        let X = read_point(self.verifier.as_bytes()).ok()?;
+        let R = read_point(self.R.as_bytes()).ok()?;
+        let e = receipt_challenge(&self.verifier, &self.R, &self.statement());
+        let R_hat = vartime::k_fold_scalar_mult(&[self.s, -&e], &[params.G().basepoint(), X]);
+        if R_hat.compress() != R.compress() {
+            return None;
+        }
+
+        read_point(self.commitment.as_bytes()).ok()
+    }
+
+    /// Check the receipt as for `check`, and also that it is for
+    /// `proof` with `n` rings.
+    #[must_use]
+    pub fn check_proof(
+        &self,
+        n: usize,
+        proof: &RangeProof,
+        verifier: &CompressedDecaf,
+        params: &RangeProofParams,
+    ) -> Option<DecafPoint> {
+        if self.n() != n || self.proof_id != proof.id() {
+            return None;
+        }
+        self.check(verifier, params)
+    }
+
+    /// The id of the proof which was verified.
+    pub fn proof_id(&self) -> &[u8; 32] {
+        &self.proof_id
+    }
+
+    /// The id of the parameters the proof was verified with.
+    pub fn params_id(&self) -> &[u8; 32] {
+        &self.params_id
+    }
+
+    /// The number of rings the proof was verified with.
+    pub fn n(&self) -> usize {
+        self.n as usize
+    }
+
+    /// The height at which the proof was verified.
+    pub fn height(&self) -> u64 {
+        self.height
+    }
+
+    /// The identity key of the verifier which signed the receipt.
+    pub fn verifier(&self) -> &CompressedDecaf {
+        &self.verifier
+    }
+
+    /// Encode the receipt as `statement || X || R || s`, in 201 bytes.
+    pub fn to_bytes(&self) -> [u8; RECEIPT_LENGTH] {
+        let mut bytes = [0u8; RECEIPT_LENGTH];
+        bytes[..STATEMENT_LENGTH].copy_from_slice(&self.statement());
+        bytes[STATEMENT_LENGTH..STATEMENT_LENGTH + 32].copy_from_slice(self.verifier.as_bytes());
+        bytes[STATEMENT_LENGTH + 32..STATEMENT_LENGTH + 64].copy_from_slice(self.R.as_bytes());
+        bytes[STATEMENT_LENGTH + 64..].copy_from_slice(self.s.as_bytes());
+        bytes
+    }
+
+    /// Decode a receipt encoded by `to_bytes`.
+    ///
+    /// # Returns
+    ///
+    /// A `DecodingError::InvalidLength` if `bytes` isn't 201 bytes long
+    /// or the number of rings exceeds `RANGEPROOF_MAX_N`, a
+    /// `DecodingError::InvalidPoint` if any of the points isn't
+    /// canonically encoded, or a `DecodingError::NonCanonicalScalar`
+    /// if `s` isn't.
+    pub fn from_bytes(bytes: &[u8]) -> Result<VerificationReceipt, DecodingError> {
+        if bytes.len() != RECEIPT_LENGTH || bytes[64] as usize > RANGEPROOF_MAX_N {
+            return Err(DecodingError::InvalidLength);
+        }
+
+        let mut proof_id = [0u8; 32];
+        proof_id.copy_from_slice(&bytes[..32]);
+        let mut params_id = [0u8; 32];
+        params_id.copy_from_slice(&bytes[32..64]);
+        let mut height = [0u8; 8];
+        height.copy_from_slice(&bytes[65..73]);
+
+        Ok(VerificationReceipt {
+            proof_id: proof_id,
+            params_id: params_id,
+            n: bytes[64],
+            height: u64::from_le_bytes(height),
+            commitment: read_point(&bytes[73..STATEMENT_LENGTH])?.compress(),
+            verifier: read_point(&bytes[STATEMENT_LENGTH..])?.compress(),
+            R: read_point(&bytes[STATEMENT_LENGTH + 32..])?.compress(),
+            s: read_scalar(&bytes[STATEMENT_LENGTH + 64..])?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::OsRng;
+
+    use DEFAULT_PARAMS;
+
+    #[test]
+    fn sign_and_check_receipts() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let signer = ReceiptSigner::new(SecretBlinding::new(Scalar::random(&mut csprng)), &DEFAULT_PARAMS);
+        let (proof, C, _) = RangeProof::create_with_params(16, 1000, &DEFAULT_PARAMS, &mut csprng).unwrap().into_parts();
+
+        let receipt = signer.verify(16, &proof, 123456, &mut csprng).unwrap();
+        assert_eq!(receipt.height(), 123456);
+        assert_eq!(receipt.check(signer.identity(), &DEFAULT_PARAMS).unwrap().compress(), C.compress());
+        assert!(receipt.check_proof(16, &proof, signer.identity(), &DEFAULT_PARAMS).is_some());
+        assert!(receipt.check_proof(15, &proof, signer.identity(), &DEFAULT_PARAMS).is_none());
+
+        let other = ReceiptSigner::new(SecretBlinding::new(Scalar::random(&mut csprng)), &DEFAULT_PARAMS);
+        assert!(receipt.check(other.identity(), &DEFAULT_PARAMS).is_none());
+
+        let mut bytes = receipt.to_bytes();
+        let decoded = VerificationReceipt::from_bytes(&bytes).unwrap();
+        assert!(decoded.check(signer.identity(), &DEFAULT_PARAMS).is_some());
+
+        // Changing the height invalidates the signature.
+        bytes[65] ^= 1;
+        let forged = VerificationReceipt::from_bytes(&bytes).unwrap();
+        assert!(forged.check(signer.identity(), &DEFAULT_PARAMS).is_none());
+
+        assert_eq!(signer.verify(15, &proof, 0, &mut csprng).err(), Some(VerifyError::WrongRingCount));
+    }
+}
@@ -0,0 +1,164 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Detecting replayed rangeproofs.
+//!
+//! A valid proof stays valid however often it is submitted, so a pool
+//! which accepts proofs must remember which it has already seen.  The
+//! uniqueness tag of a proof is the hash of its transcript, the
+//! challenge `e_0` and the digit commitments `C_i`, bound to some
+//! context data such as a chain or pool identifier.  The same proof
+//! has the same tag within a context, however it is wrapped or
+//! re-encoded, while tags from different contexts are unrelated.
+//!
+//! A `ReplayFilter` keeps the set of tags seen in one context.
+
+use std::collections::HashSet;
+
+use sha2::{Digest, Sha512};
+
+use super::RangeProof;
+
+impl RangeProof {
+    /// A 32-byte tag identifying this proof within the given
+    /// `context`, the first 32 bytes of the SHA-512 hash of the context
+    /// and the transcript of the proof.
+    ///
+    /// # Note
+    ///
+    /// The tag is only meaningful for proofs which have been verified.
+    pub fn uniqueness_tag(&self, context: &[u8]) -> [u8; 32] {
+        let mut h = Sha512::default();
+        h.input(b"dalek-rangeproofs uniqueness tag");
+        h.input(&(context.len() as u64).to_le_bytes());
+        h.input(context);
+        h.input(self.e_0.as_bytes());
+        for C_i in self.C.iter() {
+            h.input(C_i.compress().as_bytes());
+        }
+        let mut tag = [0u8; 32];
+        tag.copy_from_slice(&h.result()[..32]);
+        tag
+    }
+}
+
+/// The set of uniqueness tags of the proofs seen in a single context.
+pub struct ReplayFilter {
+    context: Vec<u8>,
+    seen: HashSet<[u8; 32]>,
+}
+
+impl ReplayFilter {
+    /// Construct an empty filter for proofs in the given `context`.
+    pub fn new(context: &[u8]) -> ReplayFilter {
+        ReplayFilter { context: context.to_vec(), seen: HashSet::new() }
+    }
+
+    /// The context the tags are bound to.
+    pub fn context(&self) -> &[u8] {
+        &self.context
+    }
+
+    /// The uniqueness tag of `proof` in this filter's context.
+    pub fn tag(&self, proof: &RangeProof) -> [u8; 32] {
+        proof.uniqueness_tag(&self.context)
+    }
+
+    /// Record `proof` as seen.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the proof hadn't been seen before, and `false` if it
+    /// is a replay.
+    pub fn insert(&mut self, proof: &RangeProof) -> bool {
+        let tag = self.tag(proof);
+        self.seen.insert(tag)
+    }
+
+    /// Returns `true` if `proof` has been seen.
+    pub fn contains(&self, proof: &RangeProof) -> bool {
+        self.seen.contains(&self.tag(proof))
+    }
+
+    /// Forget `proof`, e.g. when the transaction carrying it is evicted
+    /// from the pool.  Returns `true` if it had been seen.
+    pub fn remove(&mut self, proof: &RangeProof) -> bool {
+        let tag = self.tag(proof);
+        self.seen.remove(&tag)
+    }
+
+    /// Returns the proofs among `proofs` which have been seen, or occur
+    /// more than once, by index, without recording any of them.
+    pub fn replays(&self, proofs: &[RangeProof]) -> Vec<usize> {
+        let mut batch = HashSet::with_capacity(proofs.len());
+        proofs.iter().enumerate().filter_map(|(j, proof)| {
+            let tag = self.tag(proof);
+            if self.seen.contains(&tag) || !batch.insert(tag) {
+                Some(j)
+            } else {
+                None
+            }
+        }).collect()
+    }
+
+    /// The number of proofs seen.
+    pub fn len(&self) -> usize {
+        self.seen.len()
+    }
+
+    /// Returns `true` if no proofs have been seen.
+    pub fn is_empty(&self) -> bool {
+        self.seen.is_empty()
+    }
+
+    /// Forget every proof.
+    pub fn clear(&mut self) {
+        self.seen.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::OsRng;
+
+    use DEFAULT_PARAMS;
+
+    #[test]
+    fn detect_replayed_proofs() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let proofs: Vec<RangeProof> = [1, 2].iter().map(|&value| {
+            RangeProof::create_with_params(4, value, &DEFAULT_PARAMS, &mut csprng).unwrap().into_proof()
+        }).collect();
+        assert!(proofs[0].uniqueness_tag(b"pool") != proofs[0].uniqueness_tag(b"other pool"));
+        assert!(proofs[0].uniqueness_tag(b"pool") != proofs[1].uniqueness_tag(b"pool"));
+
+        let mut filter = ReplayFilter::new(b"pool");
+        assert!(filter.insert(&proofs[0]));
+        let reencoded = RangeProof::from_bytes(&proofs[0].to_bytes()).unwrap();
+        assert!(!filter.insert(&reencoded));
+        assert!(filter.contains(&proofs[0]));
+        assert!(!filter.contains(&proofs[1]));
+        assert_eq!(filter.len(), 1);
+
+        let batch = vec![reencoded, RangeProof::from_bytes(&proofs[1].to_bytes()).unwrap(),
+                         RangeProof::from_bytes(&proofs[1].to_bytes()).unwrap()];
+        assert_eq!(filter.replays(&batch), vec![0, 2]);
+        assert_eq!(filter.len(), 1);
+
+        assert!(filter.remove(&proofs[0]));
+        assert!(!filter.contains(&proofs[0]));
+        assert!(filter.is_empty());
+    }
+}
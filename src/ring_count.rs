@@ -0,0 +1,145 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! A validated number of rings.
+//!
+//! The core API takes the number of rings `n` as a `usize`, rejecting
+//! any `n > RANGEPROOF_MAX_N` at run time, and returns commitments as
+//! `DecafPoint`s.  Where `n` comes from configuration or from the
+//! network, it can instead be validated once as a `RingCount`, and
+//! passed to `RangeProof::create_with_ring_count` and
+//! `RangeProof::verify_with_ring_count`.  Since a `RingCount` isn't an
+//! integer, it can't be confused with a value or an index.
+//!
+//! A `RingCount` is opt-in: these two methods are the only ones taking
+//! one, and every other method still takes `n` as a `usize`, so that
+//! existing callers keep compiling.  Code holding a `RingCount` passes
+//! `n.get()` to those.  The results of the two methods are a
+//! `ProverOutput` and a `VerifiedCommitment`, whose commitments can be
+//! taken as `PedersenCommitment`s and compared with
+//! `VerifiedCommitment::proves`, rather than as raw points.
+
+use std::convert::TryFrom;
+
+use rand::Rng;
+
+use errors::{RangeProofError, VerifyError};
+use output::ProverOutput;
+use params::RangeProofParams;
+use verification::VerifiedCommitment;
+
+use super::RangeProof;
+use super::RANGEPROOF_MAX_N;
+
+/// A number of rings `n <= RANGEPROOF_MAX_N`, so that the range
+/// `[0,3^n]` can be proven.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct RingCount(u8);
+
+impl RingCount {
+    /// The largest number of rings, `RANGEPROOF_MAX_N`.
+    pub const MAX: RingCount = RingCount(RANGEPROOF_MAX_N as u8);
+
+    /// Validate the number of rings `n`.
+    ///
+    /// # Returns
+    ///
+    /// None if `n > RANGEPROOF_MAX_N`.
+    pub fn new(n: usize) -> Option<RingCount> {
+        if n > RANGEPROOF_MAX_N {
+            return None;
+        }
+        Some(RingCount(n as u8))
+    }
+
+    /// The number of rings.
+    pub fn get(&self) -> usize {
+        self.0 as usize
+    }
+
+    /// The largest value which can be proven in range with this many
+    /// rings, as for `RangeProof::max_value`.
+    pub fn max_value(&self) -> u64 {
+        RangeProof::max_value(self.get())
+    }
+}
+
+impl TryFrom<usize> for RingCount {
+    type Error = RangeProofError;
+
+    /// Validate the number of rings `n`, as for `RingCount::new`,
+    /// returning a `RangeProofError::InvalidRingCount` if
+    /// `n > RANGEPROOF_MAX_N`.
+    fn try_from(n: usize) -> Result<RingCount, RangeProofError> {
+        RingCount::new(n).ok_or(RangeProofError::InvalidRingCount(n))
+    }
+}
+
+impl From<RingCount> for usize {
+    fn from(n: RingCount) -> usize {
+        n.get()
+    }
+}
+
+impl RangeProof {
+    /// Construct a rangeproof for `value` in the range `[0,3^n]`, as
+    /// for `try_create_with_params`.
+    pub fn create_with_ring_count<T: Rng>(
+        n: RingCount,
+        value: u64,
+        params: &RangeProofParams,
+        csprng: &mut T,
+    ) -> Result<ProverOutput, RangeProofError> {
+        RangeProof::try_create_with_params(n.get(), value, params, csprng)
+    }
+
+    /// Verify the rangeproof with `n` rings, as for `verify_checked`,
+    /// but without panicking.
+    pub fn verify_with_ring_count(
+        &self,
+        n: RingCount,
+        params: &RangeProofParams,
+    ) -> Result<VerifiedCommitment, VerifyError> {
+        self.verify_checked(n.get(), params)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::OsRng;
+
+    use DEFAULT_PARAMS;
+
+    #[test]
+    fn ring_counts_are_validated() {
+        let mut csprng = OsRng::new().unwrap();
+
+        assert_eq!(RingCount::new(RANGEPROOF_MAX_N), Some(RingCount::MAX));
+        assert_eq!(RingCount::new(RANGEPROOF_MAX_N + 1), None);
+        assert_eq!(RingCount::new(2).unwrap().max_value(), 8);
+        assert_eq!(RingCount::try_from(8), Ok(RingCount::new(8).unwrap()));
+        assert_eq!(RingCount::try_from(RANGEPROOF_MAX_N + 1),
+                   Err(RangeProofError::InvalidRingCount(RANGEPROOF_MAX_N + 1)));
+
+        let n = RingCount::new(8).unwrap();
+        let output = RangeProof::create_with_ring_count(n, 42, &DEFAULT_PARAMS, &mut csprng).unwrap();
+        let verified = output.proof().verify_with_ring_count(n, &DEFAULT_PARAMS).unwrap();
+        assert!(verified.proves(&output.pedersen_commitment()));
+        assert_eq!(output.proof().verify_with_ring_count(RingCount::MAX, &DEFAULT_PARAMS).err(),
+                   Some(VerifyError::WrongRingCount));
+
+        let n = RingCount::new(2).unwrap();
+        assert!(RangeProof::create_with_ring_count(n, 9, &DEFAULT_PARAMS, &mut csprng).is_err());
+    }
+}
@@ -0,0 +1,228 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Read-only views of the rings of a rangeproof.
+//!
+//! A rangeproof for `[0,3^n]` consists of a shared challenge `e_0` and
+//! `n` rings, one per base-3 digit.  The `i`th ring is a commitment
+//! `C_i` to `d_i * 3^i * H` (for a digit `d_i` in `{0,1,2}`) together
+//! with the two ring signature responses `s_1,i` and `s_2,i`.
+//!
+//! `RangeProof::inspect` gathers these views, along with the size of
+//! the encoded proof, into an `Inspection` for explorers and analysis
+//! tools which display the internals of a proof.
+
+use std::fmt;
+use std::iter;
+use std::slice;
+
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::decaf::DecafPoint;
+
+use super::RangeProof;
+
+/// A read-only view of a single ring of a rangeproof.
+#[derive(Clone, Copy, Debug)]
+pub struct Ring<'a> {
+    index: usize,
+    C: &'a DecafPoint,
+    s_1: &'a Scalar,
+    s_2: &'a Scalar,
+}
+
+impl<'a> Ring<'a> {
+    /// The index `i` of this ring, i.e. of the base-3 digit it is for.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// The commitment `C_i` to the `i`th digit.
+    pub fn C(&self) -> &'a DecafPoint {
+        self.C
+    }
+
+    /// The response `s_1,i`.
+    pub fn s_1(&self) -> &'a Scalar {
+        self.s_1
+    }
+
+    /// The response `s_2,i`.
+    pub fn s_2(&self) -> &'a Scalar {
+        self.s_2
+    }
+}
+
+/// An iterator over the rings of a rangeproof, in order of increasing
+/// index.
+pub struct Rings<'a> {
+    inner: iter::Enumerate<iter::Zip<iter::Zip<slice::Iter<'a, DecafPoint>,
+                                               slice::Iter<'a, Scalar>>,
+                                     slice::Iter<'a, Scalar>>>,
+}
+
+impl<'a> Iterator for Rings<'a> {
+    type Item = Ring<'a>;
+
+    fn next(&mut self) -> Option<Ring<'a>> {
+        self.inner.next().map(|(i, ((C, s_1), s_2))| {
+            Ring { index: i, C: C, s_1: s_1, s_2: s_2 }
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a> ExactSizeIterator for Rings<'a> {}
+
+impl<'a> IntoIterator for &'a RangeProof {
+    type Item = Ring<'a>;
+    type IntoIter = Rings<'a>;
+
+    fn into_iter(self) -> Rings<'a> {
+        self.iter()
+    }
+}
+
+/// A read-only view of the internals of a rangeproof.
+#[derive(Clone, Copy)]
+pub struct Inspection<'a> {
+    proof: &'a RangeProof,
+}
+
+impl<'a> Inspection<'a> {
+    /// The number of rings in the proof.
+    pub fn ring_count(&self) -> usize {
+        self.proof.iter().len()
+    }
+
+    /// A view of the `i`th ring, if the proof has one.
+    pub fn ring(&self, i: usize) -> Option<Ring<'a>> {
+        self.proof.iter().nth(i)
+    }
+
+    /// Iterate over views of the rings, in order of increasing index.
+    pub fn rings(&self) -> Rings<'a> {
+        self.proof.iter()
+    }
+
+    /// The shared challenge `e_0`.
+    pub fn challenge(&self) -> &'a Scalar {
+        &self.proof.e_0
+    }
+
+    /// The size in bytes of the encoded proof.
+    pub fn size_in_bytes(&self) -> usize {
+        self.proof.size_in_bytes()
+    }
+}
+
+impl<'a> fmt::Debug for Inspection<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Inspection")
+            .field("ring_count", &self.ring_count())
+            .field("size_in_bytes", &self.size_in_bytes())
+            .field("rings", &self.rings().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl RangeProof {
+    /// Inspect the internals of the proof through read-only views.
+    pub fn inspect(&self) -> Inspection {
+        Inspection { proof: self }
+    }
+
+    /// The shared challenge `e_0` of the proof.
+    pub fn e_0(&self) -> &Scalar {
+        &self.e_0
+    }
+
+    /// The challenge `e_0` of the proof, as for `e_0`.
+    ///
+    /// Since `e_0` is the hash of the first points of every ring, a
+    /// composite protocol may bind other signatures or statements to
+    /// the proof by including the challenge in their own transcripts.
+    ///
+    /// # Note
+    ///
+    /// The challenge is only meaningful once the proof has been
+    /// verified.
+    pub fn challenge(&self) -> &Scalar {
+        &self.e_0
+    }
+
+    /// The number of rings in the proof.
+    pub fn n(&self) -> usize {
+        self.C.len()
+    }
+
+    /// Iterate over read-only views of the rings of the proof.
+    ///
+    /// # Note
+    ///
+    /// A proof which hasn't been verified may have inconsistent
+    /// numbers of commitments and responses, in which case only as
+    /// many rings as there are complete triples are yielded.
+    pub fn iter(&self) -> Rings {
+        Rings { inner: self.C.iter().zip(self.s_1.iter()).zip(self.s_2.iter()).enumerate() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::OsRng;
+
+    use curve25519_dalek::curve::Identity;
+
+    use DEFAULT_PARAMS;
+
+    #[test]
+    fn iterate_over_rings() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let n = 16;
+        let (proof, commitment, _) =
+            RangeProof::create_with_params(n, 13449261, &DEFAULT_PARAMS, &mut csprng).unwrap().into_parts();
+
+        assert_eq!(proof.n(), n);
+        assert_eq!(proof.iter().len(), n);
+
+        let mut C = DecafPoint::identity();
+        for (i, ring) in (&proof).into_iter().enumerate() {
+            assert_eq!(ring.index(), i);
+            assert!(ring.s_1() == &proof.s_1[i]);
+            assert!(ring.s_2() == &proof.s_2[i]);
+            C = &C + ring.C();
+        }
+        assert_eq!(C.compress(), commitment.compress());
+    }
+
+    #[test]
+    fn inspect_proof() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let proof = RangeProof::create_with_params(8, 1000, &DEFAULT_PARAMS, &mut csprng).unwrap().into_proof();
+        let inspection = proof.inspect();
+
+        assert_eq!(inspection.ring_count(), 8);
+        assert_eq!(inspection.rings().len(), 8);
+        assert_eq!(inspection.size_in_bytes(), proof.to_bytes().len());
+        assert!(inspection.challenge() == proof.e_0());
+        assert!(proof.challenge() == proof.e_0());
+        assert_eq!(inspection.ring(7).unwrap().C().compress(), proof.C[7].compress());
+        assert!(inspection.ring(8).is_none());
+    }
+}
@@ -0,0 +1,115 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! JSON Schemas for the human-readable serde forms, with the
+//! `schemars` feature.
+//!
+//! `RangeProof`, `ProofBundle`, `DleqProof`, `PublicValueProof` and
+//! `CommitmentAccumulator` implement `schemars::JsonSchema`, as do the
+//! `Trace` and `AuditTranscript` responses with their features, so
+//! that e.g. `schema_for!(ProofBundle)` gives REST API consumers a
+//! machine-checkable contract.  Every point and scalar is described as
+//! the 64-character hex string of its encoding, as serialized to
+//! human-readable formats; the schemas don't describe the raw bytes
+//! used by binary formats.
+//!
+//! A schema only checks the shape of a value: a proof which matches
+//! it may still have non-canonical points or scalars, and may not
+//! verify.
+
+use schemars;
+use schemars::JsonSchema;
+use schemars::gen::SchemaGenerator;
+use schemars::schema::{InstanceType, Schema, SchemaObject, StringValidation};
+
+use super::RangeProof;
+use super::RANGEPROOF_MAX_N;
+
+/// The schema of a point or scalar, as the hex string of its 32-byte
+/// encoding.
+pub struct Hex32;
+
+impl JsonSchema for Hex32 {
+    fn is_referenceable() -> bool {
+        false
+    }
+
+    fn schema_name() -> String {
+        "Hex32".to_owned()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        SchemaObject {
+            instance_type: Some(InstanceType::String.into()),
+            string: Some(Box::new(StringValidation {
+                max_length: Some(64),
+                min_length: Some(64),
+                pattern: Some("^[0-9a-fA-F]{64}$".to_owned()),
+            })),
+            ..Default::default()
+        }.into()
+    }
+}
+
+/// The serialized form of a `RangeProof`, as deserialized by
+/// `serialization`.
+#[derive(JsonSchema)]
+#[schemars(rename = "RangeProof", deny_unknown_fields)]
+#[allow(dead_code)]
+struct RangeProofSchema {
+    /// The challenge `e_0`.
+    e_0: Hex32,
+    /// The digit commitments `C_i`.
+    #[schemars(length(max = 41))]
+    C: Vec<Hex32>,
+    /// The responses `s_1,i`.
+    #[schemars(length(max = 41))]
+    s_1: Vec<Hex32>,
+    /// The responses `s_2,i`.
+    #[schemars(length(max = 41))]
+    s_2: Vec<Hex32>,
+}
+
+impl JsonSchema for RangeProof {
+    fn schema_name() -> String {
+        "RangeProof".to_owned()
+    }
+
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        // The derived bound must agree with the deserializer's.
+        debug_assert_eq!(RANGEPROOF_MAX_N, 41);
+        RangeProofSchema::json_schema(gen)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json;
+
+    use bundle::ProofBundle;
+
+    #[test]
+    fn schemas_describe_hex_fields() {
+        let schema = serde_json::to_value(schema_for!(RangeProof)).unwrap();
+        assert_eq!(schema["additionalProperties"], false);
+        assert_eq!(schema["properties"]["e_0"]["pattern"], "^[0-9a-fA-F]{64}$");
+        assert_eq!(schema["properties"]["C"]["maxItems"], RANGEPROOF_MAX_N);
+        assert_eq!(schema["properties"]["s_2"]["items"]["maxLength"], 64);
+
+        let schema = serde_json::to_value(schema_for!(ProofBundle)).unwrap();
+        assert_eq!(schema["properties"]["params_id"]["pattern"], "^[0-9a-fA-F]{64}$");
+        assert_eq!(schema["properties"]["claimed_commitment"]["type"], "string");
+        assert_eq!(schema["properties"]["proof"]["$ref"], "#/definitions/RangeProof");
+    }
+}
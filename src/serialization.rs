@@ -0,0 +1,476 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Serde support for rangeproofs.
+//!
+//! This module is only available with the `serde` feature, which is
+//! enabled by default.  Without it, proofs are encoded with the byte
+//! encodings of `RangeProof::to_bytes` and friends, which are always
+//! available.
+//!
+//! Every point and scalar is serialized according to whether the
+//! format is human-readable:
+//!
+//! * for human-readable formats such as JSON, as a lowercase hex string
+//!   of its 32-byte encoding, so that proofs can be exposed by REST
+//!   APIs and block explorers;
+//! * for binary formats such as CBOR or bincode, as its raw 32-byte
+//!   encoding, using `serialize_bytes`.
+//!
+//! A `RangeProof` is serialized as a struct with the fields `e_0`, `C`,
+//! `s_1` and `s_2`.  The `bytes32`, `point`, `points`, `scalar` and
+//! `scalars` modules can be used with `#[serde(with = "...")]` so that
+//! other types containing points and scalars get the same behaviour
+//! from a plain `#[derive]`.
+//!
+//! Deserialization is strict: a `RangeProof` is only produced if it
+//! has no unknown fields, the same number of entries in each of its
+//! vectors, at most `RANGEPROOF_MAX_N` rings, and only canonically
+//! encoded points and scalars.  A vector is rejected as soon as it has
+//! more than `RANGEPROOF_MAX_N` entries, whatever length the format
+//! claims for it, and no point is decompressed until every vector has
+//! been read and their lengths checked.  Whether trailing data after the proof
+//! is rejected is up to the format; e.g. `serde_cbor::from_slice`
+//! rejects it.
+
+use std::cmp;
+use std::fmt;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde::de::{Error, SeqAccess, Visitor};
+
+#[cfg(feature = "postcard")]
+use postcard;
+
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::decaf::DecafPoint;
+
+use encoding::{from_hex_32, read_point, read_scalar, to_hex};
+use errors::DecodingError;
+
+use super::RangeProof;
+use super::RANGEPROOF_MAX_N;
+
+/// A 32-byte encoding, serialized as hex or as raw bytes depending on
+/// the format.
+struct Bytes32([u8; 32]);
+
+impl Serialize for Bytes32 {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer
+    {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&to_hex(&self.0))
+        } else {
+            serializer.serialize_bytes(&self.0)
+        }
+    }
+}
+
+struct Bytes32Visitor;
+
+impl<'de> Visitor<'de> for Bytes32Visitor {
+    type Value = Bytes32;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("32 bytes, or a hex string encoding 32 bytes")
+    }
+
+    fn visit_str<E: Error>(self, hex: &str) -> Result<Bytes32, E> {
+        from_hex_32(hex).map(Bytes32).map_err(E::custom)
+    }
+
+    fn visit_bytes<E: Error>(self, bytes: &[u8]) -> Result<Bytes32, E> {
+        if bytes.len() != 32 {
+            return Err(E::invalid_length(bytes.len(), &self));
+        }
+        let mut array = [0u8; 32];
+        array.copy_from_slice(bytes);
+        Ok(Bytes32(array))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Bytes32, A::Error> {
+        let mut array = [0u8; 32];
+        for i in 0..32 {
+            array[i] = seq.next_element()?.ok_or_else(|| A::Error::invalid_length(i, &self))?;
+        }
+        if seq.next_element::<u8>()?.is_some() {
+            return Err(A::Error::invalid_length(33, &self));
+        }
+        Ok(Bytes32(array))
+    }
+}
+
+impl<'de> Deserialize<'de> for Bytes32 {
+    fn deserialize<D>(deserializer: D) -> Result<Bytes32, D::Error>
+        where D: Deserializer<'de>
+    {
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_str(Bytes32Visitor)
+        } else {
+            deserializer.deserialize_bytes(Bytes32Visitor)
+        }
+    }
+}
+
+/// Serialize a `[u8; 32]`, such as a parameter identifier, as hex or
+/// raw bytes.
+pub mod bytes32 {
+    use super::*;
+
+    /// Serialize `bytes` as hex or as raw bytes, depending on the format.
+    pub fn serialize<S: Serializer>(bytes: &[u8; 32], serializer: S) -> Result<S::Ok, S::Error> {
+        Bytes32(*bytes).serialize(serializer)
+    }
+
+    /// Deserialize 32 bytes from hex or raw bytes.
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<[u8; 32], D::Error> {
+        Bytes32::deserialize(deserializer).map(|bytes| bytes.0)
+    }
+}
+
+/// Serialize a `DecafPoint` as hex or raw bytes, and deserialize it
+/// only from a canonical encoding.
+pub mod point {
+    use super::*;
+
+    use encoding::read_point;
+
+    /// Serialize `point` as hex or as raw bytes, depending on the format.
+    pub fn serialize<S: Serializer>(point: &DecafPoint, serializer: S) -> Result<S::Ok, S::Error> {
+        Bytes32(*point.compress().as_bytes()).serialize(serializer)
+    }
+
+    /// Deserialize a canonically-encoded point from hex or raw bytes.
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<DecafPoint, D::Error> {
+        let bytes = Bytes32::deserialize(deserializer)?;
+
+        read_point(&bytes.0).map_err(D::Error::custom)
+    }
+}
+
+/// Serialize a `Vec<DecafPoint>` as a sequence of hex strings or raw
+/// bytes, and deserialize it only from canonical encodings.
+pub mod points {
+    use super::*;
+
+    use encoding::read_point;
+
+    /// Serialize `points` as hex or as raw bytes, depending on the format.
+    pub fn serialize<S: Serializer>(points: &Vec<DecafPoint>, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(points.iter().map(|p| Bytes32(*p.compress().as_bytes())))
+    }
+
+    /// Deserialize canonically-encoded points from hex or raw bytes.
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<DecafPoint>, D::Error> {
+        let encodings = Vec::<Bytes32>::deserialize(deserializer)?;
+
+        encodings.iter().map(|bytes| read_point(&bytes.0).map_err(D::Error::custom)).collect()
+    }
+}
+
+/// Serialize a `Scalar` as hex or raw bytes, and deserialize it only
+/// from a canonical encoding.
+pub mod scalar {
+    use super::*;
+
+    use encoding::read_scalar;
+
+    /// Serialize `scalar` as hex or as raw bytes, depending on the format.
+    pub fn serialize<S: Serializer>(scalar: &Scalar, serializer: S) -> Result<S::Ok, S::Error> {
+        Bytes32(*scalar.as_bytes()).serialize(serializer)
+    }
+
+    /// Deserialize a canonically-encoded scalar from hex or raw bytes.
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Scalar, D::Error> {
+        let bytes = Bytes32::deserialize(deserializer)?;
+
+        read_scalar(&bytes.0).map_err(D::Error::custom)
+    }
+}
+
+/// Serialize a `Vec<Scalar>` as a sequence of hex strings or raw
+/// bytes, and deserialize it only from canonical encodings.
+pub mod scalars {
+    use super::*;
+
+    use encoding::read_scalar;
+
+    /// Serialize `scalars` as hex or as raw bytes, depending on the format.
+    pub fn serialize<S: Serializer>(scalars: &Vec<Scalar>, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(scalars.iter().map(|s| Bytes32(*s.as_bytes())))
+    }
+
+    /// Deserialize canonically-encoded scalars from hex or raw bytes.
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Scalar>, D::Error> {
+        let encodings = Vec::<Bytes32>::deserialize(deserializer)?;
+
+        encodings.iter().map(|bytes| read_scalar(&bytes.0).map_err(D::Error::custom)).collect()
+    }
+}
+
+/// The encodings of one of the vectors of a `RangeProof`, of which
+/// there are at most `RANGEPROOF_MAX_N`.
+struct RingEncodings(Vec<Bytes32>);
+
+struct RingEncodingsVisitor;
+
+impl<'de> Visitor<'de> for RingEncodingsVisitor {
+    type Value = RingEncodings;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "a sequence of at most {} encodings", RANGEPROOF_MAX_N)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<RingEncodings, A::Error> {
+        // Don't trust the length the format claims for the sequence.
+        let capacity = cmp::min(seq.size_hint().unwrap_or(0), RANGEPROOF_MAX_N);
+        let mut encodings = Vec::with_capacity(capacity);
+        while let Some(bytes) = seq.next_element()? {
+            if encodings.len() == RANGEPROOF_MAX_N {
+                return Err(A::Error::custom(DecodingError::LimitExceeded));
+            }
+            encodings.push(bytes);
+        }
+        Ok(RingEncodings(encodings))
+    }
+}
+
+impl<'de> Deserialize<'de> for RingEncodings {
+    fn deserialize<D>(deserializer: D) -> Result<RingEncodings, D::Error>
+        where D: Deserializer<'de>
+    {
+        deserializer.deserialize_seq(RingEncodingsVisitor)
+    }
+}
+
+/// The serialized form of a `RangeProof`, before validation.
+#[derive(Deserialize)]
+#[serde(rename = "RangeProof", deny_unknown_fields)]
+struct RangeProofRepr {
+    e_0: Bytes32,
+    C: RingEncodings,
+    s_1: RingEncodings,
+    s_2: RingEncodings,
+}
+
+impl<'de> Deserialize<'de> for RangeProof {
+    fn deserialize<D>(deserializer: D) -> Result<RangeProof, D::Error>
+        where D: Deserializer<'de>
+    {
+        let repr = RangeProofRepr::deserialize(deserializer)?;
+
+        let n = repr.C.0.len();
+        if n != repr.s_1.0.len() || n != repr.s_2.0.len() {
+            return Err(D::Error::custom(DecodingError::InvalidLength));
+        }
+
+        let decode = |bytes: &Bytes32| read_scalar(&bytes.0).map_err(D::Error::custom);
+        Ok(RangeProof{
+            e_0: decode(&repr.e_0)?,
+            C: repr.C.0.iter().map(|bytes| read_point(&bytes.0).map_err(D::Error::custom)).collect::<Result<_, _>>()?,
+            s_1: repr.s_1.0.iter().map(&decode).collect::<Result<_, _>>()?,
+            s_2: repr.s_2.0.iter().map(&decode).collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+impl RangeProof {
+    /// Encode this rangeproof with `postcard`.
+    #[cfg(feature = "postcard")]
+    pub fn to_postcard(&self) -> Vec<u8> {
+        // Serializing into a Vec cannot fail.
+        postcard::to_allocvec(self).expect("postcard serialization of a rangeproof failed")
+    }
+
+    /// Decode a rangeproof encoded with `postcard`.
+    ///
+    /// # Returns
+    ///
+    /// A `DecodingError::TrailingBytes` if `bytes` continues after the
+    /// end of the proof, a `DecodingError::InvalidPostcard` if it is
+    /// otherwise malformed or is not the canonical encoding of the
+    /// proof it decodes to.
+    #[cfg(feature = "postcard")]
+    pub fn from_postcard(bytes: &[u8]) -> Result<RangeProof, DecodingError> {
+        let (proof, rest) = postcard::take_from_bytes::<RangeProof>(bytes)
+            .map_err(|_| DecodingError::InvalidPostcard)?;
+        if !rest.is_empty() {
+            return Err(DecodingError::TrailingBytes);
+        }
+        // Varint lengths have redundant encodings, so only accept the
+        // bytes this proof would itself be encoded as.
+        if proof.to_postcard() != bytes {
+            return Err(DecodingError::InvalidPostcard);
+        }
+        Ok(proof)
+    }
+}
+
+/// Encode a Pedersen commitment with `postcard`.
+#[cfg(feature = "postcard")]
+pub fn commitment_to_postcard(commitment: &DecafPoint) -> Vec<u8> {
+    // Serializing into a Vec cannot fail.
+    postcard::to_allocvec(&Bytes32(*commitment.compress().as_bytes())).expect("postcard serialization of a commitment failed")
+}
+
+/// Decode a Pedersen commitment encoded with `postcard`.
+///
+/// # Returns
+///
+/// As for `RangeProof::from_postcard`, and a `DecodingError::InvalidPoint`
+/// if the commitment is not a canonically encoded point.
+#[cfg(feature = "postcard")]
+pub fn commitment_from_postcard(bytes: &[u8]) -> Result<DecafPoint, DecodingError> {
+    let (point, rest) = postcard::take_from_bytes::<Bytes32>(bytes)
+        .map_err(|_| DecodingError::InvalidPostcard)?;
+    if !rest.is_empty() {
+        return Err(DecodingError::TrailingBytes);
+    }
+    let commitment = read_point(&point.0)?;
+    if commitment_to_postcard(&commitment) != bytes {
+        return Err(DecodingError::InvalidPostcard);
+    }
+    Ok(commitment)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::OsRng;
+    use serde_cbor;
+    use serde_json;
+
+    use DEFAULT_PARAMS;
+
+    #[test]
+    fn serde_cbor_roundtrip() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let n = 16;
+        let (proof, commitment, _) =
+            RangeProof::create_with_params(n, 13449261, &DEFAULT_PARAMS, &mut csprng).unwrap().into_parts();
+
+        let bytes = serde_cbor::to_vec(&proof).unwrap();
+        let decoded: RangeProof = serde_cbor::from_slice(&bytes).unwrap();
+        assert_eq!(decoded.verify_with_params(n, &DEFAULT_PARAMS).unwrap().compress(),
+                   commitment.compress());
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(serde_cbor::from_slice::<RangeProof>(&trailing).is_err());
+    }
+
+    #[test]
+    fn serde_rejects_inconsistent_lengths() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let proof =
+            RangeProof::create_with_params(4, 42, &DEFAULT_PARAMS, &mut csprng).unwrap().into_proof();
+        let mut truncated = proof;
+        truncated.s_2.pop();
+
+        let bytes = serde_cbor::to_vec(&truncated).unwrap();
+        assert!(serde_cbor::from_slice::<RangeProof>(&bytes).is_err());
+    }
+
+    #[test]
+    fn serde_rejects_too_many_rings() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let proof =
+            RangeProof::create_with_params(1, 2, &DEFAULT_PARAMS, &mut csprng).unwrap().into_proof();
+        let mut json = serde_json::to_value(&proof).unwrap();
+        for field in ["C", "s_1", "s_2"].iter() {
+            let entry = json[*field][0].clone();
+            *json[*field].as_array_mut().unwrap() = vec![entry; RANGEPROOF_MAX_N + 1];
+        }
+
+        let error = serde_json::from_value::<RangeProof>(json).err().unwrap();
+        assert!(error.to_string().contains(&DecodingError::LimitExceeded.to_string()));
+    }
+
+    #[test]
+    fn serde_json_uses_hex_fields() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let n = 4;
+        let (proof, commitment, _) =
+            RangeProof::create_with_params(n, 42, &DEFAULT_PARAMS, &mut csprng).unwrap().into_parts();
+
+        let json = serde_json::to_value(&proof).unwrap();
+        assert_eq!(json["e_0"].as_str().unwrap(), to_hex(proof.e_0.as_bytes()));
+        assert_eq!(json["C"][3].as_str().unwrap(), to_hex(proof.C[3].compress().as_bytes()));
+        assert_eq!(json["s_2"].as_array().unwrap().len(), n);
+
+        let decoded: RangeProof = serde_json::from_value(json).unwrap();
+        assert_eq!(decoded.verify_with_params(n, &DEFAULT_PARAMS).unwrap().compress(),
+                   commitment.compress());
+
+        assert_eq!(from_hex_32("00").err(), Some(DecodingError::InvalidHex));
+        assert_eq!(from_hex_32(&"zz".repeat(32)).err(), Some(DecodingError::InvalidHex));
+    }
+
+    #[test]
+    fn binary_formats_use_raw_bytes() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let n = 40;
+        let proof =
+            RangeProof::create_with_params(n, 134492616741, &DEFAULT_PARAMS, &mut csprng).unwrap().into_proof();
+
+        // Each point and scalar is a 2-byte CBOR header and 32 raw bytes.
+        let bytes = serde_cbor::ser::to_vec_packed(&proof).unwrap();
+        assert_eq!(bytes.len(), 1 + 4 + 34 + 3 * (2 + 34 * n));
+
+        // A human-readable encoding of a non-canonical scalar is rejected.
+        let mut json = serde_json::to_value(&proof).unwrap();
+        json["e_0"] = serde_json::Value::String("ff".repeat(32));
+        assert!(serde_json::from_value::<RangeProof>(json).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "postcard")]
+    fn postcard_roundtrip() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let n = 16;
+        let (proof, commitment, _) =
+            RangeProof::create_with_params(n, 13449261, &DEFAULT_PARAMS, &mut csprng).unwrap().into_parts();
+
+        let mut bytes = proof.to_postcard();
+        let decoded = RangeProof::from_postcard(&bytes).unwrap();
+        assert_eq!(decoded.verify_with_params(n, &DEFAULT_PARAMS).unwrap().compress(),
+                   commitment.compress());
+
+        bytes.push(0);
+        assert_eq!(RangeProof::from_postcard(&bytes).err(), Some(DecodingError::TrailingBytes));
+
+        let mut bytes = commitment_to_postcard(&commitment);
+        let C = commitment_from_postcard(&bytes).unwrap();
+        assert_eq!(C.compress(), commitment.compress());
+
+        // The length prefix 32 padded to two varint bytes decodes to the
+        // same commitment, but is not its canonical encoding.
+        let mut padded = vec![0x80 | bytes[0], 0x00];
+        padded.extend_from_slice(&bytes[1..]);
+        assert_eq!(commitment_from_postcard(&padded).err(), Some(DecodingError::InvalidPostcard));
+
+        let len = bytes.len();
+        bytes[len - 32..].copy_from_slice(&[0xff; 32]);
+        assert_eq!(commitment_from_postcard(&bytes).err(), Some(DecodingError::InvalidPoint));
+
+        bytes.push(0);
+        assert_eq!(commitment_from_postcard(&bytes).err(), Some(DecodingError::TrailingBytes));
+    }
+}
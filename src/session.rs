@@ -0,0 +1,284 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Long-lived prover and verifier sessions.
+//!
+//! A `Prover` or `Verifier` holds a reference to precomputed
+//! `RangeProofParams` along with its configuration, so that an
+//! application which creates or verifies many proofs sets them up
+//! once and then only passes the values or proofs.
+//!
+//! A `CappedProver` additionally tracks the total of the values it has
+//! proven, and refuses to prove any value which would take the total
+//! past a configured cap, for issuance-limited assets whose proving
+//! service is where the limit is enforced.
+
+use rand::Rng;
+
+use errors::{CapError, RangeProofError, VerifyError};
+use output::ProverOutput;
+use params::RangeProofParams;
+use verification::VerifiedCommitment;
+
+use super::RangeProof;
+use super::RANGEPROOF_MAX_N;
+
+/// Creates rangeproofs for the range `[0,3^n]` with fixed parameters.
+///
+/// By default, proofs are for `n = RANGEPROOF_MAX_N`, so that every
+/// `u64` is in range, and are created in constant time.
+pub struct Prover<'a> {
+    params: &'a RangeProofParams,
+    n: usize,
+    vartime: bool,
+}
+
+impl<'a> Prover<'a> {
+    /// Construct a `Prover` with the precomputed `params`.
+    pub fn new(params: &'a RangeProofParams) -> Prover<'a> {
+        Prover { params: params, n: RANGEPROOF_MAX_N, vartime: false }
+    }
+
+    /// Create proofs for the range `[0,3^n]`.
+    ///
+    /// # Returns
+    ///
+    /// A `RangeProofError::InvalidRingCount` if `n > RANGEPROOF_MAX_N`.
+    pub fn n(mut self, n: usize) -> Result<Prover<'a>, RangeProofError> {
+        RangeProof::check_n(n)?;
+        self.n = n;
+        Ok(self)
+    }
+
+    /// Create proofs in variable time, which is faster but leaks the
+    /// value through timing side-channels.
+    pub fn vartime(mut self, vartime: bool) -> Prover<'a> {
+        self.vartime = vartime;
+        self
+    }
+
+    /// Construct a rangeproof for `value`.
+    ///
+    /// # Returns
+    ///
+    /// As for `RangeProof::create`.
+    pub fn prove<T: Rng>(
+        &self,
+        value: u64,
+        csprng: &mut T,
+    ) -> Option<ProverOutput> {
+        let G = self.params.G();
+        let powers = self.params.H_powers();
+
+        if self.vartime {
+            RangeProof::create_vartime_with_powers(self.n, value, G, powers, csprng).ok()
+        } else {
+            RangeProof::create_with_powers(self.n, value, G, powers, csprng).ok()
+        }
+    }
+
+    /// Construct rangeproofs for each of the `values`, in constant
+    /// time, in parallel if the `parallel` feature is enabled.
+    ///
+    /// # Returns
+    ///
+    /// As for `RangeProof::create_many`.
+    pub fn prove_many<T: Rng>(
+        &self,
+        values: &[u64],
+        csprng: &mut T,
+    ) -> Option<Vec<ProverOutput>> {
+        RangeProof::create_many_with_powers(self.n, values, self.params.G(),
+                                            self.params.H_powers(), csprng)
+    }
+}
+
+/// A `Prover` which refuses to prove values totalling more than a cap.
+pub struct CappedProver<'a> {
+    prover: Prover<'a>,
+    cap: u64,
+    proven: u64,
+}
+
+impl<'a> CappedProver<'a> {
+    /// Construct a `CappedProver` proving with `prover` values which
+    /// total at most `cap`.
+    pub fn new(prover: Prover<'a>, cap: u64) -> CappedProver<'a> {
+        CappedProver { prover: prover, cap: cap, proven: 0 }
+    }
+
+    /// Construct a rangeproof for `value`, and add it to the total.
+    ///
+    /// # Returns
+    ///
+    /// A `CapError::CapExceeded` if the total would exceed the cap, or
+    /// a `CapError::OutOfRange` if `value` isn't in range for the
+    /// prover's `n`, in which case the total is unchanged.
+    ///
+    /// Otherwise, as for `Prover::prove`.
+    pub fn prove<T: Rng>(
+        &mut self,
+        value: u64,
+        csprng: &mut T,
+    ) -> Result<ProverOutput, CapError> {
+        self.check(&[value])?;
+        let output = self.prover.prove(value, csprng).expect("value was checked to be in range");
+        self.proven += value;
+
+        Ok(output)
+    }
+
+    /// Construct rangeproofs for each of the `values`, and add them to
+    /// the total, as for `Prover::prove_many`.
+    ///
+    /// # Returns
+    ///
+    /// As for `prove`, where either all of the `values` are proven and
+    /// added to the total, or none are.
+    pub fn prove_many<T: Rng>(
+        &mut self,
+        values: &[u64],
+        csprng: &mut T,
+    ) -> Result<Vec<ProverOutput>, CapError> {
+        let total = self.check(values)?;
+        let outputs = self.prover.prove_many(values, csprng).expect("values were checked to be in range");
+        self.proven = total;
+
+        Ok(outputs)
+    }
+
+    /// Check that each of the `values` is in range and that they fit
+    /// under the cap, returning the new total.
+    fn check(&self, values: &[u64]) -> Result<u64, CapError> {
+        let mut total = self.proven as u128;
+        for &value in values.iter() {
+            RangeProof::check_range(self.prover.n, value)?;
+            total += value as u128;
+            if total > self.cap as u128 {
+                return Err(CapError::CapExceeded { value: value, proven: self.proven, cap: self.cap });
+            }
+        }
+        Ok(total as u64)
+    }
+
+    /// The total of the values proven so far.
+    pub fn proven(&self) -> u64 {
+        self.proven
+    }
+
+    /// The cap on the total.
+    pub fn cap(&self) -> u64 {
+        self.cap
+    }
+
+    /// The largest value which can still be proven.
+    pub fn remaining(&self) -> u64 {
+        self.cap - self.proven
+    }
+}
+
+/// Verifies rangeproofs for the range `[0,3^n]` with fixed parameters.
+///
+/// By default, proofs are expected to be for `n = RANGEPROOF_MAX_N`.
+pub struct Verifier<'a> {
+    params: &'a RangeProofParams,
+    n: usize,
+}
+
+impl<'a> Verifier<'a> {
+    /// Construct a `Verifier` with the precomputed `params`.
+    pub fn new(params: &'a RangeProofParams) -> Verifier<'a> {
+        Verifier { params: params, n: RANGEPROOF_MAX_N }
+    }
+
+    /// Accept only proofs for the range `[0,3^n]`.
+    ///
+    /// # Returns
+    ///
+    /// A `RangeProofError::InvalidRingCount` if `n > RANGEPROOF_MAX_N`.
+    pub fn n(mut self, n: usize) -> Result<Verifier<'a>, RangeProofError> {
+        RangeProof::check_n(n)?;
+        self.n = n;
+        Ok(self)
+    }
+
+    /// Verify the `proof`, returning the commitment to the in-range
+    /// value if successful.
+    pub fn verify(&self, proof: &RangeProof) -> Result<VerifiedCommitment, VerifyError> {
+        proof.verify_checked(self.n, self.params)
+    }
+
+    /// Verify each of the `proofs`, as for `RangeProof::verify_batch_each`.
+    #[must_use]
+    pub fn verify_each(&self, proofs: &[RangeProof]) -> Vec<Result<VerifiedCommitment, VerifyError>> {
+        RangeProof::verify_batch_each(self.n, proofs, self.params)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::OsRng;
+
+    use DEFAULT_PARAMS;
+
+    #[test]
+    fn prover_and_verifier_sessions() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let prover = Prover::new(&DEFAULT_PARAMS).n(16).unwrap();
+        let verifier = Verifier::new(&DEFAULT_PARAMS).n(16).unwrap();
+
+        let (proof, commitment, _) = prover.prove(13449261, &mut csprng).unwrap().into_parts();
+        assert_eq!(verifier.verify(&proof).unwrap().commitment().compress(),
+                   commitment.compress());
+
+        let (proof, commitment, _) = prover.vartime(true).prove(13449261, &mut csprng).unwrap().into_parts();
+        assert_eq!(verifier.verify(&proof).unwrap().commitment().compress(),
+                   commitment.compress());
+
+        let outputs = Prover::new(&DEFAULT_PARAMS).prove_many(&[0, 1, u64::max_value()], &mut csprng).unwrap();
+        let proofs: Vec<RangeProof> = outputs.into_iter().map(ProverOutput::into_proof).collect();
+        assert!(Verifier::new(&DEFAULT_PARAMS).verify_each(&proofs).iter().all(|result| result.is_ok()));
+        assert_eq!(verifier.verify(&proofs[0]).err(), Some(VerifyError::WrongRingCount));
+
+        assert_eq!(Prover::new(&DEFAULT_PARAMS).n(RANGEPROOF_MAX_N + 1).err(),
+                   Some(RangeProofError::InvalidRingCount(RANGEPROOF_MAX_N + 1)));
+        assert_eq!(Verifier::new(&DEFAULT_PARAMS).n(RANGEPROOF_MAX_N + 1).err(),
+                   Some(RangeProofError::InvalidRingCount(RANGEPROOF_MAX_N + 1)));
+    }
+
+    #[test]
+    fn capped_prover_enforces_cap() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let mut prover = CappedProver::new(Prover::new(&DEFAULT_PARAMS).n(8).unwrap(), 1000);
+        assert!(prover.prove(600, &mut csprng).is_ok());
+        assert!(prover.prove_many(&[100, 200], &mut csprng).is_ok());
+        assert_eq!(prover.proven(), 900);
+
+        assert_eq!(prover.prove(101, &mut csprng).err(),
+                   Some(CapError::CapExceeded { value: 101, proven: 900, cap: 1000 }));
+        assert!(prover.prove_many(&[50, 51], &mut csprng).is_err());
+        match prover.prove(6561, &mut csprng) {
+            Err(CapError::OutOfRange(e)) => assert_eq!(e.max, 6560),
+            _ => panic!("expected an out of range error"),
+        }
+        assert_eq!(prover.remaining(), 100);
+
+        let (proof, C, _) = prover.prove(100, &mut csprng).unwrap().into_parts();
+        assert_eq!(Verifier::new(&DEFAULT_PARAMS).n(8).unwrap().verify(&proof).unwrap().commitment().compress(),
+                   C.compress());
+        assert_eq!(prover.remaining(), 0);
+    }
+}
@@ -0,0 +1,163 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Rangeproofs for signed values.
+//!
+//! A signed value `v` in `[-3^n, 3^n]` is proven by proving that
+//! `v + 3^n` is in `[0, 2*3^n]` with a `TwoSidedRangeProof`, and
+//! removing the offset from the commitment on both sides:  the
+//! prover's commitment and the verifier's output are both commitments
+//! to `v` itself.  The offset never has to be handled by the caller.
+
+use rand::Rng;
+
+use curve25519_dalek::decaf::DecafPoint;
+
+use blinding::SecretBlinding;
+use errors::DecodingError;
+use params::RangeProofParams;
+use two_sided::TwoSidedRangeProof;
+
+/// The maximum bound `n` for signed rangeproofs, so that `v + 3^n`
+/// always fits in a `u64`.
+pub const SIGNED_RANGEPROOF_MAX_N: usize = 39;
+
+/// A rangeproof that a committed signed value `v` satisfies
+/// `-3^n <= v <= 3^n`.
+pub struct SignedRangeProof {
+    proof: TwoSidedRangeProof,
+}
+
+impl SignedRangeProof {
+    /// Construct a proof for the signed `value`, in constant time.
+    ///
+    /// # Inputs
+    ///
+    /// * `n`, so that the range is `[-3^n,3^n]` with
+    /// `n <= SIGNED_RANGEPROOF_MAX_N`;
+    /// * The `value` to prove within range `[-3^n,3^n]`;
+    /// * The precomputed `params`;
+    /// * `csprng`, an implementation of `rand::Rng`, which should be
+    /// cryptographically secure.
+    ///
+    /// # Returns
+    ///
+    /// If `n > SIGNED_RANGEPROOF_MAX_N`, or `value` is not in the range
+    /// `[-3^n,3^n]`, return None.
+    ///
+    /// Otherwise, returns `Some((proof, commitment, blinding))`, where
+    /// `commitment = blinding*G + value*H`.
+    pub fn create<T: Rng>(
+        n: usize,
+        value: i64,
+        params: &RangeProofParams,
+        csprng: &mut T,
+    ) -> Option<(SignedRangeProof, DecafPoint, SecretBlinding)> {
+        if n > SIGNED_RANGEPROOF_MAX_N {
+            return None;
+        }
+
+        let offset = 3i128.pow(n as u32);
+        let shifted = value as i128 + offset;
+        if shifted < 0 || shifted > 2 * offset {
+            return None;
+        }
+
+        let (proof, commitment, blinding) = TwoSidedRangeProof::prove_between(
+            0, 2 * offset as u64, shifted as u64, params, csprng)?;
+        let commitment = &commitment - params.H_powers().power(n);
+
+        Some((SignedRangeProof { proof: proof }, commitment, blinding))
+    }
+
+    /// Verify the proof, returning a Pedersen commitment to the signed
+    /// value if successful.
+    ///
+    /// Returns None if `n > SIGNED_RANGEPROOF_MAX_N`.
+    #[must_use]
+    pub fn verify(&self, n: usize, params: &RangeProofParams) -> Option<DecafPoint> {
+        if n > SIGNED_RANGEPROOF_MAX_N {
+            return None;
+        }
+
+        let offset = 3u64.pow(n as u32);
+        self.proof.verify_between(0, 2 * offset, params)
+            .map(|C| &C - params.H_powers().power(n))
+    }
+
+    /// Encode the proof as for `TwoSidedRangeProof::to_bytes`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.proof.to_bytes()
+    }
+
+    /// Decode a proof encoded by `to_bytes`, as for
+    /// `TwoSidedRangeProof::from_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<SignedRangeProof, DecodingError> {
+        Ok(SignedRangeProof { proof: TwoSidedRangeProof::from_bytes(bytes)? })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::OsRng;
+
+    use curve25519_dalek::scalar::Scalar;
+
+    use DEFAULT_PARAMS;
+
+    #[test]
+    fn prove_and_verify_signed() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let n = 10;
+        let bound = 59049;
+        for &value in [-bound, -13449, -1, 0, 1, 42, bound].iter() {
+            let (proof, commitment, blinding) =
+                SignedRangeProof::create(n, value, &DEFAULT_PARAMS, &mut csprng).unwrap();
+            let C = proof.verify(n, &DEFAULT_PARAMS).unwrap();
+            assert_eq!(C.compress(), commitment.compress());
+
+            let magnitude = DEFAULT_PARAMS.H() * &Scalar::from_u64(value.abs() as u64);
+            let value_H = if value < 0 { -&magnitude } else { magnitude };
+            let C_hat = &(DEFAULT_PARAMS.G() * blinding.expose_secret()) + &value_H;
+            assert_eq!(C.compress(), C_hat.compress());
+        }
+
+        assert!(SignedRangeProof::create(n, bound + 1, &DEFAULT_PARAMS, &mut csprng).is_none());
+        assert!(SignedRangeProof::create(n, -bound - 1, &DEFAULT_PARAMS, &mut csprng).is_none());
+        assert!(SignedRangeProof::create(SIGNED_RANGEPROOF_MAX_N, i64::min_value(),
+                                         &DEFAULT_PARAMS, &mut csprng).is_none());
+        assert!(SignedRangeProof::create(SIGNED_RANGEPROOF_MAX_N + 1, 0,
+                                         &DEFAULT_PARAMS, &mut csprng).is_none());
+    }
+
+    #[test]
+    fn signed_encoding_roundtrip() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let n = 5;
+        let (proof, commitment, _) =
+            SignedRangeProof::create(n, -200, &DEFAULT_PARAMS, &mut csprng).unwrap();
+        let bytes = proof.to_bytes();
+
+        let decoded = SignedRangeProof::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.to_bytes(), bytes);
+        assert_eq!(decoded.verify(n, &DEFAULT_PARAMS).unwrap().compress(), commitment.compress());
+        assert!(decoded.verify(n + 1, &DEFAULT_PARAMS).is_none());
+
+        let mut long = bytes.clone();
+        long.push(0);
+        assert_eq!(SignedRangeProof::from_bytes(&long).err(), Some(DecodingError::TrailingBytes));
+    }
+}
@@ -0,0 +1,108 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! `sqlx` column types, with the `sqlx` feature.
+//!
+//! A `RangeProof` is stored as the binary column (`BYTEA` in Postgres,
+//! `BLOB` in MySQL and SQLite) of its canonical encoding
+//! `RangeProof::to_bytes`, and a `PedersenCommitment` or
+//! `CompressedCommitment` as the 32 bytes of its compressed point.  The
+//! impls are generic over the database, so they work with whichever
+//! drivers the application enables in its own `sqlx` dependency.
+//!
+//! Decoding a column checks it exactly as `RangeProof::from_bytes` and
+//! `commitments::from_bytes` do, so a row holding a malformed proof or
+//! commitment is a decoding error rather than a value which panics or
+//! fails to verify later.  The number of rings isn't stored: as for
+//! any proof, the verifier supplies it.
+
+use sqlx::database::{Database, HasArguments, HasValueRef};
+use sqlx::decode::Decode;
+use sqlx::encode::{Encode, IsNull};
+use sqlx::error::BoxDynError;
+use sqlx::types::Type;
+
+use commitments::{self, CompressedCommitment, PedersenCommitment};
+
+use super::RangeProof;
+
+impl<DB: Database> Type<DB> for RangeProof where Vec<u8>: Type<DB> {
+    fn type_info() -> DB::TypeInfo {
+        <Vec<u8> as Type<DB>>::type_info()
+    }
+
+    fn compatible(ty: &DB::TypeInfo) -> bool {
+        <Vec<u8> as Type<DB>>::compatible(ty)
+    }
+}
+
+impl<'q, DB: Database> Encode<'q, DB> for RangeProof where Vec<u8>: Encode<'q, DB> {
+    fn encode_by_ref(&self, buf: &mut <DB as HasArguments<'q>>::ArgumentBuffer) -> IsNull {
+        self.to_bytes().encode(buf)
+    }
+}
+
+impl<'r, DB: Database> Decode<'r, DB> for RangeProof where &'r [u8]: Decode<'r, DB> {
+    fn decode(value: <DB as HasValueRef<'r>>::ValueRef) -> Result<RangeProof, BoxDynError> {
+        let bytes = <&[u8] as Decode<DB>>::decode(value)?;
+
+        Ok(RangeProof::from_bytes(bytes)?)
+    }
+}
+
+impl<DB: Database> Type<DB> for PedersenCommitment where Vec<u8>: Type<DB> {
+    fn type_info() -> DB::TypeInfo {
+        <Vec<u8> as Type<DB>>::type_info()
+    }
+
+    fn compatible(ty: &DB::TypeInfo) -> bool {
+        <Vec<u8> as Type<DB>>::compatible(ty)
+    }
+}
+
+impl<'q, DB: Database> Encode<'q, DB> for PedersenCommitment where Vec<u8>: Encode<'q, DB> {
+    fn encode_by_ref(&self, buf: &mut <DB as HasArguments<'q>>::ArgumentBuffer) -> IsNull {
+        commitments::to_bytes(self.as_point()).to_vec().encode(buf)
+    }
+}
+
+impl<'r, DB: Database> Decode<'r, DB> for PedersenCommitment where &'r [u8]: Decode<'r, DB> {
+    fn decode(value: <DB as HasValueRef<'r>>::ValueRef) -> Result<PedersenCommitment, BoxDynError> {
+        let bytes = <&[u8] as Decode<DB>>::decode(value)?;
+
+        Ok(PedersenCommitment::new(commitments::from_bytes(bytes)?))
+    }
+}
+
+impl<DB: Database> Type<DB> for CompressedCommitment where Vec<u8>: Type<DB> {
+    fn type_info() -> DB::TypeInfo {
+        <Vec<u8> as Type<DB>>::type_info()
+    }
+
+    fn compatible(ty: &DB::TypeInfo) -> bool {
+        <Vec<u8> as Type<DB>>::compatible(ty)
+    }
+}
+
+impl<'q, DB: Database> Encode<'q, DB> for CompressedCommitment where Vec<u8>: Encode<'q, DB> {
+    fn encode_by_ref(&self, buf: &mut <DB as HasArguments<'q>>::ArgumentBuffer) -> IsNull {
+        self.as_bytes().to_vec().encode(buf)
+    }
+}
+
+impl<'r, DB: Database> Decode<'r, DB> for CompressedCommitment where &'r [u8]: Decode<'r, DB> {
+    fn decode(value: <DB as HasValueRef<'r>>::ValueRef) -> Result<CompressedCommitment, BoxDynError> {
+        let bytes = <&[u8] as Decode<DB>>::decode(value)?;
+
+        Ok(CompressedCommitment::from(&commitments::from_bytes(bytes)?))
+    }
+}
@@ -0,0 +1,193 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Capturing and replaying the Fiat-Shamir transcript of a proof.
+//!
+//! A `Trace` records, in order, every input hashed to a challenge and
+//! every challenge derived while verifying a proof:
+//!
+//! ```text
+//! for each ring i:
+//!     absorb P_1,i; challenge e_1,i
+//!     absorb P_2,i; challenge e_2,i
+//!     absorb R_i
+//! challenge e_0
+//! ```
+//!
+//! For a valid proof this is exactly the prover's transcript, since
+//! the prover derives the same challenges from the same points.  With
+//! the `serde` feature, a trace serializes with serde, so that one
+//! produced by another implementation can be replayed against a proof
+//! here with `Trace::replay`, which reports the first event at which
+//! the two transcripts disagree.
+
+#[cfg(feature = "schemars")]
+use schemars;
+
+use curve25519_dalek::scalar::Scalar;
+
+use challenges::e_0_challenge;
+use params::RangeProofParams;
+
+use super::RangeProof;
+use super::RANGEPROOF_MAX_N;
+
+/// A single event in a transcript.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub enum TraceEvent {
+    /// Bytes absorbed into the hash for the next challenge.
+    Absorb {
+        /// What the bytes are, e.g. `"P_1,3"`.
+        label: String,
+        /// The absorbed bytes.
+        bytes: Vec<u8>,
+    },
+    /// A derived challenge.
+    Challenge {
+        /// Which challenge this is, e.g. `"e_1,3"`.
+        label: String,
+        /// The challenge.
+        #[cfg_attr(feature = "serde", serde(with = "::serialization::scalar"))]
+        #[cfg_attr(feature = "schemars", schemars(with = "::schema::Hex32"))]
+        value: Scalar,
+    },
+}
+
+/// The first point at which a trace disagrees with a proof.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TraceMismatch {
+    /// The index of the event in the trace.
+    pub index: usize,
+    /// The event recomputed from the proof, if the proof's transcript
+    /// is that long.
+    pub expected: Option<TraceEvent>,
+    /// The event in the trace, if the trace is that long.
+    pub found: Option<TraceEvent>,
+}
+
+/// The transcript of a proof, as a sequence of events.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schemars", derive(JsonSchema))]
+pub struct Trace {
+    /// The events, in order.
+    pub events: Vec<TraceEvent>,
+}
+
+impl Trace {
+    fn absorb(&mut self, label: String, bytes: &[u8]) {
+        self.events.push(TraceEvent::Absorb { label: label, bytes: bytes.to_vec() });
+    }
+
+    fn challenge(&mut self, label: String, value: Scalar) {
+        self.events.push(TraceEvent::Challenge { label: label, value: value });
+    }
+
+    /// Record the transcript of verifying `proof` with `n` rings.
+    ///
+    /// The transcript is recorded whether or not the proof is valid;
+    /// it's empty if `n > RANGEPROOF_MAX_N` or the proof doesn't have
+    /// `n` rings.
+    pub fn capture(proof: &RangeProof, n: usize, params: &RangeProofParams) -> Trace {
+        let mut trace = Trace::default();
+        if n > RANGEPROOF_MAX_N || proof.n() != n {
+            return trace;
+        }
+
+        let mut R = Vec::with_capacity(n);
+        for i in 0..n {
+            let ring = proof.recompute_ring(i, params.G(), params.H_powers());
+            trace.absorb(format!("P_1,{}", i), ring.P_1.as_bytes());
+            trace.challenge(format!("e_1,{}", i), ring.e_1);
+            trace.absorb(format!("P_2,{}", i), ring.P_2.as_bytes());
+            trace.challenge(format!("e_2,{}", i), ring.e_2);
+            trace.absorb(format!("R_{}", i), ring.R.as_bytes());
+            R.push(ring.R);
+        }
+        trace.challenge("e_0".to_string(), e_0_challenge(R));
+
+        trace
+    }
+
+    /// Check this trace against the transcript of verifying `proof`
+    /// with `n` rings, returning the first event at which they differ.
+    pub fn replay(&self, proof: &RangeProof, n: usize, params: &RangeProofParams) -> Result<(), TraceMismatch> {
+        let expected = Trace::capture(proof, n, params);
+        let len = expected.events.len().max(self.events.len());
+
+        for index in 0..len {
+            let expected_event = expected.events.get(index);
+            let found_event = self.events.get(index);
+            if expected_event != found_event {
+                return Err(TraceMismatch {
+                    index: index,
+                    expected: expected_event.cloned(),
+                    found: found_event.cloned(),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::OsRng;
+    #[cfg(feature = "serde")]
+    use serde_json;
+
+    use DEFAULT_PARAMS;
+
+    #[test]
+    fn capture_and_replay_trace() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let proof =
+            RangeProof::create_with_params(4, 42, &DEFAULT_PARAMS, &mut csprng).unwrap().into_proof();
+        let trace = Trace::capture(&proof, 4, &DEFAULT_PARAMS);
+        assert_eq!(trace.events.len(), 5 * 4 + 1);
+        match trace.events[20] {
+            TraceEvent::Challenge { ref value, .. } => assert!(value == proof.e_0()),
+            _ => panic!("expected the e_0 challenge last"),
+        }
+
+        assert_eq!(trace.replay(&proof, 4, &DEFAULT_PARAMS), Ok(()));
+
+        let mut tampered = trace.clone();
+        tampered.events[7] = TraceEvent::Absorb { label: "P_2,1".to_string(), bytes: vec![0; 32] };
+        assert_eq!(tampered.replay(&proof, 4, &DEFAULT_PARAMS).err().unwrap().index, 7);
+
+        tampered.events.truncate(3);
+        let mismatch = tampered.replay(&proof, 4, &DEFAULT_PARAMS).err().unwrap();
+        assert_eq!((mismatch.index, mismatch.found), (3, None));
+
+        assert!(Trace::capture(&proof, RANGEPROOF_MAX_N + 1, &DEFAULT_PARAMS).events.is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn trace_serde_roundtrip() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let proof =
+            RangeProof::create_with_params(4, 42, &DEFAULT_PARAMS, &mut csprng).unwrap().into_proof();
+        let trace = Trace::capture(&proof, 4, &DEFAULT_PARAMS);
+
+        let json = serde_json::to_string(&trace).unwrap();
+        let decoded: Trace = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.replay(&proof, 4, &DEFAULT_PARAMS), Ok(()));
+    }
+}
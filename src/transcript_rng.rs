@@ -0,0 +1,286 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Synthetic nonce generation.
+//!
+//! The random scalars used by a prover are drawn from a `TranscriptRng`,
+//! a `ChaChaRng` seeded by hashing the public statement (the
+//! "transcript"), the secret witness, and fresh external entropy.  A
+//! broken or repeated external RNG then cannot cause nonces to repeat
+//! across different statements or witnesses, which would leak the
+//! witness.
+//!
+//! Omitting the external entropy gives deterministic proving: the
+//! nonces are then a function of the transcript and witness alone,
+//! which must include a secret for the proofs to be hiding.
+
+use std::ptr;
+use std::sync::atomic;
+
+use rand::{Rng, SeedableRng};
+use rand::chacha::ChaChaRng;
+
+use sha2::{Digest, Sha512};
+
+use curve25519_dalek::decaf::DecafBasepointTable;
+
+use blinding::zeroize;
+use output::ProverOutput;
+use params::PowersOfH;
+use params::RangeProofParams;
+
+use super::RangeProof;
+
+/// A domain separator for each kind of input to the transcript hash.
+const TRANSCRIPT: u8 = 0;
+const WITNESS: u8 = 1;
+const ENTROPY: u8 = 2;
+
+/// Accumulates the inputs to a `TranscriptRng`.
+pub struct TranscriptRngBuilder {
+    hash: Sha512,
+}
+
+impl TranscriptRngBuilder {
+    fn absorb(mut self, kind: u8, label: &[u8], bytes: &[u8]) -> TranscriptRngBuilder {
+        self.hash.input(&[kind]);
+        self.hash.input(&(label.len() as u64).to_le_bytes());
+        self.hash.input(label);
+        self.hash.input(&(bytes.len() as u64).to_le_bytes());
+        self.hash.input(bytes);
+        self
+    }
+
+    /// Bind the nonces to public `bytes`, such as the statement being
+    /// proven, under the given `label`.
+    pub fn commit_transcript(self, label: &[u8], bytes: &[u8]) -> TranscriptRngBuilder {
+        self.absorb(TRANSCRIPT, label, bytes)
+    }
+
+    /// Bind the nonces to secret `bytes`, such as the value being
+    /// proven, under the given `label`.
+    pub fn commit_witness(self, label: &[u8], bytes: &[u8]) -> TranscriptRngBuilder {
+        self.absorb(WITNESS, label, bytes)
+    }
+
+    /// Mix in 32 bytes of external entropy from `rng`, and construct
+    /// the `TranscriptRng`.
+    pub fn finalize<R: Rng>(self, rng: &mut R) -> TranscriptRng {
+        let mut entropy = [0u8; 32];
+        rng.fill_bytes(&mut entropy);
+
+        self.absorb(ENTROPY, b"rng", &entropy).finalize_deterministic()
+    }
+
+    /// Construct the `TranscriptRng` without any external entropy.
+    ///
+    /// # Note
+    ///
+    /// The output is entirely determined by the committed transcript
+    /// and witness, so the witness must contain enough secret entropy
+    /// for the nonces to be unpredictable.
+    pub fn finalize_deterministic(self) -> TranscriptRng {
+        let digest = self.hash.result();
+
+        let mut seed = [0u32; 8];
+        for i in 0..8 {
+            seed[i] = (digest[4*i] as u32)
+                   | ((digest[4*i + 1] as u32) << 8)
+                   | ((digest[4*i + 2] as u32) << 16)
+                   | ((digest[4*i + 3] as u32) << 24);
+        }
+
+        let mut key = [0u8; 64];
+        key.copy_from_slice(&digest[..]);
+
+        TranscriptRng { rng: ChaChaRng::from_seed(&seed[..]), key: key }
+    }
+}
+
+/// A `rand::Rng` for nonces, derived from a transcript, a witness and
+/// external entropy.
+///
+/// Its whole state, including the ChaCha state, the buffered keystream
+/// and the key from which its substreams are derived, is overwritten
+/// when it is dropped.
+pub struct TranscriptRng {
+    rng: ChaChaRng,
+    key: [u8; 64],
+}
+
+impl TranscriptRng {
+    /// Begin a new `TranscriptRng` for the protocol named by `label`.
+    pub fn builder(label: &[u8]) -> TranscriptRngBuilder {
+        TranscriptRngBuilder { hash: Sha512::default() }
+            .commit_transcript(b"dalek-rangeproofs transcript rng", label)
+    }
+
+    /// Derive the independent `index`th substream of this RNG.
+    ///
+    /// Substreams depend only on the inputs this RNG was constructed
+    /// from and on `index`, not on how much of this RNG or of other
+    /// substreams has been consumed, so that work split across
+    /// substreams may be done in any order with the same result.
+    pub fn substream(&self, index: u64) -> TranscriptRng {
+        TranscriptRng::builder(b"substream")
+            .commit_witness(b"key", &self.key[..])
+            .commit_transcript(b"index", &index.to_le_bytes())
+            .finalize_deterministic()
+    }
+
+    /// Begin the `TranscriptRng` for a rangeproof of `value` in
+    /// `[0,3^n]` with the basepoints `G` and `powers`.
+    pub(crate) fn for_proof(
+        n: usize,
+        value: u64,
+        G: &DecafBasepointTable,
+        powers: &PowersOfH,
+    ) -> TranscriptRngBuilder {
+        let mut builder = TranscriptRng::builder(b"rangeproof")
+            .commit_transcript(b"n", &[n as u8])
+            .commit_transcript(b"G", G.basepoint().compress().as_bytes());
+        if powers.len() > 0 {
+            builder = builder.commit_transcript(b"H", powers.power(0).compress().as_bytes());
+        }
+
+        builder.commit_witness(b"value", &value.to_le_bytes())
+    }
+}
+
+impl Rng for TranscriptRng {
+    fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.rng.fill_bytes(dest)
+    }
+}
+
+impl Drop for TranscriptRng {
+    fn drop(&mut self) {
+        zeroize(&mut self.key);
+        // The ChaCha state holds the key from which every nonce already
+        // drawn can be recomputed, so replace it with the unseeded state.
+        unsafe { ptr::write_volatile(&mut self.rng, ChaChaRng::new_unseeded()) };
+        atomic::compiler_fence(atomic::Ordering::SeqCst);
+    }
+}
+
+impl RangeProof {
+    /// Construct a rangeproof for `value` deterministically, in
+    /// constant time.
+    ///
+    /// # Inputs
+    ///
+    /// * `n`, so that the range is `[0,3^n]` with `n <= RANGEPROOF_MAX_N`;
+    /// * The `value` to prove within range `[0,3^n]`;
+    /// * The precomputed `params`;
+    /// * A `secret` from which the nonces and blinding are derived,
+    /// along with `n`, `value` and `params`.
+    ///
+    /// # Returns
+    ///
+    /// As for `RangeProof::create`.  Calling this twice with the same
+    /// inputs gives the same proof, commitment and blinding.
+    ///
+    /// # Note
+    ///
+    /// The `secret` should be at least 32 bytes of uniformly random
+    /// key material, which must never be revealed.  Reusing it for the
+    /// same `value` results in linkable commitments, so it should be
+    /// unique per proof unless that is intended.
+    pub fn create_deterministic(
+        n: usize,
+        value: u64,
+        params: &RangeProofParams,
+        secret: &[u8],
+    ) -> Option<ProverOutput> {
+        let mut rng = TranscriptRng::builder(b"deterministic")
+            .commit_witness(b"secret", secret)
+            .finalize_deterministic();
+
+        RangeProof::create_with_params(n, value, params, &mut rng)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::OsRng;
+
+    use DEFAULT_PARAMS;
+
+    #[test]
+    fn transcript_rng_is_bound_to_its_inputs() {
+        let a: Vec<u32> = TranscriptRng::builder(b"test")
+            .commit_witness(b"w", b"witness")
+            .finalize_deterministic()
+            .gen_iter().take(8).collect();
+        let b: Vec<u32> = TranscriptRng::builder(b"test")
+            .commit_witness(b"w", b"witness")
+            .finalize_deterministic()
+            .gen_iter().take(8).collect();
+        let c: Vec<u32> = TranscriptRng::builder(b"test")
+            .commit_transcript(b"w", b"witness")
+            .finalize_deterministic()
+            .gen_iter().take(8).collect();
+        assert_eq!(a, b);
+        assert!(a != c);
+
+        let mut csprng = OsRng::new().unwrap();
+        let d: Vec<u32> = TranscriptRng::builder(b"test")
+            .commit_witness(b"w", b"witness")
+            .finalize(&mut csprng)
+            .gen_iter().take(8).collect();
+        assert!(a != d);
+    }
+
+    #[test]
+    fn substreams_are_independent_of_consumption() {
+        let mut rng = TranscriptRng::builder(b"test")
+            .commit_witness(b"w", b"witness")
+            .finalize_deterministic();
+
+        let a: Vec<u32> = rng.substream(1).gen_iter().take(8).collect();
+        let _ = rng.next_u64();
+        let _ = rng.substream(0).next_u64();
+        let b: Vec<u32> = rng.substream(1).gen_iter().take(8).collect();
+        let c: Vec<u32> = rng.substream(2).gen_iter().take(8).collect();
+        assert_eq!(a, b);
+        assert!(a != c);
+    }
+
+    #[test]
+    fn create_deterministic_is_deterministic() {
+        let n = 16;
+        let secret = [7u8; 32];
+
+        let (proof, commitment, blinding) =
+            RangeProof::create_deterministic(n, 13449261, &DEFAULT_PARAMS, &secret).unwrap().into_parts();
+        let again =
+            RangeProof::create_deterministic(n, 13449261, &DEFAULT_PARAMS, &secret).unwrap().into_proof();
+        assert_eq!(proof.to_bytes(), again.to_bytes());
+        assert_eq!(proof.verify_with_params(n, &DEFAULT_PARAMS).unwrap().compress(),
+                   commitment.compress());
+
+        let (other, _, other_blinding) =
+            RangeProof::create_deterministic(n, 13449262, &DEFAULT_PARAMS, &secret).unwrap().into_parts();
+        assert!(proof.to_bytes() != other.to_bytes());
+        assert!(blinding.expose_secret() != other_blinding.expose_secret());
+    }
+}
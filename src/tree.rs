@@ -0,0 +1,162 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Rangeproofs with tree-hashed challenges.
+//!
+//! An ordinary rangeproof derives its challenge `e_0` by hashing the
+//! points `R_0, ..., R_{n-1}` closing each ring in sequence, so the
+//! hash can't begin until every ring is committed, and can't be split
+//! between threads.  A `TreeRangeProof` instead derives `e_0` with
+//! `e_0_tree_challenge`, which hashes the `R_i` in a binary tree whose
+//! levels can be hashed in parallel.  This matters for very large `n`,
+//! or for aggregating the rings of many proofs under one challenge.
+//!
+//! The proof has the same shape as a `RangeProof`, but a proof made in
+//! one mode never verifies in the other, so the encoding is prefixed
+//! with the version `TREE_CHALLENGE_VERSION` of the tree hash, and a
+//! future change to the tree hash will get a new version.
+
+use rand::Rng;
+
+use curve25519_dalek::decaf::DecafPoint;
+
+use blinding::SecretBlinding;
+use challenges::{e_0_tree_challenge, TREE_CHALLENGE_VERSION};
+use errors::DecodingError;
+use params::RangeProofParams;
+
+use super::RangeProof;
+
+/// A rangeproof whose challenge is derived by `e_0_tree_challenge`.
+pub struct TreeRangeProof {
+    proof: RangeProof,
+}
+
+impl TreeRangeProof {
+    /// Construct a rangeproof for `value` with a tree-hashed challenge,
+    /// in constant time.
+    ///
+    /// # Returns
+    ///
+    /// If `n > RANGEPROOF_MAX_N` or `value` is not in the range
+    /// `[0,3^n]`, return None.
+    ///
+    /// Otherwise, returns `Some((proof, commitment, blinding))`, where
+    /// `commitment = blinding*G + value*H`.
+    pub fn create<T: Rng>(
+        n: usize,
+        value: u64,
+        params: &RangeProofParams,
+        csprng: &mut T,
+    ) -> Option<(TreeRangeProof, DecafPoint, SecretBlinding)> {
+        let output = RangeProof::create_with_challenge(n, value, params.G(), params.H_powers(),
+                                                       e_0_tree_challenge, csprng).ok()?;
+        let (proof, commitment, blinding) = output.into_parts();
+
+        Some((TreeRangeProof { proof: proof }, commitment, blinding))
+    }
+
+    /// Verify the rangeproof, returning a Pedersen commitment to the
+    /// in-range value if successful.
+    ///
+    /// # Returns
+    ///
+    /// None if the proof doesn't verify, including if
+    /// `n > RANGEPROOF_MAX_N`.
+    #[must_use]
+    pub fn verify(&self, n: usize, params: &RangeProofParams) -> Option<DecafPoint> {
+        self.proof.check_with_challenge(n, params.G(), params.H_powers(), e_0_tree_challenge).ok()
+    }
+
+    /// The underlying proof, whose challenge is tree-hashed, and which
+    /// therefore doesn't verify as an ordinary rangeproof.
+    pub fn proof(&self) -> &RangeProof {
+        &self.proof
+    }
+
+    /// Encode the proof as `version || RangeProof::to_bytes`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(1 + self.proof.size_in_bytes());
+        bytes.push(TREE_CHALLENGE_VERSION);
+        bytes.extend_from_slice(&self.proof.to_bytes());
+        bytes
+    }
+
+    /// Decode a proof encoded by `to_bytes`.
+    ///
+    /// # Returns
+    ///
+    /// A `DecodingError::UnsupportedVersion` if the proof was made with
+    /// a version of the tree hash other than `TREE_CHALLENGE_VERSION`,
+    /// and otherwise as for `RangeProof::from_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<TreeRangeProof, DecodingError> {
+        match bytes.first() {
+            None => Err(DecodingError::InvalidLength),
+            Some(&TREE_CHALLENGE_VERSION) => {
+                Ok(TreeRangeProof { proof: RangeProof::from_bytes(&bytes[1..])? })
+            }
+            Some(&v) => Err(DecodingError::UnsupportedVersion(v)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::OsRng;
+
+    use curve25519_dalek::decaf::CompressedDecaf;
+
+    use DEFAULT_PARAMS;
+    use RANGEPROOF_MAX_N;
+
+    #[test]
+    fn tree_challenge_create_and_verify() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let n = 16;
+        let (proof, C, _) = TreeRangeProof::create(n, 13449261, &DEFAULT_PARAMS, &mut csprng).unwrap();
+        assert_eq!(proof.verify(n, &DEFAULT_PARAMS).unwrap().compress(), C.compress());
+        assert!(proof.verify(n - 1, &DEFAULT_PARAMS).is_none());
+        assert!(proof.verify(RANGEPROOF_MAX_N + 1, &DEFAULT_PARAMS).is_none());
+        assert!(TreeRangeProof::create(RANGEPROOF_MAX_N + 1, 0, &DEFAULT_PARAMS, &mut csprng).is_none());
+
+        // The modes are not interchangeable.
+        assert!(proof.proof().verify_with_params(n, &DEFAULT_PARAMS).is_none());
+        let sequential = RangeProof::create_with_params(n, 13449261, &DEFAULT_PARAMS, &mut csprng)
+            .unwrap().into_proof();
+        assert!(TreeRangeProof { proof: sequential }.verify(n, &DEFAULT_PARAMS).is_none());
+
+        let mut bytes = proof.to_bytes();
+        let decoded = TreeRangeProof::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.verify(n, &DEFAULT_PARAMS).unwrap().compress(), C.compress());
+
+        bytes[0] = TREE_CHALLENGE_VERSION + 1;
+        assert_eq!(TreeRangeProof::from_bytes(&bytes).err(),
+                   Some(DecodingError::UnsupportedVersion(TREE_CHALLENGE_VERSION + 1)));
+        assert_eq!(TreeRangeProof::from_bytes(&[]).err(), Some(DecodingError::InvalidLength));
+    }
+
+    #[test]
+    fn tree_challenge_depends_on_every_leaf() {
+        let R: Vec<CompressedDecaf> = (0..5u8).map(|i| CompressedDecaf([i; 32])).collect();
+        let e_0 = e_0_tree_challenge(&R);
+
+        for i in 0..R.len() {
+            let mut changed = R.clone();
+            changed[i] = CompressedDecaf([0xff; 32]);
+            assert!(e_0_tree_challenge(&changed) != e_0);
+        }
+        assert!(e_0_tree_challenge(&R[..4]) != e_0);
+    }
+}
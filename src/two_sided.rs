@@ -0,0 +1,314 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Rangeproofs for arbitrary intervals `[a, b]`.
+//!
+//! To prove that a value `v` is in `[a, b]`, the prover creates two
+//! ordinary rangeproofs with `n` rings, where `3^n > b - a`: one for
+//! `v - a`, with commitment `C_lower`, and one for `b - v`, with
+//! commitment `C_upper`.  Since the two values sum to `b - a`, the
+//! excess
+//!
+//! ```text
+//! X = C_lower + C_upper - (b - a)*H
+//! ```
+//!
+//! is a multiple of `G` alone, and the prover shows that it knows its
+//! discrete logarithm with a Schnorr proof `(K, s)`.  This binds the
+//! two values together: both are in `[0, 3^n)`, so neither can exceed
+//! `b - a`.  The commitment to `v` itself is `C_lower + a*H`.
+//!
+//! `RangeProof::create_in_range` and `RangeProof::verify_in_range` are
+//! the same operations, named for callers starting from an ordinary
+//! rangeproof.
+
+use rand::Rng;
+
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::decaf::DecafPoint;
+use curve25519_dalek::decaf::vartime;
+
+use blinding::SecretBlinding;
+use challenges::two_sided_challenge;
+use commitments;
+use encoding::{read_point, read_scalar, RING_LENGTH};
+use errors::DecodingError;
+use params::RangeProofParams;
+use transcript_rng::TranscriptRng;
+
+use super::RangeProof;
+use super::RANGEPROOF_MAX_N;
+
+/// The number of rings needed for values in `[0, width]`, i.e. the
+/// least `n >= 1` with `3^n > width`.
+pub(crate) fn rings_for_width(width: u64) -> usize {
+    let mut n = 1;
+    let mut bound = 3u128;
+    while bound <= width as u128 {
+        bound *= 3;
+        n += 1;
+    }
+    n
+}
+
+/// A proof that a committed value is in the interval `[a, b]`.
+pub struct TwoSidedRangeProof {
+    lower: RangeProof,
+    upper: RangeProof,
+    K: DecafPoint,
+    s: Scalar,
+}
+
+impl TwoSidedRangeProof {
+    /// Construct a proof that `value` is in `[a, b]`, in constant time.
+    ///
+    /// # Inputs
+    ///
+    /// * The bounds `a` and `b` of the interval, which are public;
+    /// * The `value` to prove within the interval;
+    /// * The precomputed `params`;
+    /// * `csprng`, an implementation of `rand::Rng`, which should be
+    /// cryptographically secure.
+    ///
+    /// # Returns
+    ///
+    /// If `a > b` or `value` is not in `[a, b]`, return None.
+    ///
+    /// Otherwise, returns `Some((proof, commitment, blinding))`, where
+    /// `commitment = blinding*G + value*H`.
+    pub fn prove_between<T: Rng>(
+        a: u64,
+        b: u64,
+        value: u64,
+        params: &RangeProofParams,
+        csprng: &mut T,
+    ) -> Option<(TwoSidedRangeProof, DecafPoint, SecretBlinding)> {
+        if a > b || value < a || value > b {
+            return None;
+        }
+        let n = rings_for_width(b - a);
+
+        let (lower, C_lower, r_lower) =
+            RangeProof::create_with_params(n, value - a, params, csprng)?.into_parts();
+        let (upper, C_upper, r_upper) =
+            RangeProof::create_with_params(n, b - value, params, csprng)?.into_parts();
+
+        // The excess X = C_lower + C_upper - (b-a)*H is x*G.
+        let x = r_lower.expose_secret() + r_upper.expose_secret();
+        let C_lower_bytes = C_lower.compress();
+        let C_upper_bytes = C_upper.compress();
+
+        let mut rng = TranscriptRng::builder(b"two-sided")
+            .commit_transcript(b"a", &a.to_le_bytes())
+            .commit_transcript(b"b", &b.to_le_bytes())
+            .commit_transcript(b"C_lower", C_lower_bytes.as_bytes())
+            .commit_transcript(b"C_upper", C_upper_bytes.as_bytes())
+            .commit_witness(b"x", x.as_bytes())
+            .finalize(csprng);
+        let k = Scalar::random(&mut rng);
+        let K = params.G() * &k;
+
+        let c = two_sided_challenge(a, b, &C_lower_bytes, &C_upper_bytes, &K.compress());
+        let s = Scalar::multiply_add(&c, &x, &k);
+
+        let commitment = commitments::add_value(&C_lower, a, params);
+
+        Some((TwoSidedRangeProof { lower: lower, upper: upper, K: K, s: s }, commitment, r_lower))
+    }
+
+    /// Verify that the proof is for the interval `[a, b]`, returning a
+    /// Pedersen commitment to the value if successful.
+    #[must_use]
+    pub fn verify_between(&self, a: u64, b: u64, params: &RangeProofParams) -> Option<DecafPoint> {
+        if a > b {
+            return None;
+        }
+        let n = rings_for_width(b - a);
+
+        let C_lower = self.lower.verify_with_params(n, params)?;
+        let C_upper = self.upper.verify_with_params(n, params)?;
+
+        let X = commitments::sub_value(&(&C_lower + &C_upper), b - a, params);
+        let K = self.K.compress();
+        let c = two_sided_challenge(a, b, &C_lower.compress(), &C_upper.compress(), &K);
+        let K_hat = vartime::k_fold_scalar_mult(&[self.s, -&c], &[params.G().basepoint(), X]);
+        if K_hat.compress() != K {
+            return None;
+        }
+
+        Some(commitments::add_value(&C_lower, a, params))
+    }
+
+    /// Encode the proof as `n || K || s || lower || upper`, where `n`
+    /// is the number of rings of each of the rangeproofs, as a single
+    /// byte, and `lower` and `upper` are encoded as for
+    /// `RangeProof::to_bytes`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(65 + self.lower.size_in_bytes() + self.upper.size_in_bytes());
+        bytes.push(self.lower.C.len() as u8);
+        bytes.extend_from_slice(self.K.compress().as_bytes());
+        bytes.extend_from_slice(self.s.as_bytes());
+        bytes.extend_from_slice(&self.lower.to_bytes());
+        bytes.extend_from_slice(&self.upper.to_bytes());
+        bytes
+    }
+
+    /// Decode a proof encoded by `to_bytes`.
+    ///
+    /// # Returns
+    ///
+    /// A `DecodingError::InvalidLength` if `n > RANGEPROOF_MAX_N` or
+    /// `bytes` is too short for `n`, a `DecodingError::TrailingBytes`
+    /// if it is too long, and otherwise as for `RangeProof::from_bytes`
+    /// if `K`, `s` or either rangeproof isn't canonically encoded.
+    pub fn from_bytes(bytes: &[u8]) -> Result<TwoSidedRangeProof, DecodingError> {
+        let n = *bytes.first().ok_or(DecodingError::InvalidLength)? as usize;
+        if n > RANGEPROOF_MAX_N {
+            return Err(DecodingError::InvalidLength);
+        }
+        let proof_length = 32 + RING_LENGTH * n;
+        let length = 65 + 2 * proof_length;
+        if bytes.len() < length {
+            return Err(DecodingError::InvalidLength);
+        } else if bytes.len() > length {
+            return Err(DecodingError::TrailingBytes);
+        }
+
+        Ok(TwoSidedRangeProof {
+            K: read_point(&bytes[1..33])?,
+            s: read_scalar(&bytes[33..65])?,
+            lower: RangeProof::from_bytes_exact(&bytes[65..65 + proof_length], n)?,
+            upper: RangeProof::from_bytes_exact(&bytes[65 + proof_length..], n)?,
+        })
+    }
+}
+
+impl RangeProof {
+    /// Construct a proof that `value` is in `[min, max]`, in constant
+    /// time, as for `TwoSidedRangeProof::prove_between`.
+    ///
+    /// The proof is a `TwoSidedRangeProof`, which proves `value - min`
+    /// and `max - value` in range with the least `n` such that
+    /// `3^n > max - min`.
+    ///
+    /// # Returns
+    ///
+    /// If `min > max` or `value` is not in `[min, max]`, return None.
+    ///
+    /// Otherwise, returns `Some((proof, commitment, blinding))`, where
+    /// `commitment = blinding*G + value*H` is a commitment to `value`
+    /// itself, not to the offset values.
+    pub fn create_in_range<T: Rng>(
+        min: u64,
+        max: u64,
+        value: u64,
+        params: &RangeProofParams,
+        csprng: &mut T,
+    ) -> Option<(TwoSidedRangeProof, DecafPoint, SecretBlinding)> {
+        TwoSidedRangeProof::prove_between(min, max, value, params, csprng)
+    }
+
+    /// Verify that `proof` is for the interval `[min, max]`, as for
+    /// `TwoSidedRangeProof::verify_between`, returning a Pedersen
+    /// commitment to the original value if successful.
+    #[must_use]
+    pub fn verify_in_range(
+        proof: &TwoSidedRangeProof,
+        min: u64,
+        max: u64,
+        params: &RangeProofParams,
+    ) -> Option<DecafPoint> {
+        proof.verify_between(min, max, params)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::OsRng;
+
+    use DEFAULT_PARAMS;
+
+    #[test]
+    fn rings_for_widths() {
+        assert_eq!(rings_for_width(0), 1);
+        assert_eq!(rings_for_width(2), 1);
+        assert_eq!(rings_for_width(3), 2);
+        assert_eq!(rings_for_width(999), 7);
+        assert_eq!(rings_for_width(u64::max_value()), 41);
+    }
+
+    #[test]
+    fn prove_and_verify_between() {
+        let mut csprng = OsRng::new().unwrap();
+
+        for &value in [1, 42, 1000].iter() {
+            let (proof, commitment, blinding) =
+                TwoSidedRangeProof::prove_between(1, 1000, value, &DEFAULT_PARAMS, &mut csprng).unwrap();
+            let C = proof.verify_between(1, 1000, &DEFAULT_PARAMS).unwrap();
+            assert_eq!(C.compress(), commitment.compress());
+
+            let C_hat = &(DEFAULT_PARAMS.G() * blinding.expose_secret())
+                + &(DEFAULT_PARAMS.H() * &Scalar::from_u64(value));
+            assert_eq!(C.compress(), C_hat.compress());
+
+            assert!(proof.verify_between(2, 1000, &DEFAULT_PARAMS).is_none());
+        }
+
+        assert!(TwoSidedRangeProof::prove_between(1, 1000, 0, &DEFAULT_PARAMS, &mut csprng).is_none());
+        assert!(TwoSidedRangeProof::prove_between(1, 1000, 1001, &DEFAULT_PARAMS, &mut csprng).is_none());
+    }
+
+    #[test]
+    fn create_and_verify_in_range() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let (proof, commitment, blinding) =
+            RangeProof::create_in_range(1000, 50000, 31337, &DEFAULT_PARAMS, &mut csprng).unwrap();
+        let C = RangeProof::verify_in_range(&proof, 1000, 50000, &DEFAULT_PARAMS).unwrap();
+        assert_eq!(C.compress(), commitment.compress());
+        assert_eq!(C.compress(), commitments::commit(31337, &blinding, &DEFAULT_PARAMS).compress());
+
+        assert!(RangeProof::verify_in_range(&proof, 1000, 50001, &DEFAULT_PARAMS).is_none());
+        assert!(RangeProof::verify_in_range(&proof, 50000, 1000, &DEFAULT_PARAMS).is_none());
+        assert!(RangeProof::create_in_range(1000, 50000, 999, &DEFAULT_PARAMS, &mut csprng).is_none());
+        assert!(RangeProof::create_in_range(50000, 1000, 31337, &DEFAULT_PARAMS, &mut csprng).is_none());
+    }
+
+    #[test]
+    fn two_sided_encoding_roundtrip() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let (proof, commitment, _) =
+            TwoSidedRangeProof::prove_between(1, 1000, 42, &DEFAULT_PARAMS, &mut csprng).unwrap();
+        let bytes = proof.to_bytes();
+        assert_eq!(bytes.len(), 65 + 2 * (32 + RING_LENGTH * 7));
+
+        let decoded = TwoSidedRangeProof::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.to_bytes(), bytes);
+        assert_eq!(decoded.verify_between(1, 1000, &DEFAULT_PARAMS).unwrap().compress(),
+                   commitment.compress());
+
+        let mut long = bytes.clone();
+        long.push(0);
+        assert_eq!(TwoSidedRangeProof::from_bytes(&long).err(), Some(DecodingError::TrailingBytes));
+        assert_eq!(TwoSidedRangeProof::from_bytes(&bytes[..bytes.len() - 1]).err(),
+                   Some(DecodingError::InvalidLength));
+        assert_eq!(TwoSidedRangeProof::from_bytes(&[]).err(), Some(DecodingError::InvalidLength));
+
+        let mut non_canonical = bytes.clone();
+        non_canonical[64] = 0xff;
+        assert_eq!(TwoSidedRangeProof::from_bytes(&non_canonical).err(),
+                   Some(DecodingError::NonCanonicalScalar));
+    }
+}
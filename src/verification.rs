@@ -0,0 +1,365 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Verifying many rangeproofs at once, with a result for each.
+//!
+//! # Determinism
+//!
+//! The result of verifying a proof depends only on the proof, `n` and
+//! the parameters, and the batch functions here always return results
+//! in the order of the proofs.  With the `parallel` feature, the proofs
+//! are verified on `rayon`'s thread pool, but the results are
+//! collected by index, so they are bit-identical to those of verifying
+//! sequentially, however the threads are scheduled.  This makes them
+//! safe to use in consensus-critical validation, where nodes which
+//! disagree about a batch would fork.
+//!
+//! The exception is `verify_batch_each_until`, since when cancellation
+//! fires depends on timing; it shouldn't be used where every node must
+//! reach the same result.
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use curve25519_dalek::decaf::{DecafBasepointTable, DecafPoint};
+
+use commitments::PedersenCommitment;
+use ct;
+use errors::{RangeProofError, VerifyError};
+use params::PowersOfH;
+
+use super::RangeProof;
+use super::RangeProofParams;
+use super::RANGEPROOF_MAX_N;
+
+/// A Pedersen commitment which a rangeproof has been verified to prove
+/// is to a value in range.
+#[derive(Clone, Copy, Debug)]
+#[must_use = "a verified commitment should be checked against the expected commitment"]
+pub struct VerifiedCommitment(pub(crate) DecafPoint);
+
+impl VerifiedCommitment {
+    /// The commitment to the in-range value.
+    pub fn commitment(&self) -> &DecafPoint {
+        &self.0
+    }
+
+    /// The commitment to the in-range value, as a `PedersenCommitment`.
+    pub fn pedersen_commitment(&self) -> PedersenCommitment {
+        PedersenCommitment::new(self.0)
+    }
+
+    /// Returns `true` if the proof proves `commitment`.
+    #[must_use]
+    pub fn proves(&self, commitment: &PedersenCommitment) -> bool {
+        ct::points_equal(&self.0, commitment.as_point()) == 1
+    }
+
+    /// Consume the `VerifiedCommitment`, returning the commitment.
+    pub fn into_commitment(self) -> DecafPoint {
+        self.0
+    }
+}
+
+/// A rangeproof which has been verified for the range `[0,3^n]`,
+/// together with the commitment it proves is to a value in range.
+///
+/// A `VerifiedProof` can only be obtained by verifying, so code which
+/// holds one never operates on a parsed but unverified proof.
+#[must_use]
+pub struct VerifiedProof {
+    proof: RangeProof,
+    n: usize,
+    commitment: DecafPoint,
+}
+
+impl VerifiedProof {
+    /// The verified rangeproof.
+    pub fn proof(&self) -> &RangeProof {
+        &self.proof
+    }
+
+    /// The number of rings the proof was verified with.
+    pub fn n(&self) -> usize {
+        self.n
+    }
+
+    /// The commitment to the in-range value.
+    pub fn commitment(&self) -> &DecafPoint {
+        &self.commitment
+    }
+
+    /// Consume the `VerifiedProof`, returning the rangeproof and the
+    /// commitment.
+    pub fn into_parts(self) -> (RangeProof, DecafPoint) {
+        (self.proof, self.commitment)
+    }
+}
+
+impl RangeProof {
+    /// Decode a rangeproof with exactly `n` rings from the encoding
+    /// produced by `to_bytes`, and verify it.
+    ///
+    /// # Returns
+    ///
+    /// The verified proof, or None if `bytes` isn't a canonical
+    /// encoding of a proof with `n` rings or the proof doesn't verify,
+    /// including if `n > RANGEPROOF_MAX_N`.
+    pub fn from_bytes_verified(
+        bytes: &[u8],
+        n: usize,
+        G: &DecafBasepointTable,
+        H: &DecafPoint,
+    ) -> Option<VerifiedProof> {
+        if n > RANGEPROOF_MAX_N {
+            return None;
+        }
+
+        let proof = RangeProof::from_bytes_exact(bytes, n).ok()?;
+        let commitment = proof.verify(n, G, H)?;
+
+        Some(VerifiedProof { proof: proof, n: n, commitment: commitment })
+    }
+
+    /// Verify the rangeproof using precomputed `params`, returning the
+    /// reason for any failure.
+    ///
+    /// # Returns
+    ///
+    /// The verified commitment, or why the proof doesn't verify.  A
+    /// `VerifyError::WrongRingCount` if `n > RANGEPROOF_MAX_N`, since no
+    /// proof has that many rings.
+    pub fn verify_checked(
+        &self,
+        n: usize,
+        params: &RangeProofParams,
+    ) -> Result<VerifiedCommitment, VerifyError> {
+        self.check_with_powers(n, params.G(), params.H_powers()).map(VerifiedCommitment)
+    }
+
+    /// Verify the rangeproof, as for `verify`, returning the reason for
+    /// any failure rather than panicking or returning None.
+    ///
+    /// # Returns
+    ///
+    /// A `RangeProofError::InvalidRingCount` if `n > RANGEPROOF_MAX_N`,
+    /// and otherwise a `RangeProofError::Verify` with the reason the
+    /// proof doesn't verify, as for `verify_checked`.
+    ///
+    /// Otherwise, returns the commitment to the in-range value.
+    pub fn try_verify(
+        &self,
+        n: usize,
+        G: &DecafBasepointTable,
+        H: &DecafPoint,
+    ) -> Result<VerifiedCommitment, RangeProofError> {
+        RangeProof::check_n(n)?;
+
+        Ok(VerifiedCommitment(self.check_with_powers(n, G, &PowersOfH::with_len(n, H))?))
+    }
+
+    /// Verify the rangeproof using precomputed `params`, as for
+    /// `try_verify`.
+    pub fn try_verify_with_params(
+        &self,
+        n: usize,
+        params: &RangeProofParams,
+    ) -> Result<VerifiedCommitment, RangeProofError> {
+        RangeProof::check_n(n)?;
+
+        Ok(self.verify_checked(n, params)?)
+    }
+
+    /// Decode a rangeproof with exactly `n` rings and verify it, as for
+    /// `from_bytes_verified`, returning the reason for any failure.
+    ///
+    /// # Returns
+    ///
+    /// A `RangeProofError::Decoding` if `bytes` isn't an encoding of a
+    /// proof with `n` rings, and otherwise as for `try_verify`.
+    pub fn try_from_bytes_verified(
+        bytes: &[u8],
+        n: usize,
+        G: &DecafBasepointTable,
+        H: &DecafPoint,
+    ) -> Result<VerifiedProof, RangeProofError> {
+        RangeProof::check_n(n)?;
+
+        let proof = RangeProof::from_bytes_exact(bytes, n)?;
+        let commitment = proof.try_verify(n, G, H)?.into_commitment();
+
+        Ok(VerifiedProof { proof: proof, n: n, commitment: commitment })
+    }
+
+    /// Verify each of the `proofs`, which should each be for the range
+    /// `[0,3^n]`.
+    ///
+    /// Every proof is verified, even after a failure, so that a caller
+    /// holding many proofs (e.g. the outputs of a transaction) learns
+    /// which ones are invalid without having to verify them again
+    /// individually.  When the `parallel` feature is enabled, the
+    /// proofs are verified on `rayon`'s thread pool.
+    ///
+    /// # Returns
+    ///
+    /// A `Vec` whose `j`th entry is the result of verifying `proofs[j]`,
+    /// as for `verify_checked`.
+    #[must_use]
+    pub fn verify_batch_each(
+        n: usize,
+        proofs: &[RangeProof],
+        params: &RangeProofParams,
+    ) -> Vec<Result<VerifiedCommitment, VerifyError>> {
+        #[cfg(feature = "parallel")]
+        let results = proofs.par_iter().map(|proof| proof.verify_checked(n, params)).collect();
+        #[cfg(not(feature = "parallel"))]
+        let results = proofs.iter().map(|proof| proof.verify_checked(n, params)).collect();
+
+        results
+    }
+
+    /// Verify all of the `proofs`, which should each be for the range
+    /// `[0,3^n]`.
+    ///
+    /// # Returns
+    ///
+    /// The verified commitments, in order, if every proof verifies.
+    /// Otherwise, the index of the first proof which failed, and why.
+    /// The first failure is always the one with the least index, even
+    /// when the proofs are verified in parallel.
+    pub fn verify_batch_all(
+        n: usize,
+        proofs: &[RangeProof],
+        params: &RangeProofParams,
+    ) -> Result<Vec<VerifiedCommitment>, (usize, VerifyError)> {
+        RangeProof::verify_batch_each(n, proofs, params)
+            .into_iter()
+            .enumerate()
+            .map(|(j, result)| result.map_err(|e| (j, e)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::OsRng;
+
+    use curve25519_dalek::scalar::Scalar;
+
+    use errors::DecodingError;
+    use DEFAULT_PARAMS;
+
+    #[test]
+    fn verify_batch_each_reports_every_failure_in_order() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let n = 16;
+        let mut proofs = Vec::new();
+        let mut commitments = Vec::new();
+        for &value in [13449261, 42, 0, 1].iter() {
+            let (proof, commitment, _) =
+                RangeProof::create_with_params(n, value, &DEFAULT_PARAMS, &mut csprng).unwrap().into_parts();
+            proofs.push(proof);
+            commitments.push(commitment);
+        }
+        // Break the second proof's challenge, and drop a ring from the last.
+        proofs[1].e_0 = Scalar::one();
+        proofs[3].C.pop();
+
+        let results = RangeProof::verify_batch_each(n, &proofs, &DEFAULT_PARAMS);
+        assert_eq!(results.len(), 4);
+        assert_eq!(results[0].unwrap().commitment().compress(), commitments[0].compress());
+        assert_eq!(results[1].err(), Some(VerifyError::InvalidProof));
+        assert_eq!(results[2].unwrap().into_commitment().compress(), commitments[2].compress());
+        assert_eq!(results[3].err(), Some(VerifyError::WrongRingCount));
+    }
+
+    #[test]
+    fn from_bytes_verified() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let n = 16;
+        let (proof, C, _) = RangeProof::create_with_params(n, 1000, &DEFAULT_PARAMS, &mut csprng).unwrap().into_parts();
+        let mut bytes = proof.to_bytes();
+        let (G, H) = (DEFAULT_PARAMS.G(), DEFAULT_PARAMS.H());
+
+        let verified = RangeProof::from_bytes_verified(&bytes, n, G, H).unwrap();
+        assert_eq!(verified.n(), n);
+        assert_eq!(verified.commitment().compress(), C.compress());
+        assert_eq!(verified.proof().to_bytes(), bytes);
+
+        assert!(RangeProof::from_bytes_verified(&bytes, n - 1, G, H).is_none());
+        bytes[0] ^= 1;
+        assert!(RangeProof::from_bytes_verified(&bytes, n, G, H).is_none());
+    }
+
+    #[test]
+    fn try_variants_distinguish_failures() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let n = 16;
+        let (G, H) = (DEFAULT_PARAMS.G(), DEFAULT_PARAMS.H());
+        let (proof, C, _) = RangeProof::try_create(n, 1000, G, H, &mut csprng).unwrap().into_parts();
+        assert_eq!(proof.try_verify(n, G, H).unwrap().commitment().compress(), C.compress());
+        assert!(proof.try_verify_with_params(n, &DEFAULT_PARAMS).is_ok());
+
+        match RangeProof::try_create_vartime(2, 9, G, H, &mut csprng) {
+            Err(RangeProofError::OutOfRange(e)) => assert_eq!(e.min_n, 3),
+            _ => panic!("created a proof for an out-of-range value"),
+        }
+        assert_eq!(RangeProof::try_create(42, 0, G, H, &mut csprng).err(),
+                   Some(RangeProofError::InvalidRingCount(42)));
+        assert_eq!(proof.try_verify(42, G, H).err(), Some(RangeProofError::InvalidRingCount(42)));
+        assert_eq!(proof.try_verify(n - 1, G, H).err(), Some(RangeProofError::Verify(VerifyError::WrongRingCount)));
+
+        let mut bytes = proof.to_bytes();
+        assert_eq!(RangeProof::try_from_bytes_verified(&bytes, n - 1, G, H).err(),
+                   Some(RangeProofError::Decoding(DecodingError::TrailingBytes)));
+        bytes[0] ^= 1;
+        assert_eq!(RangeProof::try_from_bytes_verified(&bytes, n, G, H).err(),
+                   Some(RangeProofError::Verify(VerifyError::InvalidProof)));
+    }
+
+    #[test]
+    fn batch_results_are_independent_of_scheduling() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let n = 8;
+        let mut proofs = Vec::new();
+        for value in 0..32 {
+            proofs.push(RangeProof::create_with_params(n, value, &DEFAULT_PARAMS, &mut csprng).unwrap().into_proof());
+        }
+        proofs[5].e_0 = Scalar::one();
+        proofs[9].C.pop();
+        proofs[20].s_1[0] = Scalar([0xff; 32]);
+        proofs[31].e_0 = Scalar::one();
+
+        let summarize = |results: Vec<Result<VerifiedCommitment, VerifyError>>| {
+            results.into_iter()
+                .map(|result| result.map(|C| *C.commitment().compress().as_bytes()))
+                .collect::<Vec<_>>()
+        };
+        let sequential = summarize(proofs.iter().map(|proof| proof.verify_checked(n, &DEFAULT_PARAMS)).collect());
+
+        for _ in 0..4 {
+            assert_eq!(summarize(RangeProof::verify_batch_each(n, &proofs, &DEFAULT_PARAMS)), sequential);
+            assert_eq!(RangeProof::verify_batch_all(n, &proofs, &DEFAULT_PARAMS).err(),
+                       Some((5, VerifyError::InvalidProof)));
+        }
+
+        let valid = &proofs[10..20];
+        let commitments = RangeProof::verify_batch_all(n, valid, &DEFAULT_PARAMS).unwrap();
+        assert_eq!(commitments.len(), 10);
+    }
+}
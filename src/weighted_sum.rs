@@ -0,0 +1,228 @@
+// -*- coding: utf-8; mode: rust; -*-
+//
+// To the extent possible under law, the authors have waived all
+// copyright and related or neighboring rights to dalek-rangeproofs,
+// using the Creative Commons "CC0" public domain dedication.  See
+// <http://creativecommons.org/publicdomain/zero/.0/> for full
+// details.
+//
+// Authors:
+// - Isis Agora Lovecruft <isis@patternsinthevoid.net>
+// - Henry de Valence <hdevalence@hdevalence.ca>
+
+//! Rangeproofs for a weighted sum of committed values.
+//!
+//! Given commitments `C_i = r_i*G + v_i*H` and public weights `w_i`,
+//! the weighted commitment
+//!
+//! ```text
+//! C_w = Σ w_i*C_i = (Σ w_i*r_i)*G + (Σ w_i*v_i)*H
+//! ```
+//!
+//! commits to the weighted sum.  The prover creates an ordinary
+//! rangeproof for `Σ w_i*v_i`, with commitment `C_sum`, and proves with
+//! a Schnorr proof that `C_w - C_sum` is a multiple of `G` alone, i.e.
+//! that the two commit to the same value.  Nothing is revealed about
+//! the individual values.
+
+use rand::Rng;
+
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::curve::Identity;
+use curve25519_dalek::decaf::{CompressedDecaf, DecafPoint};
+use curve25519_dalek::decaf::vartime;
+
+use challenges::weighted_sum_challenge;
+use encoding::read_scalar;
+use errors::DecodingError;
+use opening::Opening;
+use params::RangeProofParams;
+use transcript_rng::TranscriptRng;
+
+use super::RangeProof;
+use super::RANGEPROOF_MAX_N;
+
+/// A proof that `Σ w_i*v_i` is in `[0,3^n]`, for commitments to the
+/// `v_i` and public weights `w_i`.
+pub struct WeightedSumRangeProof {
+    proof: RangeProof,
+    c: Scalar,
+    s: Scalar,
+}
+
+/// Returns `Σ w_i*C_i`.
+fn weighted_commitment(commitments: &[DecafPoint], weights: &[u64]) -> DecafPoint {
+    let mut C_w = DecafPoint::identity();
+    for (C, &w) in commitments.iter().zip(weights.iter()) {
+        C_w = &C_w + &(C * &Scalar::from_u64(w));
+    }
+    C_w
+}
+
+impl WeightedSumRangeProof {
+    /// Construct a proof that the weighted sum of the values of the
+    /// `openings` is in `[0,3^n]`.
+    ///
+    /// The rangeproof is created in constant time.
+    ///
+    /// # Inputs
+    ///
+    /// * `n`, so that the range is `[0,3^n]` with `n <= RANGEPROOF_MAX_N`;
+    /// * The `openings` of the commitments;
+    /// * The public `weights`, one for each opening;
+    /// * The precomputed `params`;
+    /// * `csprng`, an implementation of `rand::Rng`, which should be
+    /// cryptographically secure.
+    ///
+    /// # Returns
+    ///
+    /// If `n > RANGEPROOF_MAX_N`, the numbers of openings and weights
+    /// differ, or the weighted sum doesn't fit in a `u64` or isn't in
+    /// the range `[0,3^n]`, return None.
+    pub fn create<T: Rng>(
+        n: usize,
+        openings: &[Opening],
+        weights: &[u64],
+        params: &RangeProofParams,
+        csprng: &mut T,
+    ) -> Option<WeightedSumRangeProof> {
+        if n > RANGEPROOF_MAX_N || openings.len() != weights.len() {
+            return None;
+        }
+
+        let mut sum = 0u64;
+        let mut r_w = Scalar::zero();
+        for (opening, &w) in openings.iter().zip(weights.iter()) {
+            sum = sum.checked_add(opening.value().checked_mul(w)?)?;
+            r_w = Scalar::multiply_add(&Scalar::from_u64(w), opening.blinding().expose_secret(), &r_w);
+        }
+
+        let (proof, C_sum, r_sum) = RangeProof::create_with_params(n, sum, params, csprng)?.into_parts();
+
+        // C_w - C_sum = x*G.
+        let x = &r_w - r_sum.expose_secret();
+        let commitments: Vec<CompressedDecaf> =
+            openings.iter().map(|opening| opening.commitment(params).compress()).collect();
+        let C_sum = C_sum.compress();
+
+        let mut rng = TranscriptRng::builder(b"weighted sum")
+            .commit_transcript(b"C_sum", C_sum.as_bytes())
+            .commit_witness(b"x", x.as_bytes())
+            .finalize(csprng);
+        let k = Scalar::random(&mut rng);
+        let K = params.G() * &k;
+
+        let c = weighted_sum_challenge(weights, &commitments, &C_sum, &K.compress());
+        let s = Scalar::multiply_add(&c, &x, &k);
+
+        Some(WeightedSumRangeProof { proof: proof, c: c, s: s })
+    }
+
+    /// Verify that the weighted sum of the values committed to by
+    /// `commitments` is in `[0,3^n]`, returning the weighted commitment
+    /// `Σ w_i*C_i` if successful.
+    #[must_use]
+    pub fn verify(
+        &self,
+        n: usize,
+        commitments: &[DecafPoint],
+        weights: &[u64],
+        params: &RangeProofParams,
+    ) -> Option<DecafPoint> {
+        if commitments.len() != weights.len() {
+            return None;
+        }
+
+        let C_sum = self.proof.verify_with_params(n, params)?;
+        let C_w = weighted_commitment(commitments, weights);
+
+        let X = &C_w - &C_sum;
+        let K = vartime::k_fold_scalar_mult(&[self.s, -&self.c], &[params.G().basepoint(), X]);
+        let compressed: Vec<CompressedDecaf> = commitments.iter().map(|C| C.compress()).collect();
+        let c = weighted_sum_challenge(weights, &compressed, &C_sum.compress(), &K.compress());
+
+        if c == self.c {
+            Some(C_w)
+        } else {
+            None
+        }
+    }
+
+    /// Encode the proof as `c || s || proof`, where `proof` is encoded
+    /// as for `RangeProof::to_bytes`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(64 + self.proof.size_in_bytes());
+        bytes.extend_from_slice(self.c.as_bytes());
+        bytes.extend_from_slice(self.s.as_bytes());
+        bytes.extend_from_slice(&self.proof.to_bytes());
+        bytes
+    }
+
+    /// Decode a proof encoded by `to_bytes`.
+    ///
+    /// # Returns
+    ///
+    /// A `DecodingError` if `bytes` is too short, if `c` or `s` aren't
+    /// canonically encoded, or if the rangeproof can't be decoded.
+    pub fn from_bytes(bytes: &[u8]) -> Result<WeightedSumRangeProof, DecodingError> {
+        if bytes.len() < 64 {
+            return Err(DecodingError::InvalidLength);
+        }
+
+        Ok(WeightedSumRangeProof {
+            c: read_scalar(&bytes[..32])?,
+            s: read_scalar(&bytes[32..64])?,
+            proof: RangeProof::from_bytes(&bytes[64..])?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rand::OsRng;
+
+    use DEFAULT_PARAMS;
+
+    #[test]
+    fn prove_and_verify_weighted_sum() {
+        let mut csprng = OsRng::new().unwrap();
+
+        let mut openings = Vec::new();
+        let mut commitments = Vec::new();
+        for &value in [100, 20, 3].iter() {
+            let (_, C, blinding) =
+                RangeProof::create_with_params(8, value, &DEFAULT_PARAMS, &mut csprng).unwrap().into_parts();
+            openings.push(Opening::new(value, blinding));
+            commitments.push(C);
+        }
+        let weights = [1, 5, 10];
+
+        // 100 + 5*20 + 10*3 = 230 < 3^5
+        let proof = WeightedSumRangeProof::create(5, &openings, &weights, &DEFAULT_PARAMS, &mut csprng).unwrap();
+        let C_w = proof.verify(5, &commitments, &weights, &DEFAULT_PARAMS).unwrap();
+        let C_hat = &(&commitments[0] + &(&commitments[1] * &Scalar::from_u64(5)))
+            + &(&commitments[2] * &Scalar::from_u64(10));
+        assert_eq!(C_w.compress(), C_hat.compress());
+
+        assert!(proof.verify(5, &commitments, &[1, 5, 11], &DEFAULT_PARAMS).is_none());
+        assert!(proof.verify(5, &commitments[..2], &weights[..2], &DEFAULT_PARAMS).is_none());
+
+        // 230 >= 3^4
+        assert!(WeightedSumRangeProof::create(4, &openings, &weights, &DEFAULT_PARAMS, &mut csprng).is_none());
+        assert!(WeightedSumRangeProof::create(5, &openings, &weights[..2], &DEFAULT_PARAMS, &mut csprng).is_none());
+        assert!(WeightedSumRangeProof::create(RANGEPROOF_MAX_N + 1, &openings, &weights,
+                                              &DEFAULT_PARAMS, &mut csprng).is_none());
+        assert!(proof.verify(RANGEPROOF_MAX_N + 1, &commitments, &weights, &DEFAULT_PARAMS).is_none());
+
+        let bytes = proof.to_bytes();
+        let decoded = WeightedSumRangeProof::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.to_bytes(), bytes);
+        assert_eq!(decoded.verify(5, &commitments, &weights, &DEFAULT_PARAMS).unwrap().compress(),
+                   C_w.compress());
+        assert_eq!(WeightedSumRangeProof::from_bytes(&bytes[..63]).err(), Some(DecodingError::InvalidLength));
+        assert_eq!(WeightedSumRangeProof::from_bytes(&bytes[..bytes.len() - 1]).err(),
+                   Some(DecodingError::InvalidLength));
+    }
+}